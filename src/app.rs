@@ -12,7 +12,7 @@ use crate::ui::{
     state::AppState,
     theme::configure_style,
     views::{
-        BackupView, ChecklistSearchView, ChecklistTemplatesView, DashboardView, DocumentTemplatesView,
+        BackupView, CalendarView, ChecklistSearchView, ChecklistTemplatesView, DashboardView, DocumentTemplatesView,
         DocumentViewerView, FamilyTreeView, PersonDetailView, PersonListView, ProjectSelectorView,
         ReportsView, ResourceDetailView, ResourceListView, SettingsView, SetupWizardView,
         SplashScreenView,
@@ -50,6 +50,7 @@ pub struct GenlibApp {
 
     // Resurser
     resource_list: ResourceListView,
+    calendar: CalendarView,
     resource_detail: ResourceDetailView,
     resource_form: ResourceFormModal,
 
@@ -167,6 +168,7 @@ impl GenlibApp {
             document_templates: DocumentTemplatesView::new(),
             splash_screen: SplashScreenView::new(next_view),
             resource_list: ResourceListView::new(),
+            calendar: CalendarView::new(),
             resource_detail: ResourceDetailView::new(),
             resource_form: ResourceFormModal::new(),
             person_form_modal: PersonFormModal::new(),
@@ -214,6 +216,7 @@ impl GenlibApp {
         self.document_templates.mark_needs_refresh();
         self.resource_list.mark_needs_refresh();
         self.resource_detail.mark_needs_refresh();
+        self.calendar.mark_needs_refresh();
 
         let setup_complete = self.db.config().is_setup_complete().unwrap_or(false);
         if let Ok(config) = self.db.config().get() {
@@ -308,6 +311,7 @@ impl GenlibApp {
             View::DocumentTemplates => self.document_templates.mark_needs_refresh(),
             View::ResourceList => self.resource_list.mark_needs_refresh(),
             View::ResourceDetail => self.resource_detail.mark_needs_refresh(),
+            View::Calendar => self.calendar.mark_needs_refresh(),
         }
     }
 
@@ -331,7 +335,7 @@ impl GenlibApp {
         } else if self.state.show_document_upload {
             self.state.close_document_upload();
         } else if self.state.show_relationship_form {
-            self.state.show_relationship_form = false;
+            self.state.close_relationship_form();
         } else if self.state.show_gedcom_import {
             self.state.show_gedcom_import = false;
         } else if self.state.show_archive_modal {
@@ -364,6 +368,7 @@ impl GenlibApp {
             ShortcutAction::NavigateChecklistSearch => self.navigate_to(View::ChecklistSearch),
             ShortcutAction::NavigateSettings => self.navigate_to(View::Settings),
             ShortcutAction::NavigateResourceList => self.navigate_to(View::ResourceList),
+            ShortcutAction::NavigateCalendar => self.navigate_to(View::Calendar),
             ShortcutAction::NewPerson => {
                 self.state.open_new_person_form();
             }
@@ -452,6 +457,7 @@ impl eframe::App for GenlibApp {
                     (View::FamilyTree, "🌳 Släktträd", ShortcutAction::NavigateFamilyTree),
                     (View::ChecklistSearch, "✓ Uppgifter", ShortcutAction::NavigateChecklistSearch),
                     (View::ResourceList, "📍 Resurser", ShortcutAction::NavigateResourceList),
+                    (View::Calendar, "📅 Kalender", ShortcutAction::NavigateCalendar),
                 ];
 
                 for (view, label, shortcut_action) in nav_items {
@@ -587,6 +593,9 @@ impl eframe::App for GenlibApp {
                 View::ResourceDetail => {
                     self.resource_detail.show(ui, &mut self.state, &self.db);
                 }
+                View::Calendar => {
+                    self.calendar.show(ui, &mut self.state, &self.db);
+                }
                 View::Splash => {}
             }
         });
@@ -600,6 +609,9 @@ impl eframe::App for GenlibApp {
                 if self.state.current_view == View::PersonDetail {
                     self.person_detail.mark_needs_refresh();
                 }
+                if self.state.current_view == View::FamilyTree {
+                    self.family_tree.mark_needs_refresh();
+                }
             }
         }
 
@@ -618,6 +630,9 @@ impl eframe::App for GenlibApp {
                     if self.state.current_view == View::ResourceDetail {
                         self.resource_detail.mark_needs_refresh();
                     }
+                    if self.state.current_view == View::FamilyTree {
+                        self.family_tree.mark_needs_refresh();
+                    }
                     self.resource_list.mark_needs_refresh();
                 }
             }
@@ -636,11 +651,13 @@ impl eframe::App for GenlibApp {
         }
 
         if self.state.show_relationship_form {
-            if let Some(person_id) = self.state.selected_person_id {
+            let person_id = self.state.relationship_form_person_id.or(self.state.selected_person_id);
+            if let Some(person_id) = person_id {
                 if let Ok(Some(person)) = self.db.persons().find_by_id(person_id) {
                     if self.relationship_form_modal.show(ctx, &mut self.state, &self.db, &person) {
-                        self.state.show_relationship_form = false;
+                        self.state.close_relationship_form();
                         self.person_detail.mark_needs_refresh();
+                        self.family_tree.mark_needs_refresh();
                     }
                 }
             }
@@ -1,6 +1,6 @@
 //! Repository för checklisthantering
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, Result};
@@ -57,6 +57,27 @@ impl ChecklistRepository {
         Ok(items)
     }
 
+    /// Hämta checklistobjekt för alla personer i en fråga (person_id -> objekt)
+    pub fn find_all_by_person(&self) -> Result<HashMap<i64, Vec<PersonChecklistItem>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, person_id, template_item_id, title,
+                    sort_order, is_completed, completed_at
+             FROM person_checklist_items
+             ORDER BY sort_order",
+        )?;
+
+        let mut map: HashMap<i64, Vec<PersonChecklistItem>> = HashMap::new();
+        let items = stmt
+            .query_map([], |row| Ok(Self::row_to_item(row)))?
+            .filter_map(|r| r.ok());
+        for item in items {
+            map.entry(item.person_id).or_default().push(item);
+        }
+
+        Ok(map)
+    }
+
     /// Hämta checklistmallar
     pub fn list_templates(&self, include_inactive: bool) -> Result<Vec<ChecklistTemplate>> {
         let conn = self.conn.lock().unwrap();
@@ -276,6 +297,28 @@ impl ChecklistRepository {
         Ok(ids)
     }
 
+    /// Lägg till en mall hos flera personer (t.ex. alla i en sparad sökning).
+    /// Punkter som personen redan har från mallen hoppas över.
+    /// Returnerar antal skapade checklistpunkter.
+    pub fn apply_template_to_persons(&self, template_id: i64, person_ids: &[i64]) -> Result<usize> {
+        let template_items = self.list_template_items(template_id)?;
+        let mut created = 0;
+
+        for &person_id in person_ids {
+            let existing = self.template_item_ids_for_person(person_id)?;
+            for template_item in &template_items {
+                if template_item.id.is_some_and(|id| existing.contains(&id)) {
+                    continue;
+                }
+                let mut item = PersonChecklistItem::from_template(person_id, template_item);
+                self.create(&mut item)?;
+                created += 1;
+            }
+        }
+
+        Ok(created)
+    }
+
     /// Hämta progress för en person (completed, total)
     pub fn get_progress(&self, person_id: i64) -> Result<(i64, i64)> {
         let conn = self.conn.lock().unwrap();
@@ -651,4 +694,44 @@ mod tests {
         assert_eq!(completed, 2);
         assert_eq!(total, 3);
     }
+
+    #[test]
+    fn test_apply_template_to_persons() {
+        use crate::db::SearchFilter;
+
+        let db = Database::open_in_memory().unwrap();
+
+        let mut person_ids = Vec::new();
+        for name in ["a", "b"] {
+            let mut person = crate::models::Person::new(Some(name.to_string()), None, format!("bulk_{}", name));
+            db.persons().create(&mut person).unwrap();
+            person_ids.push(person.id.unwrap());
+        }
+
+        let mut template = ChecklistTemplate::new("Grundforskning".to_string());
+        let template_id = db.checklists().create_template(&mut template).unwrap();
+        for (i, title) in ["Födelsebok", "Dödbok"].iter().enumerate() {
+            let mut item = ChecklistTemplateItem {
+                id: None,
+                template_id,
+                title: title.to_string(),
+                sort_order: i as i32,
+            };
+            db.checklists().create_template_item(&mut item).unwrap();
+        }
+
+        let created = db.checklists().apply_template_to_persons(template_id, &person_ids).unwrap();
+        assert_eq!(created, 4);
+
+        // Andra gången finns allt redan
+        let created = db.checklists().apply_template_to_persons(template_id, &person_ids).unwrap();
+        assert_eq!(created, 0);
+
+        // Filtret för öppna punkter ska hitta båda personerna
+        let filter = SearchFilter {
+            has_open_checklist_items: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(db.persons().count_matching(&filter).unwrap(), 2);
+    }
 }
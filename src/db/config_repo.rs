@@ -3,7 +3,7 @@ use rusqlite::{params, Connection};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use crate::models::{DirNameFormat, SystemConfig};
+use crate::models::{DirNameFormat, SystemConfig, DEFAULT_LIVING_THRESHOLD_YEARS};
 
 pub struct ConfigRepository {
     conn: Arc<Mutex<Connection>>,
@@ -19,7 +19,8 @@ impl ConfigRepository {
         let conn = self.conn.lock().unwrap();
 
         let result = conn.query_row(
-            "SELECT id, media_directory_path, backup_directory_path, dir_name_format, created_at, updated_at
+            "SELECT id, media_directory_path, backup_directory_path, dir_name_format, created_at, updated_at,
+                    living_threshold_years
              FROM system_config WHERE id = 1",
             [],
             |row| {
@@ -29,6 +30,7 @@ impl ConfigRepository {
                     media_directory_path: PathBuf::from(row.get::<_, String>(1)?),
                     backup_directory_path: PathBuf::from(row.get::<_, String>(2)?),
                     dir_name_format: DirNameFormat::from_db_str(&format_str),
+                    living_threshold_years: row.get(6).unwrap_or(DEFAULT_LIVING_THRESHOLD_YEARS),
                     created_at: row.get(4).ok(),
                     updated_at: row.get(5).ok(),
                 })
@@ -52,12 +54,14 @@ impl ConfigRepository {
         let conn = self.conn.lock().unwrap();
 
         conn.execute(
-            "INSERT OR REPLACE INTO system_config (id, media_directory_path, backup_directory_path, dir_name_format, updated_at)
-             VALUES (1, ?1, ?2, ?3, datetime('now'))",
+            "INSERT OR REPLACE INTO system_config (id, media_directory_path, backup_directory_path, dir_name_format,
+                                              living_threshold_years, updated_at)
+             VALUES (1, ?1, ?2, ?3, ?4, datetime('now'))",
             params![
                 config.media_directory_path.to_string_lossy().to_string(),
                 config.backup_directory_path.to_string_lossy().to_string(),
                 config.dir_name_format.to_string(),
+                config.living_threshold_years,
             ],
         )?;
 
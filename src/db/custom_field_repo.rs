@@ -0,0 +1,284 @@
+//! Repository för egna fält (definitioner och värden)
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, Row};
+
+use crate::models::{CustomFieldDefinition, CustomFieldEntity, CustomFieldType};
+
+pub struct CustomFieldRepository {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl CustomFieldRepository {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    // ── Definitioner ─────────────────────────────────────────────────────────
+
+    /// Hämta alla fältdefinitioner för en posttyp, i visningsordning
+    pub fn find_definitions(&self, entity: CustomFieldEntity) -> Result<Vec<CustomFieldDefinition>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, entity, name, field_type, options, sort_order, created_at
+             FROM custom_field_definitions
+             WHERE entity = ?
+             ORDER BY sort_order, name",
+        )?;
+
+        let definitions = stmt
+            .query_map([entity.as_db_str()], |row| Ok(Self::row_to_definition(row)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(definitions)
+    }
+
+    /// Hämta fältdefinition via ID
+    pub fn find_definition_by_id(&self, id: i64) -> Result<Option<CustomFieldDefinition>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, entity, name, field_type, options, sort_order, created_at
+             FROM custom_field_definitions
+             WHERE id = ?",
+        )?;
+
+        let definition = stmt
+            .query_row([id], |row| Ok(Self::row_to_definition(row)))
+            .ok();
+
+        Ok(definition)
+    }
+
+    /// Skapa ny fältdefinition. Nya fält hamnar sist.
+    pub fn create_definition(&self, definition: &mut CustomFieldDefinition) -> Result<i64> {
+        let name = definition.name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Fältet måste ha ett namn"));
+        }
+        if definition.field_type == CustomFieldType::Choice && definition.options.is_empty() {
+            return Err(anyhow!("Valfält måste ha minst ett alternativ"));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let next_order: i32 = conn.query_row(
+            "SELECT COALESCE(MAX(sort_order), -1) + 1 FROM custom_field_definitions WHERE entity = ?",
+            [definition.entity.as_db_str()],
+            |row| row.get(0),
+        )?;
+
+        conn.execute(
+            "INSERT INTO custom_field_definitions (entity, name, field_type, options, sort_order)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                definition.entity.as_db_str(),
+                name,
+                definition.field_type.as_db_str(),
+                definition.options.join("\n"),
+                next_order,
+            ],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        definition.id = Some(id);
+        definition.sort_order = next_order;
+
+        Ok(id)
+    }
+
+    /// Uppdatera namn, alternativ och ordning (typ och posttyp kan inte ändras)
+    pub fn update_definition(&self, definition: &CustomFieldDefinition) -> Result<()> {
+        let id = definition.id.ok_or_else(|| anyhow!("Fält har inget ID"))?;
+
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE custom_field_definitions SET name = ?1, options = ?2, sort_order = ?3
+             WHERE id = ?4",
+            params![
+                definition.name.trim(),
+                definition.options.join("\n"),
+                definition.sort_order,
+                id,
+            ],
+        )?;
+
+        if rows == 0 {
+            return Err(anyhow!("Fält med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    /// Ta bort fältdefinition (värden tas bort via CASCADE)
+    pub fn delete_definition(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM custom_field_definitions WHERE id = ?", [id])?;
+
+        if rows == 0 {
+            return Err(anyhow!("Fält med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    /// Räkna poster som har ett värde i fältet
+    pub fn count_values(&self, field_id: i64) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM custom_field_values WHERE field_id = ?",
+            [field_id],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    // ── Värden ───────────────────────────────────────────────────────────────
+
+    /// Hämta värden för en post (fält-ID -> värde)
+    pub fn get_values(&self, entity: CustomFieldEntity, entity_id: i64) -> Result<HashMap<i64, String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT v.field_id, v.value
+             FROM custom_field_values v
+             INNER JOIN custom_field_definitions d ON d.id = v.field_id
+             WHERE d.entity = ?1 AND v.entity_id = ?2",
+        )?;
+
+        let values = stmt
+            .query_map(params![entity.as_db_str(), entity_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(values)
+    }
+
+    /// Hämta värden för alla poster av en typ (post-ID -> fält-ID -> värde)
+    pub fn get_all_values(&self, entity: CustomFieldEntity) -> Result<HashMap<i64, HashMap<i64, String>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT v.entity_id, v.field_id, v.value
+             FROM custom_field_values v
+             INNER JOIN custom_field_definitions d ON d.id = v.field_id
+             WHERE d.entity = ?",
+        )?;
+
+        let mut map: HashMap<i64, HashMap<i64, String>> = HashMap::new();
+        let rows = stmt.query_map([entity.as_db_str()], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+        })?;
+        for (entity_id, field_id, value) in rows.filter_map(|r| r.ok()) {
+            map.entry(entity_id).or_default().insert(field_id, value);
+        }
+
+        Ok(map)
+    }
+
+    /// Spara värden för en post. Tomma värden tar bort fältet från posten.
+    pub fn set_values(&self, entity_id: i64, values: &HashMap<i64, String>) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for (field_id, value) in values {
+            let value = value.trim();
+            if value.is_empty() {
+                tx.execute(
+                    "DELETE FROM custom_field_values WHERE field_id = ?1 AND entity_id = ?2",
+                    params![field_id, entity_id],
+                )?;
+            } else {
+                tx.execute(
+                    "INSERT INTO custom_field_values (field_id, entity_id, value) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (field_id, entity_id) DO UPDATE SET value = excluded.value",
+                    params![field_id, entity_id, value],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn row_to_definition(row: &Row) -> CustomFieldDefinition {
+        let options: String = row.get(4).unwrap_or_default();
+        CustomFieldDefinition {
+            id: row.get(0).ok(),
+            entity: CustomFieldEntity::from_db_str(&row.get::<_, String>(1).unwrap_or_default()),
+            name: row.get(2).unwrap_or_default(),
+            field_type: CustomFieldType::from_db_str(&row.get::<_, String>(3).unwrap_or_default()),
+            options: options
+                .lines()
+                .map(|o| o.trim().to_string())
+                .filter(|o| !o.is_empty())
+                .collect(),
+            sort_order: row.get(5).unwrap_or(0),
+            created_at: row.get(6).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::person_repo::{CustomFieldCondition, TextCondition, TextOperator};
+    use crate::db::{Database, SearchFilter};
+    use crate::models::Person;
+
+    #[test]
+    fn test_values_search_and_cleanup() {
+        let db = Database::open_in_memory().unwrap();
+
+        let mut soldier_no =
+            CustomFieldDefinition::new(CustomFieldEntity::Person, "Soldatnummer".into(), CustomFieldType::Number);
+        let soldier_field = db.custom_fields().create_definition(&mut soldier_no).unwrap();
+        let mut regiment =
+            CustomFieldDefinition::new(CustomFieldEntity::Person, "Regemente".into(), CustomFieldType::Choice);
+        regiment.options = vec!["Upplands".into(), "Dalregementet".into()];
+        let regiment_field = db.custom_fields().create_definition(&mut regiment).unwrap();
+        assert_eq!(regiment.sort_order, 1);
+
+        let mut per = Person::new(Some("Per".into()), Some("Svärd".into()), "per".into());
+        db.persons().create(&mut per).unwrap();
+        let mut olof = Person::new(Some("Olof".into()), Some("Berg".into()), "olof".into());
+        db.persons().create(&mut olof).unwrap();
+        let per_id = per.id.unwrap();
+
+        let values = HashMap::from([
+            (soldier_field, "112".to_string()),
+            (regiment_field, "Upplands".to_string()),
+        ]);
+        db.custom_fields().set_values(per_id, &values).unwrap();
+        assert_eq!(db.custom_fields().get_values(CustomFieldEntity::Person, per_id).unwrap(), values);
+
+        // Sök på eget fält
+        let filter = SearchFilter {
+            custom_fields: vec![CustomFieldCondition {
+                field_id: regiment_field,
+                condition: TextCondition::new(TextOperator::Equals, "upplands"),
+            }],
+            ..Default::default()
+        };
+        let found = db.persons().advanced_search(&filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, Some(per_id));
+
+        let filter = SearchFilter {
+            custom_fields: vec![CustomFieldCondition {
+                field_id: soldier_field,
+                condition: TextCondition::new(TextOperator::IsEmpty, ""),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(db.persons().count_matching(&filter).unwrap(), 1);
+
+        // Tomt värde tar bort fältet
+        db.custom_fields()
+            .set_values(per_id, &HashMap::from([(soldier_field, String::new())]))
+            .unwrap();
+        assert_eq!(db.custom_fields().count_values(soldier_field).unwrap(), 0);
+
+        // Borttagen person tar med sig sina värden
+        db.persons().delete(per_id).unwrap();
+        assert_eq!(db.custom_fields().count_values(regiment_field).unwrap(), 0);
+    }
+}
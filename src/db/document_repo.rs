@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use rusqlite::{params, Connection, Row};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use crate::models::{Document, DocumentType};
@@ -141,6 +142,27 @@ impl DocumentRepository {
         Ok(docs)
     }
 
+    /// Hämta dokument för alla personer i en fråga (person_id -> dokument)
+    pub fn find_all_by_person(&self) -> Result<HashMap<i64, Vec<Document>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, person_id, document_type_id, filename, relative_path,
+                    file_size, file_type, file_modified_at, created_at, updated_at
+             FROM documents
+             ORDER BY document_type_id, filename"
+        )?;
+
+        let mut map: HashMap<i64, Vec<Document>> = HashMap::new();
+        let docs = stmt
+            .query_map([], |row| Ok(Self::row_to_document(row)))?
+            .filter_map(|r| r.ok());
+        for doc in docs {
+            map.entry(doc.person_id).or_default().push(doc);
+        }
+
+        Ok(map)
+    }
+
     /// Hämta dokument via ID
     pub fn find_by_id(&self, id: i64) -> Result<Option<Document>> {
         let conn = self.conn.lock().unwrap();
@@ -0,0 +1,182 @@
+//! Släktgraf i minnet
+//!
+//! Trädvyer och förfädersberäkningar går igenom hundratals personer. I stället
+//! för en fråga per relation och person läses alla personer och relationer in
+//! med två frågor och hålls i minnet tills något skrivs.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::models::{Person, PersonRelationship, RelationshipType};
+
+/// Alla personer och deras relationer, indexerade på person-ID
+#[derive(Debug, Default)]
+pub struct KinshipGraph {
+    persons: HashMap<i64, Person>,
+    parents: HashMap<i64, Vec<i64>>,
+    children: HashMap<i64, Vec<i64>>,
+    spouses: HashMap<i64, Vec<i64>>,
+    siblings: HashMap<i64, Vec<i64>>,
+}
+
+impl KinshipGraph {
+    /// Bygg grafen från personer och relationer. Relationer till personer
+    /// som saknas hoppas över.
+    pub fn new(persons: Vec<Person>, relationships: &[PersonRelationship]) -> Self {
+        let mut graph = Self {
+            persons: persons.into_iter().filter_map(|p| Some((p.id?, p))).collect(),
+            ..Default::default()
+        };
+
+        for rel in relationships {
+            if !graph.persons.contains_key(&rel.person_a_id) || !graph.persons.contains_key(&rel.person_b_id) {
+                continue;
+            }
+            // relationship_a_to_b = vad person_a är för person_b
+            graph.add(rel.person_b_id, rel.relationship_a_to_b, rel.person_a_id);
+            graph.add(rel.person_a_id, rel.relationship_b_to_a, rel.person_b_id);
+        }
+
+        for list in [&mut graph.parents, &mut graph.children, &mut graph.spouses, &mut graph.siblings] {
+            for ids in list.values_mut() {
+                ids.sort();
+                ids.dedup();
+            }
+        }
+
+        graph
+    }
+
+    /// `other` är `relation` till `person`
+    fn add(&mut self, person: i64, relation: RelationshipType, other: i64) {
+        let list = match relation {
+            RelationshipType::Parent => &mut self.parents,
+            RelationshipType::Child => &mut self.children,
+            RelationshipType::Spouse => &mut self.spouses,
+            RelationshipType::Sibling => &mut self.siblings,
+        };
+        list.entry(person).or_default().push(other);
+    }
+
+    pub fn person(&self, id: i64) -> Option<&Person> {
+        self.persons.get(&id)
+    }
+
+    pub fn person_count(&self) -> usize {
+        self.persons.len()
+    }
+
+    pub fn parents(&self, id: i64) -> &[i64] {
+        self.parents.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn children(&self, id: i64) -> &[i64] {
+        self.children.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn spouses(&self, id: i64) -> &[i64] {
+        self.spouses.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn siblings(&self, id: i64) -> &[i64] {
+        self.siblings.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Personens familjer: barnen grupperade på den andra föräldern, med
+    /// makar först (även barnlösa) och sedan övriga. Barnen sorteras på
+    /// födelsedatum.
+    pub fn families(&self, id: i64) -> Vec<(Option<i64>, Vec<i64>)> {
+        let mut families: Vec<(Option<i64>, Vec<i64>)> = self.spouses(id).iter().map(|s| (Some(*s), Vec::new())).collect();
+        for child_id in self.children(id) {
+            let other = self.parents(*child_id).iter().copied().find(|p| *p != id);
+            match families.iter_mut().find(|(spouse, _)| *spouse == other) {
+                Some((_, children)) => children.push(*child_id),
+                None => families.push((other, vec![*child_id])),
+            }
+        }
+        for (_, children) in &mut families {
+            children.sort_by_key(|c| (self.person(*c).and_then(|p| p.birth_date), *c));
+        }
+        families
+    }
+}
+
+/// Delad cache för släktgrafen. Repositories som skriver personer eller
+/// relationer tömmer den.
+#[derive(Debug, Clone, Default)]
+pub struct KinshipCache(Arc<Mutex<CacheState>>);
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// Räknas upp vid varje ändring
+    revision: u64,
+    graph: Option<Arc<KinshipGraph>>,
+}
+
+impl KinshipCache {
+    pub fn get(&self) -> Option<Arc<KinshipGraph>> {
+        self.0.lock().unwrap().graph.clone()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.0.lock().unwrap().revision
+    }
+
+    /// Spara en graf som lästes in vid `revision`. Har något skrivits
+    /// under tiden kastas den, så att cachen aldrig blir inaktuell.
+    pub fn set(&self, graph: Arc<KinshipGraph>, revision: u64) {
+        let mut state = self.0.lock().unwrap();
+        if state.revision == revision {
+            state.graph = Some(graph);
+        }
+    }
+
+    pub fn invalidate(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.revision += 1;
+        state.graph = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::Database;
+    use crate::models::{Person, PersonRelationship, RelationshipType};
+
+    #[test]
+    fn test_graph_is_cached_until_write() {
+        let db = Database::open_in_memory().unwrap();
+        let mut ids = Vec::new();
+        for name in ["Far", "Barn", "Mor"] {
+            let mut p = Person::new(Some(name.into()), None, name.to_lowercase());
+            db.persons().create(&mut p).unwrap();
+            ids.push(p.id.unwrap());
+        }
+        let mut rel = PersonRelationship::new(ids[0], ids[1], RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+
+        let graph = db.kinship().unwrap();
+        assert_eq!(graph.person_count(), 3);
+        assert_eq!(graph.parents(ids[1]), &[ids[0]]);
+        assert_eq!(graph.children(ids[0]), &[ids[1]]);
+        assert!(graph.parents(ids[0]).is_empty());
+
+        // Samma graf återanvänds, även via en klon av databasen
+        assert!(std::sync::Arc::ptr_eq(&graph, &db.clone().kinship().unwrap()));
+
+        let mut rel = PersonRelationship::new(ids[2], ids[1], RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+        let graph = db.kinship().unwrap();
+        assert_eq!(graph.parents(ids[1]), &[ids[0], ids[2]]);
+
+        let mut mother = graph.person(ids[2]).unwrap().clone();
+        mother.surname = Some("Berg".into());
+        db.persons().update(&mut mother).unwrap();
+        assert_eq!(db.kinship().unwrap().person(ids[2]).unwrap().full_name(), "Mor Berg");
+
+        db.persons().delete(ids[0]).unwrap();
+        let graph = db.kinship().unwrap();
+        assert!(graph.person(ids[0]).is_none());
+        assert_eq!(graph.parents(ids[1]), &[ids[2]]);
+    }
+}
@@ -122,6 +122,13 @@ fn migrate_from(conn: &Connection, from_version: i32) -> Result<()> {
             7 => migrate_v6_to_v7(conn)?,
             8 => migrate_v7_to_v8(conn)?,
             9 => migrate_v8_to_v9(conn)?,
+            10 => migrate_v9_to_v10(conn)?,
+            11 => migrate_v10_to_v11(conn)?,
+            12 => migrate_v11_to_v12(conn)?,
+            13 => migrate_v12_to_v13(conn)?,
+            14 => migrate_v13_to_v14(conn)?,
+            15 => migrate_v14_to_v15(conn)?,
+            16 => migrate_v15_to_v16(conn)?,
             _ => {}
         }
 
@@ -296,6 +303,139 @@ fn migrate_v8_to_v9(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+/// Migration v9 -> v10: Lägg till sparade sökningar
+fn migrate_v9_to_v10(conn: &Connection) -> Result<()> {
+    info!("Migration v10: Lägger till saved_searches");
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS saved_searches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            filter_json TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );"
+    )?;
+
+    Ok(())
+}
+
+/// Migration v10 -> v11: Lägg till taggar för personer
+fn migrate_v10_to_v11(conn: &Connection) -> Result<()> {
+    info!("Migration v11: Lägger till tags och person_tags");
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            color TEXT NOT NULL DEFAULT '#4A90D9',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS person_tags (
+            person_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (person_id, tag_id),
+            FOREIGN KEY (person_id) REFERENCES persons(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_person_tags_tag ON person_tags(tag_id);"
+    )?;
+
+    Ok(())
+}
+
+/// Migration v11 -> v12: Sekretess per person och gräns för levande personer
+fn migrate_v11_to_v12(conn: &Connection) -> Result<()> {
+    info!("Migration v12: Lägger till privacy i persons och living_threshold_years i system_config");
+
+    conn.execute_batch(
+        "ALTER TABLE persons ADD COLUMN privacy TEXT NOT NULL DEFAULT 'auto';
+         ALTER TABLE system_config ADD COLUMN living_threshold_years INTEGER NOT NULL DEFAULT 100;"
+    )?;
+
+    Ok(())
+}
+
+/// Migration v12 -> v13: Egna fält för personer och resurser.
+/// Värden kopplas till posten via entity_id, så triggers städar bort dem när posten tas bort.
+fn migrate_v12_to_v13(conn: &Connection) -> Result<()> {
+    info!("Migration v13: Lägger till custom_field_definitions och custom_field_values");
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS custom_field_definitions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity TEXT NOT NULL,
+            name TEXT NOT NULL,
+            field_type TEXT NOT NULL DEFAULT 'text',
+            options TEXT NOT NULL DEFAULT '',
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE (entity, name)
+        );
+        CREATE TABLE IF NOT EXISTS custom_field_values (
+            field_id INTEGER NOT NULL,
+            entity_id INTEGER NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (field_id, entity_id),
+            FOREIGN KEY (field_id) REFERENCES custom_field_definitions(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_custom_field_values_entity ON custom_field_values(entity_id);
+        CREATE TRIGGER IF NOT EXISTS trg_persons_delete_custom_values AFTER DELETE ON persons
+        BEGIN
+            DELETE FROM custom_field_values WHERE entity_id = OLD.id
+                AND field_id IN (SELECT id FROM custom_field_definitions WHERE entity = 'person');
+        END;
+        CREATE TRIGGER IF NOT EXISTS trg_resources_delete_custom_values AFTER DELETE ON resources
+        BEGIN
+            DELETE FROM custom_field_values WHERE entity_id = OLD.id
+                AND field_id IN (SELECT id FROM custom_field_definitions WHERE entity = 'resource');
+        END;"
+    )?;
+
+    Ok(())
+}
+
+/// Migration v13 -> v14: Kön för personer (behövs för antavlor)
+fn migrate_v13_to_v14(conn: &Connection) -> Result<()> {
+    info!("Migration v14: Lägger till sex i persons");
+
+    conn.execute_batch("ALTER TABLE persons ADD COLUMN sex TEXT NOT NULL DEFAULT 'U';")?;
+
+    Ok(())
+}
+
+/// Migration v14 -> v15: Vigseldatum och vigselort för makar (behövs för tidslinjen)
+fn migrate_v14_to_v15(conn: &Connection) -> Result<()> {
+    info!("Migration v15: Lägger till marriage_date och marriage_place i person_relationships");
+
+    conn.execute_batch(
+        "ALTER TABLE person_relationships ADD COLUMN marriage_date TEXT;
+         ALTER TABLE person_relationships ADD COLUMN marriage_place TEXT;"
+    )?;
+
+    Ok(())
+}
+
+/// Migration v15 -> v16: Egna rapportmallar
+fn migrate_v15_to_v16(conn: &Connection) -> Result<()> {
+    info!("Migration v16: Lägger till report_templates");
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS report_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            description TEXT,
+            output_format TEXT NOT NULL DEFAULT 'markdown',
+            body TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );"
+    )?;
+
+    Ok(())
+}
+
 /// Migration v7 -> v8: Lägg till occupation i persons
 fn migrate_v7_to_v8(conn: &Connection) -> Result<()> {
     info!("Migration v8: Lägger till occupation i persons");
@@ -6,22 +6,51 @@ pub mod relationship_repo;
 pub mod config_repo;
 pub mod checklist_repo;
 pub mod resource_repo;
+pub mod saved_search_repo;
+pub mod report_template_repo;
+pub mod tag_repo;
+pub mod custom_field_repo;
+pub mod kinship;
 
 use anyhow::Result;
+use rusqlite::functions::FunctionFlags;
 use rusqlite::Connection;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-pub use person_repo::{PersonRepository, SearchField, SearchFilter};
+pub use person_repo::{
+    CustomFieldCondition, PersonRepository, SearchField, SearchFilter, TextCondition, TextOperator,
+};
 pub use document_repo::DocumentRepository;
 pub use relationship_repo::RelationshipRepository;
 pub use config_repo::ConfigRepository;
 pub use checklist_repo::ChecklistRepository;
 pub use resource_repo::ResourceRepository;
+pub use saved_search_repo::{SavedSearch, SavedSearchRepository};
+pub use report_template_repo::{ReportTemplate, ReportTemplateRepository, TemplateFormat};
+pub use tag_repo::TagRepository;
+pub use custom_field_repo::CustomFieldRepository;
+pub use kinship::{KinshipCache, KinshipGraph};
+
+/// Registrera egna SQL-funktioner på en ny anslutning.
+///
+/// `unicode_lower` gör gemener av all Unicode-text; SQLites inbyggda
+/// `LOWER` och `LIKE` hanterar bara ASCII, så Å och å skulle annars skiljas åt.
+fn register_functions(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "unicode_lower",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|text| text.to_lowercase())),
+    )?;
+    Ok(())
+}
 
 /// Huvuddatabas-wrapper med thread-safe access
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// Släktgraf i minnet, delas mellan kloner av databasen
+    kinship: KinshipCache,
 }
 
 impl Database {
@@ -43,9 +72,11 @@ impl Database {
             PRAGMA busy_timeout = 5000;
             "
         )?;
+        register_functions(&conn)?;
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            kinship: KinshipCache::default(),
         })
     }
 
@@ -53,9 +84,11 @@ impl Database {
     pub fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
         conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        register_functions(&conn)?;
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            kinship: KinshipCache::default(),
         };
         db.migrate()?;
         Ok(db)
@@ -69,7 +102,7 @@ impl Database {
 
     /// Hämta person-repository
     pub fn persons(&self) -> PersonRepository {
-        PersonRepository::new(Arc::clone(&self.conn))
+        PersonRepository::new(Arc::clone(&self.conn), self.kinship.clone())
     }
 
     /// Hämta dokument-repository
@@ -79,7 +112,7 @@ impl Database {
 
     /// Hämta relations-repository
     pub fn relationships(&self) -> RelationshipRepository {
-        RelationshipRepository::new(Arc::clone(&self.conn))
+        RelationshipRepository::new(Arc::clone(&self.conn), self.kinship.clone())
     }
 
     /// Hämta config-repository
@@ -97,13 +130,57 @@ impl Database {
         ResourceRepository::new(Arc::clone(&self.conn))
     }
 
-    /// Direkt tillgång till connection (för avancerade operationer)
+    /// Hämta repository för sparade sökningar
+    pub fn saved_searches(&self) -> SavedSearchRepository {
+        SavedSearchRepository::new(Arc::clone(&self.conn))
+    }
+
+    /// Hämta repository för egna rapportmallar
+    pub fn report_templates(&self) -> ReportTemplateRepository {
+        ReportTemplateRepository::new(Arc::clone(&self.conn))
+    }
+
+    /// Hämta tagg-repository
+    pub fn tags(&self) -> TagRepository {
+        TagRepository::new(Arc::clone(&self.conn))
+    }
+
+    /// Hämta repository för egna fält
+    pub fn custom_fields(&self) -> CustomFieldRepository {
+        CustomFieldRepository::new(Arc::clone(&self.conn))
+    }
+
+    /// Hämta släktgrafen. Läses in med två frågor första gången och
+    /// återanvänds tills en person eller relation ändras.
+    pub fn kinship(&self) -> Result<Arc<KinshipGraph>> {
+        if let Some(graph) = self.kinship.get() {
+            return Ok(graph);
+        }
+
+        let revision = self.kinship.revision();
+        let graph = Arc::new(KinshipGraph::new(
+            self.persons().find_all()?,
+            &self.relationships().find_all()?,
+        ));
+        self.kinship.set(Arc::clone(&graph), revision);
+        Ok(graph)
+    }
+
+    /// Töm släktgrafen, t.ex. efter att databasfilen ersatts
+    pub fn invalidate_kinship(&self) {
+        self.kinship.invalidate();
+    }
+
+    /// Direkt tillgång till connection (för avancerade operationer).
+    /// Släktgrafen töms eftersom anropet kan ändra vad som helst.
     pub fn with_connection<F, T>(&self, f: F) -> Result<T>
     where
         F: FnOnce(&Connection) -> Result<T>,
     {
         let conn = self.conn.lock().unwrap();
-        f(&conn)
+        let result = f(&conn);
+        self.kinship.invalidate();
+        result
     }
 }
 
@@ -111,6 +188,7 @@ impl Clone for Database {
     fn clone(&self) -> Self {
         Self {
             conn: Arc::clone(&self.conn),
+            kinship: self.kinship.clone(),
         }
     }
 }
@@ -1,12 +1,14 @@
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
-use crate::models::Person;
+use super::kinship::KinshipCache;
+use crate::models::{Person, Privacy, Sex};
 
 /// Vilket fält som sökningen gäller
-#[derive(Default, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SearchField {
     /// Kombinerat förnamn + efternamn
     #[default]
@@ -23,8 +25,135 @@ pub enum SearchField {
     GedcomId,
 }
 
-/// Avancerade sökfilter för personlistan
-#[derive(Default, Clone)]
+/// Jämförelseoperator för textvillkor
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextOperator {
+    /// Innehåller värdet
+    #[default]
+    Contains,
+    /// Innehåller inte värdet (tomma fält räknas som träff)
+    NotContains,
+    /// Exakt lika (skiftlägesokänsligt)
+    Equals,
+    /// Börjar med värdet
+    StartsWith,
+    /// Fältet saknar värde
+    IsEmpty,
+    /// Fältet har ett värde
+    IsNotEmpty,
+}
+
+impl TextOperator {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TextOperator::Contains => "innehåller",
+            TextOperator::NotContains => "innehåller inte",
+            TextOperator::Equals => "är lika med",
+            TextOperator::StartsWith => "börjar med",
+            TextOperator::IsEmpty => "saknas",
+            TextOperator::IsNotEmpty => "finns",
+        }
+    }
+
+    pub fn all() -> &'static [TextOperator] {
+        &[
+            TextOperator::Contains,
+            TextOperator::NotContains,
+            TextOperator::Equals,
+            TextOperator::StartsWith,
+            TextOperator::IsEmpty,
+            TextOperator::IsNotEmpty,
+        ]
+    }
+
+    /// Kräver operatorn ett jämförelsevärde?
+    pub fn needs_value(&self) -> bool {
+        !matches!(self, TextOperator::IsEmpty | TextOperator::IsNotEmpty)
+    }
+}
+
+/// Villkor på ett textfält, t.ex. "yrke innehåller soldat"
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextCondition {
+    pub operator: TextOperator,
+    pub value: String,
+}
+
+impl TextCondition {
+    pub fn new(operator: TextOperator, value: impl Into<String>) -> Self {
+        Self {
+            operator,
+            value: value.into(),
+        }
+    }
+
+    /// Villkor som saknar värde trots att operatorn kräver det ignoreras
+    pub fn is_active(&self) -> bool {
+        !self.operator.needs_value() || !self.value.trim().is_empty()
+    }
+
+    /// Bygg SQL-villkor för en kolumn. Returnerar villkoret och eventuell parameter.
+    fn to_sql(&self, column: &str, param_index: usize) -> (String, Option<String>) {
+        let value = self.value.trim();
+        match self.operator {
+            TextOperator::Contains => (
+                format!(" AND {}", like_sql(column, param_index)),
+                Some(like_pattern("%", value, "%")),
+            ),
+            TextOperator::NotContains => (
+                format!(
+                    " AND ({0} IS NULL OR NOT {1})",
+                    column,
+                    like_sql(column, param_index)
+                ),
+                Some(like_pattern("%", value, "%")),
+            ),
+            TextOperator::Equals => (
+                format!(" AND {}", like_sql(column, param_index)),
+                Some(like_pattern("", value, "")),
+            ),
+            TextOperator::StartsWith => (
+                format!(" AND {}", like_sql(column, param_index)),
+                Some(like_pattern("", value, "%")),
+            ),
+            TextOperator::IsEmpty => (format!(" AND ({0} IS NULL OR TRIM({0}) = '')", column), None),
+            TextOperator::IsNotEmpty => (format!(" AND ({0} IS NOT NULL AND TRIM({0}) != '')", column), None),
+        }
+    }
+}
+
+/// Skiftlägesokänslig LIKE-jämförelse mot en kolumn, även för å/ä/ö.
+/// Mönstret ska byggas med [`like_pattern`].
+fn like_sql(column: &str, param_index: usize) -> String {
+    format!("unicode_lower({}) LIKE ?{} ESCAPE '\\'", column, param_index)
+}
+
+/// Bygg ett LIKE-mönster av användartext: jokertecknen `%` och `_` samt
+/// escape-tecknet `\` matchas bokstavligt och texten görs till gemener.
+fn like_pattern(prefix: &str, value: &str, suffix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + value.len() + suffix.len());
+    pattern.push_str(prefix);
+    for c in value.to_lowercase().chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push_str(suffix);
+    pattern
+}
+
+/// Villkor på ett eget fält, t.ex. "Regemente är lika med Upplands"
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomFieldCondition {
+    pub field_id: i64,
+    pub condition: TextCondition,
+}
+
+/// Avancerade sökfilter för personlistan.
+/// Serialiseras till JSON när den sparas som sparad sökning.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SearchFilter {
     /// Fritextsökning
     pub query: String,
@@ -48,6 +177,18 @@ pub struct SearchFilter {
     pub has_profile_image: Option<bool>,
     /// Endast bokmärkta
     pub only_bookmarked: bool,
+    /// Villkor på yrke
+    pub occupation: Option<TextCondition>,
+    /// Villkor på födelseort
+    pub birth_place: Option<TextCondition>,
+    /// Villkor på efternamn
+    pub surname: Option<TextCondition>,
+    /// Har ej avklarade checklistpunkter
+    pub has_open_checklist_items: Option<bool>,
+    /// Har alla dessa taggar
+    pub tag_ids: Vec<i64>,
+    /// Villkor på egna fält (alla måste uppfyllas)
+    pub custom_fields: Vec<CustomFieldCondition>,
 }
 
 impl SearchFilter {
@@ -65,6 +206,12 @@ impl SearchFilter {
             || self.has_documents.is_some()
             || self.has_profile_image.is_some()
             || self.only_bookmarked
+            || self.occupation.as_ref().is_some_and(|c| c.is_active())
+            || self.birth_place.as_ref().is_some_and(|c| c.is_active())
+            || self.surname.as_ref().is_some_and(|c| c.is_active())
+            || self.has_open_checklist_items.is_some()
+            || !self.tag_ids.is_empty()
+            || self.custom_fields.iter().any(|c| c.condition.is_active())
     }
 
     /// Återställ alla filter
@@ -82,16 +229,23 @@ impl SearchFilter {
         self.has_documents = None;
         self.has_profile_image = None;
         self.only_bookmarked = false;
+        self.occupation = None;
+        self.birth_place = None;
+        self.surname = None;
+        self.has_open_checklist_items = None;
+        self.tag_ids.clear();
+        self.custom_fields.clear();
     }
 }
 
 pub struct PersonRepository {
     conn: Arc<Mutex<Connection>>,
+    kinship: KinshipCache,
 }
 
 impl PersonRepository {
-    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
-        Self { conn }
+    pub fn new(conn: Arc<Mutex<Connection>>, kinship: KinshipCache) -> Self {
+        Self { conn, kinship }
     }
 
     /// Hämta alla personer
@@ -99,7 +253,8 @@ impl PersonRepository {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             "SELECT id, firstname, surname, birth_place, birth_date, death_date, age,
-                    occupation, gedcom_id, directory_name, profile_image_path, created_at, updated_at
+                    occupation, gedcom_id, directory_name, profile_image_path, created_at, updated_at,
+                    notes, privacy, sex
              FROM persons
              ORDER BY surname, firstname"
         )?;
@@ -117,7 +272,8 @@ impl PersonRepository {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             "SELECT id, firstname, surname, birth_place, birth_date, death_date, age,
-                    occupation, gedcom_id, directory_name, profile_image_path, created_at, updated_at
+                    occupation, gedcom_id, directory_name, profile_image_path, created_at, updated_at,
+                    notes, privacy, sex
              FROM persons
              WHERE id = ?"
         )?;
@@ -134,7 +290,8 @@ impl PersonRepository {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             "SELECT id, firstname, surname, birth_place, birth_date, death_date, age,
-                    occupation, gedcom_id, directory_name, profile_image_path, created_at, updated_at
+                    occupation, gedcom_id, directory_name, profile_image_path, created_at, updated_at,
+                    notes, privacy, sex
              FROM persons
              WHERE directory_name = ?"
         )?;
@@ -162,10 +319,52 @@ impl PersonRepository {
 
         let mut sql = String::from(
             "SELECT DISTINCT p.id, p.firstname, p.surname, p.birth_place, p.birth_date, p.death_date, p.age,
-                    p.occupation, p.gedcom_id, p.directory_name, p.profile_image_path, p.created_at, p.updated_at
+                    p.occupation, p.gedcom_id, p.directory_name, p.profile_image_path, p.created_at, p.updated_at,
+                    p.notes, p.privacy, p.sex
              FROM persons p"
         );
+        let mut params_vec: Vec<String> = Vec::new();
+        Self::push_filter_sql(filter, &mut sql, &mut params_vec);
+
+        sql.push_str(" ORDER BY p.surname, p.firstname");
+
+        let mut stmt = conn.prepare(&sql)?;
+
+        // Bind parameters dynamiskt
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec
+            .iter()
+            .map(|s| s as &dyn rusqlite::ToSql)
+            .collect();
+
+        let persons: Vec<Person> = stmt
+            .query_map(rusqlite::params_from_iter(params_refs), |row| {
+                Ok(Self::row_to_person(row))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(persons)
+    }
+
+    /// Räkna personer som matchar ett filter (för sparade sökningar)
+    pub fn count_matching(&self, filter: &SearchFilter) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut sql = String::from("SELECT COUNT(DISTINCT p.id) FROM persons p");
+        let mut params_vec: Vec<String> = Vec::new();
+        Self::push_filter_sql(filter, &mut sql, &mut params_vec);
+
+        let count: i64 = conn.query_row(
+            &sql,
+            rusqlite::params_from_iter(params_vec.iter()),
+            |row| row.get(0),
+        )?;
 
+        Ok(count)
+    }
+
+    /// Lägg till joins och WHERE-villkor för ett sökfilter
+    fn push_filter_sql(filter: &SearchFilter, sql: &mut String, params_vec: &mut Vec<String>) {
         // Join för bokmärken om filtret är aktivt
         if filter.only_bookmarked {
             sql.push_str(" INNER JOIN bookmarked_persons bp ON p.id = bp.person_id");
@@ -173,8 +372,7 @@ impl PersonRepository {
 
         sql.push_str(" WHERE 1=1");
 
-        let mut param_index = 1;
-        let mut params_vec: Vec<String> = Vec::new();
+        let mut param_index = params_vec.len() + 1;
 
         // Fritextsökning
         if !filter.query.is_empty() {
@@ -184,36 +382,37 @@ impl PersonRepository {
                     let words: Vec<&str> = filter.query.split_whitespace().collect();
                     for word in &words {
                         sql.push_str(&format!(
-                            " AND (p.firstname LIKE ?{0} OR p.surname LIKE ?{0})",
-                            param_index
+                            " AND ({} OR {})",
+                            like_sql("p.firstname", param_index),
+                            like_sql("p.surname", param_index)
                         ));
-                        params_vec.push(format!("%{}%", word));
+                        params_vec.push(like_pattern("%", word, "%"));
                         param_index += 1;
                     }
                 }
                 SearchField::Firstname => {
-                    sql.push_str(&format!(" AND p.firstname LIKE ?{}", param_index));
-                    params_vec.push(format!("%{}%", filter.query));
+                    sql.push_str(&format!(" AND {}", like_sql("p.firstname", param_index)));
+                    params_vec.push(like_pattern("%", &filter.query, "%"));
                     param_index += 1;
                 }
                 SearchField::Surname => {
-                    sql.push_str(&format!(" AND p.surname LIKE ?{}", param_index));
-                    params_vec.push(format!("%{}%", filter.query));
+                    sql.push_str(&format!(" AND {}", like_sql("p.surname", param_index)));
+                    params_vec.push(like_pattern("%", &filter.query, "%"));
                     param_index += 1;
                 }
                 SearchField::Directory => {
-                    sql.push_str(&format!(" AND p.directory_name LIKE ?{}", param_index));
-                    params_vec.push(format!("%{}%", filter.query));
+                    sql.push_str(&format!(" AND {}", like_sql("p.directory_name", param_index)));
+                    params_vec.push(like_pattern("%", &filter.query, "%"));
                     param_index += 1;
                 }
                 SearchField::BirthPlace => {
-                    sql.push_str(&format!(" AND p.birth_place LIKE ?{}", param_index));
-                    params_vec.push(format!("%{}%", filter.query));
+                    sql.push_str(&format!(" AND {}", like_sql("p.birth_place", param_index)));
+                    params_vec.push(like_pattern("%", &filter.query, "%"));
                     param_index += 1;
                 }
                 SearchField::GedcomId => {
-                    sql.push_str(&format!(" AND p.gedcom_id LIKE ?{}", param_index));
-                    params_vec.push(format!("%{}%", filter.query));
+                    sql.push_str(&format!(" AND {}", like_sql("p.gedcom_id", param_index)));
+                    params_vec.push(like_pattern("%", &filter.query, "%"));
                     param_index += 1;
                 }
             }
@@ -253,6 +452,7 @@ impl PersonRepository {
         if let Some(date) = filter.death_before {
             sql.push_str(&format!(" AND p.death_date <= ?{}", param_index));
             params_vec.push(date.to_string());
+            param_index += 1;
         }
 
         // Har relationer
@@ -286,36 +486,77 @@ impl PersonRepository {
             }
         }
 
-        sql.push_str(" ORDER BY p.surname, p.firstname");
-
-        let mut stmt = conn.prepare(&sql)?;
+        // Textvillkor på yrke, födelseort och efternamn
+        let conditions = [
+            ("p.occupation", &filter.occupation),
+            ("p.birth_place", &filter.birth_place),
+            ("p.surname", &filter.surname),
+        ];
+        for (column, condition) in conditions {
+            let Some(condition) = condition.as_ref().filter(|c| c.is_active()) else {
+                continue;
+            };
+            let (clause, param) = condition.to_sql(column, param_index);
+            sql.push_str(&clause);
+            if let Some(param) = param {
+                params_vec.push(param);
+                param_index += 1;
+            }
+        }
 
-        // Bind parameters dynamiskt
-        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec
-            .iter()
-            .map(|s| s as &dyn rusqlite::ToSql)
-            .collect();
+        // Har öppna checklistpunkter
+        if let Some(has_open) = filter.has_open_checklist_items {
+            let exists = "EXISTS (SELECT 1 FROM person_checklist_items WHERE person_id = p.id AND is_completed = 0)";
+            if has_open {
+                sql.push_str(&format!(" AND {}", exists));
+            } else {
+                sql.push_str(&format!(" AND NOT {}", exists));
+            }
+        }
 
-        let persons: Vec<Person> = stmt
-            .query_map(rusqlite::params_from_iter(params_refs), |row| {
-                Ok(Self::row_to_person(row))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+        // Taggar (personen måste ha alla valda)
+        for tag_id in &filter.tag_ids {
+            sql.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM person_tags WHERE person_id = p.id AND tag_id = ?{})",
+                param_index
+            ));
+            params_vec.push(tag_id.to_string());
+            param_index += 1;
+        }
 
-        Ok(persons)
+        // Egna fält (fält-ID är ett heltal och kan byggas in i SQL direkt)
+        for custom in filter.custom_fields.iter().filter(|c| c.condition.is_active()) {
+            let column = format!(
+                "(SELECT value FROM custom_field_values WHERE field_id = {} AND entity_id = p.id)",
+                custom.field_id
+            );
+            let (clause, param) = custom.condition.to_sql(&column, param_index);
+            sql.push_str(&clause);
+            if let Some(param) = param {
+                params_vec.push(param);
+                param_index += 1;
+            }
+        }
     }
 
     /// Skapa ny person
     pub fn create(&self, person: &mut Person) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let id = Self::insert(&conn, person)?;
+        self.kinship.invalidate();
+        Ok(id)
+    }
+
+    /// Skapa ny person på en given anslutning, t.ex. inuti en transaktion
+    /// via `Database::with_connection`
+    pub(crate) fn insert(conn: &Connection, person: &mut Person) -> Result<i64> {
         person.validate()?;
         person.calculate_age();
 
-        let conn = self.conn.lock().unwrap();
         conn.execute(
             "INSERT INTO persons (firstname, surname, birth_place, birth_date, death_date, age,
-                                  occupation, gedcom_id, directory_name, profile_image_path)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                                  occupation, gedcom_id, directory_name, profile_image_path, notes, privacy, sex)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             params![
                 person.firstname,
                 person.surname,
@@ -327,6 +568,9 @@ impl PersonRepository {
                 person.gedcom_id,
                 person.directory_name,
                 person.profile_image_path,
+                person.notes,
+                person.privacy.as_db_str(),
+                person.sex.as_db_str(),
             ],
         )?;
 
@@ -347,8 +591,8 @@ impl PersonRepository {
             "UPDATE persons SET
                 firstname = ?1, surname = ?2, birth_place = ?3, birth_date = ?4, death_date = ?5,
                 age = ?6, occupation = ?7, gedcom_id = ?8, directory_name = ?9, profile_image_path = ?10,
-                updated_at = datetime('now')
-             WHERE id = ?11",
+                notes = ?11, privacy = ?12, sex = ?13, updated_at = datetime('now')
+             WHERE id = ?14",
             params![
                 person.firstname,
                 person.surname,
@@ -360,9 +604,13 @@ impl PersonRepository {
                 person.gedcom_id,
                 person.directory_name,
                 person.profile_image_path,
+                person.notes,
+                person.privacy.as_db_str(),
+                person.sex.as_db_str(),
                 id,
             ],
         )?;
+        self.kinship.invalidate();
 
         if rows == 0 {
             return Err(anyhow!("Person med ID {} hittades inte", id));
@@ -375,6 +623,7 @@ impl PersonRepository {
     pub fn delete(&self, id: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let rows = conn.execute("DELETE FROM persons WHERE id = ?", [id])?;
+        self.kinship.invalidate();
 
         if rows == 0 {
             return Err(anyhow!("Person med ID {} hittades inte", id));
@@ -435,7 +684,8 @@ impl PersonRepository {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             "SELECT p.id, p.firstname, p.surname, p.birth_place, p.birth_date, p.death_date, p.age,
-                    p.occupation, p.gedcom_id, p.directory_name, p.profile_image_path, p.created_at, p.updated_at
+                    p.occupation, p.gedcom_id, p.directory_name, p.profile_image_path, p.created_at, p.updated_at,
+                    p.notes, p.privacy, p.sex
              FROM persons p
              INNER JOIN bookmarked_persons bp ON p.id = bp.person_id
              ORDER BY p.surname, p.firstname"
@@ -487,6 +737,7 @@ impl PersonRepository {
             "UPDATE persons SET profile_image_path = ?, updated_at = datetime('now') WHERE id = ?",
             params![image_path, person_id],
         )?;
+        self.kinship.invalidate();
 
         if rows == 0 {
             return Err(anyhow!("Person med ID {} hittades inte", person_id));
@@ -518,6 +769,15 @@ impl PersonRepository {
             profile_image_path: row.get(10).ok(),
             created_at: row.get(11).ok(),
             updated_at: row.get(12).ok(),
+            notes: row.get::<_, Option<String>>(13).ok().flatten(),
+            privacy: row
+                .get::<_, String>(14)
+                .map(|s| Privacy::from_db_str(&s))
+                .unwrap_or_default(),
+            sex: row
+                .get::<_, String>(15)
+                .map(|s| Sex::from_db_str(&s))
+                .unwrap_or_default(),
         }
     }
 }
@@ -594,6 +854,50 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[test]
+    fn test_search_escapes_wildcards_and_folds_non_ascii_case() {
+        let db = setup_db();
+        let repo = db.persons();
+
+        let mut asa = Person::new(Some("Åsa".into()), Some("Öberg".into()), "asa_o".into());
+        asa.occupation = Some("Lots 100%".into());
+        let mut anna = Person::new(Some("Anna".into()), Some("Berg".into()), "anna_b".into());
+        anna.occupation = Some("Lots 1000".into());
+        repo.create(&mut asa).unwrap();
+        repo.create(&mut anna).unwrap();
+
+        // Å och å ska räknas som samma bokstav
+        let results = repo.search("åsa öberg", None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].firstname, Some("Åsa".into()));
+
+        // % och _ är vanliga tecken i sökningen, inte jokertecken
+        assert_eq!(repo.search("%", None).unwrap().len(), 0);
+        assert_eq!(repo.search("_", None).unwrap().len(), 0);
+
+        let filter = SearchFilter {
+            occupation: Some(TextCondition::new(TextOperator::Contains, "100%")),
+            ..Default::default()
+        };
+        let results = repo.advanced_search(&filter).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].firstname, Some("Åsa".into()));
+
+        let filter = SearchFilter {
+            surname: Some(TextCondition::new(TextOperator::StartsWith, "ÖB")),
+            ..Default::default()
+        };
+        assert_eq!(repo.advanced_search(&filter).unwrap().len(), 1);
+
+        let filter = SearchFilter {
+            occupation: Some(TextCondition::new(TextOperator::NotContains, "%")),
+            ..Default::default()
+        };
+        let results = repo.advanced_search(&filter).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].firstname, Some("Anna".into()));
+    }
+
     #[test]
     fn test_bookmark() {
         let db = setup_db();
@@ -1,23 +1,27 @@
 use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
 use rusqlite::{params, Connection, Row};
 use std::sync::{Arc, Mutex};
 
+use super::kinship::KinshipCache;
 use crate::models::{PersonRelationship, RelationshipType, RelationshipView};
 
 pub struct RelationshipRepository {
     conn: Arc<Mutex<Connection>>,
+    kinship: KinshipCache,
 }
 
 impl RelationshipRepository {
-    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
-        Self { conn }
+    pub fn new(conn: Arc<Mutex<Connection>>, kinship: KinshipCache) -> Self {
+        Self { conn, kinship }
     }
 
     /// Hämta alla relationer
     pub fn find_all(&self) -> Result<Vec<PersonRelationship>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes, created_at
+            "SELECT id, person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes, created_at,
+                    marriage_date, marriage_place
              FROM person_relationships
              ORDER BY id"
         )?;
@@ -45,7 +49,8 @@ impl RelationshipRepository {
     pub fn find_by_person(&self, person_id: i64) -> Result<Vec<PersonRelationship>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes, created_at
+            "SELECT id, person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes, created_at,
+                    marriage_date, marriage_place
              FROM person_relationships
              WHERE person_a_id = ? OR person_b_id = ?"
         )?;
@@ -140,7 +145,8 @@ impl RelationshipRepository {
         let conn = self.conn.lock().unwrap();
         let result = conn
             .query_row(
-                "SELECT id, person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes, created_at
+                "SELECT id, person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes, created_at,
+                    marriage_date, marriage_place
                  FROM person_relationships WHERE id = ?",
                 [id],
                 |row| Ok(Self::row_to_relationship(row)),
@@ -176,15 +182,27 @@ impl RelationshipRepository {
         }
 
         let conn = self.conn.lock().unwrap();
+        let id = Self::insert(&conn, rel)?;
+        self.kinship.invalidate();
+        Ok(id)
+    }
+
+    /// Skapa relation på en given anslutning, t.ex. inuti en transaktion
+    /// via `Database::with_connection`. Dubbletter avvisas av databasens
+    /// unika nyckel på personparet.
+    pub(crate) fn insert(conn: &Connection, rel: &mut PersonRelationship) -> Result<i64> {
         conn.execute(
-            "INSERT INTO person_relationships (person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO person_relationships (person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a, notes,
+                                               marriage_date, marriage_place)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
                 rel.person_a_id,
                 rel.person_b_id,
                 rel.relationship_a_to_b as i32,
                 rel.relationship_b_to_a as i32,
                 rel.notes,
+                rel.marriage_date.map(|d| d.to_string()),
+                rel.marriage_place,
             ],
         )?;
 
@@ -194,10 +212,23 @@ impl RelationshipRepository {
         Ok(id)
     }
 
+    /// Uppdatera anteckningar och vigseluppgifter (personer och typ ändras inte)
+    pub fn update(&self, rel: &PersonRelationship) -> Result<()> {
+        let id = rel.id.ok_or_else(|| anyhow!("Relation saknar ID"))?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE person_relationships SET notes = ?1, marriage_date = ?2, marriage_place = ?3 WHERE id = ?4",
+            params![rel.notes, rel.marriage_date.map(|d| d.to_string()), rel.marriage_place, id],
+        )?;
+        self.kinship.invalidate();
+        Ok(())
+    }
+
     /// Ta bort relation
     pub fn delete(&self, id: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM person_relationships WHERE id = ?", [id])?;
+        self.kinship.invalidate();
         Ok(())
     }
 
@@ -208,6 +239,7 @@ impl RelationshipRepository {
             "DELETE FROM person_relationships WHERE person_a_id = ? OR person_b_id = ?",
             params![person_id, person_id],
         )?;
+        self.kinship.invalidate();
         Ok(rows)
     }
 
@@ -258,6 +290,12 @@ impl RelationshipRepository {
                 .unwrap_or(RelationshipType::Sibling),
             notes: row.get(5).ok(),
             created_at: row.get(6).ok(),
+            marriage_date: row
+                .get::<_, Option<String>>(7)
+                .ok()
+                .flatten()
+                .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok()),
+            marriage_place: row.get(8).ok().flatten(),
         }
     }
 }
@@ -272,6 +310,42 @@ mod tests {
         Database::open_in_memory().unwrap()
     }
 
+    #[test]
+    fn test_insert_person_and_relationship_in_one_transaction() {
+        use crate::db::PersonRepository;
+
+        let db = setup_db();
+        let mut child = Person::new(Some("Barn".into()), Some("Barnsson".into()), "barn".into());
+        db.persons().create(&mut child).unwrap();
+        let child_id = child.id.unwrap();
+
+        // Relationen pekar på en person som inte finns, så hela transaktionen rullas tillbaka
+        let result = db.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut parent = Person::new(Some("Far".into()), Some("Farsson".into()), "far".into());
+            let parent_id = PersonRepository::insert(&tx, &mut parent)?;
+            let mut rel = PersonRelationship::new(parent_id, 9999, RelationshipType::Parent);
+            RelationshipRepository::insert(&tx, &mut rel)?;
+            tx.commit()?;
+            Ok(parent_id)
+        });
+        assert!(result.is_err());
+        assert_eq!(db.persons().count().unwrap(), 1);
+
+        let parent_id = db
+            .with_connection(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                let mut parent = Person::new(Some("Far".into()), Some("Farsson".into()), "far".into());
+                let parent_id = PersonRepository::insert(&tx, &mut parent)?;
+                let mut rel = PersonRelationship::new(parent_id, child_id, RelationshipType::Parent);
+                RelationshipRepository::insert(&tx, &mut rel)?;
+                tx.commit()?;
+                Ok(parent_id)
+            })
+            .unwrap();
+        assert_eq!(db.kinship().unwrap().parents(child_id), &[parent_id]);
+    }
+
     #[test]
     fn test_create_relationship() {
         let db = setup_db();
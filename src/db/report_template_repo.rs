@@ -0,0 +1,226 @@
+//! Repository för egna rapportmallar
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, Row};
+
+/// Utdataformat för en rapportmall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TemplateFormat {
+    Html,
+    #[default]
+    Markdown,
+    Text,
+}
+
+impl TemplateFormat {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TemplateFormat::Html => "HTML",
+            TemplateFormat::Markdown => "Markdown",
+            TemplateFormat::Text => "Text",
+        }
+    }
+
+    pub fn all() -> &'static [TemplateFormat] {
+        &[TemplateFormat::Html, TemplateFormat::Markdown, TemplateFormat::Text]
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TemplateFormat::Html => "html",
+            TemplateFormat::Markdown => "md",
+            TemplateFormat::Text => "txt",
+        }
+    }
+
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            TemplateFormat::Html => "html",
+            TemplateFormat::Markdown => "markdown",
+            TemplateFormat::Text => "text",
+        }
+    }
+
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "html" => TemplateFormat::Html,
+            "text" => TemplateFormat::Text,
+            _ => TemplateFormat::Markdown,
+        }
+    }
+}
+
+/// En rapportmall som användaren skrivit
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportTemplate {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub format: TemplateFormat,
+    /// Mallens text, se `services::report_template`
+    pub body: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl ReportTemplate {
+    pub fn new(name: String, format: TemplateFormat, body: String) -> Self {
+        Self {
+            id: None,
+            name,
+            description: None,
+            format,
+            body,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+pub struct ReportTemplateRepository {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl ReportTemplateRepository {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Hämta alla mallar sorterade på namn
+    pub fn find_all(&self) -> Result<Vec<ReportTemplate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, output_format, body, created_at, updated_at
+             FROM report_templates
+             ORDER BY name",
+        )?;
+
+        let templates = stmt
+            .query_map([], |row| Ok(Self::row_to_template(row)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(templates)
+    }
+
+    /// Hämta mall via ID
+    pub fn find_by_id(&self, id: i64) -> Result<Option<ReportTemplate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, output_format, body, created_at, updated_at
+             FROM report_templates
+             WHERE id = ?",
+        )?;
+
+        let template = stmt
+            .query_row([id], |row| Ok(Self::row_to_template(row)))
+            .ok();
+
+        Ok(template)
+    }
+
+    /// Spara ny mall
+    pub fn create(&self, template: &mut ReportTemplate) -> Result<i64> {
+        if template.name.trim().is_empty() {
+            return Err(anyhow!("Mallen måste ha ett namn"));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO report_templates (name, description, output_format, body) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                template.name.trim(),
+                template.description,
+                template.format.as_db_str(),
+                template.body
+            ],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        template.id = Some(id);
+
+        Ok(id)
+    }
+
+    /// Uppdatera namn, beskrivning, format och text
+    pub fn update(&self, template: &ReportTemplate) -> Result<()> {
+        let id = template.id.ok_or_else(|| anyhow!("Rapportmallen har inget ID"))?;
+        if template.name.trim().is_empty() {
+            return Err(anyhow!("Mallen måste ha ett namn"));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE report_templates
+             SET name = ?1, description = ?2, output_format = ?3, body = ?4, updated_at = datetime('now')
+             WHERE id = ?5",
+            params![
+                template.name.trim(),
+                template.description,
+                template.format.as_db_str(),
+                template.body,
+                id
+            ],
+        )?;
+
+        if rows == 0 {
+            return Err(anyhow!("Rapportmall med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    /// Ta bort mall
+    pub fn delete(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM report_templates WHERE id = ?", [id])?;
+
+        if rows == 0 {
+            return Err(anyhow!("Rapportmall med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    fn row_to_template(row: &Row) -> ReportTemplate {
+        ReportTemplate {
+            id: row.get(0).ok(),
+            name: row.get(1).unwrap_or_default(),
+            description: row.get(2).ok().flatten(),
+            format: TemplateFormat::from_db_str(&row.get::<_, String>(3).unwrap_or_default()),
+            body: row.get(4).unwrap_or_default(),
+            created_at: row.get(5).ok(),
+            updated_at: row.get(6).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_report_template_crud() {
+        let db = Database::open_in_memory().unwrap();
+        let repo = db.report_templates();
+
+        let mut template = ReportTemplate::new("Föreningen".into(), TemplateFormat::Html, "{{ full_name }}".into());
+        repo.create(&mut template).unwrap();
+        assert!(repo.create(&mut ReportTemplate::new(" ".into(), TemplateFormat::Text, String::new())).is_err());
+
+        template.format = TemplateFormat::Text;
+        template.description = Some("Till medlemsbladet".into());
+        repo.update(&template).unwrap();
+
+        let loaded = repo.find_by_id(template.id.unwrap()).unwrap().unwrap();
+        assert_eq!(loaded.format, TemplateFormat::Text);
+        assert_eq!(loaded.description.as_deref(), Some("Till medlemsbladet"));
+        assert_eq!(repo.find_all().unwrap().len(), 1);
+
+        repo.delete(template.id.unwrap()).unwrap();
+        assert!(repo.find_all().unwrap().is_empty());
+    }
+}
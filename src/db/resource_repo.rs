@@ -131,6 +131,7 @@ impl ResourceRepository {
         Ok(result)
     }
 
+    /// Sök resurser på namn, information och egna fält
     pub fn search(&self, query: &str, type_filter: Option<i64>) -> Result<Vec<(Resource, ResourceType)>> {
         let conn = self.conn.lock().unwrap();
         let pattern = format!("%{}%", query.to_lowercase());
@@ -142,7 +143,10 @@ impl ResourceRepository {
                     rt.id, rt.name, rt.directory_name, rt.created_at, rt.updated_at
              FROM resources r
              JOIN resource_types rt ON r.resource_type_id = rt.id
-             WHERE (LOWER(r.name) LIKE ?1 OR LOWER(r.information) LIKE ?1)
+             WHERE (LOWER(r.name) LIKE ?1 OR LOWER(r.information) LIKE ?1
+                    OR EXISTS (SELECT 1 FROM custom_field_values v
+                                 JOIN custom_field_definitions d ON d.id = v.field_id
+                                 WHERE d.entity = 'resource' AND v.entity_id = r.id AND LOWER(v.value) LIKE ?1))
                AND r.resource_type_id = ?2
              ORDER BY rt.name, r.name"
         } else {
@@ -153,6 +157,9 @@ impl ResourceRepository {
              FROM resources r
              JOIN resource_types rt ON r.resource_type_id = rt.id
              WHERE LOWER(r.name) LIKE ?1 OR LOWER(r.information) LIKE ?1
+                OR EXISTS (SELECT 1 FROM custom_field_values v
+                                 JOIN custom_field_definitions d ON d.id = v.field_id
+                                 WHERE d.entity = 'resource' AND v.entity_id = r.id AND LOWER(v.value) LIKE ?1)
              ORDER BY rt.name, r.name"
         };
 
@@ -0,0 +1,176 @@
+//! Repository för sparade sökningar (smarta grupper)
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection, Row};
+
+use super::person_repo::SearchFilter;
+
+/// En namngiven, sparad sökning
+#[derive(Debug, Clone)]
+pub struct SavedSearch {
+    pub id: Option<i64>,
+    pub name: String,
+    pub filter: SearchFilter,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl SavedSearch {
+    pub fn new(name: String, filter: SearchFilter) -> Self {
+        Self {
+            id: None,
+            name,
+            filter,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+pub struct SavedSearchRepository {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SavedSearchRepository {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Hämta alla sparade sökningar
+    pub fn find_all(&self) -> Result<Vec<SavedSearch>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, filter_json, created_at, updated_at
+             FROM saved_searches
+             ORDER BY name",
+        )?;
+
+        let searches = stmt
+            .query_map([], |row| Ok(Self::row_to_saved_search(row)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(searches)
+    }
+
+    /// Hämta sparad sökning via ID
+    pub fn find_by_id(&self, id: i64) -> Result<Option<SavedSearch>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, filter_json, created_at, updated_at
+             FROM saved_searches
+             WHERE id = ?",
+        )?;
+
+        let search = stmt
+            .query_row([id], |row| Ok(Self::row_to_saved_search(row)))
+            .ok();
+
+        Ok(search)
+    }
+
+    /// Spara ny sökning
+    pub fn create(&self, search: &mut SavedSearch) -> Result<i64> {
+        if search.name.trim().is_empty() {
+            return Err(anyhow!("Sökningen måste ha ett namn"));
+        }
+        let filter_json = serde_json::to_string(&search.filter).context("Kunde inte serialisera filter")?;
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO saved_searches (name, filter_json) VALUES (?1, ?2)",
+            params![search.name.trim(), filter_json],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        search.id = Some(id);
+
+        Ok(id)
+    }
+
+    /// Uppdatera namn och filter
+    pub fn update(&self, search: &SavedSearch) -> Result<()> {
+        let id = search.id.ok_or_else(|| anyhow!("Sparad sökning har inget ID"))?;
+        let filter_json = serde_json::to_string(&search.filter).context("Kunde inte serialisera filter")?;
+
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE saved_searches SET name = ?1, filter_json = ?2, updated_at = datetime('now')
+             WHERE id = ?3",
+            params![search.name.trim(), filter_json, id],
+        )?;
+
+        if rows == 0 {
+            return Err(anyhow!("Sparad sökning med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    /// Ta bort sparad sökning
+    pub fn delete(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM saved_searches WHERE id = ?", [id])?;
+
+        if rows == 0 {
+            return Err(anyhow!("Sparad sökning med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    fn row_to_saved_search(row: &Row) -> SavedSearch {
+        // Okänt eller trasigt filter ger ett tomt filter hellre än att sökningen försvinner
+        let filter = row
+            .get::<_, String>(2)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        SavedSearch {
+            id: row.get(0).ok(),
+            name: row.get(1).unwrap_or_default(),
+            filter,
+            created_at: row.get(3).ok(),
+            updated_at: row.get(4).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::person_repo::{TextCondition, TextOperator};
+    use crate::db::Database;
+    use crate::models::Person;
+
+    #[test]
+    fn test_saved_search_roundtrip_and_count() {
+        let db = Database::open_in_memory().unwrap();
+
+        let mut soldier = Person::new(Some("Per".into()), Some("Svärd".into()), "per".into());
+        soldier.occupation = Some("Soldat".into());
+        db.persons().create(&mut soldier).unwrap();
+        let mut farmer = Person::new(Some("Olof".into()), Some("Berg".into()), "olof".into());
+        farmer.occupation = Some("Bonde".into());
+        db.persons().create(&mut farmer).unwrap();
+
+        let filter = SearchFilter {
+            occupation: Some(TextCondition::new(TextOperator::Contains, "sold")),
+            ..Default::default()
+        };
+        let mut search = SavedSearch::new("Soldater".into(), filter);
+        db.saved_searches().create(&mut search).unwrap();
+
+        let loaded = db.saved_searches().find_by_id(search.id.unwrap()).unwrap().unwrap();
+        assert_eq!(loaded.name, "Soldater");
+        assert_eq!(loaded.filter, search.filter);
+        assert_eq!(db.persons().count_matching(&loaded.filter).unwrap(), 1);
+
+        // Äldre sparade filter utan nya fält ska fortfarande kunna läsas
+        let old: SearchFilter = serde_json::from_str(r#"{"query":"Berg"}"#).unwrap();
+        assert_eq!(db.persons().count_matching(&old).unwrap(), 1);
+    }
+}
@@ -1,7 +1,7 @@
 /// SQL-schema för Genlib Desktop
 /// Kompatibelt med Django-export för migration
 
-pub const SCHEMA_VERSION: i32 = 9;
+pub const SCHEMA_VERSION: i32 = 16;
 
 pub const CREATE_TABLES: &str = r#"
 -- Systeminställningar (singleton, id=1)
@@ -10,6 +10,7 @@ CREATE TABLE IF NOT EXISTS system_config (
     media_directory_path TEXT NOT NULL,
     backup_directory_path TEXT NOT NULL,
     dir_name_format TEXT NOT NULL DEFAULT 'firstname_first',
+    living_threshold_years INTEGER NOT NULL DEFAULT 100,
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
@@ -26,6 +27,8 @@ CREATE TABLE IF NOT EXISTS persons (
     occupation TEXT,
     gedcom_id TEXT,
     notes TEXT,
+    privacy TEXT NOT NULL DEFAULT 'auto',
+    sex TEXT NOT NULL DEFAULT 'U',
     directory_name TEXT NOT NULL UNIQUE,
     profile_image_path TEXT,
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
@@ -45,6 +48,8 @@ CREATE TABLE IF NOT EXISTS person_relationships (
     relationship_a_to_b INTEGER NOT NULL,
     relationship_b_to_a INTEGER NOT NULL,
     notes TEXT,
+    marriage_date TEXT,
+    marriage_place TEXT,
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     FOREIGN KEY (person_a_id) REFERENCES persons(id) ON DELETE CASCADE,
     FOREIGN KEY (person_b_id) REFERENCES persons(id) ON DELETE CASCADE,
@@ -196,6 +201,81 @@ CREATE TABLE IF NOT EXISTS resource_documents (
 );
 
 CREATE INDEX IF NOT EXISTS idx_resource_documents_resource ON resource_documents(resource_id);
+
+-- Sparade sökningar (smarta grupper)
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    filter_json TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Egna rapportmallar
+CREATE TABLE IF NOT EXISTS report_templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    output_format TEXT NOT NULL DEFAULT 'markdown',
+    body TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Taggar
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    color TEXT NOT NULL DEFAULT '#4A90D9',
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Personers taggar
+CREATE TABLE IF NOT EXISTS person_tags (
+    person_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (person_id, tag_id),
+    FOREIGN KEY (person_id) REFERENCES persons(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_person_tags_tag ON person_tags(tag_id);
+
+-- Egna fält (definitioner per posttyp)
+CREATE TABLE IF NOT EXISTS custom_field_definitions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL DEFAULT 'text',
+    options TEXT NOT NULL DEFAULT '',
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (entity, name)
+);
+
+-- Värden för egna fält (entity_id pekar på persons eller resources beroende på fältets entity)
+CREATE TABLE IF NOT EXISTS custom_field_values (
+    field_id INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (field_id, entity_id),
+    FOREIGN KEY (field_id) REFERENCES custom_field_definitions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_custom_field_values_entity ON custom_field_values(entity_id);
+
+CREATE TRIGGER IF NOT EXISTS trg_persons_delete_custom_values AFTER DELETE ON persons
+BEGIN
+    DELETE FROM custom_field_values WHERE entity_id = OLD.id
+        AND field_id IN (SELECT id FROM custom_field_definitions WHERE entity = 'person');
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_resources_delete_custom_values AFTER DELETE ON resources
+BEGIN
+    DELETE FROM custom_field_values WHERE entity_id = OLD.id
+        AND field_id IN (SELECT id FROM custom_field_definitions WHERE entity = 'resource');
+END;
 "#;
 
 /// Standard dokumenttyper att skapa vid första start
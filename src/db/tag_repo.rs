@@ -0,0 +1,241 @@
+//! Repository för taggar och personers taggar
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, Row};
+
+use crate::models::Tag;
+
+pub struct TagRepository {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl TagRepository {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Hämta alla taggar
+    pub fn find_all(&self) -> Result<Vec<Tag>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, name, color, created_at FROM tags ORDER BY name")?;
+
+        let tags = stmt
+            .query_map([], |row| Ok(Self::row_to_tag(row)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(tags)
+    }
+
+    /// Hämta tagg via ID
+    pub fn find_by_id(&self, id: i64) -> Result<Option<Tag>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, name, color, created_at FROM tags WHERE id = ?")?;
+
+        let tag = stmt.query_row([id], |row| Ok(Self::row_to_tag(row))).ok();
+
+        Ok(tag)
+    }
+
+    /// Skapa ny tagg
+    pub fn create(&self, tag: &mut Tag) -> Result<i64> {
+        let name = tag.name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Taggen måste ha ett namn"));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO tags (name, color) VALUES (?1, ?2)",
+            params![name, tag.color],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        tag.id = Some(id);
+
+        Ok(id)
+    }
+
+    /// Uppdatera namn och färg
+    pub fn update(&self, tag: &Tag) -> Result<()> {
+        let id = tag.id.ok_or_else(|| anyhow!("Tagg har inget ID"))?;
+
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
+            params![tag.name.trim(), tag.color, id],
+        )?;
+
+        if rows == 0 {
+            return Err(anyhow!("Tagg med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    /// Ta bort tagg (kopplingar till personer tas bort via CASCADE)
+    pub fn delete(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM tags WHERE id = ?", [id])?;
+
+        if rows == 0 {
+            return Err(anyhow!("Tagg med ID {} hittades inte", id));
+        }
+
+        Ok(())
+    }
+
+    /// Hämta taggar för en person
+    pub fn find_by_person(&self, person_id: i64) -> Result<Vec<Tag>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.color, t.created_at
+             FROM tags t
+             INNER JOIN person_tags pt ON pt.tag_id = t.id
+             WHERE pt.person_id = ?
+             ORDER BY t.name",
+        )?;
+
+        let tags = stmt
+            .query_map([person_id], |row| Ok(Self::row_to_tag(row)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(tags)
+    }
+
+    /// Hämta taggar för alla personer i en fråga (person_id -> taggar)
+    pub fn find_all_by_person(&self) -> Result<HashMap<i64, Vec<Tag>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT pt.person_id, t.id, t.name, t.color, t.created_at
+             FROM person_tags pt
+             INNER JOIN tags t ON pt.tag_id = t.id
+             ORDER BY t.name",
+        )?;
+
+        let mut map: HashMap<i64, Vec<Tag>> = HashMap::new();
+        let rows = stmt.query_map([], |row| {
+            let person_id: i64 = row.get(0)?;
+            let tag = Tag {
+                id: row.get(1).ok(),
+                name: row.get(2).unwrap_or_default(),
+                color: row.get(3).unwrap_or_default(),
+                created_at: row.get(4).ok(),
+            };
+            Ok((person_id, tag))
+        })?;
+        for (person_id, tag) in rows.filter_map(|r| r.ok()) {
+            map.entry(person_id).or_default().push(tag);
+        }
+
+        Ok(map)
+    }
+
+    /// Tagga en person (ingen effekt om taggen redan finns)
+    pub fn add_to_person(&self, tag_id: i64, person_id: i64) -> Result<()> {
+        self.add_to_persons(tag_id, &[person_id])?;
+        Ok(())
+    }
+
+    /// Tagga flera personer. Returnerar antal nya kopplingar.
+    pub fn add_to_persons(&self, tag_id: i64, person_ids: &[i64]) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("INSERT OR IGNORE INTO person_tags (person_id, tag_id) VALUES (?1, ?2)")?;
+
+        let mut added = 0;
+        for person_id in person_ids {
+            added += stmt.execute(params![person_id, tag_id])?;
+        }
+
+        Ok(added)
+    }
+
+    /// Ta bort tagg från en person
+    pub fn remove_from_person(&self, tag_id: i64, person_id: i64) -> Result<()> {
+        self.remove_from_persons(tag_id, &[person_id])?;
+        Ok(())
+    }
+
+    /// Ta bort tagg från flera personer. Returnerar antal borttagna kopplingar.
+    pub fn remove_from_persons(&self, tag_id: i64, person_ids: &[i64]) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("DELETE FROM person_tags WHERE person_id = ?1 AND tag_id = ?2")?;
+
+        let mut removed = 0;
+        for person_id in person_ids {
+            removed += stmt.execute(params![person_id, tag_id])?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Räkna personer med en viss tagg
+    pub fn count_persons(&self, tag_id: i64) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM person_tags WHERE tag_id = ?",
+            [tag_id],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    fn row_to_tag(row: &Row) -> Tag {
+        Tag {
+            id: row.get(0).ok(),
+            name: row.get(1).unwrap_or_default(),
+            color: row.get(2).unwrap_or_default(),
+            created_at: row.get(3).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Database, SearchFilter};
+    use crate::models::{Person, DEFAULT_TAG_COLOR};
+
+    #[test]
+    fn test_tag_persons_and_filter() {
+        let db = Database::open_in_memory().unwrap();
+
+        let mut ids = Vec::new();
+        for name in ["Anders", "Britta", "Carl"] {
+            let mut p = Person::new(Some(name.into()), Some("Ek".into()), name.to_lowercase());
+            db.persons().create(&mut p).unwrap();
+            ids.push(p.id.unwrap());
+        }
+
+        let mut emigrants = Tag::new("Emigranter".into(), DEFAULT_TAG_COLOR.into());
+        let emigrants_id = db.tags().create(&mut emigrants).unwrap();
+        let mut soldiers = Tag::new("Soldater".into(), "#AA3333".into());
+        let soldiers_id = db.tags().create(&mut soldiers).unwrap();
+
+        assert_eq!(db.tags().add_to_persons(emigrants_id, &ids[..2]).unwrap(), 2);
+        // Dubbletter ignoreras
+        assert_eq!(db.tags().add_to_persons(emigrants_id, &ids[..2]).unwrap(), 0);
+        db.tags().add_to_person(soldiers_id, ids[1]).unwrap();
+
+        assert_eq!(db.tags().count_persons(emigrants_id).unwrap(), 2);
+        assert_eq!(db.tags().find_by_person(ids[1]).unwrap().len(), 2);
+        assert_eq!(db.tags().find_all_by_person().unwrap().len(), 2);
+
+        // Filter med flera taggar kräver alla
+        let filter = SearchFilter {
+            tag_ids: vec![emigrants_id, soldiers_id],
+            ..Default::default()
+        };
+        let persons = db.persons().advanced_search(&filter).unwrap();
+        assert_eq!(persons.len(), 1);
+        assert_eq!(persons[0].id, Some(ids[1]));
+
+        // Borttagen tagg tar med sig kopplingarna
+        db.tags().delete(emigrants_id).unwrap();
+        assert_eq!(db.tags().find_by_person(ids[0]).unwrap().len(), 0);
+    }
+}
@@ -6,7 +6,7 @@ use std::path::Path;
 use anyhow::{Context, Result};
 
 use crate::db::Database;
-use crate::models::{Person, PersonRelationship, RelationshipType};
+use crate::models::{Person, PersonRelationship, Privacy, RelationshipType, Sex};
 
 use super::models::{GedcomData, GedcomFamily, GedcomIndividual};
 use super::parser::GedcomParser;
@@ -220,6 +220,11 @@ impl<'a> GedcomImporter<'a> {
                 existing.occupation = new_occupation;
                 changed = true;
             }
+            let sex = indi.sex.as_deref().map(Sex::from_db_str).unwrap_or_default();
+            if existing.sex == Sex::Unknown && sex != Sex::Unknown {
+                existing.sex = sex;
+                changed = true;
+            }
             if existing.gedcom_id.is_none() {
                 existing.gedcom_id = Some(indi.id.clone());
                 changed = true;
@@ -246,6 +251,9 @@ impl<'a> GedcomImporter<'a> {
             gedcom_id: Some(indi.id.clone()),
             directory_name: unique_dir_name,
             profile_image_path: None,
+            notes: Some(indi.notes.join("\n")).filter(|n| !n.trim().is_empty()),
+            privacy: Privacy::default(),
+            sex: indi.sex.as_deref().map(Sex::from_db_str).unwrap_or_default(),
             created_at: None,
             updated_at: None,
             age: None,
@@ -278,9 +286,13 @@ impl<'a> GedcomImporter<'a> {
             .and_then(|id| id_map.get(id))
             .copied();
 
-        // Skapa make/maka-relation
+        // Skapa make/maka-relation med vigseluppgifter
         if let (Some(h_id), Some(w_id)) = (husband_db_id, wife_db_id) {
-            if self.create_relation_if_not_exists(h_id, w_id, RelationshipType::Spouse)? {
+            if !self.db.relationships().exists(h_id, w_id)? {
+                let mut relationship = PersonRelationship::new(h_id, w_id, RelationshipType::Spouse);
+                relationship.marriage_date = family.marriage_date.as_ref().and_then(|d| d.to_naive_date());
+                relationship.marriage_place = family.marriage_place.clone();
+                self.db.relationships().create(&mut relationship)?;
                 count += 1;
             }
         }
@@ -576,6 +588,11 @@ mod tests {
         assert_eq!(karl_spouse.len(), 1, "Karl ska ha 1 make/maka-relation");
         assert_eq!(karl_spouse[0].other_person_id, maria_id);
 
+        // Vigseluppgifter följer med från MARR
+        let marriage = db.relationships().find_by_id(karl_spouse[0].relationship_id).unwrap().unwrap();
+        assert_eq!(marriage.marriage_date, chrono::NaiveDate::from_ymd_opt(1934, 5, 5));
+        assert_eq!(marriage.marriage_place.as_deref(), Some("Lund"));
+
         let karl_children: Vec<_> = karl_rels.iter()
             .filter(|r| r.relationship_type == RelationshipType::Child)
             .collect();
@@ -61,6 +61,9 @@ impl fmt::Display for DirNameFormat {
     }
 }
 
+/// Standardgräns för när en person utan dödsdatum antas leva
+pub const DEFAULT_LIVING_THRESHOLD_YEARS: i32 = 100;
+
 /// Systemkonfiguration (singleton, id=1)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemConfig {
@@ -68,6 +71,8 @@ pub struct SystemConfig {
     pub media_directory_path: PathBuf,
     pub backup_directory_path: PathBuf,
     pub dir_name_format: DirNameFormat,
+    /// Personer utan dödsdatum som är födda inom så många år räknas som levande
+    pub living_threshold_years: i32,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -84,6 +89,7 @@ impl Default for SystemConfig {
             media_directory_path: data_dir.join("media"),
             backup_directory_path: data_dir.join("backups"),
             dir_name_format: DirNameFormat::default(),
+            living_threshold_years: DEFAULT_LIVING_THRESHOLD_YEARS,
             created_at: None,
             updated_at: None,
         }
@@ -186,6 +192,7 @@ pub enum ShortcutAction {
     NavigateChecklistSearch,
     NavigateSettings,
     NavigateResourceList,
+    NavigateCalendar,
     NewPerson,
     FocusSearch,
     Backup,
@@ -203,6 +210,7 @@ impl ShortcutAction {
         Self::NavigateChecklistSearch,
         Self::NavigateSettings,
         Self::NavigateResourceList,
+        Self::NavigateCalendar,
         Self::NewPerson,
         Self::FocusSearch,
         Self::Backup,
@@ -220,6 +228,7 @@ impl ShortcutAction {
             Self::NavigateChecklistSearch => "Uppgifter",
             Self::NavigateSettings => "Inställningar",
             Self::NavigateResourceList => "Resurser",
+            Self::NavigateCalendar => "Kalender",
             Self::NewPerson => "Ny person",
             Self::FocusSearch => "Sök",
             Self::Backup => "Backup",
@@ -238,6 +247,7 @@ impl ShortcutAction {
             Self::NavigateChecklistSearch => "navigate_checklist_search",
             Self::NavigateSettings => "navigate_settings",
             Self::NavigateResourceList => "navigate_resource_list",
+            Self::NavigateCalendar => "navigate_calendar",
             Self::NewPerson => "new_person",
             Self::FocusSearch => "focus_search",
             Self::Backup => "backup",
@@ -256,6 +266,7 @@ impl ShortcutAction {
             "navigate_checklist_search" => Some(Self::NavigateChecklistSearch),
             "navigate_settings" => Some(Self::NavigateSettings),
             "navigate_resource_list" => Some(Self::NavigateResourceList),
+            "navigate_calendar" => Some(Self::NavigateCalendar),
             "new_person" => Some(Self::NewPerson),
             "focus_search" => Some(Self::FocusSearch),
             "backup" => Some(Self::Backup),
@@ -372,6 +383,7 @@ pub fn default_shortcuts() -> ShortcutMap {
     m.insert(ShortcutAction::NavigateFamilyTree, KeyboardShortcut::new(egui::Key::Num3, true, false, false));
     m.insert(ShortcutAction::NavigateChecklistSearch, KeyboardShortcut::new(egui::Key::Num4, true, false, false));
     m.insert(ShortcutAction::NavigateResourceList, KeyboardShortcut::new(egui::Key::Num5, true, false, false));
+    m.insert(ShortcutAction::NavigateCalendar, KeyboardShortcut::new(egui::Key::Num6, true, false, false));
     m.insert(ShortcutAction::NavigateSettings, KeyboardShortcut::new(egui::Key::Comma, true, false, false));
     m.insert(ShortcutAction::NewPerson, KeyboardShortcut::new(egui::Key::N, true, false, false));
     m.insert(ShortcutAction::FocusSearch, KeyboardShortcut::new(egui::Key::F, true, false, false));
@@ -0,0 +1,184 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Vilken typ av post ett eget fält hör till
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CustomFieldEntity {
+    #[default]
+    Person,
+    Resource,
+}
+
+impl CustomFieldEntity {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CustomFieldEntity::Person => "Person",
+            CustomFieldEntity::Resource => "Resurs",
+        }
+    }
+
+    pub fn all() -> &'static [CustomFieldEntity] {
+        &[CustomFieldEntity::Person, CustomFieldEntity::Resource]
+    }
+
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            CustomFieldEntity::Person => "person",
+            CustomFieldEntity::Resource => "resource",
+        }
+    }
+
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "resource" => CustomFieldEntity::Resource,
+            _ => CustomFieldEntity::Person,
+        }
+    }
+}
+
+/// Datatyp för ett eget fält. Värden lagras alltid som text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CustomFieldType {
+    #[default]
+    Text,
+    Number,
+    Date,
+    Choice,
+    Url,
+}
+
+impl CustomFieldType {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "Text",
+            CustomFieldType::Number => "Tal",
+            CustomFieldType::Date => "Datum",
+            CustomFieldType::Choice => "Val",
+            CustomFieldType::Url => "Länk (URL)",
+        }
+    }
+
+    pub fn all() -> &'static [CustomFieldType] {
+        &[
+            CustomFieldType::Text,
+            CustomFieldType::Number,
+            CustomFieldType::Date,
+            CustomFieldType::Choice,
+            CustomFieldType::Url,
+        ]
+    }
+
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "text",
+            CustomFieldType::Number => "number",
+            CustomFieldType::Date => "date",
+            CustomFieldType::Choice => "choice",
+            CustomFieldType::Url => "url",
+        }
+    }
+
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "number" => CustomFieldType::Number,
+            "date" => CustomFieldType::Date,
+            "choice" => CustomFieldType::Choice,
+            "url" => CustomFieldType::Url,
+            _ => CustomFieldType::Text,
+        }
+    }
+
+    /// Platshållartext för inmatning
+    pub fn hint(&self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "",
+            CustomFieldType::Number => "t.ex. 42",
+            CustomFieldType::Date => "YYYY-MM-DD",
+            CustomFieldType::Choice => "",
+            CustomFieldType::Url => "https://...",
+        }
+    }
+}
+
+/// Definition av ett användardefinierat fält, t.ex. "Soldatnummer" eller "DNA-kit"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomFieldDefinition {
+    pub id: Option<i64>,
+    pub entity: CustomFieldEntity,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    /// Tillåtna värden för `CustomFieldType::Choice`
+    pub options: Vec<String>,
+    pub sort_order: i32,
+    pub created_at: Option<String>,
+}
+
+impl CustomFieldDefinition {
+    pub fn new(entity: CustomFieldEntity, name: String, field_type: CustomFieldType) -> Self {
+        Self {
+            id: None,
+            entity,
+            name,
+            field_type,
+            options: Vec::new(),
+            sort_order: 0,
+            created_at: None,
+        }
+    }
+
+    /// Kontrollera att ett värde passar fältets typ. Tomt värde är alltid giltigt.
+    pub fn validate_value(&self, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        let valid = match self.field_type {
+            CustomFieldType::Text => true,
+            CustomFieldType::Number => value.replace(',', ".").parse::<f64>().is_ok(),
+            CustomFieldType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+            CustomFieldType::Choice => self.options.iter().any(|o| o == value),
+            CustomFieldType::Url => value.starts_with("http://") || value.starts_with("https://"),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            let expected = match self.field_type {
+                CustomFieldType::Number => "ett tal".to_string(),
+                CustomFieldType::Date => "ett datum (YYYY-MM-DD)".to_string(),
+                CustomFieldType::Choice => format!("ett av: {}", self.options.join(", ")),
+                CustomFieldType::Url => "en länk som börjar med http:// eller https://".to_string(),
+                CustomFieldType::Text => unreachable!(),
+            };
+            Err(format!("{}: \"{}\" ska vara {}", self.name, value, expected))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_value_by_type() {
+        let number = CustomFieldDefinition::new(CustomFieldEntity::Person, "Soldatnummer".into(), CustomFieldType::Number);
+        assert!(number.validate_value("123").is_ok());
+        assert!(number.validate_value("").is_ok());
+        assert!(number.validate_value("12a").is_err());
+
+        let date = CustomFieldDefinition::new(CustomFieldEntity::Person, "Mönstrad".into(), CustomFieldType::Date);
+        assert!(date.validate_value("1890-05-01").is_ok());
+        assert!(date.validate_value("1890").is_err());
+
+        let mut choice = CustomFieldDefinition::new(CustomFieldEntity::Person, "Regemente".into(), CustomFieldType::Choice);
+        choice.options = vec!["Upplands".into(), "Dalregementet".into()];
+        assert!(choice.validate_value("Upplands").is_ok());
+        let err = choice.validate_value("Hälsinge").unwrap_err();
+        assert!(err.contains("Dalregementet"));
+
+        let url = CustomFieldDefinition::new(CustomFieldEntity::Resource, "Karta".into(), CustomFieldType::Url);
+        assert!(url.validate_value("https://example.org").is_ok());
+        assert!(url.validate_value("example.org").is_err());
+    }
+}
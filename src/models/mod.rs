@@ -4,6 +4,8 @@ pub mod relationship;
 pub mod checklist;
 pub mod config;
 pub mod resource;
+pub mod tag;
+pub mod custom_field;
 
 pub use person::*;
 pub use document::*;
@@ -11,3 +13,5 @@ pub use relationship::*;
 pub use checklist::*;
 pub use config::*;
 pub use resource::*;
+pub use tag::*;
+pub use custom_field::*;
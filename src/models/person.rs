@@ -1,6 +1,87 @@
 use chrono::{Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Sekretessinställning för en person vid export och delning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Privacy {
+    /// Privat om personen bedöms vara levande (se `PrivacyPolicy`)
+    #[default]
+    Auto,
+    /// Visas alltid
+    Public,
+    /// Maskeras alltid
+    Private,
+}
+
+impl Privacy {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Privacy::Auto => "Automatisk (levande maskeras)",
+            Privacy::Public => "Offentlig",
+            Privacy::Private => "Privat",
+        }
+    }
+
+    pub fn all() -> &'static [Privacy] {
+        &[Privacy::Auto, Privacy::Public, Privacy::Private]
+    }
+
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Privacy::Auto => "auto",
+            Privacy::Public => "public",
+            Privacy::Private => "private",
+        }
+    }
+
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "public" => Privacy::Public,
+            "private" => Privacy::Private,
+            _ => Privacy::Auto,
+        }
+    }
+}
+
+/// Kön enligt GEDCOM (M/F/U)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Sex {
+    #[default]
+    Unknown,
+    Male,
+    Female,
+}
+
+impl Sex {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Sex::Unknown => "Okänt",
+            Sex::Male => "Man",
+            Sex::Female => "Kvinna",
+        }
+    }
+
+    pub fn all() -> &'static [Sex] {
+        &[Sex::Unknown, Sex::Male, Sex::Female]
+    }
+
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Sex::Unknown => "U",
+            Sex::Male => "M",
+            Sex::Female => "F",
+        }
+    }
+
+    pub fn from_db_str(s: &str) -> Self {
+        match s.trim().to_uppercase().as_str() {
+            "M" => Sex::Male,
+            "F" => Sex::Female,
+            _ => Sex::Unknown,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Person {
     pub id: Option<i64>,
@@ -14,6 +95,9 @@ pub struct Person {
     pub gedcom_id: Option<String>,
     pub directory_name: String,
     pub profile_image_path: Option<String>,
+    pub notes: Option<String>,
+    pub privacy: Privacy,
+    pub sex: Sex,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -32,6 +116,9 @@ impl Default for Person {
             gedcom_id: None,
             directory_name: String::new(),
             profile_image_path: None,
+            notes: None,
+            privacy: Privacy::default(),
+            sex: Sex::default(),
             created_at: None,
             updated_at: None,
         }
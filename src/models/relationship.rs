@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -51,6 +52,10 @@ pub struct PersonRelationship {
     pub relationship_a_to_b: RelationshipType,
     pub relationship_b_to_a: RelationshipType,
     pub notes: Option<String>,
+    /// Vigseldatum (endast make/maka)
+    pub marriage_date: Option<NaiveDate>,
+    /// Vigselort (endast make/maka)
+    pub marriage_place: Option<String>,
     pub created_at: Option<String>,
 }
 
@@ -80,6 +85,8 @@ impl PersonRelationship {
             relationship_a_to_b: rel_a_to_b,
             relationship_b_to_a: rel_b_to_a,
             notes: None,
+            marriage_date: None,
+            marriage_place: None,
             created_at: None,
         }
     }
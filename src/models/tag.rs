@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// Standardfärg för nya taggar
+pub const DEFAULT_TAG_COLOR: &str = "#4A90D9";
+
+/// Användardefinierad tagg för personer, t.ex. "Emigranter" eller "Soldater"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: Option<i64>,
+    pub name: String,
+    /// Färg som hex-sträng, t.ex. "#4A90D9"
+    pub color: String,
+    pub created_at: Option<String>,
+}
+
+impl Tag {
+    pub fn new(name: String, color: String) -> Self {
+        Self {
+            id: None,
+            name,
+            color,
+            created_at: None,
+        }
+    }
+
+    /// Färgen som RGB, None om hex-strängen är ogiltig
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        parse_hex_color(&self.color)
+    }
+}
+
+/// Tolka "#RRGGBB" (eller "RRGGBB") till RGB
+pub fn parse_hex_color(s: &str) -> Option<(u8, u8, u8)> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+    Some((r, g, b))
+}
+
+/// Formatera RGB som "#RRGGBB"
+pub fn format_hex_color(r: u8, g: u8, b: u8) -> String {
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_color_roundtrip() {
+        assert_eq!(parse_hex_color("#4A90D9"), Some((0x4A, 0x90, 0xD9)));
+        assert_eq!(parse_hex_color("ff0000"), Some((255, 0, 0)));
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#gg0000"), None);
+        assert_eq!(format_hex_color(0x4A, 0x90, 0xD9), "#4A90D9");
+    }
+}
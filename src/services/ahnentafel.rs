@@ -0,0 +1,342 @@
+//! Antavla (anrapport) numrerad enligt Sosa–Stradonitz
+//!
+//! Utgångspersonen har nummer 1, en persons far har dubbla numret och modern
+//! dubbla numret plus ett. Varje förfader beskrivs i ett stycke med
+//! levnadsdata, orter, yrke och anteckningar. En förfader som förekommer
+//! flera gånger (anförlust) beskrivs bara första gången; senare nummer
+//! hänvisar dit och deras förfäder tas inte med igen.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::models::{Person, RelationshipType};
+use crate::services::chart_export::xml_escape;
+use crate::services::pedigree_collapse::{depth, expand_pedigree, MAX_GENERATIONS};
+use crate::services::timeline::age_at;
+
+/// En förfader i antavlan
+#[derive(Debug, Clone)]
+pub struct AhnentafelEntry {
+    pub number: u64,
+    pub person: Person,
+    /// Första numret där samma person redan beskrivits
+    pub same_as: Option<u64>,
+    /// Vigseldatum och ort (för fadern i ett föräldrapar)
+    pub marriage: Option<(Option<String>, Option<String>)>,
+    /// Finns föräldrar med i antavlan?
+    pub has_father: bool,
+    pub has_mother: bool,
+}
+
+impl AhnentafelEntry {
+    /// Generation räknat från utgångspersonen (1 = utgångspersonen)
+    pub fn generation(&self) -> u32 {
+        depth(self.number) + 1
+    }
+
+    /// Styckets rubrik: "4. Karl Berg"
+    pub fn heading(&self) -> String {
+        format!("{}. {}", self.number, self.person.full_name())
+    }
+
+    /// Styckets text efter rubriken
+    pub fn body(&self) -> String {
+        if let Some(first) = self.same_as {
+            return format!("Samma person som nr {}, se där.", first);
+        }
+
+        let person = &self.person;
+        let mut sentences = vital_sentences(person);
+        if let Some((date, place)) = &self.marriage {
+            let mut text = String::from("Gift");
+            if let Some(date) = date {
+                text.push_str(&format!(" {}", date));
+            }
+            if let Some(place) = place {
+                text.push_str(&format!(" i {}", place));
+            }
+            sentences.push(format!("{} med nr {}.", text, self.number + 1));
+        }
+        match (self.has_father, self.has_mother) {
+            (true, true) => sentences.push(format!("Föräldrar: nr {} och {}.", self.number * 2, self.number * 2 + 1)),
+            (true, false) => sentences.push(format!("Far: nr {}.", self.number * 2)),
+            (false, true) => sentences.push(format!("Mor: nr {}.", self.number * 2 + 1)),
+            (false, false) => {}
+        }
+        if self.number > 1 {
+            sentences.push(format!("{} till nr {}.", if self.number % 2 == 0 { "Far" } else { "Mor" }, self.number / 2));
+        }
+        sentences.extend(notes(person));
+
+        sentences.join(" ")
+    }
+}
+
+/// Födelse, död och yrke som meningar ("Född 1850-03-01 i Lund." ...)
+pub(crate) fn vital_sentences(person: &Person) -> Vec<String> {
+    let mut sentences = Vec::new();
+
+    let place = person.birth_place.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    match (person.birth_date, place) {
+        (Some(date), Some(place)) => sentences.push(format!("Född {} i {}.", date.format("%Y-%m-%d"), place)),
+        (Some(date), None) => sentences.push(format!("Född {}.", date.format("%Y-%m-%d"))),
+        (None, Some(place)) => sentences.push(format!("Född i {}.", place)),
+        (None, None) => {}
+    }
+    if let Some(death) = person.death_date {
+        match person.birth_date.filter(|birth| *birth <= death) {
+            Some(birth) => sentences.push(format!("Död {} ({} år).", death.format("%Y-%m-%d"), age_at(birth, death))),
+            None => sentences.push(format!("Död {}.", death.format("%Y-%m-%d"))),
+        }
+    }
+    if let Some(occupation) = person.occupation.as_deref().map(str::trim).filter(|o| !o.is_empty()) {
+        sentences.push(format!("Yrke: {}.", occupation.trim_end_matches('.')));
+    }
+    sentences
+}
+
+/// Anteckningar på en rad
+pub(crate) fn notes(person: &Person) -> Option<String> {
+    person
+        .notes
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(|n| n.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Rubrik för en generation
+pub fn generation_title(generation: u32) -> String {
+    let label = match generation {
+        1 => Some("utgångsperson"),
+        2 => Some("föräldrar"),
+        3 => Some("far- och morföräldrar"),
+        4 => Some("farfars och morfars föräldrar"),
+        _ => None,
+    };
+    match label {
+        Some(label) => format!("Generation {} – {}", generation, label),
+        None => format!("Generation {}", generation),
+    }
+}
+
+/// Antavla för en person
+#[derive(Debug, Clone, Default)]
+pub struct Ahnentafel {
+    pub root_person_id: i64,
+    pub generations: usize,
+    /// Förfäder i nummerordning
+    pub entries: Vec<AhnentafelEntry>,
+}
+
+impl Ahnentafel {
+    pub fn title(&self) -> String {
+        match self.entries.iter().find(|e| e.person.id == Some(self.root_person_id)) {
+            Some(root) => format!("Antavla för {}", root.person.full_name()),
+            None => "Antavla".to_string(),
+        }
+    }
+
+    /// Förfäderna grupperade per generation
+    pub fn by_generation(&self) -> BTreeMap<u32, Vec<&AhnentafelEntry>> {
+        let mut groups: BTreeMap<u32, Vec<&AhnentafelEntry>> = BTreeMap::new();
+        for entry in &self.entries {
+            groups.entry(entry.generation()).or_default().push(entry);
+        }
+        groups
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# {}\n", markdown_escape(&self.title()));
+        for (generation, entries) in self.by_generation() {
+            md.push_str(&format!("\n## {}\n", generation_title(generation)));
+            for entry in entries {
+                md.push_str(&format!("\n**{}** {}\n", markdown_escape(&entry.heading()), markdown_escape(&entry.body())));
+            }
+        }
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = xml_escape(&self.title());
+        let mut html = String::from("<!DOCTYPE html>\n<html lang=\"sv\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", title));
+        html.push_str("<style>body{font-family:Georgia,serif;max-width:46em;margin:2em auto;line-height:1.5}h2{border-bottom:1px solid #ccc}</style>\n");
+        html.push_str(&format!("</head>\n<body>\n<h1>{}</h1>\n", title));
+        for (generation, entries) in self.by_generation() {
+            html.push_str(&format!("<h2>{}</h2>\n", xml_escape(&generation_title(generation))));
+            for entry in entries {
+                html.push_str(&format!(
+                    "<p id=\"nr{}\"><strong>{}</strong> {}</p>\n",
+                    entry.number,
+                    xml_escape(&entry.heading()),
+                    xml_escape(&entry.body())
+                ));
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+pub(crate) fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '#' | '`' | '<' | '+') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Tjänst för att bygga antavlor
+pub struct AhnentafelService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> AhnentafelService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Bygg antavlan för en person, högst `generations` generationer bakåt
+    pub fn build(&self, root_person_id: i64, generations: usize) -> Result<Ahnentafel> {
+        let graph = self.db.kinship()?;
+        let generations = generations.min(MAX_GENERATIONS);
+        let pedigree = expand_pedigree(&graph, root_person_id, generations);
+
+        let mut first_number: HashMap<i64, u64> = HashMap::new();
+        let mut skipped: HashSet<u64> = HashSet::new();
+        let mut entries = Vec::new();
+        for (&number, &person_id) in &pedigree {
+            // Förfäder till en upprepad person finns redan beskrivna
+            if number > 1 && skipped.contains(&(number / 2)) {
+                skipped.insert(number);
+                continue;
+            }
+            let Some(person) = graph.person(person_id) else {
+                continue;
+            };
+
+            let same_as = first_number.get(&person_id).copied();
+            match same_as {
+                Some(_) => {
+                    skipped.insert(number);
+                }
+                None => {
+                    first_number.insert(person_id, number);
+                }
+            }
+
+            let marriage = match pedigree.get(&(number + 1)) {
+                Some(&mother_id) if number % 2 == 0 && same_as.is_none() => self.marriage(person_id, mother_id)?,
+                _ => None,
+            };
+
+            entries.push(AhnentafelEntry {
+                number,
+                person: person.clone(),
+                same_as,
+                marriage,
+                has_father: same_as.is_none() && pedigree.contains_key(&(number * 2)),
+                has_mother: same_as.is_none() && pedigree.contains_key(&(number * 2 + 1)),
+            });
+        }
+
+        Ok(Ahnentafel { root_person_id, generations, entries })
+    }
+
+    /// Vigseluppgifter om paret är gifta med varandra
+    fn marriage(&self, husband_id: i64, wife_id: i64) -> Result<Option<(Option<String>, Option<String>)>> {
+        let marriage = self
+            .db
+            .relationships()
+            .find_by_person(husband_id)?
+            .into_iter()
+            .find(|r| {
+                r.relationship_a_to_b == RelationshipType::Spouse
+                    && (r.person_a_id == wife_id || r.person_b_id == wife_id)
+            })
+            .map(|r| {
+                let place = r.marriage_place.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+                (r.marriage_date.map(|d| d.format("%Y-%m-%d").to_string()), place)
+            });
+        Ok(marriage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PersonRelationship, Sex};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_ahnentafel_numbering_and_collapse() {
+        let db = Database::open_in_memory().unwrap();
+        let mut ids = HashMap::new();
+        for (name, sex) in [
+            ("Barn", Sex::Male),
+            ("Far", Sex::Male),
+            ("Mor", Sex::Female),
+            ("Farfar", Sex::Male),
+            ("Farmor", Sex::Female),
+            ("Stamfar", Sex::Male),
+        ] {
+            let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+            p.sex = sex;
+            p.death_date = NaiveDate::from_ymd_opt(1900, 1, 1);
+            db.persons().create(&mut p).unwrap();
+            ids.insert(name, p.id.unwrap());
+        }
+        let mut farfar = db.persons().find_by_id(ids["Farfar"]).unwrap().unwrap();
+        farfar.birth_date = NaiveDate::from_ymd_opt(1820, 4, 2);
+        farfar.birth_place = Some("Lund".into());
+        farfar.death_date = NaiveDate::from_ymd_opt(1880, 4, 1);
+        farfar.occupation = Some("Bonde".into());
+        farfar.notes = Some("Ägde gården *Norra* 1850–1870.".into());
+        db.persons().update(&mut farfar).unwrap();
+
+        // Far och mor är halvsyskon med samma far (Farfar)
+        for (parent, child) in [
+            ("Far", "Barn"),
+            ("Mor", "Barn"),
+            ("Farfar", "Far"),
+            ("Farmor", "Far"),
+            ("Farfar", "Mor"),
+            ("Stamfar", "Farfar"),
+        ] {
+            let mut rel = PersonRelationship::new(ids[parent], ids[child], RelationshipType::Parent);
+            db.relationships().create(&mut rel).unwrap();
+        }
+        let mut marriage = PersonRelationship::new(ids["Farfar"], ids["Farmor"], RelationshipType::Spouse);
+        marriage.marriage_date = NaiveDate::from_ymd_opt(1845, 6, 1);
+        marriage.marriage_place = Some("Lund".into());
+        db.relationships().create(&mut marriage).unwrap();
+
+        let tafel = AhnentafelService::new(&db).build(ids["Barn"], 5).unwrap();
+        let numbers: Vec<(u64, Option<u64>)> = tafel.entries.iter().map(|e| (e.number, e.same_as)).collect();
+        // 6 = Farfar igen; hans far (12) tas inte med en gång till
+        assert_eq!(numbers, vec![(1, None), (2, None), (3, None), (4, None), (5, None), (6, Some(4)), (8, None)]);
+
+        let farfar = &tafel.entries[3];
+        assert_eq!(farfar.heading(), "4. Farfar Berg");
+        assert_eq!(
+            farfar.body(),
+            "Född 1820-04-02 i Lund. Död 1880-04-01 (59 år). Yrke: Bonde. Gift 1845-06-01 i Lund med nr 5. \
+             Far: nr 8. Far till nr 2. Ägde gården *Norra* 1850–1870."
+        );
+        assert_eq!(tafel.entries[5].body(), "Samma person som nr 4, se där.");
+
+        let md = tafel.to_markdown();
+        assert!(md.starts_with("# Antavla för Barn Berg\n"));
+        assert!(md.contains("## Generation 3 – far- och morföräldrar\n"));
+        assert!(md.contains("gården \\*Norra\\*"));
+
+        let html = tafel.to_html();
+        assert!(html.contains("<p id=\"nr6\"><strong>6. Farfar Berg</strong> Samma person som nr 4, se där.</p>"));
+    }
+}
@@ -0,0 +1,500 @@
+//! Släktanalys: fördelningar och medelvärden över hela urvalet
+//!
+//! Beräkningarna görs på personer och relationer i minnet, så att samma
+//! resultat kan visas som diagram i rapportvyn och exporteras som JSON eller
+//! CSV. Personer utan de datum som en beräkning kräver hoppas över i den.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::Datelike;
+use serde::Serialize;
+
+use crate::db::KinshipGraph;
+use crate::models::{Person, PersonRelationship, RelationshipType, Sex};
+use crate::services::export::csv_escape;
+use crate::services::timeline::age_at;
+
+/// Antal efternamn som följs över tid
+const TOP_SURNAMES: usize = 8;
+/// Antal födelseorter och släktlinjer som listas
+const TOP_ROWS: usize = 15;
+
+/// Antal personer med ett efternamn som föddes under ett decennium
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SurnameDecade {
+    pub surname: String,
+    pub decade: i32,
+    pub count: usize,
+}
+
+/// Medellivslängd för personer födda under ett decennium
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LifespanRow {
+    pub decade: i32,
+    /// "M", "F" eller "U" (samma koder som i databasen)
+    pub sex: String,
+    pub average_years: f64,
+    pub count: usize,
+}
+
+/// Barnadödlighet för personer födda under ett decennium
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ChildMortalityRow {
+    pub decade: i32,
+    /// Födda med känt utfall (dödsdatum, eller levande över 15 år)
+    pub births: usize,
+    /// Döda före 1 års ålder
+    pub died_under_1: usize,
+    /// Döda före 15 års ålder
+    pub died_under_15: usize,
+}
+
+impl ChildMortalityRow {
+    /// Andel döda före 15 års ålder, i procent
+    pub fn percent(&self) -> f64 {
+        if self.births == 0 {
+            0.0
+        } else {
+            self.died_under_15 as f64 * 100.0 / self.births as f64
+        }
+    }
+}
+
+/// Ålder vid första giftermålet
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MarriageAgeRow {
+    pub sex: String,
+    pub average_age: f64,
+    pub min_age: i32,
+    pub max_age: i32,
+    pub count: usize,
+}
+
+/// Antal par med ett visst antal gemensamma barn
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ChildrenPerCouple {
+    pub children: usize,
+    pub couples: usize,
+}
+
+/// Antal födda på en ort
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PlaceCount {
+    pub place: String,
+    pub count: usize,
+}
+
+/// Antal generationer av ättlingar från en stamfar eller stammor
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LineDepth {
+    pub person_id: i64,
+    pub full_name: String,
+    /// Generationer inklusive stamfadern (1 = inga barn i urvalet)
+    pub generations: usize,
+    pub descendants: usize,
+}
+
+/// Samlad släktanalys
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Analytics {
+    pub surnames_by_decade: Vec<SurnameDecade>,
+    pub lifespan_by_decade: Vec<LifespanRow>,
+    pub child_mortality: Vec<ChildMortalityRow>,
+    pub first_marriage_age: Vec<MarriageAgeRow>,
+    pub children_per_couple: Vec<ChildrenPerCouple>,
+    pub average_children_per_couple: f64,
+    pub top_birth_places: Vec<PlaceCount>,
+    pub generation_depth: Vec<LineDepth>,
+}
+
+fn decade(year: i32) -> i32 {
+    year.div_euclid(10) * 10
+}
+
+impl Analytics {
+    /// Beräkna analysen för personerna. Relationer till personer utanför
+    /// urvalet räknas inte.
+    pub fn compute(persons: &[Person], relationships: &[PersonRelationship]) -> Self {
+        let graph = KinshipGraph::new(persons.to_vec(), relationships);
+        let ids: HashSet<i64> = persons.iter().filter_map(|p| p.id).collect();
+        let spouse_relations: Vec<&PersonRelationship> = relationships
+            .iter()
+            .filter(|r| r.relationship_a_to_b == RelationshipType::Spouse)
+            .filter(|r| ids.contains(&r.person_a_id) && ids.contains(&r.person_b_id))
+            .collect();
+
+        let (children_per_couple, average_children_per_couple) = Self::children_per_couple(&graph, persons, &spouse_relations);
+
+        Self {
+            surnames_by_decade: Self::surnames_by_decade(persons),
+            lifespan_by_decade: Self::lifespan_by_decade(persons),
+            child_mortality: Self::child_mortality(persons),
+            first_marriage_age: Self::first_marriage_age(&graph, &spouse_relations),
+            children_per_couple,
+            average_children_per_couple,
+            top_birth_places: Self::top_birth_places(persons),
+            generation_depth: Self::generation_depth(&graph, persons),
+        }
+    }
+
+    /// De vanligaste efternamnen, räknade per födelsedecennium
+    fn surnames_by_decade(persons: &[Person]) -> Vec<SurnameDecade> {
+        let mut totals: HashMap<&str, usize> = HashMap::new();
+        for person in persons {
+            if let Some(surname) = person.surname.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+                *totals.entry(surname).or_default() += 1;
+            }
+        }
+        let mut top: Vec<(&str, usize)> = totals.into_iter().collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top.truncate(TOP_SURNAMES);
+
+        let mut counts: BTreeMap<(usize, i32), usize> = BTreeMap::new();
+        for person in persons {
+            let (Some(surname), Some(birth)) = (person.surname.as_deref().map(str::trim), person.birth_date) else {
+                continue;
+            };
+            if let Some(rank) = top.iter().position(|(s, _)| *s == surname) {
+                *counts.entry((rank, decade(birth.year()))).or_default() += 1;
+            }
+        }
+
+        counts
+            .into_iter()
+            .map(|((rank, decade), count)| SurnameDecade { surname: top[rank].0.to_string(), decade, count })
+            .collect()
+    }
+
+    fn lifespan_by_decade(persons: &[Person]) -> Vec<LifespanRow> {
+        let mut sums: BTreeMap<(i32, &'static str), (i64, usize)> = BTreeMap::new();
+        for person in persons {
+            let (Some(birth), Some(death)) = (person.birth_date, person.death_date) else {
+                continue;
+            };
+            if death < birth {
+                continue;
+            }
+            let entry = sums.entry((decade(birth.year()), person.sex.as_db_str())).or_default();
+            entry.0 += age_at(birth, death) as i64;
+            entry.1 += 1;
+        }
+
+        sums.into_iter()
+            .map(|((decade, sex), (sum, count))| LifespanRow {
+                decade,
+                sex: sex.to_string(),
+                average_years: sum as f64 / count as f64,
+                count,
+            })
+            .collect()
+    }
+
+    fn child_mortality(persons: &[Person]) -> Vec<ChildMortalityRow> {
+        let today = chrono::Local::now().date_naive();
+        let mut rows: BTreeMap<i32, ChildMortalityRow> = BTreeMap::new();
+        for person in persons {
+            let Some(birth) = person.birth_date else {
+                continue;
+            };
+            // Utan dödsdatum vet vi bara utfallet om personen hunnit fylla 15
+            let age = match person.death_date {
+                Some(death) if death >= birth => age_at(birth, death),
+                Some(_) => continue,
+                None if age_at(birth, today) >= 15 => 15,
+                None => continue,
+            };
+            let decade = decade(birth.year());
+            let row = rows.entry(decade).or_insert(ChildMortalityRow {
+                decade,
+                births: 0,
+                died_under_1: 0,
+                died_under_15: 0,
+            });
+            row.births += 1;
+            if age < 1 {
+                row.died_under_1 += 1;
+            }
+            if age < 15 {
+                row.died_under_15 += 1;
+            }
+        }
+        rows.into_values().collect()
+    }
+
+    fn first_marriage_age(graph: &KinshipGraph, spouse_relations: &[&PersonRelationship]) -> Vec<MarriageAgeRow> {
+        let mut first: HashMap<i64, chrono::NaiveDate> = HashMap::new();
+        for rel in spouse_relations {
+            let Some(date) = rel.marriage_date else {
+                continue;
+            };
+            for id in [rel.person_a_id, rel.person_b_id] {
+                let earliest = first.entry(id).or_insert(date);
+                *earliest = (*earliest).min(date);
+            }
+        }
+
+        let mut ages: BTreeMap<&'static str, Vec<i32>> = BTreeMap::new();
+        for (id, date) in first {
+            let Some(person) = graph.person(id) else {
+                continue;
+            };
+            let Some(birth) = person.birth_date.filter(|b| *b <= date) else {
+                continue;
+            };
+            ages.entry(person.sex.as_db_str()).or_default().push(age_at(birth, date));
+        }
+
+        ages.into_iter()
+            .map(|(sex, ages)| MarriageAgeRow {
+                sex: sex.to_string(),
+                average_age: ages.iter().sum::<i32>() as f64 / ages.len() as f64,
+                min_age: ages.iter().copied().min().unwrap_or_default(),
+                max_age: ages.iter().copied().max().unwrap_or_default(),
+                count: ages.len(),
+            })
+            .collect()
+    }
+
+    /// Par är gifta par samt föräldrapar med gemensamma barn
+    fn children_per_couple(
+        graph: &KinshipGraph,
+        persons: &[Person],
+        spouse_relations: &[&PersonRelationship],
+    ) -> (Vec<ChildrenPerCouple>, f64) {
+        let pair = |a: i64, b: i64| (a.min(b), a.max(b));
+        let mut couples: HashMap<(i64, i64), usize> = spouse_relations
+            .iter()
+            .map(|r| (pair(r.person_a_id, r.person_b_id), 0))
+            .collect();
+        for child in persons.iter().filter_map(|p| p.id) {
+            if let [a, b] = graph.parents(child) {
+                *couples.entry(pair(*a, *b)).or_default() += 1;
+            }
+        }
+        if couples.is_empty() {
+            return (Vec::new(), 0.0);
+        }
+
+        let total: usize = couples.values().sum();
+        let average = total as f64 / couples.len() as f64;
+        let mut distribution: BTreeMap<usize, usize> = BTreeMap::new();
+        for children in couples.into_values() {
+            *distribution.entry(children).or_default() += 1;
+        }
+        let rows = distribution
+            .into_iter()
+            .map(|(children, couples)| ChildrenPerCouple { children, couples })
+            .collect();
+        (rows, average)
+    }
+
+    fn top_birth_places(persons: &[Person]) -> Vec<PlaceCount> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for place in persons.iter().filter_map(|p| p.birth_place.as_deref()) {
+            let place = place.trim();
+            if !place.is_empty() {
+                *counts.entry(place).or_default() += 1;
+            }
+        }
+        let mut places: Vec<PlaceCount> = counts
+            .into_iter()
+            .map(|(place, count)| PlaceCount { place: place.to_string(), count })
+            .collect();
+        places.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.place.cmp(&b.place)));
+        places.truncate(TOP_ROWS);
+        places
+    }
+
+    /// Släktlinjer utgår från personer utan föräldrar i urvalet men med barn.
+    /// Djupet är antalet generationer ned till den yngsta ättlingen.
+    fn generation_depth(graph: &KinshipGraph, persons: &[Person]) -> Vec<LineDepth> {
+        let mut depths: HashMap<i64, usize> = HashMap::new();
+        let mut lines = Vec::new();
+        for person in persons {
+            let Some(id) = person.id else {
+                continue;
+            };
+            if !graph.parents(id).is_empty() || graph.children(id).is_empty() {
+                continue;
+            }
+            let generations = Self::depth(graph, id, &mut depths, &mut HashSet::new());
+            let descendants = Self::descendants(graph, id);
+            lines.push(LineDepth { person_id: id, full_name: person.full_name(), generations, descendants });
+        }
+        lines.sort_by(|a, b| {
+            b.generations
+                .cmp(&a.generations)
+                .then(b.descendants.cmp(&a.descendants))
+                .then_with(|| a.full_name.cmp(&b.full_name))
+        });
+        lines.truncate(TOP_ROWS);
+        lines
+    }
+
+    /// Generationer från `id` och nedåt. `visiting` skyddar mot cykler i
+    /// felaktigt inmatade relationer.
+    fn depth(graph: &KinshipGraph, id: i64, memo: &mut HashMap<i64, usize>, visiting: &mut HashSet<i64>) -> usize {
+        if let Some(depth) = memo.get(&id) {
+            return *depth;
+        }
+        if !visiting.insert(id) {
+            return 0;
+        }
+        let mut deepest = 0;
+        for child in graph.children(id) {
+            deepest = deepest.max(Self::depth(graph, *child, memo, visiting));
+        }
+        visiting.remove(&id);
+        memo.insert(id, deepest + 1);
+        deepest + 1
+    }
+
+    fn descendants(graph: &KinshipGraph, id: i64) -> usize {
+        let mut seen = HashSet::new();
+        let mut stack = graph.children(id).to_vec();
+        while let Some(next) = stack.pop() {
+            if seen.insert(next) {
+                stack.extend_from_slice(graph.children(next));
+            }
+        }
+        seen.len()
+    }
+
+    /// Analysen som CSV, en sektion per mått
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+
+        csv.push_str("Efternamn per födelsedecennium\nEfternamn,Decennium,Antal\n");
+        for row in &self.surnames_by_decade {
+            csv.push_str(&format!("{},{},{}\n", csv_escape(&row.surname), row.decade, row.count));
+        }
+
+        csv.push_str("\nMedellivslängd per födelsedecennium\nDecennium,Kön,Medelålder,Antal\n");
+        for row in &self.lifespan_by_decade {
+            csv.push_str(&format!("{},{},{:.1},{}\n", row.decade, row.sex, row.average_years, row.count));
+        }
+
+        csv.push_str("\nBarnadödlighet\nDecennium,Födda,Döda före 1 år,Döda före 15 år,Andel (%)\n");
+        for row in &self.child_mortality {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.1}\n",
+                row.decade,
+                row.births,
+                row.died_under_1,
+                row.died_under_15,
+                row.percent()
+            ));
+        }
+
+        csv.push_str("\nÅlder vid första giftermål\nKön,Medelålder,Lägst,Högst,Antal\n");
+        for row in &self.first_marriage_age {
+            csv.push_str(&format!(
+                "{},{:.1},{},{},{}\n",
+                row.sex, row.average_age, row.min_age, row.max_age, row.count
+            ));
+        }
+
+        csv.push_str(&format!(
+            "\nBarn per par (medel {:.2})\nBarn,Par\n",
+            self.average_children_per_couple
+        ));
+        for row in &self.children_per_couple {
+            csv.push_str(&format!("{},{}\n", row.children, row.couples));
+        }
+
+        csv.push_str("\nVanligaste födelseorter\nOrt,Antal\n");
+        for row in &self.top_birth_places {
+            csv.push_str(&format!("{},{}\n", csv_escape(&row.place), row.count));
+        }
+
+        csv.push_str("\nGenerationsdjup per släktlinje\nPerson-ID,Stamfar/stammor,Generationer,Ättlingar\n");
+        for row in &self.generation_depth {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                row.person_id,
+                csv_escape(&row.full_name),
+                row.generations,
+                row.descendants
+            ));
+        }
+
+        csv
+    }
+}
+
+/// Visningsnamn för en könskod i analysen
+pub fn sex_label(code: &str) -> &'static str {
+    Sex::from_db_str(code).display_name()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn person(id: i64, name: &str, sex: Sex, birth: Option<(i32, u32, u32)>, death: Option<(i32, u32, u32)>) -> Person {
+        let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+        p.id = Some(id);
+        p.sex = sex;
+        p.birth_date = birth.and_then(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d));
+        p.death_date = death.and_then(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d));
+        p
+    }
+
+    #[test]
+    fn test_compute() {
+        let mut persons = vec![
+            person(1, "Karl", Sex::Male, Some((1850, 3, 1)), Some((1920, 1, 1))),
+            person(2, "Anna", Sex::Female, Some((1855, 6, 1)), Some((1925, 6, 1))),
+            person(3, "Lisa", Sex::Female, Some((1880, 1, 1)), Some((1880, 5, 1))),
+            person(4, "Erik", Sex::Male, Some((1882, 2, 2)), Some((1950, 2, 1))),
+            person(5, "Maja", Sex::Female, Some((1905, 1, 1)), None),
+        ];
+        persons[0].birth_place = Some("Lund".into());
+        persons[1].birth_place = Some(" Lund ".into());
+        persons[2].birth_place = Some("Malmö".into());
+        persons[4].surname = Some("Holm".into());
+
+        let mut marriage = PersonRelationship::new(1, 2, RelationshipType::Spouse);
+        marriage.marriage_date = NaiveDate::from_ymd_opt(1878, 5, 5);
+        let relationships = vec![
+            marriage,
+            PersonRelationship::new(1, 3, RelationshipType::Parent),
+            PersonRelationship::new(2, 3, RelationshipType::Parent),
+            PersonRelationship::new(1, 4, RelationshipType::Parent),
+            PersonRelationship::new(2, 4, RelationshipType::Parent),
+            PersonRelationship::new(4, 5, RelationshipType::Parent),
+        ];
+
+        let analytics = Analytics::compute(&persons, &relationships);
+
+        assert_eq!(analytics.surnames_by_decade[0], SurnameDecade { surname: "Berg".into(), decade: 1850, count: 2 });
+        assert!(analytics.surnames_by_decade.contains(&SurnameDecade { surname: "Holm".into(), decade: 1900, count: 1 }));
+
+        let men_1850 = analytics.lifespan_by_decade.iter().find(|r| r.decade == 1850 && r.sex == "M").unwrap();
+        assert_eq!((men_1850.average_years, men_1850.count), (69.0, 1));
+
+        let mortality_1880 = analytics.child_mortality.iter().find(|r| r.decade == 1880).unwrap();
+        assert_eq!((mortality_1880.births, mortality_1880.died_under_1, mortality_1880.died_under_15), (2, 1, 1));
+        assert_eq!(mortality_1880.percent(), 50.0);
+
+        assert_eq!(analytics.first_marriage_age.len(), 2);
+        let women = analytics.first_marriage_age.iter().find(|r| r.sex == "F").unwrap();
+        assert_eq!(women.average_age, 22.0);
+
+        // Karl och Anna har två barn, Erik har bara en känd förälder till Maja
+        assert_eq!(analytics.children_per_couple, vec![ChildrenPerCouple { children: 2, couples: 1 }]);
+        assert_eq!(analytics.average_children_per_couple, 2.0);
+
+        assert_eq!(analytics.top_birth_places[0], PlaceCount { place: "Lund".into(), count: 2 });
+
+        let depths: Vec<(i64, usize, usize)> =
+            analytics.generation_depth.iter().map(|l| (l.person_id, l.generations, l.descendants)).collect();
+        assert_eq!(depths, vec![(2, 3, 3), (1, 3, 3)]);
+
+        let csv = analytics.to_csv();
+        assert!(csv.contains("Berg,1850,2\n"));
+        assert!(csv.contains("1880,2,1,1,50.0\n"));
+        assert!(csv.contains("Lund,2\n"));
+    }
+}
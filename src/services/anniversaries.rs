@@ -0,0 +1,451 @@
+//! Årsdagar: födelsedagar, dödsdagar och bröllopsdagar
+//!
+//! Räknar fram nästa återkommande datum för varje person med känt födelse-
+//! eller dödsdatum och för varje äktenskap med vigseldatum. Urvalet kan
+//! begränsas till bokmärkta personer eller en tagg. Årsdagarna kan även
+//! exporteras som iCalendar-fil (RFC 5545) med årlig upprepning, så att de
+//! syns i vanliga kalenderprogram.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+
+use crate::db::{Database, SearchFilter};
+use crate::models::{Person, RelationshipType};
+use crate::services::privacy::{PrivacyPolicy, RedactionStyle};
+
+/// Längsta rad i en iCalendar-fil (oktetter, exklusive radbrytning)
+const ICS_LINE_LIMIT: usize = 75;
+
+/// Typ av årsdag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnniversaryKind {
+    Birth,
+    Death,
+    Marriage,
+}
+
+impl AnniversaryKind {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            AnniversaryKind::Birth => "Födelsedag",
+            AnniversaryKind::Death => "Dödsdag",
+            AnniversaryKind::Marriage => "Bröllopsdag",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            AnniversaryKind::Birth => "🎂",
+            AnniversaryKind::Death => "✝",
+            AnniversaryKind::Marriage => "💍",
+        }
+    }
+
+    fn uid_key(&self) -> &'static str {
+        match self {
+            AnniversaryKind::Birth => "birth",
+            AnniversaryKind::Death => "death",
+            AnniversaryKind::Marriage => "marriage",
+        }
+    }
+}
+
+/// Vilka personer som tas med
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnniversaryScope {
+    #[default]
+    All,
+    Bookmarked,
+    Tag(i64),
+}
+
+/// En årsdag och dess nästa infallande datum
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anniversary {
+    pub kind: AnniversaryKind,
+    pub person_id: i64,
+    pub name: String,
+    /// Make/maka vid bröllopsdagar
+    pub partner_id: Option<i64>,
+    pub partner_name: Option<String>,
+    /// Ursprungligt datum
+    pub date: NaiveDate,
+    /// Nästa gång årsdagen infaller
+    pub next: NaiveDate,
+    /// Antal år vid nästa tillfälle
+    pub years: i32,
+    /// Antas personen leva (endast födelsedagar)
+    pub living: bool,
+    pub place: Option<String>,
+}
+
+impl Anniversary {
+    /// Jämna år lyfts fram i vyn
+    pub fn is_round(&self) -> bool {
+        self.years % 10 == 0 || self.years % 25 == 0
+    }
+
+    /// "Anna Berg fyller 80", "Karl Berg dog för 100 år sedan" osv.
+    pub fn title(&self) -> String {
+        match self.kind {
+            AnniversaryKind::Birth if self.living => format!("{} fyller {}", self.name, self.years),
+            AnniversaryKind::Birth => format!("{} föddes för {} år sedan", self.name, self.years),
+            AnniversaryKind::Death => format!("{} dog för {} år sedan", self.name, self.years),
+            AnniversaryKind::Marriage => format!(
+                "{} och {} gifte sig för {} år sedan",
+                self.name,
+                self.partner_name.as_deref().unwrap_or("?"),
+                self.years
+            ),
+        }
+    }
+
+    /// Rubrik utan antal år, för återkommande kalenderhändelser
+    fn summary(&self) -> String {
+        let names = match &self.partner_name {
+            Some(partner) => format!("{} och {}", self.name, partner),
+            None => self.name.clone(),
+        };
+        format!("{}: {} ({})", self.kind.display_name(), names, self.date.year())
+    }
+}
+
+pub struct AnniversaryService<'a> {
+    db: &'a Database,
+    privacy: Option<PrivacyPolicy>,
+}
+
+impl<'a> AnniversaryService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, privacy: None }
+    }
+
+    /// Utelämna personer som policyn maskerar (t.ex. vid export)
+    pub fn with_privacy(mut self, policy: PrivacyPolicy) -> Self {
+        self.privacy = Some(policy);
+        self
+    }
+
+    /// Årsdagar som infaller från och med `from` och `days` dagar framåt,
+    /// sorterade på datum
+    pub fn upcoming(&self, from: NaiveDate, days: i64, scope: AnniversaryScope) -> Result<Vec<Anniversary>> {
+        let until = from + Duration::days(days);
+        let mut list: Vec<Anniversary> = self
+            .collect(from, scope)?
+            .into_iter()
+            .filter(|a| a.next < until)
+            .collect();
+        list.sort_by(|a, b| a.next.cmp(&b.next).then_with(|| a.name.cmp(&b.name)));
+        Ok(list)
+    }
+
+    /// Alla årsdagar i urvalet, sorterade på månad och dag. `today` avgör
+    /// nästa tillfälle och ålder, och ska vara användarens lokala datum.
+    pub fn all(&self, today: NaiveDate, scope: AnniversaryScope) -> Result<Vec<Anniversary>> {
+        let mut list = self.collect(today, scope)?;
+        list.sort_by(|a, b| {
+            (a.date.month(), a.date.day(), &a.name).cmp(&(b.date.month(), b.date.day(), &b.name))
+        });
+        Ok(list)
+    }
+
+    /// Exportera urvalets årsdagar som iCalendar-fil. Returnerar antal händelser.
+    pub fn export_ics(&self, path: &Path, today: NaiveDate, scope: AnniversaryScope) -> Result<usize> {
+        let list = self.all(today, scope)?;
+        std::fs::write(path, to_ics(&list, Utc::now()))?;
+        Ok(list.len())
+    }
+
+    fn collect(&self, from: NaiveDate, scope: AnniversaryScope) -> Result<Vec<Anniversary>> {
+        let persons = match scope {
+            AnniversaryScope::All => self.db.persons().find_all()?,
+            AnniversaryScope::Bookmarked => self.db.persons().get_bookmarked()?,
+            AnniversaryScope::Tag(tag_id) => self.db.persons().advanced_search(&SearchFilter {
+                tag_ids: vec![tag_id],
+                ..Default::default()
+            })?,
+        };
+        let living = PrivacyPolicy::from_config(self.db, RedactionStyle::None)?;
+        let included = |person: &Person| self.privacy.as_ref().map_or(true, |p| !p.masks(person));
+
+        let mut list = Vec::new();
+        let mut in_scope = HashSet::new();
+        for person in &persons {
+            let Some(id) = person.id else { continue };
+            in_scope.insert(id);
+            if !included(person) {
+                continue;
+            }
+            let name = person.full_name();
+
+            if let Some(date) = person.birth_date {
+                let is_living = living.is_presumed_living(person);
+                if let Some(a) = anniversary(AnniversaryKind::Birth, id, &name, date, from) {
+                    list.push(Anniversary {
+                        living: is_living,
+                        place: person.birth_place.clone(),
+                        ..a
+                    });
+                }
+            }
+            if let Some(date) = person.death_date {
+                list.extend(anniversary(AnniversaryKind::Death, id, &name, date, from));
+            }
+        }
+
+        let graph = self.db.kinship()?;
+        for rel in self.db.relationships().find_all()? {
+            let Some(date) = rel.marriage_date else { continue };
+            if rel.relationship_a_to_b != RelationshipType::Spouse
+                || !(in_scope.contains(&rel.person_a_id) || in_scope.contains(&rel.person_b_id))
+            {
+                continue;
+            }
+            let (Some(a), Some(b)) = (graph.person(rel.person_a_id), graph.person(rel.person_b_id)) else {
+                continue;
+            };
+            if !included(a) || !included(b) {
+                continue;
+            }
+            if let Some(item) = anniversary(AnniversaryKind::Marriage, rel.person_a_id, &a.full_name(), date, from) {
+                list.push(Anniversary {
+                    partner_id: Some(rel.person_b_id),
+                    partner_name: Some(b.full_name()),
+                    place: rel.marriage_place.clone(),
+                    ..item
+                });
+            }
+        }
+
+        Ok(list)
+    }
+}
+
+/// Årsdagen med nästa datum från och med `from`, eller `None` om datumet
+/// ligger i framtiden
+fn anniversary(kind: AnniversaryKind, person_id: i64, name: &str, date: NaiveDate, from: NaiveDate) -> Option<Anniversary> {
+    let next = next_occurrence(date, from);
+    let years = next.year() - date.year();
+    (years > 0).then(|| Anniversary {
+        kind,
+        person_id,
+        name: name.to_string(),
+        partner_id: None,
+        partner_name: None,
+        date,
+        next,
+        years,
+        living: false,
+        place: None,
+    })
+}
+
+/// Nästa gång månad och dag i `date` infaller, från och med `from`.
+/// Den 29 februari firas den 28 februari under vanliga år.
+pub fn next_occurrence(date: NaiveDate, from: NaiveDate) -> NaiveDate {
+    let this_year = on_year(date, from.year());
+    if this_year >= from {
+        this_year
+    } else {
+        on_year(date, from.year() + 1)
+    }
+}
+
+fn on_year(date: NaiveDate, year: i32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, date.month(), date.day())
+        .or_else(|| NaiveDate::from_ymd_opt(year, 2, 28))
+        .unwrap_or(date)
+}
+
+/// Bygg en iCalendar-fil med en årligen återkommande heldagshändelse per årsdag
+pub fn to_ics(list: &[Anniversary], now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Genlib//Genlib Desktop//SV".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Släktens årsdagar".to_string(),
+    ];
+
+    for item in list {
+        let uid = match item.partner_id {
+            Some(partner) => format!("genlib-{}-{}-{}@genlib", item.kind.uid_key(), item.person_id, partner),
+            None => format!("genlib-{}-{}@genlib", item.kind.uid_key(), item.person_id),
+        };
+        let rrule = if item.date.month() == 2 && item.date.day() == 29 {
+            "RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1"
+        } else {
+            "RRULE:FREQ=YEARLY"
+        };
+        let mut description = format!("{} {}", item.kind.display_name(), item.date.format("%Y-%m-%d"));
+        if let Some(place) = &item.place {
+            description.push_str(&format!(", {}", place));
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", item.date.format("%Y%m%d")));
+        lines.push(rrule.to_string());
+        lines.push(format!("SUMMARY:{}", escape_text(&item.summary())));
+        lines.push(format!("DESCRIPTION:{}", escape_text(&description)));
+        lines.push(format!("CATEGORIES:{}", escape_text(item.kind.display_name())));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|l| fold_line(l)).collect::<Vec<_>>().join("")
+}
+
+/// Escapa TEXT-värden enligt RFC 5545 (3.3.11)
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Vik raden vid 75 oktetter utan att dela tecken, avslutad med CRLF
+fn fold_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > ICS_LINE_LIMIT {
+            out.push_str("\r\n ");
+            // Inledande mellanslag räknas in i nästa rad
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PersonRelationship, Privacy, Tag};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn create(db: &Database, name: &str, birth: Option<NaiveDate>, death: Option<NaiveDate>) -> i64 {
+        let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+        p.birth_date = birth;
+        p.death_date = death;
+        db.persons().create(&mut p).unwrap();
+        p.id.unwrap()
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        let from = date(2025, 6, 15);
+        assert_eq!(next_occurrence(date(1900, 6, 15), from), date(2025, 6, 15));
+        assert_eq!(next_occurrence(date(1900, 6, 14), from), date(2026, 6, 14));
+        assert_eq!(next_occurrence(date(1904, 2, 29), from), date(2026, 2, 28));
+        assert_eq!(next_occurrence(date(1904, 2, 29), date(2027, 6, 1)), date(2028, 2, 29));
+    }
+
+    #[test]
+    fn test_upcoming_and_scope() {
+        let db = Database::open_in_memory().unwrap();
+        let from = date(2025, 6, 1);
+        let karl = create(&db, "Karl", Some(date(1875, 6, 3)), Some(date(1950, 12, 24)));
+        let maria = create(&db, "Maria", Some(date(1880, 1, 1)), Some(date(1925, 6, 20)));
+        let anna = create(&db, "Anna", Some(date(1945, 6, 10)), None);
+        create(&db, "Okänd", None, None);
+
+        let mut marriage = PersonRelationship::new(karl, maria, RelationshipType::Spouse);
+        marriage.marriage_date = Some(date(1900, 6, 30));
+        marriage.marriage_place = Some("Lund".into());
+        db.relationships().create(&mut marriage).unwrap();
+
+        let service = AnniversaryService::new(&db);
+        let month = service.upcoming(from, 30, AnniversaryScope::All).unwrap();
+        let titles: Vec<String> = month.iter().map(|a| a.title()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Karl Berg föddes för 150 år sedan",
+                "Anna Berg fyller 80",
+                "Maria Berg dog för 100 år sedan",
+                "Karl Berg och Maria Berg gifte sig för 125 år sedan",
+            ]
+        );
+        assert!(month.iter().all(|a| a.is_round()));
+        assert_eq!(service.upcoming(from, 7, AnniversaryScope::All).unwrap().len(), 1);
+
+        db.persons().toggle_bookmark(maria).unwrap();
+        let bookmarked = service.upcoming(from, 365, AnniversaryScope::Bookmarked).unwrap();
+        assert_eq!(bookmarked.len(), 3);
+        assert!(bookmarked.iter().any(|a| a.kind == AnniversaryKind::Marriage));
+
+        let mut tag = Tag::new("Emigranter".into(), "#4A90D9".into());
+        let tag_id = db.tags().create(&mut tag).unwrap();
+        db.tags().add_to_person(tag_id, anna).unwrap();
+        let tagged = service.upcoming(from, 365, AnniversaryScope::Tag(tag_id)).unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].person_id, anna);
+
+        // Levande personer utelämnas när en sekretesspolicy används
+        let policy = PrivacyPolicy::new(100, RedactionStyle::Omit);
+        let shared = AnniversaryService::new(&db).with_privacy(policy).all(from, AnniversaryScope::All).unwrap();
+        assert!(shared.iter().all(|a| a.person_id != anna));
+        assert_eq!(shared.len(), 5);
+
+        let mut private = db.persons().find_by_id(maria).unwrap().unwrap();
+        private.privacy = Privacy::Private;
+        db.persons().update(&mut private).unwrap();
+        let policy = PrivacyPolicy::new(100, RedactionStyle::Omit);
+        let shared = AnniversaryService::new(&db).with_privacy(policy).all(from, AnniversaryScope::All).unwrap();
+        assert_eq!(shared.len(), 2);
+    }
+
+    #[test]
+    fn test_ics_output() {
+        let item = Anniversary {
+            kind: AnniversaryKind::Birth,
+            person_id: 7,
+            name: "Anna, \"Annie\"; Berg".into(),
+            partner_id: None,
+            partner_name: None,
+            date: date(1904, 2, 29),
+            next: date(2026, 2, 28),
+            years: 122,
+            living: false,
+            place: Some("Väster\\by socken i en mycket lång beskrivning som måste vikas över flera rader".into()),
+        };
+        let now = Utc::now();
+        let ics = to_ics(&[item], now);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:genlib-birth-7@genlib\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:19040229\r\n"));
+        assert!(ics.contains("RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1\r\n"));
+        assert!(ics.contains(&format!("DTSTAMP:{}\r\n", now.format("%Y%m%dT%H%M%SZ"))));
+        assert!(ics.contains("SUMMARY:Födelsedag: Anna\\, \"Annie\"\\; Berg (1904)\r\n"));
+        assert!(ics.contains("Väster\\\\by"));
+
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= ICS_LINE_LIMIT, "för lång rad: {}", line);
+        }
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains("flera rader\r\n"));
+    }
+}
@@ -0,0 +1,896 @@
+//! Export av familjeträd till SVG och PDF
+//!
+//! Trädets noder och länkar ritas med de positioner som layouten gav, så
+//! utskriften ser ut som vyn på skärmen. PDF kan antingen delas upp på flera
+//! A4/A3-sidor som tejpas ihop till en väggtavla, eller bli en enda stor
+//! affischsida för tryckeri.
+
+use std::collections::HashMap;
+use std::io::{BufWriter, Cursor};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use image::{imageops::FilterType, ImageFormat, RgbImage};
+use printpdf::{
+    path::PaintMode, Color, ColorBits, ColorSpace, Image, ImageTransform, ImageXObject, IndirectFontRef,
+    Line, Mm, PdfDocument, PdfLayerReference, Point, Px, Rect, Rgb,
+};
+
+use crate::db::Database;
+use crate::models::{Person, Sex};
+use crate::services::{pdf_layout, FamilyTree, LinkType, PrivacyPolicy, RedactionStyle, TreeMode};
+
+/// Millimeter per layoutenhet (en nod på 140 enheter blir 35 mm bred)
+const MM_PER_UNIT: f32 = 0.25;
+/// Marginal runt diagrammet (layoutenheter) och på PDF-sidor (mm)
+const CHART_MARGIN: f32 = 40.0;
+const PAGE_MARGIN_MM: f32 = 10.0;
+/// Största sidstorlek som PDF tillåter (200 tum)
+const MAX_PAGE_MM: f32 = 5080.0;
+
+const BOX_WIDTH: f32 = 140.0;
+const PHOTO_WIDTH: f32 = 36.0;
+const PHOTO_HEIGHT: f32 = 44.0;
+const PADDING: f32 = 8.0;
+const NAME_SIZE: f32 = 12.0;
+const DETAIL_SIZE: f32 = 10.0;
+const LINE_SPACING: f32 = 4.0;
+
+/// Exportformat för träddiagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartFormat {
+    Svg,
+    Pdf,
+}
+
+impl ChartFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ChartFormat::Svg => "svg",
+            ChartFormat::Pdf => "pdf",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ChartFormat::Svg => "SVG",
+            ChartFormat::Pdf => "PDF",
+        }
+    }
+}
+
+/// Pappersformat för uppdelad PDF
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaperSize {
+    #[default]
+    A4,
+    A3,
+}
+
+impl PaperSize {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A3 => "A3",
+        }
+    }
+
+    pub fn all() -> &'static [PaperSize] {
+        &[PaperSize::A4, PaperSize::A3]
+    }
+
+    /// (bredd, höjd) i mm, stående
+    pub fn dimensions_mm(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+        }
+    }
+}
+
+/// Hur diagrammet fördelas på PDF-sidor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageMode {
+    /// Delas upp på flera sidor av valt pappersformat
+    #[default]
+    Tiled,
+    /// En enda sida i diagrammets storlek
+    Poster,
+}
+
+impl PageMode {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PageMode::Tiled => "Flera sidor",
+            PageMode::Poster => "Affisch (en sida)",
+        }
+    }
+
+    pub fn all() -> &'static [PageMode] {
+        &[PageMode::Tiled, PageMode::Poster]
+    }
+}
+
+/// Färgschema för utskrift
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartColorScheme {
+    /// Som på skärmen: fokuspersonen blå, övriga grå
+    #[default]
+    Classic,
+    /// Ljusblått för män, ljusrosa för kvinnor
+    BySex,
+    /// Svartvitt, för skrivare utan färg
+    Monochrome,
+}
+
+impl ChartColorScheme {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ChartColorScheme::Classic => "Klassiskt",
+            ChartColorScheme::BySex => "Efter kön",
+            ChartColorScheme::Monochrome => "Svartvitt",
+        }
+    }
+
+    pub fn all() -> &'static [ChartColorScheme] {
+        &[ChartColorScheme::Classic, ChartColorScheme::BySex, ChartColorScheme::Monochrome]
+    }
+}
+
+/// Inställningar för export av träddiagram
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    pub show_photos: bool,
+    pub show_years: bool,
+    pub show_places: bool,
+    pub color_scheme: ChartColorScheme,
+    pub paper: PaperSize,
+    pub page_mode: PageMode,
+    pub landscape: bool,
+    /// Hur levande och privata personer visas
+    pub redaction: RedactionStyle,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            show_photos: true,
+            show_years: true,
+            show_places: false,
+            color_scheme: ChartColorScheme::default(),
+            paper: PaperSize::default(),
+            page_mode: PageMode::default(),
+            landscape: true,
+            redaction: RedactionStyle::default(),
+        }
+    }
+}
+
+/// Resultat av export av träddiagram
+#[derive(Debug)]
+pub struct ChartExportResult {
+    pub format: ChartFormat,
+    pub person_count: usize,
+    pub page_count: usize,
+    pub file_size: usize,
+}
+
+impl ChartExportResult {
+    pub fn summary(&self) -> String {
+        match self.format {
+            ChartFormat::Svg => format!("Träd exporterat: {} personer, {} bytes", self.person_count, self.file_size),
+            ChartFormat::Pdf => format!(
+                "Träd exporterat: {} personer på {} sidor, {} bytes",
+                self.person_count, self.page_count, self.file_size
+            ),
+        }
+    }
+}
+
+type Rgb8 = (u8, u8, u8);
+
+/// En ruta i diagrammet (koordinater i layoutenheter, övre vänstra hörnet)
+#[derive(Debug, Clone)]
+struct ChartBox {
+    person_id: i64,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    /// Första raden är namnet
+    lines: Vec<String>,
+    has_photo: bool,
+    fill: Rgb8,
+    stroke: Rgb8,
+    stroke_width: f32,
+    text: Rgb8,
+}
+
+#[derive(Debug, Clone)]
+struct ChartLine {
+    points: Vec<(f32, f32)>,
+    color: Rgb8,
+    width: f32,
+}
+
+/// Färdigberäknat diagram, oberoende av utdataformat
+#[derive(Debug, Default)]
+struct Chart {
+    width: f32,
+    height: f32,
+    boxes: Vec<ChartBox>,
+    lines: Vec<ChartLine>,
+}
+
+/// Tjänst för export av träddiagram
+pub struct ChartExportService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ChartExportService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Exportera trädet till fil
+    pub fn export(
+        &self,
+        tree: &FamilyTree,
+        format: ChartFormat,
+        options: &ChartOptions,
+        path: &Path,
+    ) -> Result<ChartExportResult> {
+        let policy = PrivacyPolicy::from_config(self.db, options.redaction)?;
+        let photos = if options.show_photos {
+            self.load_photos(tree, &policy)
+        } else {
+            HashMap::new()
+        };
+        let chart = build_chart(tree, options, &policy, &photos);
+
+        let (bytes, page_count) = match format {
+            ChartFormat::Svg => (render_svg(&chart, &photos, &|_| None).into_bytes(), 1),
+            ChartFormat::Pdf => render_pdf(&chart, &photos, options)?,
+        };
+        std::fs::write(path, &bytes).context("Kunde inte skriva fil")?;
+
+        Ok(ChartExportResult {
+            format,
+            person_count: chart.boxes.len(),
+            page_count,
+            file_size: bytes.len(),
+        })
+    }
+
+    /// Trädet som SVG där rutorna länkar till `href(person_id)`
+    pub(crate) fn svg_with_links(
+        &self,
+        tree: &FamilyTree,
+        options: &ChartOptions,
+        href: &dyn Fn(i64) -> Option<String>,
+    ) -> Result<String> {
+        let policy = PrivacyPolicy::from_config(self.db, options.redaction)?;
+        let photos = if options.show_photos {
+            self.load_photos(tree, &policy)
+        } else {
+            HashMap::new()
+        };
+        let chart = build_chart(tree, options, &policy, &photos);
+        Ok(render_svg(&chart, &photos, href))
+    }
+
+    /// Läs in profilbilder som små miniatyrer. Privata personer får ingen bild.
+    fn load_photos(&self, tree: &FamilyTree, policy: &PrivacyPolicy) -> HashMap<i64, RgbImage> {
+        let Ok(config) = self.db.config().get() else {
+            return HashMap::new();
+        };
+
+        let mut photos = HashMap::new();
+        for (id, node) in &tree.nodes {
+            if policy.masks(&node.person) {
+                continue;
+            }
+            let Some(ref relative) = node.person.profile_image_path else {
+                continue;
+            };
+            let path = config.media_directory_path.join(relative);
+            if let Some(thumbnail) = load_thumbnail(&path, (PHOTO_WIDTH * 2.0) as u32, (PHOTO_HEIGHT * 2.0) as u32) {
+                photos.insert(*id, thumbnail);
+            }
+        }
+        photos
+    }
+}
+
+/// Läs en bild och beskär den till miniatyr i angiven storlek (pixlar)
+pub(crate) fn load_thumbnail(path: &Path, width: u32, height: u32) -> Option<RgbImage> {
+    let data = std::fs::read(path).ok()?;
+    let image = image::load_from_memory(&data).ok()?;
+    Some(image.resize_to_fill(width, height, FilterType::Triangle).to_rgb8())
+}
+
+/// Placera en bild på PDF-sidan med nedre vänstra hörnet i (x, y)
+pub(crate) fn add_pdf_image(layer: &PdfLayerReference, photo: &RgbImage, x: Mm, y: Mm, width_mm: f32) {
+    let image = Image::from(ImageXObject {
+        width: Px(photo.width() as usize),
+        height: Px(photo.height() as usize),
+        color_space: ColorSpace::Rgb,
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data: photo.as_raw().clone(),
+        image_filter: None,
+        smask: None,
+        clipping_bbox: None,
+    });
+    image.add_to_layer(
+        layer.clone(),
+        ImageTransform {
+            translate_x: Some(x),
+            translate_y: Some(y),
+            dpi: Some(photo.width() as f32 * 25.4 / width_mm),
+            ..Default::default()
+        },
+    );
+}
+
+/// Beräkna rutor och linjer för trädet. Diagrammet flyttas så att det
+/// börjar i (marginal, marginal).
+fn build_chart(
+    tree: &FamilyTree,
+    options: &ChartOptions,
+    policy: &PrivacyPolicy,
+    photos: &HashMap<i64, RgbImage>,
+) -> Chart {
+    let mut ids: Vec<i64> = tree.nodes.keys().copied().collect();
+    ids.sort();
+
+    let mut boxes = Vec::new();
+    for id in ids {
+        let node = &tree.nodes[&id];
+        let Some(person) = policy.redact(&node.person) else {
+            continue;
+        };
+
+        let mut lines = vec![person.full_name()];
+        if options.show_years {
+            let years = format_years(&person);
+            if !years.is_empty() {
+                lines.push(years);
+            }
+        }
+        if options.show_places {
+            if let Some(place) = person.birth_place.as_ref().filter(|p| !p.trim().is_empty()) {
+                lines.push(place.clone());
+            }
+        }
+
+        let has_photo = photos.contains_key(&id);
+        let width = if has_photo { BOX_WIDTH + PHOTO_WIDTH + PADDING } else { BOX_WIDTH };
+        let text_height = NAME_SIZE + (lines.len() - 1) as f32 * (DETAIL_SIZE + LINE_SPACING);
+        let content_height = if has_photo { text_height.max(PHOTO_HEIGHT) } else { text_height };
+        let height = content_height + 2.0 * PADDING;
+
+        let is_focus = tree.focus_person_id == Some(id);
+        let (fill, stroke, stroke_width, text) = box_colors(options.color_scheme, person.sex, is_focus);
+
+        boxes.push(ChartBox {
+            person_id: id,
+            x: node.x - width / 2.0,
+            y: node.y - height / 2.0,
+            width,
+            height,
+            lines,
+            has_photo,
+            fill,
+            stroke,
+            stroke_width,
+            text,
+        });
+    }
+
+    let centers: HashMap<i64, (f32, f32)> = boxes
+        .iter()
+        .map(|b| (b.person_id, (b.x + b.width / 2.0, b.y + b.height / 2.0)))
+        .collect();
+    let horizontal = matches!(tree.mode, TreeMode::Pedigree | TreeMode::Fan);
+
+    let mut seen = std::collections::HashSet::new();
+    let mut lines = Vec::new();
+    for link in &tree.links {
+        if !seen.insert((link.from_id, link.to_id, link.link_type as u8)) {
+            continue;
+        }
+        let (Some(&from), Some(&to)) = (centers.get(&link.from_id), centers.get(&link.to_id)) else {
+            continue;
+        };
+        let (color, width) = link_style(options.color_scheme, link.link_type);
+        let points = match link.link_type {
+            LinkType::Parent if horizontal => {
+                let mid_x = (from.0 + to.0) / 2.0;
+                vec![to, (mid_x, to.1), (mid_x, from.1), from]
+            }
+            LinkType::Parent => {
+                let mid_y = (from.1 + to.1) / 2.0;
+                vec![from, (from.0, mid_y), (to.0, mid_y), to]
+            }
+            _ => vec![from, to],
+        };
+        lines.push(ChartLine { points, color, width });
+    }
+
+    // Upprepade förfäder ritas som en hänvisning till första förekomsten
+    for reference in &tree.references {
+        let Some(original) = tree.nodes.get(&reference.person_id) else {
+            continue;
+        };
+        let (Some(person), Some(&child)) = (policy.redact(&original.person), centers.get(&reference.child_id)) else {
+            continue;
+        };
+
+        let lines_text = vec![
+            person.full_name(),
+            format!("se nr {}", original.ahnentafel.unwrap_or_default()),
+        ];
+        let height = NAME_SIZE + DETAIL_SIZE + LINE_SPACING + 2.0 * PADDING;
+        let (fill, stroke, _, text) = box_colors(ChartColorScheme::Monochrome, person.sex, false);
+        let (color, width) = link_style(options.color_scheme, LinkType::Parent);
+        let mid_x = (reference.x + child.0) / 2.0;
+        lines.push(ChartLine {
+            points: vec![child, (mid_x, child.1), (mid_x, reference.y), (reference.x, reference.y)],
+            color,
+            width,
+        });
+        boxes.push(ChartBox {
+            person_id: reference.person_id,
+            x: reference.x - BOX_WIDTH / 2.0,
+            y: reference.y - height / 2.0,
+            width: BOX_WIDTH,
+            height,
+            lines: lines_text,
+            has_photo: false,
+            fill,
+            stroke: if options.color_scheme == ChartColorScheme::Monochrome { stroke } else { (156, 163, 175) },
+            stroke_width: 1.0,
+            text,
+        });
+    }
+
+    let mut chart = Chart { width: 0.0, height: 0.0, boxes, lines };
+    if chart.boxes.is_empty() {
+        return chart;
+    }
+
+    let min_x = chart.boxes.iter().map(|b| b.x).fold(f32::MAX, f32::min);
+    let min_y = chart.boxes.iter().map(|b| b.y).fold(f32::MAX, f32::min);
+    let max_x = chart.boxes.iter().map(|b| b.x + b.width).fold(f32::MIN, f32::max);
+    let max_y = chart.boxes.iter().map(|b| b.y + b.height).fold(f32::MIN, f32::max);
+    let (dx, dy) = (CHART_MARGIN - min_x, CHART_MARGIN - min_y);
+    for b in &mut chart.boxes {
+        b.x += dx;
+        b.y += dy;
+    }
+    for line in &mut chart.lines {
+        for point in &mut line.points {
+            point.0 += dx;
+            point.1 += dy;
+        }
+    }
+    chart.width = max_x - min_x + 2.0 * CHART_MARGIN;
+    chart.height = max_y - min_y + 2.0 * CHART_MARGIN;
+    chart
+}
+
+fn format_years(person: &Person) -> String {
+    match (person.birth_date, person.death_date) {
+        (Some(b), Some(d)) => format!("{} - {}", b.format("%Y"), d.format("%Y")),
+        (Some(b), None) => format!("f. {}", b.format("%Y")),
+        (None, Some(d)) => format!("d. {}", d.format("%Y")),
+        (None, None) => String::new(),
+    }
+}
+
+/// (fyllnad, kant, kantbredd, text)
+fn box_colors(scheme: ChartColorScheme, sex: Sex, is_focus: bool) -> (Rgb8, Rgb8, f32, Rgb8) {
+    const DARK: Rgb8 = (17, 24, 39);
+    const BORDER: Rgb8 = (209, 213, 219);
+    const PRIMARY: Rgb8 = (59, 130, 246);
+
+    match scheme {
+        ChartColorScheme::Classic if is_focus => (PRIMARY, PRIMARY, 1.0, (255, 255, 255)),
+        ChartColorScheme::Classic => ((243, 244, 246), BORDER, 1.0, DARK),
+        ChartColorScheme::BySex => {
+            let fill = match sex {
+                Sex::Male => (219, 234, 254),
+                Sex::Female => (252, 231, 243),
+                Sex::Unknown => (243, 244, 246),
+            };
+            if is_focus {
+                (fill, PRIMARY, 3.0, DARK)
+            } else {
+                (fill, BORDER, 1.0, DARK)
+            }
+        }
+        ChartColorScheme::Monochrome => ((255, 255, 255), (0, 0, 0), if is_focus { 3.0 } else { 1.0 }, (0, 0, 0)),
+    }
+}
+
+fn link_style(scheme: ChartColorScheme, link_type: LinkType) -> (Rgb8, f32) {
+    match (scheme, link_type) {
+        (ChartColorScheme::Monochrome, LinkType::Sibling) => ((0, 0, 0), 1.0),
+        (ChartColorScheme::Monochrome, _) => ((0, 0, 0), 1.5),
+        (_, LinkType::Parent) => ((107, 114, 128), 2.0),
+        (_, LinkType::Spouse) => ((239, 68, 68), 2.0),
+        (_, LinkType::Sibling) => ((59, 130, 246), 1.0),
+    }
+}
+
+// ── SVG ──────────────────────────────────────────────────────────────────────
+
+fn render_svg(chart: &Chart, photos: &HashMap<i64, RgbImage>, href: &dyn Fn(i64) -> Option<String>) -> String {
+    let mut svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.0} {h:.0}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n\
+         <g font-family=\"Helvetica, Arial, sans-serif\">\n",
+        w = chart.width,
+        h = chart.height
+    );
+
+    for line in &chart.lines {
+        let points: Vec<String> = line.points.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+        svg.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+            points.join(" "),
+            hex(line.color),
+            line.width
+        ));
+    }
+
+    for b in &chart.boxes {
+        let link = href(b.person_id);
+        if let Some(ref link) = link {
+            svg.push_str(&format!("<a href=\"{}\">\n", xml_escape(link)));
+        }
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"8\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+            b.x,
+            b.y,
+            b.width,
+            b.height,
+            hex(b.fill),
+            hex(b.stroke),
+            b.stroke_width
+        ));
+
+        let mut text_x = b.x + PADDING;
+        if let Some(photo) = photos.get(&b.person_id).filter(|_| b.has_photo) {
+            let mut png = Vec::new();
+            if photo.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).is_ok() {
+                svg.push_str(&format!(
+                    "<image x=\"{:.1}\" y=\"{:.1}\" width=\"{}\" height=\"{}\" href=\"data:image/png;base64,{}\"/>\n",
+                    b.x + PADDING,
+                    b.y + PADDING,
+                    PHOTO_WIDTH,
+                    PHOTO_HEIGHT,
+                    base64_encode(&png)
+                ));
+            }
+            text_x += PHOTO_WIDTH + PADDING;
+        }
+
+        let mut baseline = b.y + PADDING + NAME_SIZE;
+        for (i, text) in b.lines.iter().enumerate() {
+            let (size, weight) = if i == 0 { (NAME_SIZE, " font-weight=\"bold\"") } else { (DETAIL_SIZE, "") };
+            if i > 0 {
+                baseline += DETAIL_SIZE + LINE_SPACING;
+            }
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{}\"{} fill=\"{}\">{}</text>\n",
+                text_x,
+                baseline - 2.0,
+                size,
+                weight,
+                hex(b.text),
+                xml_escape(text)
+            ));
+        }
+        if link.is_some() {
+            svg.push_str("</a>\n");
+        }
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+fn hex((r, g, b): Rgb8) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub(crate) fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Base64 (RFC 4648) för inbäddade bilder
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+// ── PDF ──────────────────────────────────────────────────────────────────────
+
+/// Sidindelning: (sidbredd, sidhöjd, kolumner, rader, mm per layoutenhet)
+fn page_grid(chart: &Chart, options: &ChartOptions) -> (f32, f32, usize, usize, f32) {
+    let content_w = chart.width * MM_PER_UNIT;
+    let content_h = chart.height * MM_PER_UNIT;
+
+    match options.page_mode {
+        PageMode::Poster => {
+            let largest = content_w.max(content_h) + 2.0 * PAGE_MARGIN_MM;
+            let scale = if largest > MAX_PAGE_MM { MAX_PAGE_MM / largest } else { 1.0 };
+            let page_w = (content_w * scale + 2.0 * PAGE_MARGIN_MM).max(100.0);
+            let page_h = (content_h * scale + 2.0 * PAGE_MARGIN_MM).max(100.0);
+            (page_w, page_h, 1, 1, MM_PER_UNIT * scale)
+        }
+        PageMode::Tiled => {
+            let (w, h) = options.paper.dimensions_mm();
+            let (page_w, page_h) = if options.landscape { (h, w) } else { (w, h) };
+            let area_w = page_w - 2.0 * PAGE_MARGIN_MM;
+            let area_h = page_h - 2.0 * PAGE_MARGIN_MM;
+            let cols = ((content_w / area_w).ceil() as usize).max(1);
+            let rows = ((content_h / area_h).ceil() as usize).max(1);
+            (page_w, page_h, cols, rows, MM_PER_UNIT)
+        }
+    }
+}
+
+fn pdf_color((r, g, b): Rgb8) -> Color {
+    Color::Rgb(Rgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, None))
+}
+
+/// Rendera till PDF. Returnerar filinnehåll och antal sidor.
+fn render_pdf(chart: &Chart, photos: &HashMap<i64, RgbImage>, options: &ChartOptions) -> Result<(Vec<u8>, usize)> {
+    let (page_w, page_h, cols, rows, scale) = page_grid(chart, options);
+    let area_w = page_w - 2.0 * PAGE_MARGIN_MM;
+    let area_h = page_h - 2.0 * PAGE_MARGIN_MM;
+
+    let (doc, first_page, first_layer) = PdfDocument::new("Släktträd", Mm(page_w), Mm(page_h), "Lager 1");
+    let (font, font_bold) = pdf_layout::embed_fonts(&doc)?;
+
+    for row in 0..rows {
+        for col in 0..cols {
+            let layer = if row == 0 && col == 0 {
+                doc.get_page(first_page).get_layer(first_layer)
+            } else {
+                let (page, layer) = doc.add_page(Mm(page_w), Mm(page_h), "Lager 1");
+                doc.get_page(page).get_layer(layer)
+            };
+
+            let tile = Tile {
+                offset_x: col as f32 * area_w,
+                offset_y: row as f32 * area_h,
+                page_h,
+                scale,
+            };
+            draw_tile(&layer, chart, photos, &tile, (area_w, area_h), (&font, &font_bold));
+
+            if cols * rows > 1 {
+                layer.set_fill_color(pdf_color((107, 114, 128)));
+                layer.use_text(
+                    format!("Rad {} av {}, kolumn {} av {}", row + 1, rows, col + 1, cols),
+                    7.0,
+                    Mm(PAGE_MARGIN_MM),
+                    Mm(PAGE_MARGIN_MM / 2.0),
+                    &font,
+                );
+            }
+        }
+    }
+
+    let mut bytes = Vec::new();
+    doc.save(&mut BufWriter::new(Cursor::new(&mut bytes)))
+        .context("Kunde inte spara PDF")?;
+    Ok((bytes, cols * rows))
+}
+
+/// Var på diagrammet en sida börjar (mm) och hur det skalas
+struct Tile {
+    offset_x: f32,
+    offset_y: f32,
+    page_h: f32,
+    scale: f32,
+}
+
+impl Tile {
+    /// Layoutkoordinat -> sidkoordinat (PDF har origo nere till vänster)
+    fn point(&self, x: f32, y: f32) -> (Mm, Mm) {
+        (
+            Mm(PAGE_MARGIN_MM + x * self.scale - self.offset_x),
+            Mm(self.page_h - PAGE_MARGIN_MM - (y * self.scale - self.offset_y)),
+        )
+    }
+}
+
+fn draw_tile(
+    layer: &PdfLayerReference,
+    chart: &Chart,
+    photos: &HashMap<i64, RgbImage>,
+    tile: &Tile,
+    (area_w, area_h): (f32, f32),
+    (font, font_bold): (&IndirectFontRef, &IndirectFontRef),
+) {
+    // Hoppa över det som hamnar helt utanför sidan
+    let visible = |x0: f32, y0: f32, x1: f32, y1: f32| {
+        x1 * tile.scale >= tile.offset_x
+            && x0 * tile.scale <= tile.offset_x + area_w
+            && y1 * tile.scale >= tile.offset_y
+            && y0 * tile.scale <= tile.offset_y + area_h
+    };
+    let pt_per_unit = tile.scale / 0.3528;
+
+    for line in &chart.lines {
+        let xs = line.points.iter().map(|p| p.0);
+        let ys = line.points.iter().map(|p| p.1);
+        let (x0, x1) = (xs.clone().fold(f32::MAX, f32::min), xs.fold(f32::MIN, f32::max));
+        let (y0, y1) = (ys.clone().fold(f32::MAX, f32::min), ys.fold(f32::MIN, f32::max));
+        if !visible(x0, y0, x1, y1) {
+            continue;
+        }
+        layer.set_outline_color(pdf_color(line.color));
+        layer.set_outline_thickness(line.width * pt_per_unit);
+        layer.add_line(Line {
+            points: line
+                .points
+                .iter()
+                .map(|(x, y)| {
+                    let (px, py) = tile.point(*x, *y);
+                    (Point::new(px, py), false)
+                })
+                .collect(),
+            is_closed: false,
+        });
+    }
+
+    for b in &chart.boxes {
+        if !visible(b.x, b.y, b.x + b.width, b.y + b.height) {
+            continue;
+        }
+
+        let (llx, lly) = tile.point(b.x, b.y + b.height);
+        let (urx, ury) = tile.point(b.x + b.width, b.y);
+        layer.set_fill_color(pdf_color(b.fill));
+        layer.set_outline_color(pdf_color(b.stroke));
+        layer.set_outline_thickness(b.stroke_width * pt_per_unit);
+        layer.add_rect(Rect::new(llx, lly, urx, ury).with_mode(PaintMode::FillStroke));
+
+        let mut text_x = b.x + PADDING;
+        if let Some(photo) = photos.get(&b.person_id).filter(|_| b.has_photo) {
+            let (x, y) = tile.point(b.x + PADDING, b.y + PADDING + PHOTO_HEIGHT);
+            add_pdf_image(layer, photo, x, y, PHOTO_WIDTH * tile.scale);
+            text_x += PHOTO_WIDTH + PADDING;
+        }
+
+        layer.set_fill_color(pdf_color(b.text));
+        let mut baseline = b.y + PADDING + NAME_SIZE;
+        for (i, text) in b.lines.iter().enumerate() {
+            if i > 0 {
+                baseline += DETAIL_SIZE + LINE_SPACING;
+            }
+            let (size, font) = if i == 0 { (NAME_SIZE, font_bold) } else { (DETAIL_SIZE, font) };
+            let (x, y) = tile.point(text_x, baseline - 2.0);
+            layer.use_text(text.as_str(), size * pt_per_unit, x, y, font);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::family_tree::{FamilyTreeLink, FamilyTreeNode};
+    use chrono::NaiveDate;
+
+    fn sample_tree() -> FamilyTree {
+        let mut tree = FamilyTree::new();
+        tree.focus_person_id = Some(1);
+        let people = [
+            (1, "Karl & Co", 0.0, 0.0, Some(1880)),
+            (2, "Anna <Berg>", 200.0, 0.0, Some(1885)),
+            (3, "Levande", 100.0, 180.0, None),
+        ];
+        for (id, name, x, y, birth) in people {
+            let mut person = Person::new(Some(name.into()), None, format!("p{}", id));
+            person.id = Some(id);
+            person.birth_date = birth.and_then(|y| NaiveDate::from_ymd_opt(y, 1, 1));
+            person.death_date = birth.and_then(|y| NaiveDate::from_ymd_opt(y + 60, 1, 1));
+            person.birth_place = Some("Uppsala".into());
+            tree.nodes.insert(id, FamilyTreeNode { person, x, y, generation: 0, ahnentafel: None });
+        }
+        for (from_id, to_id, link_type) in [(1, 2, LinkType::Spouse), (1, 3, LinkType::Parent), (2, 3, LinkType::Parent)] {
+            tree.links.push(FamilyTreeLink { from_id, to_id, link_type });
+        }
+        tree
+    }
+
+    #[test]
+    fn test_svg_contains_escaped_names_and_redacts_living() {
+        let tree = sample_tree();
+        let options = ChartOptions { show_places: true, ..Default::default() };
+        let policy = PrivacyPolicy::new(100, RedactionStyle::Placeholder);
+        let chart = build_chart(&tree, &options, &policy, &HashMap::new());
+
+        assert_eq!(chart.boxes.len(), 3);
+        assert_eq!(chart.lines.len(), 3);
+        // Diagrammet börjar vid marginalen
+        assert_eq!(chart.boxes[0].x, CHART_MARGIN);
+
+        let svg = render_svg(&chart, &HashMap::new(), &|_| None);
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.contains("Karl &amp; Co"));
+        assert!(svg.contains("Anna &lt;Berg&gt;"));
+        assert!(svg.contains("1880 - 1940"));
+        assert!(svg.contains("Uppsala"));
+        assert!(!svg.contains("Levande"));
+        assert!(svg.contains(">Privat<"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        let linked = render_svg(&chart, &HashMap::new(), &|id| (id == 2).then(|| "2.html".to_string()));
+        assert_eq!(linked.matches("<a href=\"2.html\">").count(), 1);
+        assert_eq!(linked.matches("</a>").count(), 1);
+    }
+
+    #[test]
+    fn test_page_grid() {
+        let chart = Chart {
+            width: 2000.0,
+            height: 600.0,
+            ..Default::default()
+        };
+
+        // 500 x 150 mm på liggande A4 (277 x 190 mm yta) -> 2 x 1 sidor
+        let tiled = page_grid(&chart, &ChartOptions::default());
+        assert_eq!((tiled.2, tiled.3), (2, 1));
+
+        let a3 = ChartOptions { paper: PaperSize::A3, ..Default::default() };
+        assert_eq!(page_grid(&chart, &a3).2, 2);
+
+        let poster = ChartOptions { page_mode: PageMode::Poster, ..Default::default() };
+        let (w, h, cols, rows, _) = page_grid(&chart, &poster);
+        assert_eq!((w, h, cols, rows), (520.0, 170.0, 1, 1));
+    }
+
+    #[test]
+    fn test_pdf_renders_all_tiles() {
+        let tree = sample_tree();
+        let options = ChartOptions { page_mode: PageMode::Poster, ..Default::default() };
+        let policy = PrivacyPolicy::new(100, RedactionStyle::None);
+        let mut photos = HashMap::new();
+        photos.insert(1, RgbImage::from_pixel(4, 4, image::Rgb([200, 100, 50])));
+        let chart = build_chart(&tree, &options, &policy, &photos);
+
+        let (bytes, pages) = render_pdf(&chart, &photos, &options).unwrap();
+        assert_eq!(pages, 1);
+        assert!(bytes.starts_with(b"%PDF"));
+        assert!(bytes.windows(6).any(|w| w == b"DejaVu"));
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+}
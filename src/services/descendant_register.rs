@@ -0,0 +1,570 @@
+//! Ättlingsregister i Register/NGSQ-stil
+//!
+//! Stamfadern har nummer 1. Varje ättling får nästa lediga nummer när den
+//! listas som barn, generation för generation. Barn som själva har familj
+//! (make/maka eller barn) och ryms inom valt antal generationer får ett eget
+//! stycke och markeras med "+" i barnlistan. En ättling som nås på flera
+//! vägar (t.ex. när kusiner gifte sig) behåller sitt första nummer.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use crate::db::{Database, KinshipGraph};
+use crate::models::{Person, RelationshipType, Sex};
+use crate::services::ahnentafel::{markdown_escape, notes, vital_sentences};
+use crate::services::chart_export::xml_escape;
+use crate::services::PrivacyPolicy;
+
+/// Ett barn i en familj
+#[derive(Debug, Clone)]
+pub struct RegisterChild {
+    pub number: u64,
+    pub person: Person,
+    /// Har barnet ett eget stycke?
+    pub has_entry: bool,
+}
+
+/// Make eller maka med vigsel och föräldrar
+#[derive(Debug, Clone)]
+pub struct RegisterSpouse {
+    pub person: Person,
+    pub marriage_date: Option<NaiveDate>,
+    pub marriage_place: Option<String>,
+    pub father: Option<Person>,
+    pub mother: Option<Person>,
+}
+
+/// Barn med en viss make/maka (eller med okänd förälder)
+#[derive(Debug, Clone)]
+pub struct RegisterFamily {
+    pub spouse: Option<RegisterSpouse>,
+    pub children: Vec<RegisterChild>,
+}
+
+/// Ett stycke i registret
+#[derive(Debug, Clone)]
+pub struct RegisterEntry {
+    pub number: u64,
+    /// 1 = stamfadern
+    pub generation: u32,
+    pub person: Person,
+    /// Numret för föräldern i registret
+    pub parent_number: Option<u64>,
+    pub families: Vec<RegisterFamily>,
+    /// Dokument kopplade till personen ("filnamn (typ)")
+    pub sources: Vec<String>,
+}
+
+impl RegisterEntry {
+    /// Personens text: levnadsdata, giften, anteckningar och källor
+    pub fn body(&self) -> String {
+        let mut sentences = vital_sentences(&self.person);
+        for spouse in self.families.iter().filter_map(|f| f.spouse.as_ref()) {
+            sentences.push(spouse.sentence());
+        }
+        sentences.extend(notes(&self.person));
+        if !self.sources.is_empty() {
+            sentences.push(format!("Källor: {}.", self.sources.join("; ")));
+        }
+        sentences.join(" ")
+    }
+
+    /// Rubrik över barnlistan
+    pub fn family_title(&self, family: &RegisterFamily) -> String {
+        match &family.spouse {
+            Some(spouse) if self.families.len() > 1 => format!("Barn med {}:", spouse.person.full_name()),
+            None if self.families.len() > 1 => "Barn med okänd förälder:".to_string(),
+            _ => "Barn:".to_string(),
+        }
+    }
+}
+
+impl RegisterSpouse {
+    /// "Gift 1878-05-05 i Lund med Anna Holm, född ..., dotter till ..."
+    pub fn sentence(&self) -> String {
+        let mut text = String::from("Gift");
+        if let Some(date) = self.marriage_date {
+            text.push_str(&format!(" {}", date.format("%Y-%m-%d")));
+        }
+        if let Some(place) = &self.marriage_place {
+            text.push_str(&format!(" i {}", place));
+        }
+        text.push_str(&format!(" med {}", self.person.full_name()));
+        let vitals = short_vitals(&self.person);
+        if !vitals.is_empty() {
+            text.push_str(&format!(", {}", vitals));
+        }
+
+        let parents: Vec<String> = [&self.father, &self.mother]
+            .into_iter()
+            .flatten()
+            .map(|p| p.full_name())
+            .collect();
+        if !parents.is_empty() {
+            let relation = match self.person.sex {
+                Sex::Male => "son",
+                Sex::Female => "dotter",
+                _ => "barn",
+            };
+            text.push_str(&format!(", {} till {}", relation, parents.join(" och ")));
+        }
+        text.push('.');
+        text
+    }
+}
+
+impl RegisterChild {
+    /// "+ 5 ii. Karl Berg, f. 1850-03-01 i Lund, d. 1920-01-01"
+    pub fn line(&self, index: usize) -> String {
+        let marker = if self.has_entry { "+ " } else { "" };
+        let mut text = format!("{}{} {}. {}", marker, self.number, roman(index + 1), self.person.full_name());
+        let vitals = short_vitals(&self.person);
+        if !vitals.is_empty() {
+            text.push_str(&format!(", {}", vitals));
+        }
+        text
+    }
+}
+
+/// Kort levnadsdata: "f. 1850-03-01 i Lund, d. 1920-01-01"
+fn short_vitals(person: &Person) -> String {
+    let mut parts = Vec::new();
+    let place = person.birth_place.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    match (person.birth_date, place) {
+        (Some(date), Some(place)) => parts.push(format!("f. {} i {}", date.format("%Y-%m-%d"), place)),
+        (Some(date), None) => parts.push(format!("f. {}", date.format("%Y-%m-%d"))),
+        (None, Some(place)) => parts.push(format!("f. i {}", place)),
+        (None, None) => {}
+    }
+    if let Some(death) = person.death_date {
+        parts.push(format!("d. {}", death.format("%Y-%m-%d")));
+    }
+    parts.join(", ")
+}
+
+/// Små romerska siffror för barnens ordning (i, ii, iii ...)
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 9] = [
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut text = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            text.push_str(numeral);
+            n -= value;
+        }
+    }
+    text
+}
+
+/// Ättlingsregister från en stamfar eller stammor
+#[derive(Debug, Clone, Default)]
+pub struct DescendantRegister {
+    pub root_person_id: i64,
+    pub generations: usize,
+    /// Stycken i nummerordning
+    pub entries: Vec<RegisterEntry>,
+}
+
+impl DescendantRegister {
+    pub fn title(&self) -> String {
+        match self.entries.iter().find(|e| e.person.id == Some(self.root_person_id)) {
+            Some(root) => format!("Ättlingar till {}", root.person.full_name()),
+            None => "Ättlingar".to_string(),
+        }
+    }
+
+    /// Rubrik för stycket, med förfädernas förnamn upp till stamfadern:
+    /// "5. Karl Berg (Per, Anders)"
+    pub fn heading(&self, entry: &RegisterEntry) -> String {
+        let by_number: HashMap<u64, &RegisterEntry> = self.entries.iter().map(|e| (e.number, e)).collect();
+        let mut lineage = Vec::new();
+        let mut parent = entry.parent_number;
+        while let Some(number) = parent {
+            let Some(ancestor) = by_number.get(&number) else {
+                break;
+            };
+            lineage.push(ancestor.person.firstname.clone().unwrap_or_else(|| ancestor.person.full_name()));
+            parent = ancestor.parent_number;
+        }
+
+        if lineage.is_empty() {
+            format!("{}. {}", entry.number, entry.person.full_name())
+        } else {
+            format!("{}. {} ({})", entry.number, entry.person.full_name(), lineage.join(", "))
+        }
+    }
+
+    /// Maskera eller utelämna privata personer. Utelämnade barn stryks ur
+    /// barnlistorna och deras stycken tas bort; numreringen behålls.
+    /// Stamfadern står alltid kvar, som "Privat" om den annars skulle
+    /// utelämnas.
+    pub fn redact(&mut self, privacy: &PrivacyPolicy) {
+        let root_id = Some(self.root_person_id);
+        let mut omitted = HashSet::new();
+        for entry in &self.entries {
+            if entry.person.id != root_id && privacy.redact(&entry.person).is_none() {
+                omitted.insert(entry.number);
+            }
+        }
+
+        let redact_opt = |person: &mut Option<Person>| {
+            *person = person.as_ref().and_then(|p| privacy.redact(p));
+        };
+
+        self.entries.retain(|e| !omitted.contains(&e.number));
+        for entry in &mut self.entries {
+            let masked = privacy.masks(&entry.person);
+            if masked {
+                entry.sources.clear();
+            }
+            if entry.person.id == root_id {
+                entry.person = privacy.redact_or_placeholder(&entry.person);
+            } else if let Some(person) = privacy.redact(&entry.person) {
+                entry.person = person;
+            }
+            for family in &mut entry.families {
+                if let Some(spouse) = &mut family.spouse {
+                    match privacy.redact(&spouse.person) {
+                        Some(person) => {
+                            // Vigseln gäller båda makarna
+                            if masked || privacy.masks(&spouse.person) {
+                                spouse.marriage_date = None;
+                                spouse.marriage_place = None;
+                            }
+                            spouse.person = person;
+                            redact_opt(&mut spouse.father);
+                            redact_opt(&mut spouse.mother);
+                        }
+                        None => family.spouse = None,
+                    }
+                }
+                family.children.retain_mut(|child| match privacy.redact(&child.person) {
+                    Some(person) => {
+                        child.person = person;
+                        child.has_entry &= !omitted.contains(&child.number);
+                        true
+                    }
+                    None => false,
+                });
+            }
+            entry.families.retain(|f| f.spouse.is_some() || !f.children.is_empty());
+        }
+    }
+
+    /// Styckena grupperade per generation
+    pub fn by_generation(&self) -> Vec<(u32, Vec<&RegisterEntry>)> {
+        let mut groups: Vec<(u32, Vec<&RegisterEntry>)> = Vec::new();
+        for entry in &self.entries {
+            match groups.last_mut() {
+                Some((generation, entries)) if *generation == entry.generation => entries.push(entry),
+                _ => groups.push((entry.generation, vec![entry])),
+            }
+        }
+        groups
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# {}\n", markdown_escape(&self.title()));
+        for (generation, entries) in self.by_generation() {
+            md.push_str(&format!("\n## Generation {}\n", generation));
+            for entry in entries {
+                md.push_str(&format!(
+                    "\n**{}** {}\n",
+                    markdown_escape(&self.heading(entry)),
+                    markdown_escape(&entry.body())
+                ));
+                for family in entry.families.iter().filter(|f| !f.children.is_empty()) {
+                    md.push_str(&format!("\n{}\n\n", markdown_escape(&entry.family_title(family))));
+                    for (i, child) in family.children.iter().enumerate() {
+                        md.push_str(&format!("- {}\n", markdown_escape(&child.line(i))));
+                    }
+                }
+            }
+        }
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = xml_escape(&self.title());
+        let mut html = String::from("<!DOCTYPE html>\n<html lang=\"sv\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", title));
+        html.push_str("<style>body{font-family:Georgia,serif;max-width:46em;margin:2em auto;line-height:1.5}h2{border-bottom:1px solid #ccc}ul{list-style:none}</style>\n");
+        html.push_str(&format!("</head>\n<body>\n<h1>{}</h1>\n", title));
+        for (generation, entries) in self.by_generation() {
+            html.push_str(&format!("<h2>Generation {}</h2>\n", generation));
+            for entry in entries {
+                html.push_str(&format!(
+                    "<p id=\"nr{}\"><strong>{}</strong> {}</p>\n",
+                    entry.number,
+                    xml_escape(&self.heading(entry)),
+                    xml_escape(&entry.body())
+                ));
+                for family in entry.families.iter().filter(|f| !f.children.is_empty()) {
+                    html.push_str(&format!("<p>{}</p>\n<ul>\n", xml_escape(&entry.family_title(family))));
+                    for (i, child) in family.children.iter().enumerate() {
+                        let line = xml_escape(&child.line(i));
+                        if child.has_entry {
+                            html.push_str(&format!("<li><a href=\"#nr{}\">{}</a></li>\n", child.number, line));
+                        } else {
+                            html.push_str(&format!("<li>{}</li>\n", line));
+                        }
+                    }
+                    html.push_str("</ul>\n");
+                }
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Tjänst för att bygga ättlingsregister
+pub struct DescendantRegisterService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> DescendantRegisterService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Bygg registret från en person, med stycken för högst `generations`
+    /// generationer (stamfadern inräknad)
+    pub fn build(&self, root_person_id: i64, generations: usize, include_sources: bool) -> Result<DescendantRegister> {
+        let graph = self.db.kinship()?;
+        let mut register = DescendantRegister {
+            root_person_id,
+            generations,
+            entries: Vec::new(),
+        };
+        if graph.person(root_person_id).is_none() {
+            return Ok(register);
+        }
+
+        let type_names: HashMap<i64, String> = if include_sources {
+            self.db
+                .documents()
+                .get_all_types()?
+                .into_iter()
+                .filter_map(|t| Some((t.id?, t.name)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut numbers: HashMap<i64, u64> = HashMap::from([(root_person_id, 1)]);
+        let mut next_number = 2;
+        let mut queue = VecDeque::from([(root_person_id, 1u64, 1u32, None)]);
+        while let Some((person_id, number, generation, parent_number)) = queue.pop_front() {
+            let Some(person) = graph.person(person_id) else {
+                continue;
+            };
+
+            let mut families = Vec::new();
+            for (spouse_id, children) in graph.families(person_id) {
+                let spouse = match spouse_id {
+                    Some(id) => self.spouse(&graph, person_id, id)?,
+                    None => None,
+                };
+                let mut listed = Vec::new();
+                for child_id in children {
+                    let Some(child) = graph.person(child_id) else {
+                        continue;
+                    };
+                    let has_family = !graph.children(child_id).is_empty() || !graph.spouses(child_id).is_empty();
+                    let (child_number, has_entry) = match numbers.get(&child_id) {
+                        Some(existing) => (*existing, register_has_entry(&queue, &register, *existing)),
+                        None => {
+                            let child_number = next_number;
+                            next_number += 1;
+                            numbers.insert(child_id, child_number);
+                            let has_entry = has_family && (generation as usize) < generations;
+                            if has_entry {
+                                queue.push_back((child_id, child_number, generation + 1, Some(number)));
+                            }
+                            (child_number, has_entry)
+                        }
+                    };
+                    listed.push(RegisterChild { number: child_number, person: child.clone(), has_entry });
+                }
+                families.push(RegisterFamily { spouse, children: listed });
+            }
+
+            let sources = if include_sources {
+                self.db
+                    .documents()
+                    .find_by_person(person_id)?
+                    .into_iter()
+                    .map(|doc| match doc.document_type_id.and_then(|id| type_names.get(&id)) {
+                        Some(type_name) => format!("{} ({})", doc.filename, type_name),
+                        None => doc.filename,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            register.entries.push(RegisterEntry {
+                number,
+                generation,
+                person: person.clone(),
+                parent_number,
+                families,
+                sources,
+            });
+        }
+
+        register.entries.sort_by_key(|e| e.number);
+        Ok(register)
+    }
+
+    fn spouse(&self, graph: &KinshipGraph, person_id: i64, spouse_id: i64) -> Result<Option<RegisterSpouse>> {
+        let Some(person) = graph.person(spouse_id) else {
+            return Ok(None);
+        };
+        let marriage = self
+            .db
+            .relationships()
+            .find_by_person(person_id)?
+            .into_iter()
+            .find(|r| {
+                r.relationship_a_to_b == RelationshipType::Spouse
+                    && (r.person_a_id == spouse_id || r.person_b_id == spouse_id)
+            });
+        let parents: Vec<Person> = graph
+            .parents(spouse_id)
+            .iter()
+            .filter_map(|id| graph.person(*id).cloned())
+            .collect();
+        let (father, mother) = crate::services::family_tree::split_parents(parents);
+
+        Ok(Some(RegisterSpouse {
+            person: person.clone(),
+            marriage_date: marriage.as_ref().and_then(|m| m.marriage_date),
+            marriage_place: marriage
+                .and_then(|m| m.marriage_place)
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty()),
+            father,
+            mother,
+        }))
+    }
+}
+
+/// Har ättlingen med numret redan ett stycke, eller väntar den på ett?
+fn register_has_entry(queue: &VecDeque<(i64, u64, u32, Option<u64>)>, register: &DescendantRegister, number: u64) -> bool {
+    register.entries.iter().any(|e| e.number == number) || queue.iter().any(|(_, n, _, _)| *n == number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PersonRelationship, Privacy};
+    use crate::services::RedactionStyle;
+    use chrono::Datelike;
+
+    fn add(db: &Database, name: &str, sex: Sex, birth: Option<(i32, u32, u32)>) -> i64 {
+        let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+        p.sex = sex;
+        p.birth_date = birth.and_then(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d));
+        if p.birth_date.map_or(true, |d| d.year() < 1950) {
+            p.death_date = NaiveDate::from_ymd_opt(1940, 1, 1);
+        }
+        db.persons().create(&mut p).unwrap();
+        p.id.unwrap()
+    }
+
+    fn relate(db: &Database, a: i64, b: i64, a_to_b: RelationshipType) -> PersonRelationship {
+        let mut rel = PersonRelationship::new(a, b, a_to_b);
+        db.relationships().create(&mut rel).unwrap();
+        rel
+    }
+
+    #[test]
+    fn test_register_numbering_and_families() {
+        let db = Database::open_in_memory().unwrap();
+        let anders = add(&db, "Anders", Sex::Male, Some((1820, 1, 1)));
+        let kerstin = add(&db, "Kerstin", Sex::Female, None);
+        let per = add(&db, "Per", Sex::Male, Some((1850, 1, 1)));
+        let lisa = add(&db, "Lisa", Sex::Female, Some((1845, 1, 1)));
+        let anna = add(&db, "Anna", Sex::Female, Some((1852, 1, 1)));
+        let annas_far = add(&db, "Nils", Sex::Male, None);
+        let karl = add(&db, "Karl", Sex::Male, Some((1880, 1, 1)));
+        let sara = add(&db, "Sara", Sex::Female, Some((1990, 1, 1)));
+
+        let mut marriage = relate(&db, anders, kerstin, RelationshipType::Spouse);
+        marriage.marriage_date = NaiveDate::from_ymd_opt(1844, 6, 1);
+        marriage.marriage_place = Some("Lund".into());
+        db.relationships().update(&marriage).unwrap();
+        for child in [per, lisa] {
+            relate(&db, anders, child, RelationshipType::Parent);
+            relate(&db, kerstin, child, RelationshipType::Parent);
+        }
+        relate(&db, per, anna, RelationshipType::Spouse);
+        relate(&db, annas_far, anna, RelationshipType::Parent);
+        relate(&db, per, karl, RelationshipType::Parent);
+        relate(&db, anna, karl, RelationshipType::Parent);
+        relate(&db, karl, sara, RelationshipType::Parent);
+
+        let register = DescendantRegisterService::new(&db).build(anders, 3, false).unwrap();
+        let numbers: Vec<(u64, u32, String)> = register
+            .entries
+            .iter()
+            .map(|e| (e.number, e.generation, e.person.full_name()))
+            .collect();
+        // Lisa (äldst) får nr 2 men har ingen egen familj; Sara ligger utanför djupet
+        assert_eq!(
+            numbers,
+            vec![
+                (1, 1, "Anders Berg".to_string()),
+                (3, 2, "Per Berg".to_string()),
+                (4, 3, "Karl Berg".to_string()),
+            ]
+        );
+
+        let root = &register.entries[0];
+        assert!(root.body().contains("Gift 1844-06-01 i Lund med Kerstin Berg, d. 1940-01-01."));
+        let lines: Vec<String> = root.families[0].children.iter().enumerate().map(|(i, c)| c.line(i)).collect();
+        assert_eq!(lines, ["2 i. Lisa Berg, f. 1845-01-01, d. 1940-01-01", "+ 3 ii. Per Berg, f. 1850-01-01, d. 1940-01-01"]);
+
+        let per_entry = &register.entries[1];
+        assert_eq!(register.heading(per_entry), "3. Per Berg (Anders)");
+        assert!(per_entry.body().contains("med Anna Berg, f. 1852-01-01, d. 1940-01-01, dotter till Nils Berg."));
+
+        let karl_entry = &register.entries[2];
+        assert_eq!(register.heading(karl_entry), "4. Karl Berg (Per, Anders)");
+        assert_eq!(karl_entry.families[0].children[0].line(0), "5 i. Sara Berg, f. 1990-01-01");
+
+        // Levande Sara maskeras
+        let mut redacted = register.clone();
+        redacted.redact(&PrivacyPolicy::new(100, RedactionStyle::Placeholder));
+        assert_eq!(redacted.entries[2].families[0].children[0].line(0), "5 i. Privat");
+
+        // En privat stamfader behålls som nr 1 och vigseln döljs
+        let mut private_root = register.clone();
+        private_root.entries[0].person.privacy = Privacy::Private;
+        private_root.redact(&PrivacyPolicy::new(100, RedactionStyle::Omit));
+        assert_eq!(private_root.title(), "Ättlingar till Privat");
+        assert_eq!(private_root.entries[0].number, 1);
+        let body = private_root.entries[0].body();
+        assert!(body.contains("Kerstin Berg"));
+        assert!(!body.contains("1844") && !body.contains("Lund"), "{}", body);
+        assert_eq!(private_root.heading(&private_root.entries[1]), "3. Per Berg (Privat)");
+
+        let md = register.to_markdown();
+        assert!(md.contains("- \\+ 3 ii. Per Berg"));
+        let html = register.to_html();
+        assert!(html.contains("<li><a href=\"#nr3\">+ 3 ii. Per Berg"));
+    }
+}
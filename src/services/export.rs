@@ -1,15 +1,32 @@
-//! Export-tjänst för att exportera data till olika format (JSON, CSV, PDF)
+//! Export-tjänst för att exportera data till olika format (JSON, CSV, PDF,
+//! Excel, DOT och GraphML för släktgrafen samt Markdown och HTML för antavla
+//! och ättlingsregister). Persondossiern skrivs bara som PDF och webbplatsen
+//! som en katalog med HTML-sidor.
 
 use anyhow::{Context, Result};
 use chrono::Utc;
-use printpdf::{BuiltinFont, Mm, PdfDocument};
+use image::RgbImage;
 use serde::Serialize;
-use std::fs::File;
-use std::io::BufWriter;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
-use crate::db::Database;
-use crate::models::{Person, RelationshipType};
+use crate::db::{Database, SearchFilter};
+use crate::models::{
+    CustomFieldDefinition, CustomFieldEntity, CustomFieldType, DocumentType, Person, PersonRelationship,
+    RelationshipType,
+};
+use crate::services::ahnentafel::{generation_title, Ahnentafel, AhnentafelService};
+use crate::services::analytics::Analytics;
+use crate::services::chart_export::load_thumbnail;
+use crate::services::descendant_register::{DescendantRegister, DescendantRegisterService};
+use crate::services::graph_export;
+use crate::services::pdf_layout::{Column, PdfReport, Weight};
+use crate::services::person_dossier::{PersonDossier, PersonDossierService};
+use crate::services::website_export::WebsiteExporter;
+use crate::services::xlsx::{Cell, Sheet, Workbook};
+use crate::services::{
+    PedigreeCollapse, PedigreeCollapseService, PrivacyPolicy, RedactionStyle, ResearchGap, ResearchGapsService,
+};
 
 /// Exportformat
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +34,14 @@ pub enum ExportFormat {
     Json,
     Csv,
     Pdf,
+    /// Graphviz
+    Dot,
+    /// GraphML (Gephi, yEd)
+    GraphMl,
+    Markdown,
+    Html,
+    /// Excel-arbetsbok med ett blad per tabell
+    Xlsx,
 }
 
 impl ExportFormat {
@@ -25,6 +50,11 @@ impl ExportFormat {
             ExportFormat::Json => "json",
             ExportFormat::Csv => "csv",
             ExportFormat::Pdf => "pdf",
+            ExportFormat::Dot => "dot",
+            ExportFormat::GraphMl => "graphml",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Xlsx => "xlsx",
         }
     }
 
@@ -33,6 +63,11 @@ impl ExportFormat {
             ExportFormat::Json => "JSON",
             ExportFormat::Csv => "CSV",
             ExportFormat::Pdf => "PDF",
+            ExportFormat::Dot => "DOT",
+            ExportFormat::GraphMl => "GraphML",
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Xlsx => "Excel",
         }
     }
 }
@@ -46,6 +81,27 @@ pub enum ReportType {
     AllRelationships,
     /// Statistiksammanfattning
     Statistics,
+    /// Förfäder som saknar uppgifter (kräver utgångsperson)
+    ResearchGaps,
+    /// Alla resurser (gårdar, platser, företag ...)
+    AllResources,
+    /// Förfäder som förekommer flera gånger i antavlan (kräver utgångsperson)
+    PedigreeCollapse,
+    /// Personer och relationer som graf, för externa grafverktyg
+    RelationshipGraph,
+    /// Fördelningar över urvalet (livslängd, efternamn, barn per par ...)
+    Analytics,
+    /// Förfäder numrerade enligt Sosa–Stradonitz, ett stycke per person
+    /// (kräver utgångsperson)
+    Ahnentafel,
+    /// Ättlingar i generationer med barnlistor och korshänvisningar
+    /// (kräver utgångsperson)
+    DescendantRegister,
+    /// Allt om en person: uppgifter, relationer, checklista, bilder och
+    /// dokument (kräver utgångsperson)
+    PersonDossier,
+    /// Statisk webbplats med en sida per person, skrivs till en katalog
+    Website,
 }
 
 impl ReportType {
@@ -54,6 +110,15 @@ impl ReportType {
             ReportType::AllPersons => "Alla personer",
             ReportType::AllRelationships => "Alla relationer",
             ReportType::Statistics => "Statistik",
+            ReportType::ResearchGaps => "Forskningsluckor",
+            ReportType::AllResources => "Alla resurser",
+            ReportType::PedigreeCollapse => "Anförlust",
+            ReportType::RelationshipGraph => "Släktgraf",
+            ReportType::Analytics => "Släktanalys",
+            ReportType::Ahnentafel => "Antavla",
+            ReportType::DescendantRegister => "Ättlingsregister",
+            ReportType::PersonDossier => "Persondossier",
+            ReportType::Website => "Webbplats",
         }
     }
 
@@ -62,6 +127,92 @@ impl ReportType {
             ReportType::AllPersons => "personer",
             ReportType::AllRelationships => "relationer",
             ReportType::Statistics => "statistik",
+            ReportType::ResearchGaps => "forskningsluckor",
+            ReportType::AllResources => "resurser",
+            ReportType::PedigreeCollapse => "anforlust",
+            ReportType::RelationshipGraph => "slaktgraf",
+            ReportType::Analytics => "slaktanalys",
+            ReportType::Ahnentafel => "antavla",
+            ReportType::DescendantRegister => "attlingar",
+            ReportType::PersonDossier => "dossier",
+            ReportType::Website => "webbplats",
+        }
+    }
+
+    /// Alla rapporttyper (för val i UI)
+    pub fn all() -> &'static [ReportType] {
+        &[
+            ReportType::AllPersons,
+            ReportType::AllRelationships,
+            ReportType::Statistics,
+            ReportType::ResearchGaps,
+            ReportType::AllResources,
+            ReportType::PedigreeCollapse,
+            ReportType::RelationshipGraph,
+            ReportType::Analytics,
+            ReportType::Ahnentafel,
+            ReportType::DescendantRegister,
+            ReportType::PersonDossier,
+            ReportType::Website,
+        ]
+    }
+
+    /// Format som rapporten kan exporteras i
+    pub fn formats(&self) -> &'static [ExportFormat] {
+        match self {
+            ReportType::RelationshipGraph => &[ExportFormat::Dot, ExportFormat::GraphMl],
+            ReportType::Analytics => &[ExportFormat::Json, ExportFormat::Csv],
+            ReportType::Ahnentafel | ReportType::DescendantRegister => {
+                &[ExportFormat::Pdf, ExportFormat::Markdown, ExportFormat::Html]
+            }
+            ReportType::PersonDossier => &[ExportFormat::Pdf],
+            ReportType::Website => &[ExportFormat::Html],
+            ReportType::AllPersons | ReportType::AllRelationships | ReportType::Statistics => {
+                &[ExportFormat::Json, ExportFormat::Csv, ExportFormat::Pdf, ExportFormat::Xlsx]
+            }
+            _ => &[ExportFormat::Json, ExportFormat::Csv, ExportFormat::Pdf],
+        }
+    }
+
+    /// Kräver rapporten en utgångsperson?
+    pub fn requires_root_person(&self) -> bool {
+        matches!(
+            self,
+            ReportType::ResearchGaps
+                | ReportType::PedigreeCollapse
+                | ReportType::Ahnentafel
+                | ReportType::DescendantRegister
+                | ReportType::PersonDossier
+        )
+    }
+}
+
+/// Inställningar för rapporter som utgår från en person eller ett urval
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    /// Utgångsperson för anrapporter (valfri för släktgrafen)
+    pub root_person_id: Option<i64>,
+    /// Max antal generationer bakåt (uppåt och nedåt från utgångspersonen i släktgrafen)
+    pub max_generations: usize,
+    /// Dokumenttyp som förväntas finnas (t.ex. Dödsattest)
+    pub document_type_id: Option<i64>,
+    /// Begränsa rapporten till personer som matchar filtret (t.ex. en sparad sökning)
+    pub person_filter: Option<SearchFilter>,
+    /// Hur levande och privata personer maskeras
+    pub redaction: RedactionStyle,
+    /// Lista personens dokument som källor (ättlingsregistret)
+    pub include_sources: bool,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            root_person_id: None,
+            max_generations: 5,
+            document_type_id: None,
+            person_filter: None,
+            redaction: RedactionStyle::default(),
+            include_sources: false,
         }
     }
 }
@@ -78,6 +229,9 @@ pub struct PersonExport {
     pub age: Option<i32>,
     pub is_alive: bool,
     pub directory_name: String,
+    pub notes: Option<String>,
+    /// Egna fält (fältnamn -> värde)
+    pub custom_fields: BTreeMap<String, String>,
 }
 
 impl From<&Person> for PersonExport {
@@ -92,10 +246,27 @@ impl From<&Person> for PersonExport {
             age: p.age,
             is_alive: p.is_alive(),
             directory_name: p.directory_name.clone(),
+            notes: p.notes.clone(),
+            custom_fields: BTreeMap::new(),
         }
     }
 }
 
+/// Exporterbar resurs
+#[derive(Debug, Serialize)]
+pub struct ResourceExport {
+    pub id: i64,
+    pub resource_type: String,
+    pub name: String,
+    pub information: Option<String>,
+    pub comment: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub directory_name: String,
+    /// Egna fält (fältnamn -> värde)
+    pub custom_fields: BTreeMap<String, String>,
+}
+
 /// Exporterbar relationsdata
 #[derive(Debug, Serialize)]
 pub struct RelationshipExport {
@@ -132,14 +303,124 @@ pub struct DecadeCount {
     pub count: i64,
 }
 
+/// Exporterbar forskningslucka
+#[derive(Debug, Serialize)]
+pub struct ResearchGapExport {
+    pub generation: usize,
+    pub person_id: i64,
+    pub full_name: String,
+    pub birth_date: Option<String>,
+    pub death_date: Option<String>,
+    pub birth_place: Option<String>,
+    pub missing: Vec<String>,
+}
+
+impl ResearchGapExport {
+    fn from_gap(gap: &ResearchGap, document_type: Option<&DocumentType>) -> Self {
+        let p = &gap.person;
+        Self {
+            generation: gap.generation,
+            person_id: p.id.unwrap_or(0),
+            full_name: p.full_name(),
+            birth_date: p.birth_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            death_date: p.death_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            birth_place: p.birth_place.clone(),
+            missing: gap.missing_labels(document_type),
+        }
+    }
+}
+
+/// Exporterbar anförlust
+#[derive(Debug, Serialize)]
+pub struct PedigreeCollapseExport {
+    pub root_person_id: i64,
+    pub generations: usize,
+    pub slots: usize,
+    pub distinct_ancestors: usize,
+    pub collapse_percent: f64,
+    /// Släktskapskoefficient mellan utgångspersonens föräldrar
+    pub parents_coefficient: f64,
+    pub inbreeding_coefficient: f64,
+    pub ancestors: Vec<CollapseAncestorExport>,
+    pub related_couples: Vec<RelatedCoupleExport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollapseAncestorExport {
+    pub person_id: i64,
+    pub full_name: String,
+    pub birth_date: Option<String>,
+    pub death_date: Option<String>,
+    pub generation: u32,
+    pub ahnentafel_numbers: Vec<u64>,
+    pub coefficient: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelatedCoupleExport {
+    pub child_number: u64,
+    pub father_name: String,
+    pub mother_name: String,
+    pub kinship: String,
+    pub coefficient: f64,
+}
+
+impl PedigreeCollapseExport {
+    fn new(collapse: &PedigreeCollapse) -> Self {
+        Self {
+            root_person_id: collapse.root_person_id,
+            generations: collapse.generations,
+            slots: collapse.slots,
+            distinct_ancestors: collapse.distinct_ancestors,
+            collapse_percent: collapse.collapse_percent(),
+            parents_coefficient: collapse.parents_coefficient,
+            inbreeding_coefficient: collapse.inbreeding_coefficient(),
+            ancestors: collapse
+                .ancestors
+                .iter()
+                .map(|a| CollapseAncestorExport {
+                    person_id: a.person.id.unwrap_or(0),
+                    full_name: a.person.full_name(),
+                    birth_date: a.person.birth_date.map(|d| d.format("%Y-%m-%d").to_string()),
+                    death_date: a.person.death_date.map(|d| d.format("%Y-%m-%d").to_string()),
+                    generation: a.generation(),
+                    ahnentafel_numbers: a.numbers.clone(),
+                    coefficient: a.coefficient,
+                })
+                .collect(),
+            related_couples: collapse
+                .couples
+                .iter()
+                .map(|c| RelatedCoupleExport {
+                    child_number: c.child_number,
+                    father_name: c.father.full_name(),
+                    mother_name: c.mother.full_name(),
+                    kinship: c.kinship_label().to_string(),
+                    coefficient: c.coefficient,
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Export-tjänst
 pub struct ExportService<'a> {
     db: &'a Database,
+    options: ReportOptions,
 }
 
 impl<'a> ExportService<'a> {
     pub fn new(db: &'a Database) -> Self {
-        Self { db }
+        Self {
+            db,
+            options: ReportOptions::default(),
+        }
+    }
+
+    /// Ange inställningar för rapporter som kräver urval
+    pub fn with_options(mut self, options: ReportOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Generera filnamn för export
@@ -160,15 +441,43 @@ impl<'a> ExportService<'a> {
         format: ExportFormat,
         path: &Path,
     ) -> Result<ExportResult> {
+        if !report_type.formats().contains(&format) {
+            anyhow::bail!("{} kan inte exporteras som {}", report_type.display_name(), format.display_name());
+        }
+
+        // Webbplatsen skrivs till en katalog
+        if report_type == ReportType::Website {
+            return self.export_website(path);
+        }
+
         // PDF hanteras separat
         if format == ExportFormat::Pdf {
+            match report_type {
+                ReportType::Ahnentafel => return self.export_ahnentafel_pdf(path),
+                ReportType::DescendantRegister => return self.export_descendant_register_pdf(path),
+                ReportType::PersonDossier => return self.export_person_dossier_pdf(path),
+                _ => {}
+            }
             return self.export_to_pdf(report_type, path);
         }
 
+        if format == ExportFormat::Xlsx {
+            return self.export_to_xlsx(report_type, path);
+        }
+
         let content = match report_type {
             ReportType::AllPersons => self.export_persons(format)?,
             ReportType::AllRelationships => self.export_relationships(format)?,
             ReportType::Statistics => self.export_statistics(format)?,
+            ReportType::ResearchGaps => self.export_research_gaps(format)?,
+            ReportType::AllResources => self.export_resources(format)?,
+            ReportType::PedigreeCollapse => self.export_pedigree_collapse(format)?,
+            ReportType::RelationshipGraph => self.export_relationship_graph(format)?,
+            ReportType::Analytics => self.export_analytics(format)?,
+            ReportType::Ahnentafel => self.export_ahnentafel(format)?,
+            ReportType::DescendantRegister => self.export_descendant_register(format)?,
+            ReportType::PersonDossier => unreachable!("Persondossiern exporteras bara som PDF"),
+            ReportType::Website => unreachable!("Webbplatsen hanteras separat"),
         };
 
         std::fs::write(path, &content).context("Kunde inte skriva fil")?;
@@ -181,124 +490,75 @@ impl<'a> ExportService<'a> {
         })
     }
 
-    /// Exportera till PDF
+    /// Exportera till PDF: tabeller med sidbrytning och upprepade rubriker
     fn export_to_pdf(&self, report_type: ReportType, path: &Path) -> Result<ExportResult> {
         let title = match report_type {
             ReportType::AllPersons => "Personlista - Genlib",
             ReportType::AllRelationships => "Relationslista - Genlib",
             ReportType::Statistics => "Statistik - Genlib",
+            ReportType::ResearchGaps => "Forskningsluckor - Genlib",
+            ReportType::AllResources => "Resurslista - Genlib",
+            ReportType::PedigreeCollapse => "Anförlust - Genlib",
+            ReportType::RelationshipGraph => "Släktgraf - Genlib",
+            ReportType::Analytics => "Släktanalys - Genlib",
+            ReportType::Ahnentafel => "Antavla - Genlib",
+            ReportType::DescendantRegister => "Ättlingsregister - Genlib",
+            ReportType::PersonDossier => "Persondossier - Genlib",
+            ReportType::Website => "Webbplats - Genlib",
         };
 
-        let (doc, page1, layer1) = PdfDocument::new(title, Mm(210.0), Mm(297.0), "Lager 1");
-        let current_layer = doc.get_page(page1).get_layer(layer1);
-
-        // Ladda typsnitt
-        let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
-        let font_bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
-
-        // Sidkonfiguration
-        let margin_left = Mm(20.0);
-        let margin_top = Mm(280.0);
-        let line_height = Mm(5.0);
-        let mut y_pos = margin_top;
-
-        // Titel
-        current_layer.use_text(title, 16.0, margin_left, y_pos, &font_bold);
-        y_pos = y_pos - Mm(8.0);
-
-        // Datum
-        let date_str = Utc::now().format("Genererad: %Y-%m-%d %H:%M").to_string();
-        current_layer.use_text(&date_str, 10.0, margin_left, y_pos, &font);
-        y_pos = y_pos - Mm(10.0);
-
-        // Separator
-        y_pos = y_pos - line_height;
+        let mut pdf = PdfReport::new(title)?;
+        pdf.title(title);
+        pdf.space(4.0);
 
+        let date = |d: Option<chrono::NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
         let row_count = match report_type {
             ReportType::AllPersons => {
-                let persons = self.db.persons().find_all()?;
-
-                // Kolumnrubriker
-                current_layer.use_text("Namn", 10.0, margin_left, y_pos, &font_bold);
-                current_layer.use_text("Född", 10.0, Mm(90.0), y_pos, &font_bold);
-                current_layer.use_text("Död", 10.0, Mm(120.0), y_pos, &font_bold);
-                current_layer.use_text("Ålder", 10.0, Mm(150.0), y_pos, &font_bold);
-                y_pos = y_pos - line_height;
-
-                // Data
-                let mut count = 0;
-                for person in &persons {
-                    if y_pos < Mm(20.0) {
-                        // Ny sida behövs (förenklad - hoppar över i denna implementation)
-                        break;
-                    }
-
-                    let name = person.full_name();
-                    let birth = person
-                        .birth_date
-                        .map(|d| d.format("%Y-%m-%d").to_string())
-                        .unwrap_or_default();
-                    let death = person
-                        .death_date
-                        .map(|d| d.format("%Y-%m-%d").to_string())
-                        .unwrap_or_default();
-                    let age = person
-                        .age
-                        .map(|a| a.to_string())
-                        .unwrap_or_default();
-
-                    current_layer.use_text(&name, 9.0, margin_left, y_pos, &font);
-                    current_layer.use_text(&birth, 9.0, Mm(90.0), y_pos, &font);
-                    current_layer.use_text(&death, 9.0, Mm(120.0), y_pos, &font);
-                    current_layer.use_text(&age, 9.0, Mm(150.0), y_pos, &font);
-
-                    y_pos = y_pos - line_height;
-                    count += 1;
-                }
-                count
+                let persons = self.visible_persons()?;
+                let rows: Vec<Vec<String>> = persons
+                    .iter()
+                    .map(|person| {
+                        vec![
+                            person.full_name(),
+                            date(person.birth_date),
+                            date(person.death_date),
+                            person.age.map(|a| a.to_string()).unwrap_or_default(),
+                        ]
+                    })
+                    .collect();
+                let columns = [
+                    Column::new("Namn", 5.0),
+                    Column::new("Född", 2.0),
+                    Column::new("Död", 2.0),
+                    Column::new("Ålder", 1.0).right(),
+                ];
+                pdf.table(&columns, &rows, 9.0);
+                rows.len()
             }
             ReportType::AllRelationships => {
-                let relationships = self.db.relationships().find_all()?;
-
-                // Kolumnrubriker
-                current_layer.use_text("Person A", 10.0, margin_left, y_pos, &font_bold);
-                current_layer.use_text("Relation", 10.0, Mm(80.0), y_pos, &font_bold);
-                current_layer.use_text("Person B", 10.0, Mm(120.0), y_pos, &font_bold);
-                y_pos = y_pos - line_height;
-
-                let mut count = 0;
-                for rel in &relationships {
-                    if y_pos < Mm(20.0) {
-                        break;
-                    }
-
-                    let person_a = self
-                        .db
-                        .persons()
-                        .find_by_id(rel.person_a_id)?
-                        .map(|p| p.full_name())
-                        .unwrap_or_default();
-                    let person_b = self
-                        .db
-                        .persons()
-                        .find_by_id(rel.person_b_id)?
-                        .map(|p| p.full_name())
-                        .unwrap_or_default();
-                    let rel_type = rel.relationship_a_to_b.display_name();
-
-                    current_layer.use_text(&person_a, 9.0, margin_left, y_pos, &font);
-                    current_layer.use_text(rel_type, 9.0, Mm(80.0), y_pos, &font);
-                    current_layer.use_text(&person_b, 9.0, Mm(120.0), y_pos, &font);
-
-                    y_pos = y_pos - line_height;
-                    count += 1;
-                }
-                count
+                let relationships = self.relationships()?;
+                let rows: Vec<Vec<String>> = relationships
+                    .iter()
+                    .map(|(rel, person_a, person_b)| {
+                        vec![
+                            person_a.clone(),
+                            rel.relationship_a_to_b.display_name().to_string(),
+                            person_b.clone(),
+                        ]
+                    })
+                    .collect();
+                let columns = [
+                    Column::new("Person A", 4.0),
+                    Column::new("Relation", 2.0),
+                    Column::new("Person B", 4.0),
+                ];
+                pdf.table(&columns, &rows, 9.0);
+                rows.len()
             }
             ReportType::Statistics => {
-                let persons = self.db.persons().find_all()?;
-                let relationships = self.db.relationships().find_all()?;
-                let documents_count = self.db.documents().count()?;
+                let persons = self.persons()?;
+                let relationships = self.relationships()?;
+                let documents_count = self.document_count()?;
 
                 let total = persons.len();
                 let living = persons.iter().filter(|p| p.is_alive()).count();
@@ -310,24 +570,115 @@ impl<'a> ExportService<'a> {
                     ("  - Avlidna:", deceased.to_string()),
                     ("Antal relationer:", relationships.len().to_string()),
                     ("Antal dokument:", documents_count.to_string()),
-                ];
-
-                for (label, value) in &stats {
-                    current_layer.use_text(*label, 10.0, margin_left, y_pos, &font);
-                    current_layer.use_text(value.clone(), 10.0, Mm(60.0), y_pos, &font);
-                    y_pos = y_pos - line_height;
-                }
+                ]
+                .map(|(label, value)| (label.to_string(), value));
+                pdf.key_values(&stats, 10.0, 45.0);
 
                 1
             }
+            ReportType::ResearchGaps => {
+                let (gaps, document_type) = self.research_gaps()?;
+                let rows: Vec<Vec<String>> = gaps
+                    .iter()
+                    .map(|gap| {
+                        let name = format!("{} {}", gap.person.full_name(), gap.person.years_display());
+                        vec![
+                            gap.generation.to_string(),
+                            name.trim().to_string(),
+                            gap.missing_display(document_type.as_ref()),
+                        ]
+                    })
+                    .collect();
+                let columns = [Column::new("Gen", 1.0).right(), Column::new("Namn", 6.0), Column::new("Saknas", 8.0)];
+                pdf.table(&columns, &rows, 9.0);
+                rows.len()
+            }
+            ReportType::AllResources => {
+                let resources = self.resources()?;
+                let rows: Vec<Vec<String>> = resources
+                    .iter()
+                    .map(|resource| {
+                        let coordinates = match (resource.lat, resource.lon) {
+                            (Some(lat), Some(lon)) => format!("{:.4}, {:.4}", lat, lon),
+                            _ => String::new(),
+                        };
+                        vec![resource.name.clone(), resource.resource_type.clone(), coordinates]
+                    })
+                    .collect();
+                let columns = [Column::new("Namn", 5.0), Column::new("Typ", 3.0), Column::new("Koordinater", 3.0)];
+                pdf.table(&columns, &rows, 9.0);
+                rows.len()
+            }
+            ReportType::PedigreeCollapse => {
+                let collapse = self.pedigree_collapse()?;
+
+                let summary = [
+                    (
+                        "Anförlust:",
+                        format!(
+                            "{:.1} % ({} personer på {} platser)",
+                            collapse.collapse_percent(),
+                            collapse.distinct_ancestors,
+                            collapse.slots
+                        ),
+                    ),
+                    ("Föräldrarnas släktskap:", format!("{:.4}", collapse.parents_coefficient)),
+                    ("Inavelskoefficient:", format!("{:.4}", collapse.inbreeding_coefficient())),
+                ]
+                .map(|(label, value)| (label.to_string(), value));
+                pdf.key_values(&summary, 10.0, 50.0);
+                pdf.space(4.0);
+
+                let rows: Vec<Vec<String>> = collapse
+                    .ancestors
+                    .iter()
+                    .map(|ancestor| {
+                        let name = format!("{} {}", ancestor.person.full_name(), ancestor.person.years_display());
+                        let numbers: Vec<String> = ancestor.numbers.iter().map(|n| n.to_string()).collect();
+                        vec![
+                            ancestor.generation().to_string(),
+                            name.trim().to_string(),
+                            numbers.join(", "),
+                            format!("{:.4}", ancestor.coefficient),
+                        ]
+                    })
+                    .collect();
+                let columns = [
+                    Column::new("Gen", 1.0).right(),
+                    Column::new("Namn", 6.0),
+                    Column::new("Antavlenummer", 5.0),
+                    Column::new("Koefficient", 2.5).right(),
+                ];
+                pdf.table(&columns, &rows, 9.0);
+
+                if !collapse.couples.is_empty() {
+                    pdf.heading("Släkt med varandra", 10.0);
+                    for couple in &collapse.couples {
+                        let text = format!(
+                            "Nr {} och {}: {} och {} ({}, {:.4})",
+                            couple.child_number * 2,
+                            couple.child_number * 2 + 1,
+                            couple.father.full_name(),
+                            couple.mother.full_name(),
+                            couple.kinship_label(),
+                            couple.coefficient
+                        );
+                        pdf.line(&text, 9.0, Weight::Regular);
+                    }
+                }
+                rows.len()
+            }
+            ReportType::RelationshipGraph => anyhow::bail!("Släktgrafen exporteras som DOT eller GraphML"),
+            ReportType::Analytics => anyhow::bail!("Släktanalysen exporteras som JSON eller CSV"),
+            ReportType::Ahnentafel
+            | ReportType::DescendantRegister
+            | ReportType::PersonDossier
+            | ReportType::Website => {
+                unreachable!("{} har egen layout", report_type.display_name())
+            }
         };
 
-        // Spara PDF
-        let file = File::create(path).context("Kunde inte skapa PDF-fil")?;
-        let mut writer = BufWriter::new(file);
-        doc.save(&mut writer).context("Kunde inte spara PDF")?;
-
-        let file_size = std::fs::metadata(path)?.len() as usize;
+        let (_, file_size) = pdf.save(path)?;
 
         Ok(ExportResult {
             report_type,
@@ -337,36 +688,225 @@ impl<'a> ExportService<'a> {
         })
     }
 
+    /// Exportera till Excel: en arbetsbok med blad för personer, relationer,
+    /// dokument, checklistor och statistik. Rapportens blad visas först.
+    fn export_to_xlsx(&self, report_type: ReportType, path: &Path) -> Result<ExportResult> {
+        let mut workbook = self.workbook()?;
+        workbook.set_active(match report_type {
+            ReportType::AllRelationships => "Relationer",
+            ReportType::Statistics => "Statistik",
+            _ => "Personer",
+        });
+        let file_size = workbook.save(path)?;
+
+        Ok(ExportResult {
+            report_type,
+            format: ExportFormat::Xlsx,
+            row_count: self.count_rows(report_type)?,
+            file_size,
+        })
+    }
+
+    /// Arbetsbok för urvalet. Privata personer maskeras eller utelämnas, och
+    /// deras dokument, checklistor och vigseluppgifter tas inte med.
+    fn workbook(&self) -> Result<Workbook> {
+        let privacy = self.privacy()?;
+        let persons = self.persons()?;
+        let definitions = self.db.custom_fields().find_definitions(CustomFieldEntity::Person)?;
+        let values = self.db.custom_fields().get_all_values(CustomFieldEntity::Person)?;
+        let masked: HashSet<i64> = persons.iter().filter(|p| privacy.masks(p)).filter_map(|p| p.id).collect();
+
+        let mut sheet = Sheet::new(
+            "Personer",
+            &[
+                "ID", "Förnamn", "Efternamn", "Namn", "Kön", "Född", "Födelseort", "Död", "Ålder", "Levande", "Yrke",
+                "Katalog", "Anteckningar",
+            ],
+        );
+        for definition in &definitions {
+            sheet.add_header(&definition.name);
+        }
+        // Personer som visas, med id och namn för övriga blad
+        let mut shown_persons = Vec::new();
+        for person in &persons {
+            let (Some(id), Some(shown)) = (person.id, privacy.redact(person)) else {
+                continue;
+            };
+            let mut row = vec![
+                Cell::number(id as f64),
+                Cell::opt_text(shown.firstname.as_deref()),
+                Cell::opt_text(shown.surname.as_deref()),
+                Cell::text(shown.full_name()),
+                Cell::text(shown.sex.display_name()),
+                Cell::opt_date(shown.birth_date),
+                Cell::opt_text(shown.birth_place.as_deref()),
+                Cell::opt_date(shown.death_date),
+                shown.age.map_or(Cell::Empty, Cell::number),
+                Cell::Bool(shown.is_alive()),
+                Cell::opt_text(shown.occupation.as_deref()),
+                Cell::opt_text(Some(&shown.directory_name)),
+                Cell::opt_text(shown.notes.as_deref()),
+            ];
+            // Egna fält följer sekretessen precis som i CSV-exporten
+            let person_values = values.get(&id).filter(|_| !masked.contains(&id));
+            for definition in &definitions {
+                let value = person_values.zip(definition.id).and_then(|(v, field_id)| v.get(&field_id));
+                row.push(Self::custom_field_cell(definition, value.map(String::as_str)));
+            }
+            sheet.push_row(row);
+            shown_persons.push((id, shown.full_name()));
+        }
+        let mut workbook = Workbook::new();
+        workbook.add_sheet(sheet);
+
+        let mut sheet = Sheet::new(
+            "Relationer",
+            &["ID", "Person A-ID", "Person A", "Relation", "Person B-ID", "Person B", "Vigseldatum", "Vigselort"],
+        );
+        for (rel, person_a, person_b) in self.relationships()? {
+            let private = masked.contains(&rel.person_a_id) || masked.contains(&rel.person_b_id);
+            sheet.push_row(vec![
+                rel.id.map_or(Cell::Empty, |id| Cell::number(id as f64)),
+                Cell::number(rel.person_a_id as f64),
+                Cell::text(person_a),
+                Cell::text(rel.relationship_a_to_b.display_name()),
+                Cell::number(rel.person_b_id as f64),
+                Cell::text(person_b),
+                if private { Cell::Empty } else { Cell::opt_date(rel.marriage_date) },
+                if private { Cell::Empty } else { Cell::opt_text(rel.marriage_place.as_deref()) },
+            ]);
+        }
+        workbook.add_sheet(sheet);
+
+        let types: HashMap<i64, String> = self
+            .db
+            .documents()
+            .get_all_types()?
+            .into_iter()
+            .filter_map(|t| Some((t.id?, t.name)))
+            .collect();
+        let mut documents = Sheet::new(
+            "Dokument",
+            &["ID", "Person-ID", "Person", "Typ", "Filnamn", "Sökväg", "Filtyp", "Storlek (byte)", "Ändrad"],
+        );
+        let mut checklists = Sheet::new("Checklistor", &["Person-ID", "Person", "Punkt", "Klar", "Avklarad"]);
+        for (id, name) in shown_persons.iter().filter(|(id, _)| !masked.contains(id)) {
+            for doc in self.db.documents().find_by_person(*id)? {
+                documents.push_row(vec![
+                    doc.id.map_or(Cell::Empty, |id| Cell::number(id as f64)),
+                    Cell::number(*id as f64),
+                    Cell::text(name.as_str()),
+                    Cell::opt_text(doc.document_type_id.and_then(|t| types.get(&t)).map(String::as_str)),
+                    Cell::text(doc.filename),
+                    Cell::text(doc.relative_path),
+                    Cell::opt_text(doc.file_type.as_deref()),
+                    Cell::number(doc.file_size as f64),
+                    Cell::opt_date(doc.file_modified_at.map(|t| t.date())),
+                ]);
+            }
+            for item in self.db.checklists().find_by_person(*id)? {
+                let completed = item
+                    .completed_at
+                    .as_deref()
+                    .and_then(|s| chrono::NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok());
+                checklists.push_row(vec![
+                    Cell::number(*id as f64),
+                    Cell::text(name.as_str()),
+                    Cell::text(item.title),
+                    Cell::Bool(item.is_completed),
+                    Cell::opt_date(completed),
+                ]);
+            }
+        }
+        workbook.add_sheet(documents);
+        workbook.add_sheet(checklists);
+
+        let stats = self.statistics()?;
+        let mut sheet = Sheet::new("Statistik", &["Kategori", "Uppgift", "Antal"]);
+        for (label, count) in [
+            ("Personer", stats.total_persons),
+            ("Levande", stats.living_persons),
+            ("Avlidna", stats.deceased_persons),
+            ("Relationer", stats.total_relationships),
+            ("Dokument", stats.total_documents),
+        ] {
+            sheet.push_row(vec![Cell::text("Översikt"), Cell::text(label), Cell::number(count as f64)]);
+        }
+        for rt in &stats.relationships_by_type {
+            sheet.push_row(vec![
+                Cell::text("Relationer per typ"),
+                Cell::text(rt.relationship_type.as_str()),
+                Cell::number(rt.count as f64),
+            ]);
+        }
+        for dc in &stats.persons_by_birth_decade {
+            sheet.push_row(vec![
+                Cell::text("Födelsedecennium"),
+                Cell::text(dc.decade.as_str()),
+                Cell::number(dc.count as f64),
+            ]);
+        }
+        workbook.add_sheet(sheet);
+
+        Ok(workbook)
+    }
+
+    /// Cell för ett eget fält: tal och datum skrivs som typade celler
+    fn custom_field_cell(definition: &CustomFieldDefinition, value: Option<&str>) -> Cell {
+        let Some(value) = value.filter(|v| !v.is_empty()) else {
+            return Cell::Empty;
+        };
+        let typed = match definition.field_type {
+            CustomFieldType::Number => value.replace(',', ".").parse::<f64>().ok().map(Cell::Number),
+            CustomFieldType::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(Cell::Date),
+            _ => None,
+        };
+        typed.unwrap_or_else(|| Cell::text(value))
+    }
+
     /// Exportera personer
     fn export_persons(&self, format: ExportFormat) -> Result<String> {
-        let persons = self.db.persons().find_all()?;
-        let exports: Vec<PersonExport> = persons.iter().map(PersonExport::from).collect();
+        let privacy = self.privacy()?;
+        let definitions = self.db.custom_fields().find_definitions(CustomFieldEntity::Person)?;
+        let values = self.db.custom_fields().get_all_values(CustomFieldEntity::Person)?;
+
+        let mut exports = Vec::new();
+        for person in self.persons()? {
+            let Some(shown) = privacy.redact(&person) else {
+                continue;
+            };
+            let mut export = PersonExport::from(&shown);
+            // Egna fält kan innehålla känsliga uppgifter (t.ex. DNA-kit) och följer sekretessen
+            if !privacy.masks(&person) {
+                if let Some(person_values) = person.id.and_then(|id| values.get(&id)) {
+                    export.custom_fields = Self::named_custom_values(&definitions, person_values);
+                }
+            }
+            exports.push(export);
+        }
 
         match format {
             ExportFormat::Json => {
                 serde_json::to_string_pretty(&exports).context("JSON serialisering misslyckades")
             }
-            ExportFormat::Csv => self.persons_to_csv(&exports),
-            ExportFormat::Pdf => unreachable!("PDF hanteras separat i export_to_pdf"),
+            ExportFormat::Csv => self.persons_to_csv(&exports, &definitions),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::AllPersons.display_name()),
         }
     }
 
     /// Exportera relationer
     fn export_relationships(&self, format: ExportFormat) -> Result<String> {
-        let relationships = self.db.relationships().find_all()?;
+        let relationships = self.relationships()?;
         let mut exports = Vec::new();
 
-        for rel in relationships {
-            let person_a = self.db.persons().find_by_id(rel.person_a_id)?;
-            let person_b = self.db.persons().find_by_id(rel.person_b_id)?;
-
+        for (rel, person_a_name, person_b_name) in relationships {
             // Format: "Person A är [relationship_a_to_b] till Person B"
             exports.push(RelationshipExport {
                 id: rel.id.unwrap_or(0),
                 person_a_id: rel.person_a_id,
-                person_a_name: person_a.map(|p| p.full_name()).unwrap_or_default(),
+                person_a_name,
                 person_b_id: rel.person_b_id,
-                person_b_name: person_b.map(|p| p.full_name()).unwrap_or_default(),
+                person_b_name,
                 relationship_type: rel.relationship_a_to_b.display_name().to_string(),
             });
         }
@@ -376,25 +916,38 @@ impl<'a> ExportService<'a> {
                 serde_json::to_string_pretty(&exports).context("JSON serialisering misslyckades")
             }
             ExportFormat::Csv => self.relationships_to_csv(&exports),
-            ExportFormat::Pdf => unreachable!("PDF hanteras separat i export_to_pdf"),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::AllRelationships.display_name()),
         }
     }
 
     /// Exportera statistik
     fn export_statistics(&self, format: ExportFormat) -> Result<String> {
-        let persons = self.db.persons().find_all()?;
-        let relationships = self.db.relationships().find_all()?;
+        let stats = self.statistics()?;
+
+        match format {
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&stats).context("JSON serialisering misslyckades")
+            }
+            ExportFormat::Csv => self.statistics_to_csv(&stats),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::Statistics.display_name()),
+        }
+    }
+
+    /// Räkna fram statistik för urvalet
+    fn statistics(&self) -> Result<StatisticsExport> {
+        let persons = self.persons()?;
+        let relationships = self.relationships()?;
 
         let total_persons = persons.len() as i64;
         let living_persons = persons.iter().filter(|p| p.is_alive()).count() as i64;
         let deceased_persons = total_persons - living_persons;
         let total_relationships = relationships.len() as i64;
-        let total_documents = self.db.documents().count()?;
+        let total_documents = self.document_count()?;
 
         // Räkna relationer per typ (använder relationship_a_to_b)
         let mut rel_counts: std::collections::HashMap<RelationshipType, i64> =
             std::collections::HashMap::new();
-        for rel in &relationships {
+        for (rel, _, _) in &relationships {
             *rel_counts.entry(rel.relationship_a_to_b).or_insert(0) += 1;
         }
 
@@ -424,7 +977,7 @@ impl<'a> ExportService<'a> {
             .collect();
         persons_by_birth_decade.sort_by(|a, b| a.decade.cmp(&b.decade));
 
-        let stats = StatisticsExport {
+        Ok(StatisticsExport {
             generated_at: Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
             total_persons,
             living_persons,
@@ -433,43 +986,652 @@ impl<'a> ExportService<'a> {
             total_documents,
             relationships_by_type,
             persons_by_birth_decade,
+        })
+    }
+
+    /// Släktanalys över urvalet. Analysen är sammanräknad utom
+    /// släktlinjerna, vars stamfäder visas enligt sekretessinställningarna.
+    pub fn analytics(&self) -> Result<Analytics> {
+        let persons = self.persons()?;
+        let relationships = self.db.relationships().find_all()?;
+        let mut analytics = Analytics::compute(&persons, &relationships);
+
+        let privacy = self.privacy()?;
+        let by_id: HashMap<i64, &Person> = persons.iter().filter_map(|p| Some((p.id?, p))).collect();
+        analytics.generation_depth.retain_mut(|line| {
+            match by_id.get(&line.person_id).and_then(|p| privacy.display_name(p)) {
+                Some(name) => {
+                    line.full_name = name;
+                    true
+                }
+                None => false,
+            }
+        });
+        Ok(analytics)
+    }
+
+    /// Exportera släktanalys
+    fn export_analytics(&self, format: ExportFormat) -> Result<String> {
+        let analytics = self.analytics()?;
+        match format {
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&analytics).context("JSON serialisering misslyckades")
+            }
+            ExportFormat::Csv => Ok(analytics.to_csv()),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::Analytics.display_name()),
+        }
+    }
+
+    /// Antavla enligt inställningarna, med privata personer maskerade eller
+    /// utelämnade (numreringen behålls). Utgångspersonen (nr 1) står alltid
+    /// kvar, som "Privat" om den annars skulle utelämnas.
+    fn ahnentafel(&self) -> Result<Ahnentafel> {
+        let root_id = self
+            .options
+            .root_person_id
+            .context("Ingen utgångsperson vald för rapporten")?;
+
+        let privacy = self.privacy()?;
+        let mut tafel = AhnentafelService::new(self.db).build(root_id, self.options.max_generations)?;
+        let masked: HashSet<u64> = tafel
+            .entries
+            .iter()
+            .filter(|entry| privacy.masks(&entry.person))
+            .map(|entry| entry.number)
+            .collect();
+        tafel.entries.retain_mut(|entry| {
+            let redacted = if entry.number == 1 {
+                Some(privacy.redact_or_placeholder(&entry.person))
+            } else {
+                privacy.redact(&entry.person)
+            };
+            let Some(person) = redacted else {
+                return false;
+            };
+            entry.person = person;
+            // Vigseln står hos fadern (nr n) och gäller även modern (nr n + 1)
+            if masked.contains(&entry.number) || masked.contains(&(entry.number + 1)) {
+                entry.marriage = None;
+            }
+            true
+        });
+        Ok(tafel)
+    }
+
+    /// Exportera antavla
+    fn export_ahnentafel(&self, format: ExportFormat) -> Result<String> {
+        let tafel = self.ahnentafel()?;
+        match format {
+            ExportFormat::Markdown => Ok(tafel.to_markdown()),
+            ExportFormat::Html => Ok(tafel.to_html()),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::Ahnentafel.display_name()),
+        }
+    }
+
+    /// Antavlan som PDF med radbrytning och nya sidor vid behov
+    fn export_ahnentafel_pdf(&self, path: &Path) -> Result<ExportResult> {
+        let tafel = self.ahnentafel()?;
+        let title = tafel.title();
+
+        let mut pdf = PdfReport::new(&title)?;
+        pdf.title(&title);
+        for (generation, entries) in tafel.by_generation() {
+            pdf.heading(&generation_title(generation), 12.0);
+            for entry in entries {
+                pdf.paragraph(&entry.heading(), &entry.body(), 10.0);
+                pdf.space(2.0);
+            }
+        }
+
+        let (_, file_size) = pdf.save(path)?;
+
+        Ok(ExportResult {
+            report_type: ReportType::Ahnentafel,
+            format: ExportFormat::Pdf,
+            row_count: tafel.entries.len(),
+            file_size,
+        })
+    }
+
+    /// Ättlingsregister enligt inställningarna, med levande och privata
+    /// personer maskerade eller utelämnade
+    fn descendant_register(&self) -> Result<DescendantRegister> {
+        let root_id = self
+            .options
+            .root_person_id
+            .context("Ingen utgångsperson vald för rapporten")?;
+
+        let mut register = DescendantRegisterService::new(self.db).build(
+            root_id,
+            self.options.max_generations,
+            self.options.include_sources,
+        )?;
+        register.redact(&self.privacy()?);
+        Ok(register)
+    }
+
+    /// Exportera ättlingsregister
+    fn export_descendant_register(&self, format: ExportFormat) -> Result<String> {
+        let register = self.descendant_register()?;
+        match format {
+            ExportFormat::Markdown => Ok(register.to_markdown()),
+            ExportFormat::Html => Ok(register.to_html()),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::DescendantRegister.display_name()),
+        }
+    }
+
+    /// Ättlingsregistret som PDF med radbrytning och nya sidor vid behov
+    fn export_descendant_register_pdf(&self, path: &Path) -> Result<ExportResult> {
+        let register = self.descendant_register()?;
+        let title = register.title();
+
+        let mut pdf = PdfReport::new(&title)?;
+        pdf.title(&title);
+        for (generation, entries) in register.by_generation() {
+            pdf.heading(&format!("Generation {}", generation), 12.0);
+            for entry in entries {
+                pdf.paragraph(&register.heading(entry), &entry.body(), 10.0);
+                for family in entry.families.iter().filter(|f| !f.children.is_empty()) {
+                    pdf.space(1.0);
+                    pdf.wrapped(&entry.family_title(family), 10.0, 6.0, Weight::Regular);
+                    for (i, child) in family.children.iter().enumerate() {
+                        pdf.wrapped(&child.line(i), 9.0, 10.0, Weight::Regular);
+                    }
+                }
+                pdf.space(2.0);
+            }
+        }
+
+        let (_, file_size) = pdf.save(path)?;
+
+        Ok(ExportResult {
+            report_type: ReportType::DescendantRegister,
+            format: ExportFormat::Pdf,
+            row_count: register.entries.len(),
+            file_size,
+        })
+    }
+
+    /// Persondossier för utgångspersonen. En levande eller privat person
+    /// exporteras bara utan maskering; släktingar maskeras som vanligt.
+    fn person_dossier(&self) -> Result<PersonDossier> {
+        let person_id = self
+            .options
+            .root_person_id
+            .context("Ingen utgångsperson vald för rapporten")?;
+
+        let privacy = self.privacy()?;
+        let mut dossier = PersonDossierService::new(self.db).build(person_id)?;
+        if privacy.masks(&dossier.person) {
+            anyhow::bail!(
+                "{} räknas som levande eller privat. Välj \"{}\" för att exportera dossiern.",
+                dossier.person.full_name(),
+                RedactionStyle::None.display_name()
+            );
+        }
+
+        // En släkting som inte går att slå upp tas inte med
+        let graph = self.db.kinship()?;
+        for (_, views) in &mut dossier.relationships {
+            views.retain_mut(|view| {
+                let Some(other) = graph.person(view.other_person_id) else {
+                    return false;
+                };
+                match privacy.display_name(other) {
+                    Some(name) => {
+                        view.other_person_name = name;
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+        dossier.relationships.retain(|(_, views)| !views.is_empty());
+        Ok(dossier)
+    }
+
+    /// Persondossiern som PDF: uppgifter, relationer, checklista,
+    /// miniatyrer av bilderna, textdokumentens innehåll och en förteckning
+    /// över bifogade PDF-filer
+    fn export_person_dossier_pdf(&self, path: &Path) -> Result<ExportResult> {
+        let dossier = self.person_dossier()?;
+        let title = dossier.title();
+
+        let mut pdf = PdfReport::new(&title)?;
+        pdf.title(&title);
+
+        pdf.heading("Personuppgifter", 13.0);
+        for (label, value) in dossier.fields() {
+            pdf.paragraph(&format!("{}:", label), &value, 10.0);
+        }
+        for (name, value) in &dossier.custom_fields {
+            pdf.paragraph(&format!("{}:", name), value, 10.0);
+        }
+        if let Some(notes) = dossier.person.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+            pdf.space(2.0);
+            pdf.line("Anteckningar", 10.0, Weight::Bold);
+            pdf.text_block(notes, 10.0, 0.0);
+        }
+
+        pdf.heading("Relationer", 13.0);
+        if dossier.relationships.is_empty() {
+            pdf.line("Inga registrerade relationer", 10.0, Weight::Regular);
+        }
+        for (rel_type, views) in &dossier.relationships {
+            let names: Vec<&str> = views.iter().map(|v| v.other_person_name.as_str()).collect();
+            pdf.paragraph(&format!("{}:", rel_type.display_name()), &names.join(", "), 10.0);
+        }
+
+        let (done, total) = dossier.checklist_progress();
+        pdf.heading(&format!("Checklista ({} av {} klara)", done, total), 13.0);
+        for item in &dossier.checklist {
+            let mark = if item.is_completed { "☑" } else { "☐" };
+            let completed = item.completed_at.as_deref().map(|d| format!(" ({})", d)).unwrap_or_default();
+            pdf.wrapped(&format!("{} {}{}", mark, item.title, completed), 10.0, 0.0, Weight::Regular);
+        }
+
+        let images: Vec<(RgbImage, String)> = dossier
+            .images()
+            .filter_map(|d| Some((load_thumbnail(&d.path, 400, 400)?, d.document.filename.clone())))
+            .collect();
+        if !images.is_empty() {
+            pdf.heading(&format!("Bilder ({})", images.len()), 13.0);
+            pdf.thumbnails(&images, 3);
+        }
+
+        for text in dossier.texts() {
+            pdf.heading(&text.document.filename, 13.0);
+            match &text.text {
+                Some(content) => pdf.text_block(content, 9.0, 0.0),
+                None => pdf.line("Filen kunde inte läsas", 9.0, Weight::Regular),
+            }
+        }
+
+        pdf.heading("Dokumentförteckning", 13.0);
+        if dossier.document_count() == 0 {
+            pdf.line("Inga dokument", 10.0, Weight::Regular);
+        }
+        for group in &dossier.documents {
+            pdf.space(1.0);
+            pdf.line(&group.type_name, 10.0, Weight::Bold);
+            let rows: Vec<Vec<String>> = group
+                .documents
+                .iter()
+                .map(|document| {
+                    let d = &document.document;
+                    let kind = if d.is_pdf() { "PDF" } else { "" };
+                    vec![d.filename.clone(), kind.to_string(), d.file_size_display(), d.relative_path.clone()]
+                })
+                .collect();
+            let columns = [
+                Column::new("Fil", 4.0),
+                Column::new("Typ", 1.0),
+                Column::new("Storlek", 1.5).right(),
+                Column::new("Sökväg", 5.0),
+            ];
+            pdf.table(&columns, &rows, 9.0);
+        }
+        let pdf_count = dossier.pdfs().count();
+        if pdf_count > 0 {
+            pdf.space(2.0);
+            pdf.wrapped(
+                &format!("{} bifogade PDF-filer finns i personens katalog och ingår inte i utskriften.", pdf_count),
+                9.0,
+                0.0,
+                Weight::Regular,
+            );
+        }
+
+        let (_, file_size) = pdf.save(path)?;
+
+        Ok(ExportResult {
+            report_type: ReportType::PersonDossier,
+            format: ExportFormat::Pdf,
+            row_count: dossier.document_count(),
+            file_size,
+        })
+    }
+
+    /// Webbplats för urvalet i katalogen `dir`. Levande och privata
+    /// personer maskeras även om ingen maskering är vald.
+    fn export_website(&self, dir: &Path) -> Result<ExportResult> {
+        let result = WebsiteExporter::new(self.db, self.options.redaction)?.export(&self.persons()?, dir)?;
+        Ok(ExportResult {
+            report_type: ReportType::Website,
+            format: ExportFormat::Html,
+            row_count: result.person_count,
+            file_size: result.total_size as usize,
+        })
+    }
+
+    /// Personer som ingår i rapporten (alla eller de som matchar urvalet)
+    fn persons(&self) -> Result<Vec<Person>> {
+        match self.options.person_filter {
+            Some(ref filter) => self.db.persons().advanced_search(filter),
+            None => self.db.persons().find_all(),
+        }
+    }
+
+    /// Alla resurser med typnamn och egna fält
+    fn resources(&self) -> Result<Vec<ResourceExport>> {
+        let definitions = self.db.custom_fields().find_definitions(CustomFieldEntity::Resource)?;
+        let values = self.db.custom_fields().get_all_values(CustomFieldEntity::Resource)?;
+
+        Ok(self
+            .db
+            .resources()
+            .find_all()?
+            .into_iter()
+            .map(|(resource, resource_type)| {
+                let id = resource.id.unwrap_or(0);
+                ResourceExport {
+                    id,
+                    resource_type: resource_type.name,
+                    name: resource.name,
+                    information: resource.information,
+                    comment: resource.comment,
+                    lat: resource.lat,
+                    lon: resource.lon,
+                    directory_name: resource.directory_name,
+                    custom_fields: values
+                        .get(&id)
+                        .map(|v| Self::named_custom_values(&definitions, v))
+                        .unwrap_or_default(),
+                }
+            })
+            .collect())
+    }
+
+    /// Exportera resurser
+    fn export_resources(&self, format: ExportFormat) -> Result<String> {
+        let exports = self.resources()?;
+
+        match format {
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&exports).context("JSON serialisering misslyckades")
+            }
+            ExportFormat::Csv => {
+                let definitions = self.db.custom_fields().find_definitions(CustomFieldEntity::Resource)?;
+                self.resources_to_csv(&exports, &definitions)
+            }
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::AllResources.display_name()),
+        }
+    }
+
+    /// Översätt fält-ID -> värde till fältnamn -> värde
+    fn named_custom_values(
+        definitions: &[CustomFieldDefinition],
+        values: &HashMap<i64, String>,
+    ) -> BTreeMap<String, String> {
+        definitions
+            .iter()
+            .filter_map(|d| {
+                let value = values.get(&d.id?)?;
+                Some((d.name.clone(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// Sekretessregler för exporten
+    fn privacy(&self) -> Result<PrivacyPolicy> {
+        PrivacyPolicy::from_config(self.db, self.options.redaction)
+    }
+
+    /// Personer i urvalet som de ska visas, med privata personer maskerade
+    /// eller utelämnade enligt sekretessinställningarna
+    fn visible_persons(&self) -> Result<Vec<Person>> {
+        let privacy = self.privacy()?;
+        Ok(self.persons()?.iter().filter_map(|p| privacy.redact(p)).collect())
+    }
+
+    /// Relationer där båda personerna syns i exporten, med personernas
+    /// (eventuellt maskerade) namn
+    fn relationships(&self) -> Result<Vec<(PersonRelationship, String, String)>> {
+        let names: HashMap<i64, String> = self
+            .visible_persons()?
+            .iter()
+            .filter_map(|p| p.id.map(|id| (id, p.full_name())))
+            .collect();
+
+        Ok(self
+            .db
+            .relationships()
+            .find_all()?
+            .into_iter()
+            .filter_map(|r| {
+                let person_a = names.get(&r.person_a_id)?.clone();
+                let person_b = names.get(&r.person_b_id)?.clone();
+                Some((r, person_a, person_b))
+            })
+            .collect())
+    }
+
+    /// Antal dokument för personerna i urvalet
+    fn document_count(&self) -> Result<i64> {
+        if self.options.person_filter.is_none() {
+            return self.db.documents().count();
+        }
+
+        let mut total = 0;
+        for person in self.persons()? {
+            if let Some(id) = person.id {
+                total += self.db.documents().count_by_person(id)?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Hämta forskningsluckor enligt inställningarna
+    fn research_gaps(&self) -> Result<(Vec<ResearchGap>, Option<DocumentType>)> {
+        let root_id = self
+            .options
+            .root_person_id
+            .context("Ingen utgångsperson vald för rapporten")?;
+
+        let document_type = match self.options.document_type_id {
+            Some(id) => self.db.documents().get_type_by_id(id)?,
+            None => None,
         };
 
+        let privacy = self.privacy()?;
+        let gaps = ResearchGapsService::new(self.db)
+            .find_gaps(root_id, self.options.max_generations, self.options.document_type_id)?
+            .into_iter()
+            .filter_map(|mut gap| {
+                gap.person = privacy.redact(&gap.person)?;
+                Some(gap)
+            })
+            .collect();
+
+        Ok((gaps, document_type))
+    }
+
+    /// Exportera forskningsluckor
+    fn export_research_gaps(&self, format: ExportFormat) -> Result<String> {
+        let (gaps, document_type) = self.research_gaps()?;
+        let exports: Vec<ResearchGapExport> = gaps
+            .iter()
+            .map(|g| ResearchGapExport::from_gap(g, document_type.as_ref()))
+            .collect();
+
         match format {
             ExportFormat::Json => {
-                serde_json::to_string_pretty(&stats).context("JSON serialisering misslyckades")
+                serde_json::to_string_pretty(&exports).context("JSON serialisering misslyckades")
             }
-            ExportFormat::Csv => self.statistics_to_csv(&stats),
-            ExportFormat::Pdf => unreachable!("PDF hanteras separat i export_to_pdf"),
+            ExportFormat::Csv => self.research_gaps_to_csv(&exports),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::ResearchGaps.display_name()),
+        }
+    }
+
+    /// Analysera anförlust enligt inställningarna, med privata personer
+    /// maskerade eller utelämnade
+    fn pedigree_collapse(&self) -> Result<PedigreeCollapse> {
+        let root_id = self
+            .options
+            .root_person_id
+            .context("Ingen utgångsperson vald för rapporten")?;
+
+        let privacy = self.privacy()?;
+        let mut collapse = PedigreeCollapseService::new(self.db).analyze(root_id, self.options.max_generations)?;
+        collapse.ancestors.retain_mut(|a| match privacy.redact(&a.person) {
+            Some(person) => {
+                a.person = person;
+                true
+            }
+            None => false,
+        });
+        collapse.couples.retain_mut(|c| match (privacy.redact(&c.father), privacy.redact(&c.mother)) {
+            (Some(father), Some(mother)) => {
+                (c.father, c.mother) = (father, mother);
+                true
+            }
+            _ => false,
+        });
+
+        Ok(collapse)
+    }
+
+    /// Exportera anförlust
+    fn export_pedigree_collapse(&self, format: ExportFormat) -> Result<String> {
+        let export = PedigreeCollapseExport::new(&self.pedigree_collapse()?);
+
+        match format {
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&export).context("JSON serialisering misslyckades")
+            }
+            ExportFormat::Csv => self.pedigree_collapse_to_csv(&export),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::PedigreeCollapse.display_name()),
+        }
+    }
+
+    /// Personer och relationer i släktgrafen: urvalet, begränsat till
+    /// personer inom `max_generations` generationer från utgångspersonen om
+    /// en sådan är vald. Bara förälder- och barnled räknas; makar till den
+    /// som nås tas med i samma generation. Privata personer maskeras eller
+    /// utelämnas.
+    fn relationship_graph(&self) -> Result<(Vec<Person>, Vec<PersonRelationship>)> {
+        let mut persons = self.visible_persons()?;
+
+        if let Some(root_id) = self.options.root_person_id {
+            let graph = self.db.kinship()?;
+            let mut reached = HashSet::from([root_id]);
+            reached.extend(graph.spouses(root_id));
+            let mut frontier = vec![root_id];
+            for _ in 0..self.options.max_generations {
+                frontier = frontier
+                    .iter()
+                    .flat_map(|id| [graph.parents(*id), graph.children(*id)].concat())
+                    .filter(|id| reached.insert(*id))
+                    .collect();
+                // Makar följer med utan att räknas som ett led
+                for id in &frontier {
+                    reached.extend(graph.spouses(*id));
+                }
+            }
+            persons.retain(|p| p.id.is_some_and(|id| reached.contains(&id)));
+        }
+
+        let ids: HashSet<i64> = persons.iter().filter_map(|p| p.id).collect();
+        let relationships = self
+            .db
+            .relationships()
+            .find_all()?
+            .into_iter()
+            .filter(|r| ids.contains(&r.person_a_id) && ids.contains(&r.person_b_id))
+            .collect();
+
+        Ok((persons, relationships))
+    }
+
+    /// Exportera släktgrafen
+    fn export_relationship_graph(&self, format: ExportFormat) -> Result<String> {
+        let (persons, relationships) = self.relationship_graph()?;
+
+        match format {
+            ExportFormat::Dot => Ok(graph_export::to_dot(&persons, &relationships)),
+            ExportFormat::GraphMl => Ok(graph_export::to_graphml(&persons, &relationships)),
+            _ => anyhow::bail!("{} stöds inte för {}", format.display_name(), ReportType::RelationshipGraph.display_name()),
         }
     }
 
     /// Konvertera personer till CSV
-    fn persons_to_csv(&self, persons: &[PersonExport]) -> Result<String> {
+    fn persons_to_csv(&self, persons: &[PersonExport], definitions: &[CustomFieldDefinition]) -> Result<String> {
         let mut csv = String::new();
 
-        // Header
-        csv.push_str("id,firstname,surname,full_name,birth_date,death_date,age,is_alive,directory_name\n");
+        // Header (egna fält som extra kolumner)
+        csv.push_str("id,firstname,surname,full_name,birth_date,death_date,age,is_alive,directory_name,notes");
+        Self::push_custom_field_headers(&mut csv, definitions);
 
         // Rader
         for p in persons {
             csv.push_str(&format!(
-                "{},{},{},{},{},{},{},{},{}\n",
+                "{},{},{},{},{},{},{},{},{},{}",
                 p.id,
-                Self::csv_escape(p.firstname.as_deref().unwrap_or("")),
-                Self::csv_escape(p.surname.as_deref().unwrap_or("")),
-                Self::csv_escape(&p.full_name),
+                csv_escape(p.firstname.as_deref().unwrap_or("")),
+                csv_escape(p.surname.as_deref().unwrap_or("")),
+                csv_escape(&p.full_name),
                 p.birth_date.as_deref().unwrap_or(""),
                 p.death_date.as_deref().unwrap_or(""),
                 p.age.map(|a| a.to_string()).unwrap_or_default(),
                 p.is_alive,
-                Self::csv_escape(&p.directory_name),
+                csv_escape(&p.directory_name),
+                csv_escape(p.notes.as_deref().unwrap_or("")),
+            ));
+            Self::push_custom_field_values(&mut csv, definitions, &p.custom_fields);
+        }
+
+        Ok(csv)
+    }
+
+    /// Konvertera resurser till CSV
+    fn resources_to_csv(&self, resources: &[ResourceExport], definitions: &[CustomFieldDefinition]) -> Result<String> {
+        let mut csv = String::new();
+
+        // Header (egna fält som extra kolumner)
+        csv.push_str("id,resource_type,name,information,comment,lat,lon,directory_name");
+        Self::push_custom_field_headers(&mut csv, definitions);
+
+        // Rader
+        for r in resources {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}",
+                r.id,
+                csv_escape(&r.resource_type),
+                csv_escape(&r.name),
+                csv_escape(r.information.as_deref().unwrap_or("")),
+                csv_escape(r.comment.as_deref().unwrap_or("")),
+                r.lat.map(|v| v.to_string()).unwrap_or_default(),
+                r.lon.map(|v| v.to_string()).unwrap_or_default(),
+                csv_escape(&r.directory_name),
             ));
+            Self::push_custom_field_values(&mut csv, definitions, &r.custom_fields);
         }
 
         Ok(csv)
     }
 
+    /// Avsluta CSV-rubrikraden med en kolumn per eget fält
+    fn push_custom_field_headers(csv: &mut String, definitions: &[CustomFieldDefinition]) {
+        for definition in definitions {
+            csv.push(',');
+            csv.push_str(&csv_escape(&definition.name));
+        }
+        csv.push('\n');
+    }
+
+    /// Avsluta en CSV-rad med värden för egna fält i samma ordning som rubrikerna
+    fn push_custom_field_values(
+        csv: &mut String,
+        definitions: &[CustomFieldDefinition],
+        values: &BTreeMap<String, String>,
+    ) {
+        for definition in definitions {
+            csv.push(',');
+            csv.push_str(&csv_escape(values.get(&definition.name).map(String::as_str).unwrap_or("")));
+        }
+        csv.push('\n');
+    }
+
     /// Konvertera relationer till CSV
     fn relationships_to_csv(&self, relationships: &[RelationshipExport]) -> Result<String> {
         let mut csv = String::new();
@@ -485,10 +1647,10 @@ impl<'a> ExportService<'a> {
                 "{},{},{},{},{},{}\n",
                 r.id,
                 r.person_a_id,
-                Self::csv_escape(&r.person_a_name),
+                csv_escape(&r.person_a_name),
                 r.person_b_id,
-                Self::csv_escape(&r.person_b_name),
-                Self::csv_escape(&r.relationship_type),
+                csv_escape(&r.person_b_name),
+                csv_escape(&r.relationship_type),
             ));
         }
 
@@ -527,21 +1689,70 @@ impl<'a> ExportService<'a> {
         Ok(csv)
     }
 
-    /// Escape CSV-värde
-    fn csv_escape(value: &str) -> String {
-        if value.contains(',') || value.contains('"') || value.contains('\n') {
-            format!("\"{}\"", value.replace('"', "\"\""))
-        } else {
-            value.to_string()
+    /// Konvertera forskningsluckor till CSV
+    fn research_gaps_to_csv(&self, gaps: &[ResearchGapExport]) -> Result<String> {
+        let mut csv = String::new();
+
+        // Header
+        csv.push_str("generation,person_id,full_name,birth_date,death_date,birth_place,missing\n");
+
+        // Rader
+        for g in gaps {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                g.generation,
+                g.person_id,
+                csv_escape(&g.full_name),
+                g.birth_date.as_deref().unwrap_or(""),
+                g.death_date.as_deref().unwrap_or(""),
+                csv_escape(g.birth_place.as_deref().unwrap_or("")),
+                csv_escape(&g.missing.join("; ")),
+            ));
+        }
+
+        Ok(csv)
+    }
+
+    /// Konvertera anförlust till CSV (en rad per upprepad förfader)
+    fn pedigree_collapse_to_csv(&self, export: &PedigreeCollapseExport) -> Result<String> {
+        let mut csv = String::new();
+
+        // Header
+        csv.push_str("generation,person_id,full_name,birth_date,death_date,ahnentafel_numbers,coefficient\n");
+
+        // Rader
+        for a in &export.ancestors {
+            let numbers: Vec<String> = a.ahnentafel_numbers.iter().map(|n| n.to_string()).collect();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                a.generation,
+                a.person_id,
+                csv_escape(&a.full_name),
+                a.birth_date.as_deref().unwrap_or(""),
+                a.death_date.as_deref().unwrap_or(""),
+                csv_escape(&numbers.join("; ")),
+                a.coefficient,
+            ));
         }
+
+        Ok(csv)
     }
 
     /// Räkna rader för rapport
     fn count_rows(&self, report_type: ReportType) -> Result<usize> {
         Ok(match report_type {
-            ReportType::AllPersons => self.db.persons().find_all()?.len(),
-            ReportType::AllRelationships => self.db.relationships().find_all()?.len(),
+            ReportType::AllPersons => self.visible_persons()?.len(),
+            ReportType::AllRelationships => self.relationships()?.len(),
             ReportType::Statistics => 1,
+            ReportType::ResearchGaps => self.research_gaps()?.0.len(),
+            ReportType::AllResources => self.db.resources().count()? as usize,
+            ReportType::PedigreeCollapse => self.pedigree_collapse()?.ancestors.len(),
+            ReportType::RelationshipGraph => self.relationship_graph()?.0.len(),
+            ReportType::Analytics => self.persons()?.len(),
+            ReportType::Ahnentafel => self.ahnentafel()?.entries.len(),
+            ReportType::DescendantRegister => self.descendant_register()?.entries.len(),
+            ReportType::PersonDossier => self.person_dossier()?.document_count(),
+            ReportType::Website => self.persons()?.len(),
         })
     }
 }
@@ -566,16 +1777,25 @@ impl ExportResult {
     }
 }
 
+/// Escape CSV-värde
+pub(crate) fn csv_escape(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_csv_escape() {
-        assert_eq!(ExportService::csv_escape("hello"), "hello");
-        assert_eq!(ExportService::csv_escape("hello,world"), "\"hello,world\"");
+        assert_eq!(csv_escape("hello"), "hello");
+        assert_eq!(csv_escape("hello,world"), "\"hello,world\"");
         assert_eq!(
-            ExportService::csv_escape("say \"hello\""),
+            csv_escape("say \"hello\""),
             "\"say \"\"hello\"\"\""
         );
     }
@@ -590,4 +1810,353 @@ mod tests {
         assert!(filename.starts_with("genlib_statistik_"));
         assert!(filename.ends_with(".csv"));
     }
+
+    #[test]
+    fn test_living_persons_redacted_in_exports() {
+        use crate::models::Privacy;
+        use chrono::NaiveDate;
+
+        let db = Database::open_in_memory().unwrap();
+
+        let mut ancestor = Person::new(Some("Karl".into()), Some("Berg".into()), "karl".into());
+        ancestor.birth_date = NaiveDate::from_ymd_opt(1850, 1, 1);
+        ancestor.death_date = NaiveDate::from_ymd_opt(1920, 1, 1);
+        db.persons().create(&mut ancestor).unwrap();
+
+        let mut living = Person::new(Some("Lisa".into()), Some("Berg".into()), "lisa".into());
+        living.birth_date = NaiveDate::from_ymd_opt(1990, 1, 1);
+        living.notes = Some("Telefon 070-123".into());
+        db.persons().create(&mut living).unwrap();
+
+        let mut rel = PersonRelationship::new(ancestor.id.unwrap(), living.id.unwrap(), RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+
+        // Standard: ersätt med "Privat"
+        let service = ExportService::new(&db);
+        let persons = service.export_persons(ExportFormat::Csv).unwrap();
+        assert!(persons.contains("Karl Berg"));
+        assert!(persons.contains("Privat"));
+        assert!(!persons.contains("Lisa"));
+        assert!(!persons.contains("070-123"));
+        assert!(!persons.contains("1990"));
+
+        // Utelämna: personen och dess relationer försvinner
+        let options = ReportOptions {
+            redaction: RedactionStyle::Omit,
+            ..Default::default()
+        };
+        let service = ExportService::new(&db).with_options(options);
+        assert_eq!(service.visible_persons().unwrap().len(), 1);
+        assert!(service.relationships().unwrap().is_empty());
+
+        // Markerad som offentlig visas trots att personen lever
+        living.privacy = Privacy::Public;
+        db.persons().update(&mut living).unwrap();
+        let persons = ExportService::new(&db).export_persons(ExportFormat::Json).unwrap();
+        assert!(persons.contains("Lisa"));
+    }
+
+    #[test]
+    fn test_custom_fields_in_csv_export() {
+        use crate::models::CustomFieldType;
+        use chrono::NaiveDate;
+
+        let db = Database::open_in_memory().unwrap();
+
+        let mut kit = CustomFieldDefinition::new(CustomFieldEntity::Person, "DNA-kit".into(), CustomFieldType::Text);
+        let kit_id = db.custom_fields().create_definition(&mut kit).unwrap();
+
+        let mut ancestor = Person::new(Some("Karl".into()), Some("Berg".into()), "karl".into());
+        ancestor.birth_date = NaiveDate::from_ymd_opt(1850, 1, 1);
+        ancestor.death_date = NaiveDate::from_ymd_opt(1920, 1, 1);
+        db.persons().create(&mut ancestor).unwrap();
+        let mut living = Person::new(Some("Lisa".into()), Some("Berg".into()), "lisa".into());
+        db.persons().create(&mut living).unwrap();
+
+        db.custom_fields()
+            .set_values(ancestor.id.unwrap(), &HashMap::from([(kit_id, "A-100".to_string())]))
+            .unwrap();
+        db.custom_fields()
+            .set_values(living.id.unwrap(), &HashMap::from([(kit_id, "B-200".to_string())]))
+            .unwrap();
+
+        let csv = ExportService::new(&db).export_persons(ExportFormat::Csv).unwrap();
+        let header = csv.lines().next().unwrap();
+        assert!(header.ends_with(",notes,DNA-kit"));
+        assert!(csv.contains("A-100"));
+        // Levande personers egna fält maskeras tillsammans med övriga uppgifter
+        assert!(!csv.contains("B-200"));
+        assert!(csv.lines().all(|l| l.split(',').count() == header.split(',').count()));
+    }
+
+    #[test]
+    fn test_xlsx_export() {
+        use crate::models::{CustomFieldType, Document, PersonChecklistItem};
+        use chrono::NaiveDate;
+        use std::io::Read;
+
+        let db = Database::open_in_memory().unwrap();
+        let mut year = CustomFieldDefinition::new(CustomFieldEntity::Person, "Utvandrade".into(), CustomFieldType::Number);
+        let year_id = db.custom_fields().create_definition(&mut year).unwrap();
+
+        let mut ancestor = Person::new(Some("Åke".into()), Some("Öberg".into()), "ake".into());
+        ancestor.birth_date = NaiveDate::from_ymd_opt(1850, 1, 1);
+        ancestor.death_date = NaiveDate::from_ymd_opt(1920, 6, 1);
+        db.persons().create(&mut ancestor).unwrap();
+        let mut living = Person::new(Some("Lisa".into()), Some("Berg".into()), "lisa".into());
+        living.birth_date = NaiveDate::from_ymd_opt(1990, 1, 1);
+        db.persons().create(&mut living).unwrap();
+        let (ake, lisa) = (ancestor.id.unwrap(), living.id.unwrap());
+
+        db.custom_fields().set_values(ake, &HashMap::from([(year_id, "1888".to_string())])).unwrap();
+        let mut rel = PersonRelationship::new(ake, lisa, RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+        db.documents().create(&mut Document::new(ake, "husförhör.jpg".into(), "husförhör.jpg".into())).unwrap();
+        db.documents().create(&mut Document::new(lisa, "pass.pdf".into(), "pass.pdf".into())).unwrap();
+        let mut item = PersonChecklistItem::new(ake, "Sök i emigrantregistret".into());
+        db.checklists().create(&mut item).unwrap();
+        db.checklists().toggle_completed(item.id.unwrap()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("genlib.xlsx");
+        let result = ExportService::new(&db)
+            .export_to_file(ReportType::AllRelationships, ExportFormat::Xlsx, &path)
+            .unwrap();
+        assert_eq!(result.row_count, 1);
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+            content
+        };
+
+        let book = read("xl/workbook.xml");
+        for name in ["Personer", "Relationer", "Dokument", "Checklistor", "Statistik"] {
+            assert!(book.contains(&format!("name=\"{}\"", name)));
+        }
+        assert!(book.contains("activeTab=\"1\""));
+
+        let persons = read("xl/worksheets/sheet1.xml");
+        assert!(persons.contains("Åke Öberg"));
+        assert!(persons.contains("Privat"));
+        assert!(!persons.contains("Lisa"));
+        assert!(persons.contains("s=\"2\"><v>7458</v></c>"), "dödsdatum som datumcell");
+        assert!(persons.contains("\"><v>1888</v></c>"), "eget talfält som tal");
+
+        let documents = read("xl/worksheets/sheet3.xml");
+        assert!(documents.contains("husförhör.jpg"));
+        assert!(!documents.contains("pass.pdf"));
+        let checklists = read("xl/worksheets/sheet4.xml");
+        assert!(checklists.contains("Sök i emigrantregistret"));
+        assert!(checklists.contains("t=\"b\"><v>1</v>"));
+        assert!(read("xl/worksheets/sheet5.xml").contains("Översikt"));
+
+        // Bara personer, relationer och statistik finns som Excel
+        assert!(ExportService::new(&db).export_to_file(ReportType::AllResources, ExportFormat::Xlsx, &path).is_err());
+    }
+
+    #[test]
+    fn test_ahnentafel_export() {
+        let db = Database::open_in_memory().unwrap();
+        let mut ids = Vec::new();
+        use crate::models::Sex;
+
+        for (name, sex) in [("Barn", Sex::Female), ("Far", Sex::Male), ("Mor", Sex::Female)] {
+            let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+            p.sex = sex;
+            p.death_date = chrono::NaiveDate::from_ymd_opt(1900, 1, 1);
+            // Långa anteckningar tvingar fram flera sidor
+            p.notes = Some("Anteckning om gården och socknen. ".repeat(150));
+            db.persons().create(&mut p).unwrap();
+            ids.push(p.id.unwrap());
+        }
+        for parent in &ids[1..] {
+            let mut rel = PersonRelationship::new(*parent, ids[0], RelationshipType::Parent);
+            db.relationships().create(&mut rel).unwrap();
+        }
+
+        let options = ReportOptions { root_person_id: Some(ids[0]), ..Default::default() };
+        let service = ExportService::new(&db).with_options(options);
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("antavla.md");
+        let result = service.export_to_file(ReportType::Ahnentafel, ExportFormat::Markdown, &path).unwrap();
+        assert_eq!(result.row_count, 3);
+        let md = std::fs::read_to_string(&path).unwrap();
+        assert!(md.contains("**2. Far Berg** Död 1900-01-01. Far till nr 1."));
+
+        let path = dir.path().join("antavla.pdf");
+        service.export_to_file(ReportType::Ahnentafel, ExportFormat::Pdf, &path).unwrap();
+        let pdf = String::from_utf8_lossy(&std::fs::read(&path).unwrap()).to_string();
+        assert!(pdf.matches("/Type/Page/").count() >= 3, "antavlan ska brytas över flera sidor");
+
+        assert!(service.export_to_file(ReportType::Ahnentafel, ExportFormat::Json, &path).is_err());
+
+        // Vigseln försvinner när en av makarna maskeras
+        let mut marriage = PersonRelationship::new(ids[1], ids[2], RelationshipType::Spouse);
+        marriage.marriage_date = chrono::NaiveDate::from_ymd_opt(1875, 6, 1);
+        marriage.marriage_place = Some("Lund".into());
+        db.relationships().create(&mut marriage).unwrap();
+        let path = dir.path().join("antavla_gift.md");
+        service.export_to_file(ReportType::Ahnentafel, ExportFormat::Markdown, &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("Gift 1875-06-01 i Lund med nr 3."));
+
+        let mut mor = db.persons().find_by_id(ids[2]).unwrap().unwrap();
+        mor.privacy = crate::models::Privacy::Private;
+        db.persons().update(&mut mor).unwrap();
+        service.export_to_file(ReportType::Ahnentafel, ExportFormat::Markdown, &path).unwrap();
+        let md = std::fs::read_to_string(&path).unwrap();
+        assert!(md.contains("**2. Far Berg** Död 1900-01-01. Far till nr 1."));
+        assert!(!md.contains("Gift"));
+        assert!(!md.contains("Lund"));
+
+        // En levande utgångsperson står kvar som nr 1 även när privata utelämnas
+        let mut lisa = Person::new(Some("Lisa".into()), Some("Berg".into()), "lisa".into());
+        lisa.birth_date = chrono::NaiveDate::from_ymd_opt(1990, 1, 1);
+        db.persons().create(&mut lisa).unwrap();
+        let mut rel = PersonRelationship::new(ids[0], lisa.id.unwrap(), RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+        let options = ReportOptions { root_person_id: lisa.id, redaction: RedactionStyle::Omit, ..Default::default() };
+        let service = ExportService::new(&db).with_options(options);
+        service.export_to_file(ReportType::Ahnentafel, ExportFormat::Markdown, &path).unwrap();
+        let md = std::fs::read_to_string(&path).unwrap();
+        assert!(md.starts_with("# Antavla för Privat\n"), "{}", md);
+        assert!(md.contains("**1. Privat**"));
+        assert!(md.contains("**3. Barn Berg**"));
+        assert!(!md.contains("Lisa"));
+    }
+
+    #[test]
+    fn test_person_dossier_export() {
+        use crate::models::Document;
+
+        let db = Database::open_in_memory().unwrap();
+        let media = tempfile::tempdir().unwrap();
+        let mut config = db.config().get().unwrap();
+        config.media_directory_path = media.path().to_path_buf();
+        db.config().save(&config).unwrap();
+
+        let mut anna = Person::new(Some("Anna".into()), Some("Berg".into()), "anna_berg".into());
+        anna.birth_date = chrono::NaiveDate::from_ymd_opt(1850, 3, 1);
+        anna.death_date = chrono::NaiveDate::from_ymd_opt(1920, 5, 1);
+        db.persons().create(&mut anna).unwrap();
+        let anna_id = anna.id.unwrap();
+
+        let dir = media.path().join("persons").join("anna_berg");
+        std::fs::create_dir_all(&dir).unwrap();
+        image::RgbImage::from_pixel(40, 30, image::Rgb([120, 80, 40])).save(dir.join("gard.png")).unwrap();
+        std::fs::write(dir.join("brev.txt"), "Kära syster,\n\nallt väl här.").unwrap();
+        for name in ["gard.png", "brev.txt"] {
+            db.documents().create(&mut Document::new(anna_id, name.into(), name.into())).unwrap();
+        }
+
+        let options = ReportOptions { root_person_id: Some(anna_id), ..Default::default() };
+        let service = ExportService::new(&db).with_options(options);
+        let path = media.path().join("dossier.pdf");
+        let result = service.export_to_file(ReportType::PersonDossier, ExportFormat::Pdf, &path).unwrap();
+        assert_eq!(result.row_count, 2);
+        let pdf = String::from_utf8_lossy(&std::fs::read(&path).unwrap()).to_string();
+        assert!(pdf.contains("/Subtype/Image"), "bilden ska finnas som miniatyr");
+
+        // Levande personer exporteras bara utan maskering
+        let mut living = Person::new(Some("Lisa".into()), Some("Berg".into()), "lisa_berg".into());
+        living.birth_date = chrono::NaiveDate::from_ymd_opt(1990, 1, 1);
+        db.persons().create(&mut living).unwrap();
+        let options = ReportOptions { root_person_id: living.id, ..Default::default() };
+        let service = ExportService::new(&db).with_options(options);
+        assert!(service.export_to_file(ReportType::PersonDossier, ExportFormat::Pdf, &path).is_err());
+        let options = ReportOptions { root_person_id: living.id, redaction: RedactionStyle::None, ..Default::default() };
+        let service = ExportService::new(&db).with_options(options);
+        assert!(service.export_to_file(ReportType::PersonDossier, ExportFormat::Pdf, &path).is_ok());
+
+        // Levande släktingar maskeras i utgångspersonens dossier
+        let mut rel = PersonRelationship::new(anna_id, living.id.unwrap(), RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+        let options = ReportOptions { root_person_id: Some(anna_id), ..Default::default() };
+        let dossier = ExportService::new(&db).with_options(options).person_dossier().unwrap();
+        let names: Vec<&str> = dossier
+            .relationships
+            .iter()
+            .flat_map(|(_, views)| views.iter().map(|v| v.other_person_name.as_str()))
+            .collect();
+        assert_eq!(names, vec!["Privat"]);
+    }
+
+    #[test]
+    fn test_relationship_graph_filtered_by_root() {
+        use chrono::NaiveDate;
+
+        let db = Database::open_in_memory().unwrap();
+        let mut ids = Vec::new();
+        for name in ["Farfar", "Far", "Barn", "Farbror", "Mor", "Morbror"] {
+            let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+            p.birth_date = NaiveDate::from_ymd_opt(1850, 1, 1);
+            p.death_date = NaiveDate::from_ymd_opt(1900, 1, 1);
+            db.persons().create(&mut p).unwrap();
+            ids.push(p.id.unwrap());
+        }
+        let mut unrelated = Person::new(Some("Ensam".into()), None, "ensam".into());
+        unrelated.death_date = NaiveDate::from_ymd_opt(1900, 1, 1);
+        db.persons().create(&mut unrelated).unwrap();
+        for (a, b, rel) in [
+            (ids[0], ids[1], RelationshipType::Parent),
+            (ids[1], ids[2], RelationshipType::Parent),
+            (ids[0], ids[3], RelationshipType::Parent),
+            (ids[1], ids[4], RelationshipType::Spouse),
+            (ids[4], ids[5], RelationshipType::Sibling),
+        ] {
+            let mut rel = PersonRelationship::new(a, b, rel);
+            db.relationships().create(&mut rel).unwrap();
+        }
+
+        // Hela databasen
+        let service = ExportService::new(&db);
+        let (persons, relationships) = service.relationship_graph().unwrap();
+        assert_eq!((persons.len(), relationships.len()), (7, 5));
+
+        let names_within = |generations: usize, person_filter: Option<SearchFilter>| {
+            let options = ReportOptions {
+                root_person_id: Some(ids[2]),
+                max_generations: generations,
+                person_filter,
+                ..Default::default()
+            };
+            let (persons, _) = ExportService::new(&db).with_options(options).relationship_graph().unwrap();
+            let mut names: Vec<String> = persons.iter().map(|p| p.full_name()).collect();
+            names.sort();
+            names
+        };
+
+        // Ett led från barnet: fadern och hans maka, men inte makans syskon
+        assert_eq!(names_within(1, None), ["Barn Berg", "Far Berg", "Mor Berg"]);
+        // Två led: också farfar, men inte farfars andra barn
+        assert_eq!(names_within(2, None), ["Barn Berg", "Far Berg", "Farfar Berg", "Mor Berg"]);
+        // Tre led: farbror via farfar; makans syskon nås aldrig
+        assert_eq!(
+            names_within(3, None),
+            ["Barn Berg", "Far Berg", "Farbror Berg", "Farfar Berg", "Mor Berg"]
+        );
+        // Sparad sökning begränsar urvalet ytterligare
+        let filter = SearchFilter { query: "Far".into(), ..Default::default() };
+        assert_eq!(names_within(2, Some(filter)), ["Far Berg", "Farfar Berg"]);
+
+        let options = ReportOptions {
+            root_person_id: Some(ids[2]),
+            max_generations: 2,
+            ..Default::default()
+        };
+        let service = ExportService::new(&db).with_options(options);
+        let (_, relationships) = service.relationship_graph().unwrap();
+        assert_eq!(relationships.len(), 3);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("graf.graphml");
+        let result = service.export_to_file(ReportType::RelationshipGraph, ExportFormat::GraphMl, &path).unwrap();
+        assert_eq!(result.row_count, 4);
+        assert!(std::fs::read_to_string(&path).unwrap().contains("Farfar Berg"));
+
+        // Släktgrafen finns bara som DOT och GraphML
+        assert!(service.export_to_file(ReportType::RelationshipGraph, ExportFormat::Csv, &path).is_err());
+    }
 }
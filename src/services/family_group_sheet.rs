@@ -0,0 +1,479 @@
+//! Familjeblad i PDF
+//!
+//! Ett blad per familj: de två föräldrarna (eller en ensam förälder) med
+//! egna föräldrar, levnadsdata, yrke och profilbild, vigseln och alla barn
+//! med födelse, död och make/maka. Bladen kan skrivas ut för en person (en
+//! familj per make/maka) eller för alla personer i en sparad sökning, där
+//! varje par bara skrivs ut en gång.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use image::RgbImage;
+
+use crate::db::{Database, KinshipGraph};
+use crate::models::{Person, RelationshipType, Sex};
+use crate::services::chart_export::load_thumbnail;
+use crate::services::family_tree::split_parents;
+use crate::services::pdf_layout::{Column, PdfReport, Weight};
+use crate::services::{PrivacyPolicy, RedactionStyle};
+
+const MARGIN: f32 = 15.0;
+/// Bredd för etiketterna till vänster (mm)
+const LABEL_WIDTH: f32 = 30.0;
+const PHOTO_WIDTH: f32 = 28.0;
+const PHOTO_HEIGHT: f32 = 35.0;
+const ROW_HEIGHT: f32 = 5.5;
+
+/// En förälder i familjen med sina egna föräldrar
+#[derive(Debug, Clone)]
+pub struct SheetPartner {
+    pub person: Person,
+    pub father: Option<Person>,
+    pub mother: Option<Person>,
+}
+
+/// Ett barn med sina makar
+#[derive(Debug, Clone)]
+pub struct SheetChild {
+    pub person: Person,
+    pub spouses: Vec<Person>,
+}
+
+/// En familj: ett par eller en ensam förälder med barn
+#[derive(Debug, Clone)]
+pub struct FamilyGroup {
+    /// En eller två föräldrar, mannen först
+    pub partners: Vec<SheetPartner>,
+    /// Paret är registrerat som gift
+    pub married: bool,
+    pub marriage_date: Option<NaiveDate>,
+    pub marriage_place: Option<String>,
+    pub children: Vec<SheetChild>,
+}
+
+impl FamilyGroup {
+    /// Nyckel för att skriva ut varje par en gång
+    fn key(&self) -> Vec<i64> {
+        let mut ids: Vec<i64> = self.partners.iter().filter_map(|p| p.person.id).collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn title(&self) -> String {
+        let names: Vec<String> = self.partners.iter().map(|p| p.person.full_name()).collect();
+        names.join(" och ")
+    }
+
+    /// Rubrik för en förälder: Far/Mor om familjen har barn, annars Make/Maka
+    fn role(&self, partner: &SheetPartner) -> &'static str {
+        match (self.children.is_empty(), partner.person.sex) {
+            (false, Sex::Male) => "Far",
+            (false, Sex::Female) => "Mor",
+            (false, _) => "Förälder",
+            (true, Sex::Male) => "Make",
+            (true, Sex::Female) => "Maka",
+            (true, _) => "Partner",
+        }
+    }
+}
+
+/// Resultat av export
+#[derive(Debug)]
+pub struct FamilySheetResult {
+    pub family_count: usize,
+    pub page_count: usize,
+    pub file_size: usize,
+}
+
+impl FamilySheetResult {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} familjeblad ({} sidor, {} KB)",
+            self.family_count,
+            self.page_count,
+            self.file_size / 1024
+        )
+    }
+}
+
+/// Tjänst för familjeblad
+pub struct FamilyGroupSheetService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> FamilyGroupSheetService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Personens familjer: en per make/maka eller annan förälder till
+    /// personens barn. En person utan familj får ett eget blad.
+    pub fn families(&self, person_id: i64) -> Result<Vec<FamilyGroup>> {
+        let graph = self.db.kinship()?;
+        let Some(person) = graph.person(person_id) else {
+            return Ok(Vec::new());
+        };
+
+        let mut families = Vec::new();
+        let groups = graph.families(person_id);
+        if groups.is_empty() {
+            families.push(FamilyGroup {
+                partners: vec![partner(&graph, person)],
+                married: false,
+                marriage_date: None,
+                marriage_place: None,
+                children: Vec::new(),
+            });
+        }
+
+        for (other_id, children) in groups {
+            let mut partners = vec![partner(&graph, person)];
+            if let Some(other) = other_id.and_then(|id| graph.person(id)) {
+                partners.push(partner(&graph, other));
+            }
+            partners.sort_by_key(|p| (p.person.sex != Sex::Male, p.person.id));
+
+            let marriage = match other_id {
+                Some(other_id) => self
+                    .db
+                    .relationships()
+                    .find_by_person(person_id)?
+                    .into_iter()
+                    .find(|r| {
+                        r.relationship_a_to_b == RelationshipType::Spouse
+                            && (r.person_a_id == other_id || r.person_b_id == other_id)
+                    }),
+                None => None,
+            };
+
+            let children = children
+                .iter()
+                .filter_map(|id| graph.person(*id))
+                .map(|child| SheetChild {
+                    person: child.clone(),
+                    spouses: child
+                        .id
+                        .map(|id| graph.spouses(id).iter().filter_map(|s| graph.person(*s).cloned()).collect())
+                        .unwrap_or_default(),
+                })
+                .collect();
+
+            families.push(FamilyGroup {
+                partners,
+                married: marriage.is_some(),
+                marriage_date: marriage.as_ref().and_then(|m| m.marriage_date),
+                marriage_place: marriage
+                    .and_then(|m| m.marriage_place)
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty()),
+                children,
+            });
+        }
+        Ok(families)
+    }
+
+    /// Skriv familjeblad för personerna till en PDF. Par som förekommer
+    /// flera gånger skrivs bara ut en gång.
+    pub fn export(&self, person_ids: &[i64], redaction: RedactionStyle, path: &Path) -> Result<FamilySheetResult> {
+        let policy = PrivacyPolicy::from_config(self.db, redaction)?;
+
+        let mut seen = HashSet::new();
+        let mut families = Vec::new();
+        for person_id in person_ids {
+            for family in self.families(*person_id)? {
+                if seen.insert(family.key()) {
+                    if let Some(family) = redact(family, &policy) {
+                        families.push(family);
+                    }
+                }
+            }
+        }
+        if families.is_empty() {
+            anyhow::bail!("Inga familjer att skriva ut");
+        }
+
+        let photos = self.load_photos(&families, &policy);
+        let (bytes, page_count) = render_pdf(&families, &photos)?;
+        std::fs::write(path, &bytes).context("Kunde inte skriva fil")?;
+
+        Ok(FamilySheetResult {
+            family_count: families.len(),
+            page_count,
+            file_size: bytes.len(),
+        })
+    }
+
+    /// Profilbilder för föräldrarna. Privata personer får ingen bild.
+    fn load_photos(&self, families: &[FamilyGroup], policy: &PrivacyPolicy) -> HashMap<i64, RgbImage> {
+        let Ok(config) = self.db.config().get() else {
+            return HashMap::new();
+        };
+
+        let mut photos = HashMap::new();
+        for partner in families.iter().flat_map(|f| &f.partners) {
+            let (Some(id), Some(relative)) = (partner.person.id, partner.person.profile_image_path.as_ref()) else {
+                continue;
+            };
+            if photos.contains_key(&id) || policy.masks(&partner.person) {
+                continue;
+            }
+            let path = config.media_directory_path.join(relative);
+            if let Some(photo) = load_thumbnail(&path, (PHOTO_WIDTH * 4.0) as u32, (PHOTO_HEIGHT * 4.0) as u32) {
+                photos.insert(id, photo);
+            }
+        }
+        photos
+    }
+}
+
+fn partner(graph: &KinshipGraph, person: &Person) -> SheetPartner {
+    let parents = person
+        .id
+        .map(|id| graph.parents(id).iter().filter_map(|p| graph.person(*p).cloned()).collect())
+        .unwrap_or_default();
+    let (father, mother) = split_parents(parents);
+    SheetPartner {
+        person: person.clone(),
+        father,
+        mother,
+    }
+}
+
+/// Maskera privata personer. Utelämnade personer stryks; en familj utan
+/// kvarvarande föräldrar tas bort.
+fn redact(mut family: FamilyGroup, policy: &PrivacyPolicy) -> Option<FamilyGroup> {
+    let redact_opt = |person: &Option<Person>| person.as_ref().and_then(|p| policy.redact(p));
+
+    let masked_partner = family.partners.iter().any(|p| policy.masks(&p.person));
+    family.partners = family
+        .partners
+        .into_iter()
+        .filter_map(|p| {
+            Some(SheetPartner {
+                person: policy.redact(&p.person)?,
+                father: redact_opt(&p.father),
+                mother: redact_opt(&p.mother),
+            })
+        })
+        .collect();
+    if family.partners.is_empty() {
+        return None;
+    }
+    if masked_partner {
+        family.marriage_date = None;
+        family.marriage_place = None;
+    }
+    family.children = family
+        .children
+        .into_iter()
+        .filter_map(|c| {
+            Some(SheetChild {
+                person: policy.redact(&c.person)?,
+                spouses: c.spouses.iter().filter_map(|s| policy.redact(s)).collect(),
+            })
+        })
+        .collect();
+    Some(family)
+}
+
+fn date(date: Option<NaiveDate>) -> String {
+    date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()
+}
+
+fn date_and_place(d: Option<NaiveDate>, place: Option<&str>) -> String {
+    let place = place.map(str::trim).filter(|p| !p.is_empty());
+    match (d, place) {
+        (Some(d), Some(place)) => format!("{} i {}", date(Some(d)), place),
+        (Some(d), None) => date(Some(d)),
+        (None, Some(place)) => format!("i {}", place),
+        (None, None) => String::new(),
+    }
+}
+
+/// Rendera bladen, en familj per sida (barnlistan fortsätter på nästa
+/// sida vid behov). Returnerar filinnehåll och antal sidor.
+fn render_pdf(families: &[FamilyGroup], photos: &HashMap<i64, RgbImage>) -> Result<(Vec<u8>, usize)> {
+    let mut pdf = PdfReport::new("Familjeblad")?.with_margin(MARGIN);
+    for (i, family) in families.iter().enumerate() {
+        if i > 0 {
+            pdf.new_page();
+        }
+        draw_family(&mut pdf, family, photos);
+    }
+    let page_count = pdf.page_count();
+    Ok((pdf.finish()?, page_count))
+}
+
+fn draw_family(pdf: &mut PdfReport, family: &FamilyGroup, photos: &HashMap<i64, RgbImage>) {
+    let width = pdf.content_width();
+    pdf.space(7.0);
+    pdf.text_at(0.0, "Familjeblad", 16.0, Weight::Bold);
+    pdf.space(6.0);
+    pdf.text_at(0.0, &pdf.fit(&family.title(), width, 11.0, Weight::Regular), 11.0, Weight::Regular);
+    pdf.space(4.0);
+    pdf.rule();
+
+    for partner in &family.partners {
+        draw_partner(pdf, family.role(partner), partner, photos);
+        pdf.rule();
+    }
+
+    if family.partners.len() == 2 || family.married {
+        pdf.space(ROW_HEIGHT + 1.0);
+        let marriage = date_and_place(family.marriage_date, family.marriage_place.as_deref());
+        let text = match (family.married, marriage.is_empty()) {
+            (true, false) => marriage,
+            (true, true) => "Gifta (datum saknas)".to_string(),
+            (false, _) => "Ingen registrerad vigsel".to_string(),
+        };
+        pdf.text_at(0.0, "Vigsel", 9.0, Weight::Bold);
+        pdf.text_at(LABEL_WIDTH, &pdf.fit(&text, width - LABEL_WIDTH, 10.0, Weight::Regular), 10.0, Weight::Regular);
+        pdf.space(3.0);
+        pdf.rule();
+    }
+
+    draw_children(pdf, family);
+}
+
+fn draw_partner(pdf: &mut PdfReport, role: &str, partner: &SheetPartner, photos: &HashMap<i64, RgbImage>) {
+    let person = &partner.person;
+    let photo = person.id.and_then(|id| photos.get(&id));
+    let text_width = pdf.content_width() - LABEL_WIDTH - if photo.is_some() { PHOTO_WIDTH + 4.0 } else { 0.0 };
+
+    // Bilden placeras i högerkanten med överkanten i höjd med rollen
+    let photo_height = photo.map_or(0.0, |photo| {
+        pdf.space(2.0);
+        let height = pdf.image(photo, pdf.content_width() - PHOTO_WIDTH, PHOTO_WIDTH);
+        pdf.space(-2.0);
+        height + 2.0
+    });
+
+    pdf.space(ROW_HEIGHT + 1.0);
+    pdf.text_at(0.0, role, 9.0, Weight::Bold);
+    let name = pdf.fit(&person.full_name(), text_width, 12.0, Weight::Bold);
+    pdf.text_at(LABEL_WIDTH, &name, 12.0, Weight::Bold);
+
+    let name = |p: &Option<Person>| p.as_ref().map(|p| format!("{} {}", p.full_name(), p.years_display()).trim().to_string());
+    let rows = [
+        ("Född", date_and_place(person.birth_date, person.birth_place.as_deref())),
+        ("Död", date(person.death_date)),
+        ("Yrke", person.occupation.clone().unwrap_or_default()),
+        ("Far", name(&partner.father).unwrap_or_default()),
+        ("Mor", name(&partner.mother).unwrap_or_default()),
+    ];
+    for (label, value) in &rows {
+        pdf.space(ROW_HEIGHT);
+        pdf.text_at(0.0, label, 9.0, Weight::Regular);
+        pdf.text_at(LABEL_WIDTH, &pdf.fit(value, text_width, 10.0, Weight::Regular), 10.0, Weight::Regular);
+    }
+
+    let text_height = ROW_HEIGHT + 1.0 + rows.len() as f32 * ROW_HEIGHT;
+    pdf.space((photo_height - text_height).max(0.0));
+    pdf.space(3.0);
+}
+
+fn draw_children(pdf: &mut PdfReport, family: &FamilyGroup) {
+    pdf.space(ROW_HEIGHT + 1.0);
+    pdf.text_at(0.0, &format!("Barn ({})", family.children.len()), 11.0, Weight::Bold);
+    if family.children.is_empty() {
+        pdf.space(ROW_HEIGHT);
+        pdf.text_at(0.0, "Inga kända barn", 9.0, Weight::Regular);
+        return;
+    }
+
+    let columns = [
+        Column::new("Nr", 7.0).right(),
+        Column::new("Namn", 55.0),
+        Column::new("Född", 50.0),
+        Column::new("Död", 20.0),
+        Column::new("Gift med", 48.0),
+    ];
+    let rows: Vec<Vec<String>> = family
+        .children
+        .iter()
+        .enumerate()
+        .map(|(i, child)| {
+            let person = &child.person;
+            let spouses: Vec<String> = child.spouses.iter().map(|s| s.full_name()).collect();
+            vec![
+                (i + 1).to_string(),
+                person.full_name(),
+                date_and_place(person.birth_date, person.birth_place.as_deref()),
+                date(person.death_date),
+                spouses.join(", "),
+            ]
+        })
+        .collect();
+    pdf.table(&columns, &rows, 9.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PersonRelationship;
+
+    fn add(db: &Database, name: &str, sex: Sex) -> i64 {
+        let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+        p.sex = sex;
+        p.birth_date = NaiveDate::from_ymd_opt(1850, 1, 1);
+        p.death_date = NaiveDate::from_ymd_opt(1920, 1, 1);
+        db.persons().create(&mut p).unwrap();
+        p.id.unwrap()
+    }
+
+    fn relate(db: &Database, a: i64, b: i64, a_to_b: RelationshipType) {
+        let mut rel = PersonRelationship::new(a, b, a_to_b);
+        if a_to_b == RelationshipType::Spouse {
+            rel.marriage_date = NaiveDate::from_ymd_opt(1875, 6, 1);
+        }
+        db.relationships().create(&mut rel).unwrap();
+    }
+
+    #[test]
+    fn test_families_and_export() {
+        let db = Database::open_in_memory().unwrap();
+        let anna = add(&db, "Anna", Sex::Female);
+        let karl = add(&db, "Karl", Sex::Male);
+        let erik = add(&db, "Erik", Sex::Male);
+        let lisa = add(&db, "Lisa", Sex::Female);
+        let per = add(&db, "Per", Sex::Male);
+        let maja = add(&db, "Maja", Sex::Female);
+        let nils = add(&db, "Nils", Sex::Male);
+
+        relate(&db, karl, anna, RelationshipType::Spouse);
+        relate(&db, karl, lisa, RelationshipType::Parent);
+        relate(&db, anna, lisa, RelationshipType::Parent);
+        relate(&db, lisa, nils, RelationshipType::Spouse);
+        // Anna har också ett barn med Erik, som hon inte är gift med
+        relate(&db, erik, per, RelationshipType::Parent);
+        relate(&db, anna, per, RelationshipType::Parent);
+        relate(&db, maja, anna, RelationshipType::Parent);
+
+        let service = FamilyGroupSheetService::new(&db);
+        let families = service.families(anna).unwrap();
+        assert_eq!(families.len(), 2);
+
+        let first = &families[0];
+        assert_eq!(first.title(), "Karl Berg och Anna Berg");
+        assert!(first.married);
+        assert_eq!(first.marriage_date, NaiveDate::from_ymd_opt(1875, 6, 1));
+        assert_eq!(first.partners[1].mother.as_ref().and_then(|m| m.id), Some(maja));
+        assert_eq!(first.children.len(), 1);
+        assert_eq!(first.children[0].spouses[0].id, Some(nils));
+        assert_eq!(first.role(&first.partners[0]), "Far");
+
+        let second = &families[1];
+        assert_eq!(second.title(), "Erik Berg och Anna Berg");
+        assert!(!second.married);
+
+        // Familjen Karl och Anna skrivs bara ut en gång
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("familjeblad.pdf");
+        let result = service.export(&[anna, karl, per], RedactionStyle::None, &path).unwrap();
+        assert_eq!((result.family_count, result.page_count), (3, 3));
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+    }
+}
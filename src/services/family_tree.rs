@@ -1,9 +1,57 @@
 //! Familjeträd-tjänst för att bygga trädstruktur från relationer
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+use crate::db::{Database, KinshipGraph};
+use crate::models::{Person, Sex};
+use crate::services::tree_layout::{layout_pedigree, layout_tree, LayoutConfig};
+
+/// Typ av träd som visas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TreeMode {
+    /// Förfäder, ättlingar, partners och syskon tillsammans
+    #[default]
+    Mixed,
+    /// Antavla: strikt binärt träd med far ovanför och mor nedanför
+    Pedigree,
+    /// Stamtavla: ättlingar med alla partners och barn
+    Descendants,
+    /// Solfjäder: förfäder som ringsegment, färgade efter forskningsgrad
+    Fan,
+}
+
+impl TreeMode {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TreeMode::Mixed => "Släktträd",
+            TreeMode::Pedigree => "Antavla",
+            TreeMode::Descendants => "Stamtavla",
+            TreeMode::Fan => "Solfjäder",
+        }
+    }
+
+    pub fn all() -> &'static [TreeMode] {
+        &[TreeMode::Mixed, TreeMode::Pedigree, TreeMode::Descendants, TreeMode::Fan]
+    }
+
+    pub fn default_generations(&self) -> i32 {
+        match self {
+            TreeMode::Mixed => 3,
+            TreeMode::Pedigree => 4,
+            TreeMode::Descendants => 3,
+            TreeMode::Fan => 5,
+        }
+    }
 
-use crate::db::Database;
-use crate::models::Person;
+    pub fn max_generations(&self) -> i32 {
+        match self {
+            TreeMode::Mixed => 5,
+            TreeMode::Pedigree => 7,
+            TreeMode::Descendants => 6,
+            TreeMode::Fan => 10,
+        }
+    }
+}
 
 /// En nod i familjeträdet
 #[derive(Debug, Clone)]
@@ -12,6 +60,41 @@ pub struct FamilyTreeNode {
     pub x: f32,
     pub y: f32,
     pub generation: i32, // 0 = fokusperson, negativ = förfäder, positiv = ättlingar
+    /// Antavlenummer (1 = fokusperson, 2n = far, 2n + 1 = mor), endast i antavla
+    pub ahnentafel: Option<u64>,
+}
+
+/// Hänvisning till en förfader som redan finns i antavlan (anförlust).
+/// Ritas som en liten ruta på sin egen plats som pekar på den första
+/// förekomsten, så att linjen till barnet inte går förlorad.
+#[derive(Debug, Clone)]
+pub struct TreeReference {
+    pub person_id: i64,
+    /// Antavlenummer på den här platsen
+    pub ahnentafel: u64,
+    /// Barnet som referensen är förälder till
+    pub child_id: i64,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Tom plats för en förälder som saknas i antavlan. Ritas som en
+/// platshållare där användaren kan lägga till personen.
+#[derive(Debug, Clone)]
+pub struct TreePlaceholder {
+    /// Antavlenummer för den saknade föräldern
+    pub ahnentafel: u64,
+    /// Barnet som saknar föräldern
+    pub child_id: i64,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl TreePlaceholder {
+    /// Jämna nummer är fäder, udda mödrar
+    pub fn is_father(&self) -> bool {
+        self.ahnentafel % 2 == 0
+    }
 }
 
 /// En länk mellan två noder
@@ -34,8 +117,13 @@ pub enum LinkType {
 pub struct FamilyTree {
     pub nodes: HashMap<i64, FamilyTreeNode>,
     pub links: Vec<FamilyTreeLink>,
+    /// Upprepade förfäder (endast i antavla och solfjäder)
+    pub references: Vec<TreeReference>,
+    /// Saknade föräldrar (endast i antavla)
+    pub placeholders: Vec<TreePlaceholder>,
     pub focus_person_id: Option<i64>,
     pub generations: i32, // Antal generationer att visa (uppåt och nedåt)
+    pub mode: TreeMode,
 }
 
 impl FamilyTree {
@@ -64,11 +152,12 @@ impl FamilyTree {
         let mut max_x = f32::MIN;
         let mut max_y = f32::MIN;
 
-        for node in self.nodes.values() {
-            min_x = min_x.min(node.x);
-            min_y = min_y.min(node.y);
-            max_x = max_x.max(node.x);
-            max_y = max_y.max(node.y);
+        let positions = self.nodes.values().map(|n| (n.x, n.y));
+        for (x, y) in positions.chain(self.references.iter().map(|r| (r.x, r.y))) {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
         }
 
         (min_x, min_y, max_x, max_y)
@@ -85,15 +174,31 @@ impl<'a> FamilyTreeService<'a> {
         Self { db }
     }
 
+    /// Bygg ett träd av angiven typ
+    pub fn build(&self, mode: TreeMode, person_id: i64, generations: i32) -> anyhow::Result<FamilyTree> {
+        match mode {
+            TreeMode::Mixed => self.build_tree(person_id, generations),
+            TreeMode::Pedigree => self.build_pedigree(person_id, generations),
+            TreeMode::Descendants => self.build_descendants(person_id, generations),
+            TreeMode::Fan => {
+                let mut tree = self.build_pedigree(person_id, generations)?;
+                tree.mode = TreeMode::Fan;
+                tree.placeholders.clear();
+                Ok(tree)
+            }
+        }
+    }
+
     /// Bygg ett familjeträd centrerat kring en person
     pub fn build_tree(&self, person_id: i64, generations: i32) -> anyhow::Result<FamilyTree> {
         let mut tree = FamilyTree::new();
         tree.focus_person_id = Some(person_id);
         tree.generations = generations;
 
+        let graph = self.db.kinship()?;
+
         // Hämta fokuspersonen
-        let focus_person = self.db.persons().find_by_id(person_id)?;
-        let Some(focus_person) = focus_person else {
+        let Some(focus_person) = graph.person(person_id).cloned() else {
             return Ok(tree);
         };
 
@@ -109,6 +214,7 @@ impl<'a> FamilyTreeService<'a> {
                 x: 0.0,
                 y: 0.0,
                 generation: 0,
+                ahnentafel: None,
             },
         );
         visited.insert(person_id);
@@ -117,26 +223,13 @@ impl<'a> FamilyTreeService<'a> {
         while let Some((current_id, gen)) = to_process.pop() {
             // Hämta föräldrar (generation - 1)
             if gen > -generations {
-                let parents = self.db.relationships().get_parents(current_id)?;
-                for parent in parents {
-                    if !visited.contains(&parent.other_person_id) {
-                        if let Ok(Some(p)) = self.db.persons().find_by_id(parent.other_person_id) {
-                            visited.insert(parent.other_person_id);
-                            tree.nodes.insert(
-                                parent.other_person_id,
-                                FamilyTreeNode {
-                                    person: p,
-                                    x: 0.0,
-                                    y: 0.0,
-                                    generation: gen - 1,
-                                },
-                            );
-                            to_process.push((parent.other_person_id, gen - 1));
-                        }
+                for &parent_id in graph.parents(current_id) {
+                    if add_node(&mut tree, &mut visited, &graph, parent_id, gen - 1) {
+                        to_process.push((parent_id, gen - 1));
                     }
                     // Lägg till länk
                     tree.links.push(FamilyTreeLink {
-                        from_id: parent.other_person_id,
+                        from_id: parent_id,
                         to_id: current_id,
                         link_type: LinkType::Parent,
                     });
@@ -145,138 +238,291 @@ impl<'a> FamilyTreeService<'a> {
 
             // Hämta barn (generation + 1)
             if gen < generations {
-                let children = self.db.relationships().get_children(current_id)?;
-                for child in children {
-                    if !visited.contains(&child.other_person_id) {
-                        if let Ok(Some(p)) = self.db.persons().find_by_id(child.other_person_id) {
-                            visited.insert(child.other_person_id);
-                            tree.nodes.insert(
-                                child.other_person_id,
-                                FamilyTreeNode {
-                                    person: p,
-                                    x: 0.0,
-                                    y: 0.0,
-                                    generation: gen + 1,
-                                },
-                            );
-                            to_process.push((child.other_person_id, gen + 1));
-                        }
+                for &child_id in graph.children(current_id) {
+                    if add_node(&mut tree, &mut visited, &graph, child_id, gen + 1) {
+                        to_process.push((child_id, gen + 1));
                     }
                     // Lägg till länk (förälder → barn)
                     tree.links.push(FamilyTreeLink {
                         from_id: current_id,
-                        to_id: child.other_person_id,
+                        to_id: child_id,
                         link_type: LinkType::Parent,
                     });
                 }
             }
 
             // Hämta partners (samma generation)
-            let spouses = self.db.relationships().get_spouses(current_id)?;
-            for spouse in spouses {
-                if !visited.contains(&spouse.other_person_id) {
-                    if let Ok(Some(p)) = self.db.persons().find_by_id(spouse.other_person_id) {
-                        visited.insert(spouse.other_person_id);
-                        tree.nodes.insert(
-                            spouse.other_person_id,
-                            FamilyTreeNode {
-                                person: p,
-                                x: 0.0,
-                                y: 0.0,
-                                generation: gen,
-                            },
-                        );
-                        // Partners processar inte vidare (undviker oändliga loopar)
-                    }
-                }
+            for &spouse_id in graph.spouses(current_id) {
+                // Partners processar inte vidare (undviker oändliga loopar)
+                add_node(&mut tree, &mut visited, &graph, spouse_id, gen);
                 // Lägg till länk
                 tree.links.push(FamilyTreeLink {
-                    from_id: current_id.min(spouse.other_person_id),
-                    to_id: current_id.max(spouse.other_person_id),
+                    from_id: current_id.min(spouse_id),
+                    to_id: current_id.max(spouse_id),
                     link_type: LinkType::Spouse,
                 });
             }
 
             // Hämta syskon (samma generation, men utan länk i trädet normalt)
-            let siblings = self.db.relationships().get_siblings(current_id)?;
-            for sibling in siblings {
-                if !visited.contains(&sibling.other_person_id) {
-                    if let Ok(Some(p)) = self.db.persons().find_by_id(sibling.other_person_id) {
-                        visited.insert(sibling.other_person_id);
-                        tree.nodes.insert(
-                            sibling.other_person_id,
-                            FamilyTreeNode {
-                                person: p,
-                                x: 0.0,
-                                y: 0.0,
-                                generation: gen,
-                            },
-                        );
-                        // Syskon processar inte vidare
-                    }
-                }
+            for &sibling_id in graph.siblings(current_id) {
+                // Syskon processar inte vidare
+                add_node(&mut tree, &mut visited, &graph, sibling_id, gen);
             }
         }
 
         // Beräkna layout
-        self.calculate_layout(&mut tree);
+        layout_tree(&mut tree, &LayoutConfig::default());
 
         Ok(tree)
     }
 
-    /// Beräkna positioner för alla noder
-    fn calculate_layout(&self, tree: &mut FamilyTree) {
-        // Gruppera noder per generation
-        let mut generations: HashMap<i32, Vec<i64>> = HashMap::new();
-        for (id, node) in &tree.nodes {
-            generations.entry(node.generation).or_default().push(*id);
+    /// Bygg en antavla. Varje förfader får ett antavlenummer; förekommer
+    /// samma person flera gånger (anförlust) visas den på första platsen
+    /// och som en hänvisning (`TreeReference`) på de övriga.
+    pub fn build_pedigree(&self, person_id: i64, generations: i32) -> anyhow::Result<FamilyTree> {
+        let mut tree = FamilyTree::new();
+        tree.focus_person_id = Some(person_id);
+        tree.generations = generations;
+        tree.mode = TreeMode::Pedigree;
+
+        let graph = self.db.kinship()?;
+        let Some(focus_person) = graph.person(person_id).cloned() else {
+            return Ok(tree);
+        };
+        tree.nodes.insert(
+            person_id,
+            FamilyTreeNode {
+                person: focus_person,
+                x: 0.0,
+                y: 0.0,
+                generation: 0,
+                ahnentafel: Some(1),
+            },
+        );
+
+        let mut queue: VecDeque<(i64, u64, i32)> = VecDeque::from([(person_id, 1, 0)]);
+        while let Some((current_id, number, depth)) = queue.pop_front() {
+            if depth >= generations {
+                continue;
+            }
+
+            let parents = graph
+                .parents(current_id)
+                .iter()
+                .filter_map(|id| graph.person(*id).cloned())
+                .collect();
+            let (father, mother) = split_parents(parents);
+
+            for (parent, parent_number) in [(father, number * 2), (mother, number * 2 + 1)] {
+                let Some(parent) = parent else {
+                    tree.placeholders.push(TreePlaceholder {
+                        ahnentafel: parent_number,
+                        child_id: current_id,
+                        x: 0.0,
+                        y: 0.0,
+                    });
+                    continue;
+                };
+                let Some(parent_id) = parent.id else {
+                    continue;
+                };
+
+                match tree.nodes.entry(parent_id) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(FamilyTreeNode {
+                            person: parent,
+                            x: 0.0,
+                            y: 0.0,
+                            generation: -(depth + 1),
+                            ahnentafel: Some(parent_number),
+                        });
+                        tree.links.push(FamilyTreeLink {
+                            from_id: parent_id,
+                            to_id: current_id,
+                            link_type: LinkType::Parent,
+                        });
+                        queue.push_back((parent_id, parent_number, depth + 1));
+                    }
+                    Entry::Occupied(_) => tree.references.push(TreeReference {
+                        person_id: parent_id,
+                        ahnentafel: parent_number,
+                        child_id: current_id,
+                        x: 0.0,
+                        y: 0.0,
+                    }),
+                }
+            }
         }
 
-        // Konstanter för layout
-        let node_width = 150.0;
-        let node_height = 80.0;
-        let h_spacing = 50.0;
-        let v_spacing = 100.0;
-
-        // Sortera generationer
-        let mut gen_keys: Vec<i32> = generations.keys().copied().collect();
-        gen_keys.sort();
-
-        // Placera noder generation för generation
-        for gen in gen_keys {
-            let nodes_in_gen = generations.get(&gen).unwrap();
-            let count = nodes_in_gen.len();
-
-            // Beräkna total bredd
-            let total_width = count as f32 * node_width + (count - 1).max(0) as f32 * h_spacing;
-            let start_x = -total_width / 2.0;
-
-            // Y-position baserat på generation
-            let y = gen as f32 * (node_height + v_spacing);
-
-            // Placera varje nod
-            for (i, &person_id) in nodes_in_gen.iter().enumerate() {
-                if let Some(node) = tree.nodes.get_mut(&person_id) {
-                    node.x = start_x + i as f32 * (node_width + h_spacing) + node_width / 2.0;
-                    node.y = y;
+        layout_pedigree(&mut tree, &LayoutConfig::default());
+
+        Ok(tree)
+    }
+
+    /// Bygg en stamtavla: ättlingar i rakt nedstigande led med alla
+    /// partners och barn från samtliga förhållanden
+    pub fn build_descendants(&self, person_id: i64, generations: i32) -> anyhow::Result<FamilyTree> {
+        let mut tree = FamilyTree::new();
+        tree.focus_person_id = Some(person_id);
+        tree.generations = generations;
+        tree.mode = TreeMode::Descendants;
+
+        let graph = self.db.kinship()?;
+        let Some(focus_person) = graph.person(person_id).cloned() else {
+            return Ok(tree);
+        };
+        tree.nodes.insert(
+            person_id,
+            FamilyTreeNode {
+                person: focus_person,
+                x: 0.0,
+                y: 0.0,
+                generation: 0,
+                ahnentafel: None,
+            },
+        );
+
+        let mut queue: VecDeque<(i64, i32)> = VecDeque::from([(person_id, 0)]);
+        while let Some((current_id, gen)) = queue.pop_front() {
+            // Partners (processas inte vidare)
+            for &spouse_id in graph.spouses(current_id) {
+                if let Entry::Vacant(entry) = tree.nodes.entry(spouse_id) {
+                    let Some(p) = graph.person(spouse_id) else {
+                        continue;
+                    };
+                    entry.insert(FamilyTreeNode {
+                        person: p.clone(),
+                        x: 0.0,
+                        y: 0.0,
+                        generation: gen,
+                        ahnentafel: None,
+                    });
+                }
+                tree.links.push(FamilyTreeLink {
+                    from_id: current_id.min(spouse_id),
+                    to_id: current_id.max(spouse_id),
+                    link_type: LinkType::Spouse,
+                });
+            }
+
+            if gen >= generations {
+                continue;
+            }
+
+            for &child_id in graph.children(current_id) {
+                if let Entry::Vacant(entry) = tree.nodes.entry(child_id) {
+                    let Some(p) = graph.person(child_id) else {
+                        continue;
+                    };
+                    entry.insert(FamilyTreeNode {
+                        person: p.clone(),
+                        x: 0.0,
+                        y: 0.0,
+                        generation: gen + 1,
+                        ahnentafel: None,
+                    });
+                    queue.push_back((child_id, gen + 1));
+                }
+
+                // Länka barnet till båda föräldrarna om partnern finns i trädet,
+                // så att barnen grupperas per förhållande
+                for &parent_id in graph.parents(child_id) {
+                    if tree.nodes.contains_key(&parent_id) {
+                        tree.links.push(FamilyTreeLink {
+                            from_id: parent_id,
+                            to_id: child_id,
+                            link_type: LinkType::Parent,
+                        });
+                    }
                 }
             }
         }
 
-        // Centrera fokuspersonen om den finns
-        if let Some(focus_id) = tree.focus_person_id {
-            if let Some(focus_node) = tree.nodes.get(&focus_id) {
-                let offset_x = -focus_node.x;
-                let offset_y = -focus_node.y;
+        layout_tree(&mut tree, &LayoutConfig::default());
 
-                // Flytta alla noder så fokuspersonen är i mitten
-                for node in tree.nodes.values_mut() {
-                    node.x += offset_x;
-                    node.y += offset_y;
+        Ok(tree)
+    }
+
+    /// Hämta alla förfäder till en person med generationsavstånd (1 = föräldrar).
+    /// Personer som nås via flera vägar får det kortaste avståndet.
+    /// Resultatet är sorterat på avstånd och därefter id.
+    pub fn ancestors(&self, person_id: i64, max_generations: usize) -> anyhow::Result<Vec<(i64, usize)>> {
+        let graph = self.db.kinship()?;
+        let mut visited: HashSet<i64> = HashSet::new();
+        visited.insert(person_id);
+
+        let mut result = Vec::new();
+        let mut current = vec![person_id];
+
+        for distance in 1..=max_generations {
+            let mut next = Vec::new();
+            for id in &current {
+                for &parent_id in graph.parents(*id) {
+                    if visited.insert(parent_id) {
+                        next.push(parent_id);
+                    }
                 }
             }
+            if next.is_empty() {
+                break;
+            }
+            next.sort();
+            result.extend(next.iter().map(|id| (*id, distance)));
+            current = next;
+        }
+
+        Ok(result)
+    }
+}
+
+/// Lägg till personen i trädet om den inte redan finns där. Id:t räknas som
+/// besökt först när personen finns, så att en trasig relation inte stänger
+/// vägen för en giltig.
+fn add_node(tree: &mut FamilyTree, visited: &mut HashSet<i64>, graph: &KinshipGraph, id: i64, generation: i32) -> bool {
+    if visited.contains(&id) {
+        return false;
+    }
+    let Some(person) = graph.person(id) else {
+        return false;
+    };
+    visited.insert(id);
+    tree.nodes.insert(
+        id,
+        FamilyTreeNode {
+            person: person.clone(),
+            x: 0.0,
+            y: 0.0,
+            generation,
+            ahnentafel: None,
+        },
+    );
+    true
+}
+
+/// Dela upp föräldrar i far och mor efter kön. Saknas kön fylls den lediga
+/// platsen, i id-ordning.
+pub(crate) fn split_parents(mut parents: Vec<Person>) -> (Option<Person>, Option<Person>) {
+    parents.sort_by_key(|p| p.id);
+
+    let mut father = None;
+    let mut mother = None;
+    let mut unknown = Vec::new();
+    for parent in parents {
+        match parent.sex {
+            Sex::Male if father.is_none() => father = Some(parent),
+            Sex::Female if mother.is_none() => mother = Some(parent),
+            _ => unknown.push(parent),
         }
     }
+    for parent in unknown {
+        if father.is_none() {
+            father = Some(parent);
+        } else if mother.is_none() {
+            mother = Some(parent);
+        }
+    }
+
+    (father, mother)
 }
 
 #[cfg(test)]
@@ -296,6 +542,21 @@ mod tests {
         assert!(tree.nodes.is_empty());
     }
 
+    #[test]
+    fn test_add_node_skips_missing_person() {
+        let mut anna = Person::new(Some("Anna".into()), Some("Berg".into()), "anna".into());
+        anna.id = Some(1);
+        let graph = KinshipGraph::new(vec![anna], &[]);
+        let mut tree = FamilyTree::default();
+        let mut visited = HashSet::new();
+
+        assert!(!add_node(&mut tree, &mut visited, &graph, 2, 0));
+        assert!(!visited.contains(&2));
+        assert!(add_node(&mut tree, &mut visited, &graph, 1, -1));
+        assert!(!add_node(&mut tree, &mut visited, &graph, 1, 1));
+        assert_eq!(tree.nodes[&1].generation, -1);
+    }
+
     #[test]
     fn test_build_single_person_tree() {
         let db = setup_db();
@@ -310,4 +571,111 @@ mod tests {
         assert_eq!(tree.nodes.len(), 1);
         assert_eq!(tree.focus_person_id, person.id);
     }
+
+    #[test]
+    fn test_ancestors_with_distance() {
+        use crate::models::{PersonRelationship, RelationshipType};
+
+        let db = setup_db();
+        let mut ids = Vec::new();
+        for name in ["Barn", "Far", "Farfar", "Farfarsfar"] {
+            let mut p = Person::new(Some(name.into()), None, name.to_lowercase());
+            db.persons().create(&mut p).unwrap();
+            ids.push(p.id.unwrap());
+        }
+        for pair in ids.windows(2) {
+            let mut rel = PersonRelationship::new(pair[1], pair[0], RelationshipType::Parent);
+            db.relationships().create(&mut rel).unwrap();
+        }
+
+        let service = FamilyTreeService::new(&db);
+        let ancestors = service.ancestors(ids[0], 2).unwrap();
+        assert_eq!(ancestors, vec![(ids[1], 1), (ids[2], 2)]);
+    }
+
+    #[test]
+    fn test_pedigree_numbers_and_sides() {
+        use crate::models::{PersonRelationship, RelationshipType};
+
+        let db = setup_db();
+        let create = |name: &str, sex: Sex| {
+            let mut p = Person::new(Some(name.into()), None, name.to_lowercase());
+            p.sex = sex;
+            db.persons().create(&mut p).unwrap();
+            p.id.unwrap()
+        };
+        // Modern skapas först men ska ändå hamna nedanför fadern
+        let mor = create("Mor", Sex::Female);
+        let far = create("Far", Sex::Male);
+        let barn = create("Barn", Sex::Unknown);
+        let farfar = create("Farfar", Sex::Unknown);
+        let syster = create("Syster", Sex::Female);
+        for (parent, child) in [(mor, barn), (far, barn), (farfar, far), (mor, syster)] {
+            let mut rel = PersonRelationship::new(parent, child, RelationshipType::Parent);
+            db.relationships().create(&mut rel).unwrap();
+        }
+
+        let service = FamilyTreeService::new(&db);
+        let tree = service.build(TreeMode::Pedigree, barn, 3).unwrap();
+
+        // Bara rakt uppstigande led, inga syskon
+        assert_eq!(tree.nodes.len(), 4);
+        assert!(tree.get_node(syster).is_none());
+        assert_eq!(tree.get_node(barn).unwrap().ahnentafel, Some(1));
+        assert_eq!(tree.get_node(far).unwrap().ahnentafel, Some(2));
+        assert_eq!(tree.get_node(mor).unwrap().ahnentafel, Some(3));
+        assert_eq!(tree.get_node(farfar).unwrap().ahnentafel, Some(4));
+        assert_eq!(tree.get_node(far).unwrap().person.sex, Sex::Male);
+        assert!(tree.get_node(far).unwrap().y < tree.get_node(mor).unwrap().y);
+        assert!(tree.get_node(barn).unwrap().x < tree.get_node(far).unwrap().x);
+
+        // Tomma platser för de föräldrar som saknas inom antalet generationer
+        let mut missing: Vec<(u64, i64)> = tree.placeholders.iter().map(|p| (p.ahnentafel, p.child_id)).collect();
+        missing.sort();
+        assert_eq!(missing, vec![(5, far), (6, mor), (7, mor), (8, farfar), (9, farfar)]);
+        let farmor = tree.placeholders.iter().find(|p| p.ahnentafel == 5).unwrap();
+        assert!(!farmor.is_father());
+        assert!(farmor.x > tree.get_node(far).unwrap().x);
+        assert!(farmor.y > tree.get_node(farfar).unwrap().y);
+    }
+
+    #[test]
+    fn test_descendants_include_all_partners() {
+        use crate::models::{PersonRelationship, RelationshipType};
+
+        let db = setup_db();
+        let mut ids = HashMap::new();
+        for name in ["Förälder", "Person", "Make1", "Make2", "Barn1", "Barn2", "Syskon"] {
+            let mut p = Person::new(Some(name.into()), None, name.to_lowercase());
+            db.persons().create(&mut p).unwrap();
+            ids.insert(name, p.id.unwrap());
+        }
+        let relations = [
+            ("Förälder", "Person", RelationshipType::Parent),
+            ("Förälder", "Syskon", RelationshipType::Parent),
+            ("Person", "Make1", RelationshipType::Spouse),
+            ("Person", "Make2", RelationshipType::Spouse),
+            ("Person", "Barn1", RelationshipType::Parent),
+            ("Make1", "Barn1", RelationshipType::Parent),
+            ("Person", "Barn2", RelationshipType::Parent),
+            ("Make2", "Barn2", RelationshipType::Parent),
+        ];
+        for (a, b, rel_type) in relations {
+            let mut rel = PersonRelationship::new(ids[a], ids[b], rel_type);
+            db.relationships().create(&mut rel).unwrap();
+        }
+
+        let service = FamilyTreeService::new(&db);
+        let tree = service.build(TreeMode::Descendants, ids["Person"], 2).unwrap();
+
+        assert_eq!(tree.nodes.len(), 5);
+        assert!(tree.get_node(ids["Förälder"]).is_none());
+        assert!(tree.get_node(ids["Syskon"]).is_none());
+        assert_eq!(tree.get_node(ids["Barn1"]).unwrap().generation, 1);
+
+        // Barnen hamnar under sina respektive föräldrapar
+        let x = |name: &str| tree.get_node(ids[name]).unwrap().x;
+        assert!(x("Make1") < x("Person") && x("Person") < x("Make2"));
+        assert!(x("Barn1") < x("Barn2"));
+    }
 }
@@ -0,0 +1,223 @@
+//! Solfjäderdiagram över förfäder
+//!
+//! Geometrin räknas ut här, oberoende av UI: varje förfader i en antavla
+//! (se `FamilyTreeService::build_pedigree`) blir ett ringsegment vars plats
+//! bestäms av antavlenumret. Faderns linje ligger till vänster, moderns till
+//! höger. Vinklar är i radianer i skärmkoordinater (y nedåt), medurs från
+//! positiv x-axel.
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::models::Person;
+use crate::services::FamilyTree;
+
+/// Solfjäderns form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FanShape {
+    /// Halvcirkel ovanför fokuspersonen
+    #[default]
+    Half,
+    /// Hel cirkel runt fokuspersonen
+    Full,
+}
+
+impl FanShape {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            FanShape::Half => "Halvcirkel",
+            FanShape::Full => "Helcirkel",
+        }
+    }
+
+    pub fn all() -> &'static [FanShape] {
+        &[FanShape::Half, FanShape::Full]
+    }
+
+    /// Startvinkel och total vinkel för solfjädern
+    fn angles(&self) -> (f32, f32) {
+        match self {
+            // Från vänster, via uppåt, till höger
+            FanShape::Half => (PI, PI),
+            // Från nedåt, via vänster och uppåt, runt till nedåt
+            FanShape::Full => (FRAC_PI_2, TAU),
+        }
+    }
+}
+
+/// Ett ringsegment för en person
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanSegment {
+    pub person_id: i64,
+    pub ahnentafel: u64,
+    /// 0 = fokusperson (mitten), 1 = föräldrar, ...
+    pub depth: u32,
+    pub start_angle: f32,
+    pub end_angle: f32,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    /// Upprepad förfader (anförlust); personen finns även på ett lägre nummer
+    pub is_reference: bool,
+}
+
+impl FanSegment {
+    pub fn mid_angle(&self) -> f32 {
+        (self.start_angle + self.end_angle) / 2.0
+    }
+
+    pub fn mid_radius(&self) -> f32 {
+        (self.inner_radius + self.outer_radius) / 2.0
+    }
+
+    /// Ligger punkten (relativt solfjäderns mittpunkt) inom segmentet?
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let r = (x * x + y * y).sqrt();
+        if r < self.inner_radius || r > self.outer_radius {
+            return false;
+        }
+        if self.depth == 0 {
+            return true;
+        }
+        let angle = self.start_angle + (y.atan2(x) - self.start_angle).rem_euclid(TAU);
+        angle <= self.end_angle
+    }
+}
+
+/// Beräkna segment för alla personer i trädet som har ett antavlenummer,
+/// inklusive hänvisningar till upprepade förfäder. Fokuspersonen blir en
+/// skiva med radien `center_radius`, varje generation därefter en ring med
+/// bredden `ring_width`.
+pub fn fan_segments(tree: &FamilyTree, shape: FanShape, center_radius: f32, ring_width: f32) -> Vec<FanSegment> {
+    let (base, sweep) = shape.angles();
+
+    let nodes = tree
+        .nodes
+        .iter()
+        .filter_map(|(id, node)| Some((*id, node.ahnentafel?, false)));
+    let references = tree.references.iter().map(|r| (r.person_id, r.ahnentafel, true));
+
+    let mut segments: Vec<FanSegment> = nodes
+        .chain(references)
+        .map(|(person_id, number, is_reference)| {
+            let depth = 63 - number.leading_zeros();
+            let slots = (1u64 << depth) as f32;
+            let index = (number - (1 << depth)) as f32;
+            let (inner_radius, outer_radius) = if depth == 0 {
+                (0.0, center_radius)
+            } else {
+                let inner = center_radius + (depth - 1) as f32 * ring_width;
+                (inner, inner + ring_width)
+            };
+
+            FanSegment {
+                person_id,
+                ahnentafel: number,
+                depth,
+                start_angle: base + index * sweep / slots,
+                end_angle: base + (index + 1.0) * sweep / slots,
+                inner_radius,
+                outer_radius,
+                is_reference,
+            }
+        })
+        .collect();
+
+    segments.sort_by_key(|s| s.ahnentafel);
+    segments
+}
+
+/// Hitta segmentet under en punkt (relativt mittpunkten)
+pub fn segment_at(segments: &[FanSegment], x: f32, y: f32) -> Option<&FanSegment> {
+    segments.iter().find(|s| s.contains(x, y))
+}
+
+/// Hur väl utforskad en person är, 0.0-1.0. Födelsedatum, födelseort och
+/// dödsdatum väger lika; finns en checklista räknas den som hälften.
+pub fn research_completeness(person: &Person, checklist_progress: (i64, i64)) -> f32 {
+    let facts = [
+        person.birth_date.is_some(),
+        person.birth_place.as_ref().is_some_and(|p| !p.trim().is_empty()),
+        person.death_date.is_some(),
+    ];
+    let facts_score = facts.iter().filter(|f| **f).count() as f32 / facts.len() as f32;
+
+    let (completed, total) = checklist_progress;
+    if total > 0 {
+        (facts_score + completed as f32 / total as f32) / 2.0
+    } else {
+        facts_score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::family_tree::FamilyTreeNode;
+    use chrono::NaiveDate;
+
+    fn pedigree(numbers: &[u64]) -> FamilyTree {
+        let mut tree = FamilyTree::new();
+        for &number in numbers {
+            let id = number as i64 * 10;
+            tree.nodes.insert(
+                id,
+                FamilyTreeNode {
+                    person: Person::new(Some(format!("P{}", number)), None, format!("p{}", number)),
+                    x: 0.0,
+                    y: 0.0,
+                    generation: 0,
+                    ahnentafel: Some(number),
+                },
+            );
+        }
+        tree
+    }
+
+    #[test]
+    fn test_half_fan_geometry() {
+        let tree = pedigree(&[1, 2, 3, 5]);
+        let segments = fan_segments(&tree, FanShape::Half, 50.0, 40.0);
+        assert_eq!(segments.len(), 4);
+
+        let father = &segments[1];
+        assert_eq!((father.ahnentafel, father.depth), (2, 1));
+        assert_eq!((father.start_angle, father.end_angle), (PI, 1.5 * PI));
+        assert_eq!((father.inner_radius, father.outer_radius), (50.0, 90.0));
+
+        // Farmor (5) ligger närmast toppen på faderns sida
+        let paternal_grandmother = &segments[3];
+        assert_eq!((paternal_grandmother.start_angle, paternal_grandmother.end_angle), (1.25 * PI, 1.5 * PI));
+        assert_eq!(paternal_grandmother.inner_radius, 90.0);
+    }
+
+    #[test]
+    fn test_hit_testing() {
+        let tree = pedigree(&[1, 2, 3]);
+        let half = fan_segments(&tree, FanShape::Half, 50.0, 40.0);
+
+        assert_eq!(segment_at(&half, 0.0, 0.0).map(|s| s.ahnentafel), Some(1));
+        // Uppe till vänster = far, uppe till höger = mor
+        assert_eq!(segment_at(&half, -50.0, -30.0).map(|s| s.ahnentafel), Some(2));
+        assert_eq!(segment_at(&half, 50.0, -30.0).map(|s| s.ahnentafel), Some(3));
+        // Nedanför halvcirkeln finns inget
+        assert!(segment_at(&half, 0.0, 70.0).is_none());
+        // Utanför yttersta ringen
+        assert!(segment_at(&half, 0.0, -100.0).is_none());
+
+        // I helcirkeln har far hela vänstra halvan
+        let full = fan_segments(&tree, FanShape::Full, 50.0, 40.0);
+        assert_eq!(segment_at(&full, -60.0, 30.0).map(|s| s.ahnentafel), Some(2));
+        assert_eq!(segment_at(&full, 60.0, 30.0).map(|s| s.ahnentafel), Some(3));
+    }
+
+    #[test]
+    fn test_research_completeness() {
+        let mut person = Person::new(Some("Anna".into()), None, "anna".into());
+        assert_eq!(research_completeness(&person, (0, 0)), 0.0);
+
+        person.birth_date = NaiveDate::from_ymd_opt(1850, 1, 1);
+        person.birth_place = Some("Uppsala".into());
+        person.death_date = NaiveDate::from_ymd_opt(1920, 1, 1);
+        assert_eq!(research_completeness(&person, (0, 0)), 1.0);
+        assert_eq!(research_completeness(&person, (1, 4)), 0.625);
+    }
+}
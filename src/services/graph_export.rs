@@ -0,0 +1,194 @@
+//! Släktgrafen i format för externa grafverktyg
+//!
+//! Personer blir noder med namn, kön, årtal och födelseort som attribut och
+//! relationer blir typade kanter. DOT läses av Graphviz, GraphML av bland
+//! annat Gephi och yEd. Kanter mellan förälder och barn är riktade från
+//! föräldern; äktenskap och syskon är oriktade.
+
+use crate::models::{Person, PersonRelationship, RelationshipType};
+use crate::services::chart_export::xml_escape;
+
+/// En relation som kant: förälder -> barn, annars i lagrad ordning
+fn edge(rel: &PersonRelationship) -> (i64, i64, RelationshipType) {
+    match rel.relationship_a_to_b {
+        RelationshipType::Child => (rel.person_b_id, rel.person_a_id, RelationshipType::Parent),
+        other => (rel.person_a_id, rel.person_b_id, other),
+    }
+}
+
+/// Stabil nyckel för kanttypen (oberoende av språk i UI)
+fn edge_type(relationship_type: RelationshipType) -> &'static str {
+    match relationship_type {
+        RelationshipType::Parent | RelationshipType::Child => "parent",
+        RelationshipType::Spouse => "spouse",
+        RelationshipType::Sibling => "sibling",
+    }
+}
+
+fn years(person: &Person) -> (Option<String>, Option<String>) {
+    (
+        person.birth_date.map(|d| d.format("%Y").to_string()),
+        person.death_date.map(|d| d.format("%Y").to_string()),
+    )
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Graphviz DOT
+pub fn to_dot(persons: &[Person], relationships: &[PersonRelationship]) -> String {
+    let mut dot = String::from("digraph genlib {\n");
+    dot.push_str("    node [shape=box, style=rounded];\n");
+
+    for person in persons {
+        let Some(id) = person.id else {
+            continue;
+        };
+        let name = dot_escape(&person.full_name());
+        let (birth, death) = years(person);
+
+        let mut label = name.clone();
+        if birth.is_some() || death.is_some() {
+            label.push_str(&format!("\\n{} - {}", birth.as_deref().unwrap_or(""), death.as_deref().unwrap_or("")));
+        }
+        let mut attributes = vec![
+            format!("label=\"{}\"", label),
+            format!("name=\"{}\"", name),
+            format!("sex=\"{}\"", person.sex.as_db_str()),
+        ];
+        if let Some(year) = birth {
+            attributes.push(format!("birth_year={}", year));
+        }
+        if let Some(year) = death {
+            attributes.push(format!("death_year={}", year));
+        }
+        if let Some(place) = person.birth_place.as_deref().filter(|p| !p.trim().is_empty()) {
+            attributes.push(format!("birth_place=\"{}\"", dot_escape(place)));
+        }
+        dot.push_str(&format!("    p{} [{}];\n", id, attributes.join(", ")));
+    }
+
+    for rel in relationships {
+        let (from, to, relationship_type) = edge(rel);
+        let style = match relationship_type {
+            RelationshipType::Parent | RelationshipType::Child => "",
+            RelationshipType::Spouse => ", dir=none, style=bold",
+            RelationshipType::Sibling => ", dir=none, style=dashed",
+        };
+        dot.push_str(&format!("    p{} -> p{} [type=\"{}\"{}];\n", from, to, edge_type(relationship_type), style));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// GraphML
+pub fn to_graphml(persons: &[Person], relationships: &[PersonRelationship]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (id, target, kind) in [
+        ("label", "node", "string"),
+        ("sex", "node", "string"),
+        ("birth_year", "node", "int"),
+        ("death_year", "node", "int"),
+        ("birth_place", "node", "string"),
+        ("type", "edge", "string"),
+    ] {
+        xml.push_str(&format!(
+            "  <key id=\"{id}\" for=\"{target}\" attr.name=\"{id}\" attr.type=\"{kind}\"/>\n"
+        ));
+    }
+    xml.push_str("  <graph id=\"genlib\" edgedefault=\"directed\">\n");
+
+    for person in persons {
+        let Some(id) = person.id else {
+            continue;
+        };
+        let (birth, death) = years(person);
+        let place = person.birth_place.clone().filter(|p| !p.trim().is_empty());
+
+        xml.push_str(&format!("    <node id=\"p{}\">\n", id));
+        let data = [
+            ("label", Some(person.full_name())),
+            ("sex", Some(person.sex.as_db_str().to_string())),
+            ("birth_year", birth),
+            ("death_year", death),
+            ("birth_place", place),
+        ];
+        for (key, value) in data {
+            if let Some(value) = value {
+                xml.push_str(&format!("      <data key=\"{}\">{}</data>\n", key, xml_escape(&value)));
+            }
+        }
+        xml.push_str("    </node>\n");
+    }
+
+    for rel in relationships {
+        let (from, to, relationship_type) = edge(rel);
+        let directed = matches!(relationship_type, RelationshipType::Parent | RelationshipType::Child);
+        xml.push_str(&format!(
+            "    <edge id=\"r{}\" source=\"p{}\" target=\"p{}\" directed=\"{}\">\n",
+            rel.id.unwrap_or_default(),
+            from,
+            to,
+            directed
+        ));
+        xml.push_str(&format!("      <data key=\"type\">{}</data>\n", edge_type(relationship_type)));
+        xml.push_str("    </edge>\n");
+    }
+
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Sex;
+    use chrono::NaiveDate;
+
+    fn person(id: i64, name: &str, sex: Sex) -> Person {
+        let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+        p.id = Some(id);
+        p.sex = sex;
+        p
+    }
+
+    fn relation(id: i64, a: i64, b: i64, a_to_b: RelationshipType) -> PersonRelationship {
+        let mut rel = PersonRelationship::new(a, b, a_to_b);
+        rel.id = Some(id);
+        rel
+    }
+
+    #[test]
+    fn test_dot_and_graphml() {
+        let mut father = person(1, "Karl", Sex::Male);
+        father.birth_date = NaiveDate::from_ymd_opt(1850, 3, 1);
+        father.birth_place = Some("Väsby \"gård\" & kvarn".into());
+        let persons = vec![father, person(2, "Anna", Sex::Female), person(3, "Lisa", Sex::Female)];
+        // Lagras med lägsta id först: barnet 2 är "Child" till 3 -> kant 3 -> 2
+        let relationships = vec![
+            relation(10, 1, 2, RelationshipType::Parent),
+            relation(11, 2, 3, RelationshipType::Child),
+            relation(12, 1, 3, RelationshipType::Spouse),
+        ];
+        assert_eq!(relationships[1].relationship_a_to_b, RelationshipType::Child);
+
+        let dot = to_dot(&persons, &relationships);
+        assert!(dot.starts_with("digraph genlib {"));
+        assert!(dot.contains("p1 [label=\"Karl Berg\\n1850 - \", name=\"Karl Berg\", sex=\"M\", birth_year=1850, birth_place=\"Väsby \\\"gård\\\" & kvarn\"];"));
+        assert!(dot.contains("p1 -> p2 [type=\"parent\"];"));
+        assert!(dot.contains("p3 -> p2 [type=\"parent\"];"));
+        assert!(dot.contains("p1 -> p3 [type=\"spouse\", dir=none, style=bold];"));
+
+        let xml = to_graphml(&persons, &relationships);
+        assert!(xml.contains("<key id=\"birth_year\" for=\"node\" attr.name=\"birth_year\" attr.type=\"int\"/>"));
+        assert!(xml.contains("<data key=\"birth_place\">Väsby &quot;gård&quot; &amp; kvarn</data>"));
+        assert!(xml.contains("<edge id=\"r11\" source=\"p3\" target=\"p2\" directed=\"true\">"));
+        assert!(xml.contains("<edge id=\"r12\" source=\"p1\" target=\"p3\" directed=\"false\">"));
+        assert_eq!(xml.matches("<node ").count(), 3);
+        // Okända årtal utelämnas
+        assert_eq!(xml.matches("key=\"death_year\"").count(), 0);
+    }
+}
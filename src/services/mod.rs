@@ -2,13 +2,39 @@
 //!
 //! Innehåller affärslogik som inte hör hemma i UI eller databas.
 
+pub mod ahnentafel;
+pub mod anniversaries;
+pub mod analytics;
 pub mod backup;
+pub mod chart_export;
+pub mod descendant_register;
 pub mod document_sync;
 pub mod export;
+pub mod family_group_sheet;
 pub mod family_tree;
+pub mod fan_chart;
+pub mod graph_export;
+pub mod pdf_layout;
+pub mod pedigree_collapse;
+pub mod person_dossier;
+pub mod privacy;
+pub mod project_json;
+pub mod report_template;
+pub mod research_gaps;
 pub mod restore;
+pub mod timeline;
+pub mod tree_layout;
+pub mod website_export;
+pub mod xlsx;
 
+pub use anniversaries::{Anniversary, AnniversaryScope, AnniversaryService};
 pub use backup::{BackupInfo, BackupService};
+pub use chart_export::{ChartExportService, ChartFormat, ChartOptions};
 pub use document_sync::DocumentSyncService;
-pub use family_tree::{FamilyTree, FamilyTreeService, LinkType};
+pub use family_tree::{FamilyTree, FamilyTreeService, LinkType, TreeMode};
+pub use fan_chart::FanShape;
+pub use pedigree_collapse::{PedigreeCollapse, PedigreeCollapseService};
+pub use privacy::{PrivacyPolicy, RedactionStyle};
+pub use project_json::ProjectJsonService;
+pub use research_gaps::{ResearchGap, ResearchGapsService};
 pub use restore::{RestorePreview, RestoreService};
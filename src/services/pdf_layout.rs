@@ -0,0 +1,494 @@
+//! Sidlayout för PDF-rapporter
+//!
+//! Löptext och tabeller över flera A4-sidor med automatisk sidbrytning,
+//! upprepade tabellrubriker och sidnummer. Texten skrivs med DejaVu Sans
+//! som följer med programmet (resources/fonts), så att tecken som
+//! PDF-standardtypsnitten saknar också kan skrivas ut. Radbrytning och
+//! kolumnbredder räknas fram ur typsnittets teckenbredder.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use image::RgbImage;
+use printpdf::{
+    Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point, Rgb,
+};
+
+use crate::services::chart_export::add_pdf_image;
+
+const REGULAR_TTF: &[u8] = include_bytes!("../../resources/fonts/DejaVuSans.ttf");
+const BOLD_TTF: &[u8] = include_bytes!("../../resources/fonts/DejaVuSans-Bold.ttf");
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const DEFAULT_MARGIN: f32 = 20.0;
+const PT_TO_MM: f32 = 0.3528;
+/// Avstånd mellan tabellkolumner (mm)
+const CELL_GAP: f32 = 2.0;
+
+/// Bädda in DejaVu Sans (normal, fet) i ett dokument som inte byggs med
+/// `PdfReport`, t.ex. släktträdsdiagrammen
+pub(crate) fn embed_fonts(doc: &PdfDocumentReference) -> Result<(IndirectFontRef, IndirectFontRef)> {
+    Ok((Font::load(doc, REGULAR_TTF)?.pdf, Font::load(doc, BOLD_TTF)?.pdf))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Weight {
+    Regular,
+    Bold,
+}
+
+/// Inbäddat typsnitt med teckenbredder
+struct Font {
+    pdf: IndirectFontRef,
+    face: ttf_parser::Face<'static>,
+}
+
+impl Font {
+    fn load(doc: &PdfDocumentReference, data: &'static [u8]) -> Result<Self> {
+        Ok(Self {
+            pdf: doc.add_external_font(data).context("Kunde inte bädda in typsnitt")?,
+            face: ttf_parser::Face::parse(data, 0).context("Kunde inte läsa typsnitt")?,
+        })
+    }
+
+    /// Textens bredd i mm. Tecken som saknas i typsnittet räknas som en
+    /// halv fyrkant.
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units_per_em = self.face.units_per_em() as f32;
+        let units: f32 = text
+            .chars()
+            .map(|c| {
+                self.face
+                    .glyph_index(c)
+                    .and_then(|g| self.face.glyph_hor_advance(g))
+                    .map_or(units_per_em / 2.0, f32::from)
+            })
+            .sum();
+        units / units_per_em * size * PT_TO_MM
+    }
+}
+
+/// Tabellkolumn. Bredden är relativ och fördelas över textytans bredd.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Column<'a> {
+    pub title: &'a str,
+    pub width: f32,
+    pub align_right: bool,
+}
+
+impl<'a> Column<'a> {
+    pub fn new(title: &'a str, width: f32) -> Self {
+        Self { title, width, align_right: false }
+    }
+
+    /// Högerställd (för tal)
+    pub fn right(mut self) -> Self {
+        self.align_right = true;
+        self
+    }
+}
+
+/// Ett PDF-dokument som fylls uppifrån och ned, sida för sida
+pub(crate) struct PdfReport {
+    doc: PdfDocumentReference,
+    pages: Vec<PdfLayerReference>,
+    regular: Font,
+    bold: Font,
+    margin: f32,
+    /// Position uppifrån på aktuell sida (mm)
+    y: f32,
+    /// Text i sidfoten före sidnumret
+    footer: String,
+}
+
+impl PdfReport {
+    pub fn new(title: &str) -> Result<Self> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Lager 1");
+        let first = doc.get_page(page).get_layer(layer);
+        let regular = Font::load(&doc, REGULAR_TTF)?;
+        let bold = Font::load(&doc, BOLD_TTF)?;
+        Ok(Self {
+            doc,
+            pages: vec![first],
+            regular,
+            bold,
+            margin: DEFAULT_MARGIN,
+            y: DEFAULT_MARGIN,
+            footer: title.to_string(),
+        })
+    }
+
+    /// Annan marginal än standard (sätts innan något skrivs)
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self.y = margin;
+        self
+    }
+
+    fn font(&self, weight: Weight) -> &Font {
+        match weight {
+            Weight::Regular => &self.regular,
+            Weight::Bold => &self.bold,
+        }
+    }
+
+    fn layer(&self) -> &PdfLayerReference {
+        self.pages.last().expect("dokumentet har alltid en sida")
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Textytans bredd (mm)
+    pub fn content_width(&self) -> f32 {
+        PAGE_WIDTH - 2.0 * self.margin
+    }
+
+    pub fn text_width(&self, text: &str, size: f32, weight: Weight) -> f32 {
+        self.font(weight).width(text, size)
+    }
+
+    pub fn line_height(size: f32) -> f32 {
+        size * PT_TO_MM * 1.4
+    }
+
+    pub fn space(&mut self, mm: f32) {
+        self.y += mm;
+    }
+
+    pub fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Lager 1");
+        self.pages.push(self.doc.get_page(page).get_layer(layer));
+        self.y = self.margin;
+    }
+
+    /// Byt sida om mindre än `mm` återstår. Returnerar true vid sidbyte.
+    pub fn keep(&mut self, mm: f32) -> bool {
+        if self.y + mm > PAGE_HEIGHT - self.margin && self.y > self.margin {
+            self.new_page();
+            return true;
+        }
+        false
+    }
+
+    /// Skriv text på aktuell baslinje, `x` räknat från vänstermarginalen
+    pub fn text_at(&self, x: f32, text: &str, size: f32, weight: Weight) {
+        let font = &self.font(weight).pdf;
+        self.layer()
+            .use_text(text, size, Mm(self.margin + x), Mm(PAGE_HEIGHT - self.y), font);
+    }
+
+    /// Ny rad med baslinjen en radhöjd ned (sidbyte vid behov)
+    fn next_line(&mut self, size: f32) {
+        let height = Self::line_height(size);
+        self.keep(height);
+        self.y += height;
+    }
+
+    /// Korta av texten med "…" så att den ryms i bredden
+    pub fn fit(&self, text: &str, width: f32, size: f32, weight: Weight) -> String {
+        let font = self.font(weight);
+        if font.width(text, size) <= width {
+            return text.to_string();
+        }
+        let mut short: String = text.to_string();
+        while !short.is_empty() && font.width(&format!("{}…", short.trim_end()), size) > width {
+            short.pop();
+        }
+        format!("{}…", short.trim_end())
+    }
+
+    /// Radbruten text i fullt textbredd
+    pub fn line(&mut self, text: &str, size: f32, weight: Weight) {
+        self.wrapped(text, size, 0.0, weight);
+    }
+
+    /// Indragen text, radbruten på ordgränser
+    pub fn wrapped(&mut self, text: &str, size: f32, indent: f32, weight: Weight) {
+        let font = self.font(weight);
+        let max = self.content_width() - indent;
+        for line in wrap(text, max, |s| font.width(s, size)) {
+            self.next_line(size);
+            self.text_at(indent, &line, size, weight);
+        }
+    }
+
+    /// Stycke med fet inledning och löptext efter den på samma rad
+    pub fn paragraph(&mut self, lead: &str, text: &str, size: f32) {
+        let lead_width = self.bold.width(lead, size) + self.regular.width(" ", size);
+        let width = self.content_width();
+        let font = &self.regular;
+        let lines = wrap_lines(text, width - lead_width, width, |s| font.width(s, size));
+
+        self.next_line(size);
+        self.text_at(0.0, lead, size, Weight::Bold);
+        let mut lines = lines.into_iter();
+        if let Some(first) = lines.next() {
+            self.text_at(lead_width, &first, size, Weight::Regular);
+        }
+        for line in lines {
+            self.next_line(size);
+            self.text_at(0.0, &line, size, Weight::Regular);
+        }
+    }
+
+    /// Text med bevarade radbrytningar; tomma rader blir luft
+    pub fn text_block(&mut self, text: &str, size: f32, indent: f32) {
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                self.space(Self::line_height(size) / 2.0);
+            } else {
+                self.wrapped(line, size, indent, Weight::Regular);
+            }
+        }
+    }
+
+    /// Dokumenttitel med datum för utskriften
+    pub fn title(&mut self, title: &str) {
+        self.line(title, 16.0, Weight::Bold);
+        self.line(&Utc::now().format("Genererad: %Y-%m-%d %H:%M").to_string(), 10.0, Weight::Regular);
+    }
+
+    /// Rubrik som inte hamnar ensam längst ned på en sida
+    pub fn heading(&mut self, text: &str, size: f32) {
+        self.space(4.0);
+        self.keep(20.0);
+        self.line(text, size, Weight::Bold);
+        self.space(1.0);
+    }
+
+    /// Tunn horisontell linje över textytan på aktuell position
+    pub fn rule(&self) {
+        let layer = self.layer();
+        layer.set_outline_color(Color::Rgb(Rgb::new(0.6, 0.6, 0.6, None)));
+        layer.set_outline_thickness(0.4);
+        layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(self.margin), Mm(PAGE_HEIGHT - self.y)), false),
+                (Point::new(Mm(PAGE_WIDTH - self.margin), Mm(PAGE_HEIGHT - self.y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    /// Etikett och värde i två kolumner; långa värden bryts
+    pub fn key_values(&mut self, rows: &[(String, String)], size: f32, label_width: f32) {
+        let max = self.content_width() - label_width;
+        for (label, value) in rows {
+            let lines = wrap(value, max, |s| self.regular.width(s, size));
+            self.next_line(size);
+            self.text_at(0.0, label, size, Weight::Bold);
+            for (i, line) in lines.iter().enumerate() {
+                if i > 0 {
+                    self.next_line(size);
+                }
+                self.text_at(label_width, line, size, Weight::Regular);
+            }
+        }
+    }
+
+    /// Tabell med radbrutna celler. Rubrikraden upprepas överst på varje
+    /// ny sida som tabellen fortsätter på.
+    pub fn table(&mut self, columns: &[Column], rows: &[Vec<String>], size: f32) {
+        let total: f32 = columns.iter().map(|c| c.width).sum();
+        let mut x = 0.0;
+        let layout: Vec<(f32, f32)> = columns
+            .iter()
+            .map(|c| {
+                let width = self.content_width() * c.width / total;
+                let cell = (x, width - CELL_GAP);
+                x += width;
+                cell
+            })
+            .collect();
+        let line_height = Self::line_height(size);
+
+        let header = |report: &mut Self| {
+            report.next_line(size);
+            for (column, (x, width)) in columns.iter().zip(&layout) {
+                let title = report.fit(column.title, *width, size, Weight::Bold);
+                report.cell(&title, *x, *width, column.align_right, size, Weight::Bold);
+            }
+            report.space(1.5);
+            report.rule();
+        };
+
+        self.keep(3.0 * line_height);
+        header(self);
+        for row in rows {
+            let cells: Vec<Vec<String>> = layout
+                .iter()
+                .zip(row)
+                .map(|((_, width), text)| wrap(text, *width, |s| self.regular.width(s, size)))
+                .collect();
+            let height = cells.iter().map(Vec::len).max().unwrap_or(1).max(1) as f32 * line_height;
+            if self.keep(height + 1.0) {
+                header(self);
+            }
+            let top = self.y;
+            for (i, lines) in cells.iter().enumerate() {
+                let (x, width) = layout[i];
+                self.y = top;
+                for line in lines {
+                    self.y += line_height;
+                    self.cell(line, x, width, columns[i].align_right, size, Weight::Regular);
+                }
+            }
+            self.y = top + height + 1.0;
+        }
+    }
+
+    fn cell(&self, text: &str, x: f32, width: f32, align_right: bool, size: f32, weight: Weight) {
+        let x = if align_right {
+            x + width - self.text_width(text, size, weight)
+        } else {
+            x
+        };
+        self.text_at(x, text, size, weight);
+    }
+
+    /// Placera en bild med överkanten på aktuell position, `x` från
+    /// vänstermarginalen. Returnerar bildens höjd (mm).
+    pub fn image(&self, photo: &RgbImage, x: f32, width: f32) -> f32 {
+        let height = width * photo.height() as f32 / photo.width().max(1) as f32;
+        add_pdf_image(
+            self.layer(),
+            photo,
+            Mm(self.margin + x),
+            Mm(PAGE_HEIGHT - self.y - height),
+            width,
+        );
+        height
+    }
+
+    /// Miniatyrer i rutnät med bildtext under varje bild
+    pub fn thumbnails(&mut self, images: &[(RgbImage, String)], columns: usize) {
+        const GAP: f32 = 5.0;
+        const CAPTION: f32 = 8.0;
+        let width = (self.content_width() - GAP * (columns - 1) as f32) / columns as f32;
+
+        for row in images.chunks(columns) {
+            let height = row
+                .iter()
+                .map(|(img, _)| width * img.height() as f32 / img.width().max(1) as f32)
+                .fold(0.0, f32::max);
+            self.keep(height + CAPTION);
+            for (i, (img, caption)) in row.iter().enumerate() {
+                let x = i as f32 * (width + GAP);
+                self.image(img, x, width);
+                let caption = self.fit(caption, width, 7.0, Weight::Regular);
+                let top = self.y;
+                self.y += height + 4.0;
+                self.text_at(x, &caption, 7.0, Weight::Regular);
+                self.y = top;
+            }
+            self.y += height + CAPTION;
+        }
+    }
+
+    /// Skriv sidfötter med "Sida N av M" och returnera PDF-filens innehåll
+    pub fn finish(self) -> Result<Vec<u8>> {
+        let total = self.pages.len();
+        for (i, layer) in self.pages.iter().enumerate() {
+            let text = format!("{} – Sida {} av {}", self.footer, i + 1, total);
+            let width = self.regular.width(&text, 8.0);
+            layer.use_text(
+                text,
+                8.0,
+                Mm((PAGE_WIDTH - width) / 2.0),
+                Mm(self.margin / 2.0),
+                &self.regular.pdf,
+            );
+        }
+        let mut bytes = Vec::new();
+        self.doc
+            .save(&mut std::io::BufWriter::new(std::io::Cursor::new(&mut bytes)))
+            .context("Kunde inte spara PDF")?;
+        Ok(bytes)
+    }
+
+    /// Spara till fil. Returnerar (antal sidor, filstorlek).
+    pub fn save(self, path: &Path) -> Result<(usize, usize)> {
+        let pages = self.page_count();
+        let bytes = self.finish()?;
+        std::fs::write(path, &bytes).context("Kunde inte skapa PDF-fil")?;
+        Ok((pages, bytes.len()))
+    }
+}
+
+/// Bryt text i rader som är högst `max_width` breda enligt `width`. Ord som
+/// inte ryms på en egen rad delas mellan tecken.
+pub(crate) fn wrap(text: &str, max_width: f32, width: impl Fn(&str) -> f32) -> Vec<String> {
+    wrap_lines(text, max_width, max_width, width)
+}
+
+/// Som `wrap`, men med en annan bredd för första raden
+fn wrap_lines(text: &str, first_width: f32, max_width: f32, width: impl Fn(&str) -> f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let limit = |lines: &Vec<String>| if lines.is_empty() { first_width } else { max_width };
+
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+        if width(&candidate) <= limit(&lines) {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        // Dela ord som är bredare än en hel rad
+        for c in word.chars() {
+            current.push(c);
+            if width(&current) > limit(&lines) && current.chars().count() > 1 {
+                current.pop();
+                lines.push(std::mem::take(&mut current));
+                current.push(c);
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> f32 {
+        s.chars().count() as f32
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("ett två  tre fyra", 8.0, chars), ["ett två", "tre fyra"]);
+        assert_eq!(wrap("mycketlångtord kort", 5.0, chars), ["mycke", "tlång", "tord", "kort"]);
+        assert!(wrap("   ", 10.0, chars).is_empty());
+        assert_eq!(wrap_lines("ett två tre", 3.0, 7.0, chars), ["ett", "två tre"]);
+    }
+
+    #[test]
+    fn test_table_repeats_header_and_numbers_pages() {
+        let mut report = PdfReport::new("Test").unwrap();
+        // Bredden räknas ur typsnittet, inte antal tecken
+        assert!(report.text_width("WWW", 10.0, Weight::Regular) > report.text_width("iii", 10.0, Weight::Regular));
+        assert!(report.text_width("Ŧ ǅ ẞ", 10.0, Weight::Bold) > 0.0);
+
+        let rows: Vec<Vec<String>> = (0..120)
+            .map(|i| vec![format!("Åsa Ödmark-Ægidius {}", i), "1850-01-01".into(), i.to_string()])
+            .collect();
+        let columns = [Column::new("Namn", 3.0), Column::new("Född", 2.0), Column::new("Nr", 1.0).right()];
+        report.table(&columns, &rows, 9.0);
+        assert!(report.page_count() >= 3);
+
+        let pages = report.page_count();
+        let pdf = String::from_utf8_lossy(&report.finish().unwrap()).to_string();
+        assert_eq!(pdf.matches("/Type/Page/").count(), pages);
+        assert!(pdf.contains("FontFile2"), "typsnittet ska vara inbäddat");
+    }
+}
@@ -0,0 +1,382 @@
+//! Anförlust och släktskap mellan föräldrar
+//!
+//! I en fullständig antavla har varje plats en egen person. När samma
+//! förfader förekommer på flera antavlenummer (t.ex. när kusiner gifte sig)
+//! talar man om anförlust. Här räknas förekomsterna ut och Wrights
+//! släktskapskoefficient för de par där linjerna möts: varje väg från far
+//! och mor upp till en gemensam förfader bidrar med (1/2)^(n1 + n2), där
+//! n1 och n2 är antalet led. Vägar som delar någon annan person än
+//! förfadern räknas inte, eftersom de redan täcks av en närmare förfader.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+
+use crate::db::{Database, KinshipGraph};
+use crate::models::Person;
+use crate::services::family_tree::split_parents;
+
+/// Största antal generationer som expanderas (2^16 platser)
+pub(crate) const MAX_GENERATIONS: usize = 15;
+
+/// En förfader som förekommer på flera platser i antavlan
+#[derive(Debug, Clone)]
+pub struct CollapseAncestor {
+    pub person: Person,
+    /// Antavlenummer där personen förekommer, stigande
+    pub numbers: Vec<u64>,
+    /// Bidrag till släktskapskoefficienten mellan de föräldrapar där
+    /// personens linjer möts
+    pub coefficient: f64,
+}
+
+impl CollapseAncestor {
+    /// Närmaste generation (1 = föräldrar)
+    pub fn generation(&self) -> u32 {
+        depth(self.numbers[0])
+    }
+}
+
+/// Ett föräldrapar i antavlan som är släkt med varandra
+#[derive(Debug, Clone)]
+pub struct RelatedCouple {
+    /// Antavlenummer för parets barn (far = 2n, mor = 2n + 1)
+    pub child_number: u64,
+    pub father: Person,
+    pub mother: Person,
+    /// Wrights släktskapskoefficient (0.125 för kusiner)
+    pub coefficient: f64,
+    /// Antal led upp till närmaste gemensamma förfader (far, mor)
+    pub nearest_common: (u32, u32),
+}
+
+impl RelatedCouple {
+    /// Släktskap på svenska, när båda är lika många led från förfadern
+    pub fn kinship_label(&self) -> &'static str {
+        match self.nearest_common {
+            (0, _) | (_, 0) => "förälder och barn",
+            (1, 1) => "syskon",
+            (2, 2) => "kusiner",
+            (3, 3) => "sysslingar",
+            (4, 4) => "brylingar",
+            (5, 5) => "pysslingar",
+            _ => "släkt",
+        }
+    }
+}
+
+/// Anförlust för en person
+#[derive(Debug, Clone, Default)]
+pub struct PedigreeCollapse {
+    pub root_person_id: i64,
+    pub generations: usize,
+    /// Ifyllda platser i antavlan (utöver personen själv)
+    pub slots: usize,
+    /// Antal olika personer på dessa platser
+    pub distinct_ancestors: usize,
+    pub ancestors: Vec<CollapseAncestor>,
+    pub couples: Vec<RelatedCouple>,
+    /// Släktskapskoefficient mellan personens föräldrar
+    pub parents_coefficient: f64,
+}
+
+impl PedigreeCollapse {
+    /// Anförlust i procent av de ifyllda platserna
+    pub fn collapse_percent(&self) -> f64 {
+        if self.slots == 0 {
+            return 0.0;
+        }
+        (self.slots - self.distinct_ancestors) as f64 * 100.0 / self.slots as f64
+    }
+
+    /// Personens inavelskoefficient (hälften av föräldrarnas släktskap)
+    pub fn inbreeding_coefficient(&self) -> f64 {
+        self.parents_coefficient / 2.0
+    }
+}
+
+/// Tjänst för analys av anförlust
+pub struct PedigreeCollapseService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> PedigreeCollapseService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Analysera antavlan för en person
+    pub fn analyze(&self, root_person_id: i64, generations: usize) -> Result<PedigreeCollapse> {
+        let graph = self.db.kinship()?;
+        let generations = generations.min(MAX_GENERATIONS);
+        let pedigree = expand_pedigree(&graph, root_person_id, generations);
+        Ok(analyze_pedigree(&graph, root_person_id, generations, &pedigree))
+    }
+}
+
+/// 1 -> 0, 2-3 -> 1, 4-7 -> 2 ...
+pub(crate) fn depth(number: u64) -> u32 {
+    63 - number.leading_zeros()
+}
+
+/// Fullständig antavla utan dubblettkontroll: antavlenummer -> person-ID
+pub(crate) fn expand_pedigree(graph: &KinshipGraph, root_person_id: i64, generations: usize) -> BTreeMap<u64, i64> {
+    let mut pedigree = BTreeMap::new();
+    if graph.person(root_person_id).is_none() {
+        return pedigree;
+    }
+    pedigree.insert(1, root_person_id);
+
+    let mut current = vec![(1u64, root_person_id)];
+    for _ in 0..generations {
+        let mut next = Vec::new();
+        for (number, person_id) in current {
+            let parents = graph
+                .parents(person_id)
+                .iter()
+                .filter_map(|id| graph.person(*id).cloned())
+                .collect();
+            let (father, mother) = split_parents(parents);
+            for (parent, parent_number) in [(father, number * 2), (mother, number * 2 + 1)] {
+                if let Some(parent_id) = parent.and_then(|p| p.id) {
+                    pedigree.insert(parent_number, parent_id);
+                    next.push((parent_number, parent_id));
+                }
+            }
+        }
+        current = next;
+    }
+    pedigree
+}
+
+/// Personerna på vägen från `from` upp till (men utan) `to`, där `to`
+/// ligger i `from`s delträd
+fn path_persons(pedigree: &BTreeMap<u64, i64>, from: u64, to: u64) -> Vec<i64> {
+    let mut persons = Vec::new();
+    let mut number = to >> 1;
+    while number >= from {
+        persons.push(pedigree[&number]);
+        number >>= 1;
+    }
+    persons
+}
+
+/// Ligger `number` i delträdet som börjar på `root`?
+fn in_subtree(number: u64, root: u64) -> bool {
+    let (d, r) = (depth(number), depth(root));
+    d >= r && number >> (d - r) == root
+}
+
+/// Släktskapskoefficient mellan personerna på platserna `father` och
+/// `mother`. Returnerar (koefficient, bidrag per förfader, närmaste
+/// gemensamma förfaders avstånd).
+fn relationship(
+    pedigree: &BTreeMap<u64, i64>,
+    occurrences: &HashMap<i64, Vec<u64>>,
+    father: u64,
+    mother: u64,
+) -> (f64, HashMap<i64, f64>, Option<(u32, u32)>) {
+    let mut total = 0.0;
+    let mut contributions: HashMap<i64, f64> = HashMap::new();
+    let mut nearest: Option<(u32, u32)> = None;
+
+    for (person_id, numbers) in occurrences {
+        let father_side = numbers.iter().filter(|n| in_subtree(**n, father));
+        for &p in father_side {
+            for &q in numbers.iter().filter(|n| in_subtree(**n, mother)) {
+                let father_path: HashSet<i64> = path_persons(pedigree, father, p).into_iter().collect();
+                if path_persons(pedigree, mother, q).iter().any(|id| father_path.contains(id)) {
+                    continue;
+                }
+
+                let (n1, n2) = (depth(p) - depth(father), depth(q) - depth(mother));
+                let contribution = 0.5f64.powi((n1 + n2) as i32);
+                total += contribution;
+                *contributions.entry(*person_id).or_default() += contribution;
+                if !nearest.is_some_and(|(a, b)| a + b <= n1 + n2) {
+                    nearest = Some((n1, n2));
+                }
+            }
+        }
+    }
+
+    (total, contributions, nearest)
+}
+
+fn analyze_pedigree(
+    graph: &KinshipGraph,
+    root_person_id: i64,
+    generations: usize,
+    pedigree: &BTreeMap<u64, i64>,
+) -> PedigreeCollapse {
+    let mut occurrences: HashMap<i64, Vec<u64>> = HashMap::new();
+    for (&number, &person_id) in pedigree {
+        occurrences.entry(person_id).or_default().push(number);
+    }
+
+    // Bara personer som förekommer flera gånger kan vara gemensamma förfäder
+    let repeated: HashMap<i64, Vec<u64>> = occurrences
+        .iter()
+        .filter(|(_, numbers)| numbers.len() > 1)
+        .map(|(id, numbers)| (*id, numbers.clone()))
+        .collect();
+
+    let mut result = PedigreeCollapse {
+        root_person_id,
+        generations,
+        slots: pedigree.len().saturating_sub(1),
+        distinct_ancestors: occurrences.len().saturating_sub(1),
+        ..Default::default()
+    };
+
+    let mut contributions: HashMap<i64, f64> = HashMap::new();
+    let mut seen_couples = HashSet::new();
+    for &child in pedigree.keys() {
+        let (father_number, mother_number) = (child * 2, child * 2 + 1);
+        let (Some(&father_id), Some(&mother_id)) = (pedigree.get(&father_number), pedigree.get(&mother_number))
+        else {
+            continue;
+        };
+
+        let (coefficient, per_ancestor, nearest) = relationship(pedigree, &repeated, father_number, mother_number);
+        if child == 1 {
+            result.parents_coefficient = coefficient;
+        }
+        if coefficient == 0.0 || !seen_couples.insert((father_id, mother_id)) {
+            continue;
+        }
+
+        for (id, value) in per_ancestor {
+            *contributions.entry(id).or_default() += value;
+        }
+        let (Some(father), Some(mother)) = (graph.person(father_id), graph.person(mother_id)) else {
+            continue;
+        };
+        result.couples.push(RelatedCouple {
+            child_number: child,
+            father: father.clone(),
+            mother: mother.clone(),
+            coefficient,
+            nearest_common: nearest.unwrap_or_default(),
+        });
+    }
+
+    result.ancestors = repeated
+        .into_iter()
+        .filter_map(|(id, mut numbers)| {
+            numbers.sort();
+            Some(CollapseAncestor {
+                person: graph.person(id)?.clone(),
+                numbers,
+                coefficient: contributions.get(&id).copied().unwrap_or(0.0),
+            })
+        })
+        .collect();
+    result.ancestors.sort_by_key(|a| a.numbers[0]);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PersonRelationship, RelationshipType, Sex};
+
+    /// Skapa personer och föräldrarelationer. `parents` är (barn, far, mor).
+    fn setup(names: &[&str], parents: &[(&str, &str, &str)]) -> (Database, HashMap<String, i64>) {
+        let db = Database::open_in_memory().unwrap();
+        let mut ids = HashMap::new();
+        for name in names {
+            let mut p = Person::new(Some(name.to_string()), None, name.to_lowercase());
+            db.persons().create(&mut p).unwrap();
+            ids.insert(name.to_string(), p.id.unwrap());
+        }
+        for (child, father, mother) in parents {
+            for (parent, sex) in [(father, Sex::Male), (mother, Sex::Female)] {
+                let mut person = db.persons().find_by_id(ids[*parent]).unwrap().unwrap();
+                person.sex = sex;
+                db.persons().update(&mut person).unwrap();
+                let mut rel = PersonRelationship::new(ids[*parent], ids[*child], RelationshipType::Parent);
+                db.relationships().create(&mut rel).unwrap();
+            }
+        }
+        (db, ids)
+    }
+
+    #[test]
+    fn test_first_cousin_marriage() {
+        // Far och Mor är kusiner: deras fäder är bröder, söner till Farfar och Farmor
+        let (db, ids) = setup(
+            &["Barn", "Far", "Mor", "Ffar", "Fmor", "Mfar", "Mmor", "Gfar", "Gmor"],
+            &[
+                ("Barn", "Far", "Mor"),
+                ("Far", "Ffar", "Fmor"),
+                ("Mor", "Mfar", "Mmor"),
+                ("Ffar", "Gfar", "Gmor"),
+                ("Mfar", "Gfar", "Gmor"),
+            ],
+        );
+
+        let collapse = PedigreeCollapseService::new(&db).analyze(ids["Barn"], 5).unwrap();
+
+        // Gfar på 8 (far-far-far) och 12 (mor-far-far), Gmor på 9 och 13
+        assert_eq!(collapse.ancestors.len(), 2);
+        assert_eq!(collapse.ancestors[0].person.id, Some(ids["Gfar"]));
+        assert_eq!(collapse.ancestors[0].numbers, vec![8, 12]);
+        assert_eq!(collapse.ancestors[1].numbers, vec![9, 13]);
+        assert_eq!(collapse.ancestors[0].generation(), 3);
+        assert_eq!(collapse.ancestors[0].coefficient, 0.0625);
+
+        assert_eq!(collapse.parents_coefficient, 0.125);
+        assert_eq!(collapse.inbreeding_coefficient(), 0.0625);
+        assert_eq!(collapse.couples.len(), 1);
+        assert_eq!(collapse.couples[0].child_number, 1);
+        assert_eq!(collapse.couples[0].kinship_label(), "kusiner");
+
+        // 10 platser (2-13 utom 10 och 11), 8 olika personer
+        assert_eq!((collapse.slots, collapse.distinct_ancestors), (10, 8));
+        assert_eq!(collapse.collapse_percent(), 20.0);
+    }
+
+    #[test]
+    fn test_shared_path_is_not_counted_twice() {
+        // Halvsyskon: Far och Mor har samma far (X). X:s föräldrar finns
+        // också med men nås bara via X, så de ska inte bidra.
+        let (db, ids) = setup(
+            &["Barn", "Far", "Mor", "X", "A", "B", "Xfar", "Xmor"],
+            &[("Barn", "Far", "Mor"), ("Far", "X", "A"), ("Mor", "X", "B"), ("X", "Xfar", "Xmor")],
+        );
+
+        let collapse = PedigreeCollapseService::new(&db).analyze(ids["Barn"], 4).unwrap();
+
+        assert_eq!(collapse.parents_coefficient, 0.25);
+        assert_eq!(collapse.ancestors.len(), 3);
+        let x = collapse.ancestors.iter().find(|a| a.person.id == Some(ids["X"])).unwrap();
+        assert_eq!(x.numbers, vec![4, 6]);
+        assert_eq!(x.coefficient, 0.25);
+        assert!(collapse
+            .ancestors
+            .iter()
+            .filter(|a| a.person.id != Some(ids["X"]))
+            .all(|a| a.coefficient == 0.0));
+    }
+
+    #[test]
+    fn test_pedigree_tree_has_references() {
+        use crate::services::{FamilyTreeService, TreeMode};
+
+        let (db, ids) = setup(
+            &["Barn", "Far", "Mor", "X", "A", "B"],
+            &[("Barn", "Far", "Mor"), ("Far", "X", "A"), ("Mor", "X", "B")],
+        );
+
+        let tree = FamilyTreeService::new(&db).build(TreeMode::Pedigree, ids["Barn"], 3).unwrap();
+        assert_eq!(tree.get_node(ids["X"]).unwrap().ahnentafel, Some(4));
+        assert_eq!(tree.references.len(), 1);
+        let reference = &tree.references[0];
+        assert_eq!((reference.person_id, reference.ahnentafel, reference.child_id), (ids["X"], 6, ids["Mor"]));
+        // Referensen ligger på plats 6, i samma kolumn som originalet
+        assert_eq!(reference.x, tree.get_node(ids["X"]).unwrap().x);
+        assert!(reference.y > tree.get_node(ids["X"]).unwrap().y);
+    }
+}
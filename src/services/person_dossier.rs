@@ -0,0 +1,213 @@
+//! Persondossier
+//!
+//! Samlar allt som finns om en person: personuppgifter och egna fält,
+//! relationer grupperade per typ, checklistans status och dokumenten
+//! grupperade per dokumenttyp. Textdokumentens innehåll läses in så att
+//! dossiern kan skrivas ut som en enda PDF.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::db::Database;
+use crate::models::{CustomFieldEntity, Document, Person, PersonChecklistItem, RelationshipType, RelationshipView};
+use crate::utils::file_ops;
+
+/// Ett dokument med sökväg och, för textdokument, innehållet
+#[derive(Debug, Clone)]
+pub struct DossierDocument {
+    pub document: Document,
+    pub path: PathBuf,
+    /// Innehållet i text- och markdowndokument (None om filen saknas)
+    pub text: Option<String>,
+}
+
+/// Dokument av samma typ
+#[derive(Debug, Clone)]
+pub struct DossierDocumentGroup {
+    pub type_name: String,
+    pub documents: Vec<DossierDocument>,
+}
+
+/// Allt om en person
+#[derive(Debug, Clone)]
+pub struct PersonDossier {
+    pub person: Person,
+    /// Egna fält med värde (namn, värde)
+    pub custom_fields: Vec<(String, String)>,
+    pub relationships: Vec<(RelationshipType, Vec<RelationshipView>)>,
+    pub checklist: Vec<PersonChecklistItem>,
+    pub documents: Vec<DossierDocumentGroup>,
+}
+
+impl PersonDossier {
+    pub fn title(&self) -> String {
+        format!("Dossier: {}", self.person.full_name())
+    }
+
+    /// Grundfälten som (etikett, värde), tomma fält utelämnas
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let p = &self.person;
+        let date = |d: Option<chrono::NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+        [
+            ("Förnamn", p.firstname.clone()),
+            ("Efternamn", p.surname.clone()),
+            ("Kön", Some(p.sex.display_name().to_string())),
+            ("Född", date(p.birth_date)),
+            ("Födelseort", p.birth_place.clone()),
+            ("Död", date(p.death_date)),
+            ("Ålder", p.age.map(|a| format!("{} år", a))),
+            ("Yrke", p.occupation.clone()),
+            ("GEDCOM-id", p.gedcom_id.clone()),
+            ("Katalog", Some(p.directory_name.clone())),
+            ("Sekretess", Some(p.privacy.display_name().to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| {
+            let value = value?.trim().to_string();
+            (!value.is_empty()).then_some((label, value))
+        })
+        .collect()
+    }
+
+    /// (klara, totalt) i checklistan
+    pub fn checklist_progress(&self) -> (usize, usize) {
+        let done = self.checklist.iter().filter(|i| i.is_completed).count();
+        (done, self.checklist.len())
+    }
+
+    fn all_documents(&self) -> impl Iterator<Item = &DossierDocument> {
+        self.documents.iter().flat_map(|g| &g.documents)
+    }
+
+    pub fn document_count(&self) -> usize {
+        self.all_documents().count()
+    }
+
+    pub fn images(&self) -> impl Iterator<Item = &DossierDocument> {
+        self.all_documents().filter(|d| d.document.is_image())
+    }
+
+    pub fn texts(&self) -> impl Iterator<Item = &DossierDocument> {
+        self.all_documents().filter(|d| d.document.is_text())
+    }
+
+    pub fn pdfs(&self) -> impl Iterator<Item = &DossierDocument> {
+        self.all_documents().filter(|d| d.document.is_pdf())
+    }
+}
+
+/// Tjänst för persondossier
+pub struct PersonDossierService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> PersonDossierService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    pub fn build(&self, person_id: i64) -> Result<PersonDossier> {
+        let person = self
+            .db
+            .persons()
+            .find_by_id(person_id)?
+            .context("Personen hittades inte")?;
+        let media_root = self.db.config().get()?.media_directory_path;
+
+        let custom_fields = self.custom_fields(person_id)?;
+        let relationships = self.db.relationships().find_by_person_grouped(person_id)?;
+        let checklist = self.db.checklists().find_by_person(person_id)?;
+
+        let documents = self
+            .db
+            .documents()
+            .find_by_person_grouped(person_id)?
+            .into_iter()
+            .map(|(doc_type, documents)| DossierDocumentGroup {
+                type_name: doc_type.map(|t| t.name).unwrap_or_else(|| "Övrigt".to_string()),
+                documents: documents
+                    .into_iter()
+                    .map(|document| {
+                        let path = document.full_path(&media_root, &person.directory_name);
+                        let text = if document.is_text() {
+                            file_ops::read_text_file(&path).ok()
+                        } else {
+                            None
+                        };
+                        DossierDocument { document, path, text }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(PersonDossier {
+            person,
+            custom_fields,
+            relationships,
+            checklist,
+            documents,
+        })
+    }
+
+    fn custom_fields(&self, person_id: i64) -> Result<Vec<(String, String)>> {
+        let repo = self.db.custom_fields();
+        let values = repo.get_values(CustomFieldEntity::Person, person_id)?;
+        Ok(repo
+            .find_definitions(CustomFieldEntity::Person)?
+            .into_iter()
+            .filter_map(|definition| {
+                let value = values.get(&definition.id?)?.trim();
+                (!value.is_empty()).then(|| (definition.name, value.to_string()))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PersonRelationship;
+
+    #[test]
+    fn test_build_dossier() {
+        let db = Database::open_in_memory().unwrap();
+        let media = tempfile::tempdir().unwrap();
+        let mut config = db.config().get().unwrap();
+        config.media_directory_path = media.path().to_path_buf();
+        db.config().save(&config).unwrap();
+
+        let mut anna = Person::new(Some("Anna".into()), Some("Berg".into()), "anna_berg".into());
+        anna.occupation = Some("Piga".into());
+        db.persons().create(&mut anna).unwrap();
+        let anna_id = anna.id.unwrap();
+        let mut karl = Person::new(Some("Karl".into()), Some("Berg".into()), "karl_berg".into());
+        db.persons().create(&mut karl).unwrap();
+        db.relationships()
+            .create(&mut PersonRelationship::new(karl.id.unwrap(), anna_id, RelationshipType::Parent))
+            .unwrap();
+
+        let mut item = PersonChecklistItem::new(anna_id, "Husförhör".into());
+        db.checklists().create(&mut item).unwrap();
+        db.checklists().toggle_completed(item.id.unwrap()).unwrap();
+        db.checklists().create(&mut PersonChecklistItem::new(anna_id, "Bouppteckning".into())).unwrap();
+
+        let dir = media.path().join("persons").join("anna_berg").join("anteckningar");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("brev.txt"), "Kära syster,\nallt väl här.").unwrap();
+        for (name, path) in [("brev.txt", "anteckningar/brev.txt"), ("bouppteckning.pdf", "bouppteckning.pdf")] {
+            db.documents().create(&mut Document::new(anna_id, name.into(), path.into())).unwrap();
+        }
+
+        let dossier = PersonDossierService::new(&db).build(anna_id).unwrap();
+        assert_eq!(dossier.title(), "Dossier: Anna Berg");
+        assert!(dossier.fields().contains(&("Yrke", "Piga".to_string())));
+        assert_eq!(dossier.relationships[0].0, RelationshipType::Parent);
+        assert_eq!(dossier.relationships[0].1[0].other_person_name, "Karl Berg");
+        assert_eq!(dossier.checklist_progress(), (1, 2));
+        assert_eq!(dossier.document_count(), 2);
+        assert_eq!(dossier.pdfs().count(), 1);
+        let text = dossier.texts().next().unwrap();
+        assert_eq!(text.text.as_deref(), Some("Kära syster,\nallt väl här."));
+    }
+}
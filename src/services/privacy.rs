@@ -0,0 +1,194 @@
+//! Sekretess för levande och privata personer
+//!
+//! Exporter och rapporter delas ofta vidare. `PrivacyPolicy` avgör vilka
+//! personer som ska maskeras och hur, så att samma regler kan användas av
+//! alla exportvägar (rapporter, PDF och GEDCOM-export).
+
+use anyhow::Result;
+use chrono::{Datelike, Utc};
+
+use crate::db::Database;
+use crate::models::{Person, Privacy};
+
+/// Hur privata personer visas i exporter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionStyle {
+    /// Ersätt namnet med "Privat" och ta bort uppgifter
+    #[default]
+    Placeholder,
+    /// Behåll initialerna men ta bort övriga uppgifter
+    Initials,
+    /// Utelämna personen helt (inklusive relationer)
+    Omit,
+    /// Ingen maskering (endast för eget bruk)
+    None,
+}
+
+impl RedactionStyle {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            RedactionStyle::Placeholder => "Ersätt med \"Privat\"",
+            RedactionStyle::Initials => "Endast initialer",
+            RedactionStyle::Omit => "Utelämna helt",
+            RedactionStyle::None => "Ingen maskering (eget bruk)",
+        }
+    }
+
+    pub fn all() -> &'static [RedactionStyle] {
+        &[
+            RedactionStyle::Placeholder,
+            RedactionStyle::Initials,
+            RedactionStyle::Omit,
+            RedactionStyle::None,
+        ]
+    }
+}
+
+/// Regler för vilka personer som är privata och hur de maskeras
+#[derive(Debug, Clone)]
+pub struct PrivacyPolicy {
+    /// Personer utan dödsdatum födda inom så många år räknas som levande
+    pub living_threshold_years: i32,
+    pub style: RedactionStyle,
+    current_year: i32,
+}
+
+impl PrivacyPolicy {
+    pub fn new(living_threshold_years: i32, style: RedactionStyle) -> Self {
+        Self {
+            living_threshold_years,
+            style,
+            current_year: Utc::now().year(),
+        }
+    }
+
+    /// Skapa policy med gränsen från systemkonfigurationen
+    pub fn from_config(db: &Database, style: RedactionStyle) -> Result<Self> {
+        let config = db.config().get()?;
+        Ok(Self::new(config.living_threshold_years, style))
+    }
+
+    /// Antas personen leva? Saknas både födelse- och dödsdatum räknas
+    /// personen som levande, hellre en maskering för mycket än en för lite.
+    pub fn is_presumed_living(&self, person: &Person) -> bool {
+        if person.death_date.is_some() {
+            return false;
+        }
+        match person.birth_date {
+            Some(birth) => self.current_year - birth.year() < self.living_threshold_years,
+            None => true,
+        }
+    }
+
+    /// Ska personen maskeras enligt sin sekretessinställning?
+    pub fn is_private(&self, person: &Person) -> bool {
+        match person.privacy {
+            Privacy::Public => false,
+            Privacy::Private => true,
+            Privacy::Auto => self.is_presumed_living(person),
+        }
+    }
+
+    /// Maskeras personens uppgifter i exporter med den här policyn?
+    pub fn masks(&self, person: &Person) -> bool {
+        self.style != RedactionStyle::None && self.is_private(person)
+    }
+
+    /// Returnerar personen som den ska visas i en export, eller `None` om
+    /// den ska utelämnas. Privata personer får namn, datum, platser och
+    /// anteckningar borttagna; id behålls så att relationer kan kopplas.
+    pub fn redact(&self, person: &Person) -> Option<Person> {
+        if !self.masks(person) {
+            return Some(person.clone());
+        }
+
+        let (firstname, surname) = match self.style {
+            RedactionStyle::Omit => return None,
+            RedactionStyle::Initials => (initial(&person.firstname), initial(&person.surname)),
+            _ => (Some("Privat".to_string()), None),
+        };
+
+        Some(masked(person, firstname, surname))
+    }
+
+    /// Som `redact`, men en person som skulle utelämnas visas som "Privat".
+    /// För utgångspersonen i rapporter som numreras från den.
+    pub fn redact_or_placeholder(&self, person: &Person) -> Person {
+        self.redact(person)
+            .unwrap_or_else(|| masked(person, Some("Privat".to_string()), None))
+    }
+
+    /// Visningsnamn för personen, eller `None` om den utelämnas
+    pub fn display_name(&self, person: &Person) -> Option<String> {
+        self.redact(person).map(|p| p.full_name())
+    }
+}
+
+/// Maskerad kopia av personen: bara id, namn och sekretessinställning
+fn masked(person: &Person, firstname: Option<String>, surname: Option<String>) -> Person {
+    Person {
+        id: person.id,
+        firstname,
+        surname,
+        privacy: person.privacy,
+        ..Default::default()
+    }
+}
+
+/// "Anna Maria" -> "A."
+fn initial(name: &Option<String>) -> Option<String> {
+    name.as_deref()
+        .and_then(|n| n.trim().chars().next())
+        .map(|c| format!("{}.", c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn person(birth_year: Option<i32>, death_year: Option<i32>, privacy: Privacy) -> Person {
+        let mut p = Person::new(Some("Anna Maria".into()), Some("Berg".into()), "anna".into());
+        p.id = Some(7);
+        p.birth_date = birth_year.and_then(|y| NaiveDate::from_ymd_opt(y, 3, 1));
+        p.death_date = death_year.and_then(|y| NaiveDate::from_ymd_opt(y, 6, 1));
+        p.birth_place = Some("Uppsala".into());
+        p.notes = Some("Flyttade till Amerika".into());
+        p.privacy = privacy;
+        p
+    }
+
+    #[test]
+    fn test_living_and_privacy_flags() {
+        let policy = PrivacyPolicy::new(100, RedactionStyle::Placeholder);
+        let recent = Utc::now().year() - 30;
+
+        assert!(policy.is_private(&person(Some(recent), None, Privacy::Auto)));
+        assert!(policy.is_private(&person(None, None, Privacy::Auto)));
+        assert!(!policy.is_private(&person(Some(1850), None, Privacy::Auto)));
+        assert!(!policy.is_private(&person(Some(recent), Some(recent + 20), Privacy::Auto)));
+
+        // Manuell inställning går före regeln
+        assert!(!policy.is_private(&person(Some(recent), None, Privacy::Public)));
+        assert!(policy.is_private(&person(Some(1850), Some(1920), Privacy::Private)));
+    }
+
+    #[test]
+    fn test_redaction_styles() {
+        let living = person(None, None, Privacy::Auto);
+
+        let redacted = PrivacyPolicy::new(100, RedactionStyle::Placeholder).redact(&living).unwrap();
+        assert_eq!(redacted.full_name(), "Privat");
+        assert_eq!(redacted.id, Some(7));
+        assert!(redacted.birth_place.is_none());
+        assert!(redacted.notes.is_none());
+
+        let initials = PrivacyPolicy::new(100, RedactionStyle::Initials).display_name(&living);
+        assert_eq!(initials.as_deref(), Some("A. B."));
+
+        assert!(PrivacyPolicy::new(100, RedactionStyle::Omit).redact(&living).is_none());
+
+        let untouched = PrivacyPolicy::new(100, RedactionStyle::None).redact(&living).unwrap();
+        assert_eq!(untouched.notes, living.notes);
+    }
+}
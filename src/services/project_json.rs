@@ -0,0 +1,1241 @@
+//! Projektfil i JSON: hela projektet som en versionerad, läsbar fil
+//!
+//! Filen innehåller inställningar, dokumenttyper, katalog- och checklist-
+//! mallar, taggar, egna fält, personer, bokmärken, relationer, dokument-
+//! metadata, checklistor, resurser med adresser och dokument, sparade
+//! sökningar och rapportmallar. Mediafilerna ingår inte (använd backup för
+//! dem). Posterna sorteras på id så att två exporter går att jämföra med
+//! diff, och id:n behålls vid import så att alla hänvisningar stämmer.
+//!
+//! Formatet identifieras av `format` = "genlib-project" och `version`.
+//! Nya fält läggs till som valfria inom samma version; ändringar som
+//! gamla program inte kan läsa ger en ny version. Filer med högre version
+//! än programmet känner till vägras.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::models::{CustomFieldEntity, CustomFieldType, Privacy, RelationshipType, Sex};
+
+/// Värde i fältet `format`
+pub const PROJECT_FORMAT: &str = "genlib-project";
+/// Senaste formatversion som programmet skriver och kan läsa
+pub const PROJECT_VERSION: u32 = 1;
+
+/// Hela projektet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectFile {
+    /// Alltid "genlib-project"
+    pub format: String,
+    /// Formatversion, se `PROJECT_VERSION`
+    pub version: u32,
+    /// Tidpunkt för exporten (UTC, RFC 3339)
+    #[serde(default)]
+    pub exported_at: Option<String>,
+    /// Programversionen som skrev filen
+    #[serde(default)]
+    pub app_version: Option<String>,
+    #[serde(default)]
+    pub config: Option<ProjectConfig>,
+    #[serde(default)]
+    pub document_types: Vec<DocumentTypeRecord>,
+    #[serde(default)]
+    pub directory_templates: Vec<DirectoryTemplateRecord>,
+    #[serde(default)]
+    pub checklist_templates: Vec<ChecklistTemplateRecord>,
+    #[serde(default)]
+    pub tags: Vec<TagRecord>,
+    #[serde(default)]
+    pub custom_fields: Vec<CustomFieldRecord>,
+    #[serde(default)]
+    pub persons: Vec<PersonRecord>,
+    /// Id för bokmärkta personer
+    #[serde(default)]
+    pub bookmarks: Vec<i64>,
+    #[serde(default)]
+    pub relationships: Vec<RelationshipRecord>,
+    #[serde(default)]
+    pub documents: Vec<DocumentRecord>,
+    #[serde(default)]
+    pub checklist_items: Vec<ChecklistItemRecord>,
+    #[serde(default)]
+    pub resource_types: Vec<ResourceTypeRecord>,
+    #[serde(default)]
+    pub resources: Vec<ResourceRecord>,
+    #[serde(default)]
+    pub saved_searches: Vec<SavedSearchRecord>,
+    #[serde(default)]
+    pub report_templates: Vec<ReportTemplateRecord>,
+}
+
+/// Systeminställningar. Sökvägarna gäller datorn som exporterade och
+/// används bara om projektet som importeras till saknar inställningar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    pub media_directory: String,
+    pub backup_directory: String,
+    /// "full_name", "firstname_first", "surname_first" eller "date_first"
+    pub dir_name_format: String,
+    pub living_threshold_years: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentTypeRecord {
+    pub id: i64,
+    pub name: String,
+    pub target_directory: String,
+    #[serde(default)]
+    pub default_filename: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Katalogmall för nya personkataloger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryTemplateRecord {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub directories: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistTemplateRecord {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    #[serde(default)]
+    pub items: Vec<ChecklistTemplateItemRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistTemplateItemRecord {
+    pub id: i64,
+    pub title: String,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagRecord {
+    pub id: i64,
+    pub name: String,
+    /// Hex-färg, t.ex. "#4A90D9"
+    pub color: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+/// Definition av ett eget fält
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomFieldRecord {
+    pub id: i64,
+    /// "person" eller "resource"
+    pub entity: String,
+    pub name: String,
+    /// "text", "number", "date", "choice" eller "url"
+    pub field_type: String,
+    /// Tillåtna värden för "choice"
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonRecord {
+    pub id: i64,
+    #[serde(default)]
+    pub firstname: Option<String>,
+    #[serde(default)]
+    pub surname: Option<String>,
+    /// "M", "F" eller "U"
+    #[serde(default = "default_sex")]
+    pub sex: String,
+    /// "auto", "public" eller "private"
+    #[serde(default = "default_privacy")]
+    pub privacy: String,
+    /// ÅÅÅÅ-MM-DD
+    #[serde(default)]
+    pub birth_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub birth_place: Option<String>,
+    #[serde(default)]
+    pub death_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub age: Option<i32>,
+    #[serde(default)]
+    pub occupation: Option<String>,
+    #[serde(default)]
+    pub gedcom_id: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Personens katalog under media/persons
+    pub directory_name: String,
+    #[serde(default)]
+    pub profile_image_path: Option<String>,
+    /// Id för personens taggar
+    #[serde(default)]
+    pub tags: Vec<i64>,
+    /// Egna fält: fält-id -> värde
+    #[serde(default)]
+    pub custom_fields: BTreeMap<i64, String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// Relation mellan två personer: `person_a` är `type` till `person_b`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationshipRecord {
+    pub id: i64,
+    pub person_a: i64,
+    pub person_b: i64,
+    /// "parent", "child", "spouse" eller "sibling"
+    #[serde(rename = "type")]
+    pub relationship_type: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub marriage_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub marriage_place: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+/// Metadata för en fil i en persons katalog
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentRecord {
+    pub id: i64,
+    pub person_id: i64,
+    #[serde(default)]
+    pub document_type_id: Option<i64>,
+    pub filename: String,
+    /// Sökväg relativt personens katalog
+    pub relative_path: String,
+    #[serde(default)]
+    pub file_size: i64,
+    #[serde(default)]
+    pub file_type: Option<String>,
+    #[serde(default)]
+    pub file_modified_at: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistItemRecord {
+    pub id: i64,
+    pub person_id: i64,
+    /// Mallpunkten som punkten skapades från
+    #[serde(default)]
+    pub template_item_id: Option<i64>,
+    pub title: String,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub is_completed: bool,
+    #[serde(default)]
+    pub completed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceTypeRecord {
+    pub id: i64,
+    pub name: String,
+    pub directory_name: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceRecord {
+    pub id: i64,
+    pub resource_type_id: i64,
+    pub name: String,
+    pub directory_name: String,
+    #[serde(default)]
+    pub information: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub lat: Option<f64>,
+    #[serde(default)]
+    pub lon: Option<f64>,
+    #[serde(default)]
+    pub profile_image_path: Option<String>,
+    /// Egna fält: fält-id -> värde
+    #[serde(default)]
+    pub custom_fields: BTreeMap<i64, String>,
+    #[serde(default)]
+    pub addresses: Vec<AddressRecord>,
+    #[serde(default)]
+    pub documents: Vec<ResourceDocumentRecord>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressRecord {
+    pub id: i64,
+    #[serde(default)]
+    pub street: Option<String>,
+    #[serde(default)]
+    pub postal_code: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceDocumentRecord {
+    pub id: i64,
+    #[serde(default)]
+    pub document_type_id: Option<i64>,
+    pub filename: String,
+    pub relative_path: String,
+    #[serde(default)]
+    pub file_size: i64,
+    #[serde(default)]
+    pub file_type: Option<String>,
+    #[serde(default)]
+    pub file_modified_at: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// Sparad sökning; `filter` har samma fält som personlistans sökfilter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearchRecord {
+    pub id: i64,
+    pub name: String,
+    pub filter: serde_json::Value,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportTemplateRecord {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// "html", "markdown" eller "text"
+    pub format: String,
+    pub body: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_sex() -> String {
+    Sex::default().as_db_str().to_string()
+}
+
+fn default_privacy() -> String {
+    Privacy::default().as_db_str().to_string()
+}
+
+/// Antal poster i en projektfil
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProjectSummary {
+    pub persons: usize,
+    pub relationships: usize,
+    pub documents: usize,
+    pub resources: usize,
+}
+
+impl ProjectSummary {
+    pub fn display(&self) -> String {
+        format!(
+            "{} personer, {} relationer, {} dokument, {} resurser",
+            self.persons, self.relationships, self.documents, self.resources
+        )
+    }
+}
+
+impl ProjectFile {
+    pub fn summary(&self) -> ProjectSummary {
+        ProjectSummary {
+            persons: self.persons.len(),
+            relationships: self.relationships.len(),
+            documents: self.documents.len() + self.resources.iter().map(|r| r.documents.len()).sum::<usize>(),
+            resources: self.resources.len(),
+        }
+    }
+
+    /// Läs och kontrollera format och version
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json).context("Filen är inte giltig JSON")?;
+        if value.get("format").and_then(|f| f.as_str()) != Some(PROJECT_FORMAT) {
+            bail!("Filen är ingen Genlib-projektfil");
+        }
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version == 0 {
+            bail!("Projektfilen saknar formatversion");
+        }
+        if version > PROJECT_VERSION as u64 {
+            bail!(
+                "Projektfilen har formatversion {} men programmet hanterar högst version {}. Uppdatera Genlib.",
+                version,
+                PROJECT_VERSION
+            );
+        }
+        serde_json::from_value(value).context("Projektfilen har fel struktur")
+    }
+}
+
+pub struct ProjectJsonService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ProjectJsonService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Läs hela projektet från databasen
+    pub fn snapshot(&self) -> Result<ProjectFile> {
+        self.db.with_connection(read_project)
+    }
+
+    /// Skriv projektet till en JSON-fil
+    pub fn export(&self, path: &Path) -> Result<ProjectSummary> {
+        let project = self.snapshot()?;
+        let json = serde_json::to_string_pretty(&project).context("JSON serialisering misslyckades")?;
+        std::fs::write(path, json).context("Kunde inte skriva fil")?;
+        Ok(project.summary())
+    }
+
+    /// Bygg upp projektet i en tom databas. Standardposter (dokumenttyper,
+    /// mallar, resurstyper) ersätts med filens. Allt sker i en transaktion,
+    /// så ett fel lämnar databasen orörd.
+    pub fn import_project(&self, project: &ProjectFile) -> Result<ProjectSummary> {
+        self.db.with_connection(|conn| {
+            let existing: i64 = conn.query_row(
+                "SELECT (SELECT COUNT(*) FROM persons) + (SELECT COUNT(*) FROM resources)",
+                [],
+                |row| row.get(0),
+            )?;
+            if existing > 0 {
+                bail!("Projektet innehåller redan personer eller resurser. Importera till ett nytt, tomt projekt.");
+            }
+
+            let tx = conn.unchecked_transaction()?;
+            write_project(&tx, project)?;
+            tx.commit()?;
+            Ok(project.summary())
+        })
+    }
+}
+
+/// Kör en fråga och samla raderna
+fn query<T>(conn: &Connection, sql: &str, f: impl FnMut(&Row) -> rusqlite::Result<T>) -> Result<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], f)?.collect::<rusqlite::Result<Vec<T>>>()?;
+    Ok(rows)
+}
+
+/// Egna fältvärden per post för fält av en viss typ
+fn custom_values(conn: &Connection, entity: CustomFieldEntity) -> Result<BTreeMap<i64, BTreeMap<i64, String>>> {
+    let mut stmt = conn.prepare(
+        "SELECT v.entity_id, v.field_id, v.value
+         FROM custom_field_values v
+         JOIN custom_field_definitions d ON d.id = v.field_id
+         WHERE d.entity = ?
+         ORDER BY v.entity_id, v.field_id",
+    )?;
+    let mut values: BTreeMap<i64, BTreeMap<i64, String>> = BTreeMap::new();
+    let rows = stmt.query_map([entity.as_db_str()], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+    })?;
+    for row in rows {
+        let (entity_id, field_id, value) = row?;
+        values.entry(entity_id).or_default().insert(field_id, value);
+    }
+    Ok(values)
+}
+
+fn relationship_key(rel: RelationshipType) -> &'static str {
+    match rel {
+        RelationshipType::Parent => "parent",
+        RelationshipType::Child => "child",
+        RelationshipType::Spouse => "spouse",
+        RelationshipType::Sibling => "sibling",
+    }
+}
+
+fn relationship_from_key(key: &str) -> Option<RelationshipType> {
+    RelationshipType::all().iter().copied().find(|r| relationship_key(*r) == key)
+}
+
+fn read_project(conn: &Connection) -> Result<ProjectFile> {
+    let config = conn
+        .query_row(
+            "SELECT media_directory_path, backup_directory_path, dir_name_format, living_threshold_years
+             FROM system_config WHERE id = 1",
+            [],
+            |row| {
+                Ok(ProjectConfig {
+                    media_directory: row.get(0)?,
+                    backup_directory: row.get(1)?,
+                    dir_name_format: row.get(2)?,
+                    living_threshold_years: row.get(3)?,
+                })
+            },
+        )
+        .ok();
+
+    let document_types = query(
+        conn,
+        "SELECT id, name, target_directory, default_filename, description FROM document_types ORDER BY id",
+        |row| {
+            Ok(DocumentTypeRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                target_directory: row.get(2)?,
+                default_filename: row.get(3)?,
+                description: row.get(4)?,
+            })
+        },
+    )?;
+
+    let directory_templates = query(conn, "SELECT id, name, description, directories FROM templates ORDER BY id", |row| {
+        let directories: String = row.get(3)?;
+        Ok(DirectoryTemplateRecord {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            directories: directories.lines().filter(|l| !l.trim().is_empty()).map(str::to_string).collect(),
+        })
+    })?;
+
+    let template_items = query(
+        conn,
+        "SELECT template_id, id, title, sort_order FROM checklist_template_items ORDER BY template_id, sort_order, id",
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                ChecklistTemplateItemRecord { id: row.get(1)?, title: row.get(2)?, sort_order: row.get(3)? },
+            ))
+        },
+    )?;
+    let mut checklist_templates = query(
+        conn,
+        "SELECT id, name, description, is_active FROM checklist_templates ORDER BY id",
+        |row| {
+            Ok(ChecklistTemplateRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                is_active: row.get(3)?,
+                items: Vec::new(),
+            })
+        },
+    )?;
+    for (template_id, item) in template_items {
+        if let Some(template) = checklist_templates.iter_mut().find(|t| t.id == template_id) {
+            template.items.push(item);
+        }
+    }
+
+    let tags = query(conn, "SELECT id, name, color, created_at FROM tags ORDER BY id", |row| {
+        Ok(TagRecord { id: row.get(0)?, name: row.get(1)?, color: row.get(2)?, created_at: row.get(3)? })
+    })?;
+
+    let custom_fields = query(
+        conn,
+        "SELECT id, entity, name, field_type, options, sort_order, created_at
+         FROM custom_field_definitions ORDER BY id",
+        |row| {
+            let options: String = row.get(4)?;
+            Ok(CustomFieldRecord {
+                id: row.get(0)?,
+                entity: row.get(1)?,
+                name: row.get(2)?,
+                field_type: row.get(3)?,
+                options: options.lines().filter(|l| !l.trim().is_empty()).map(str::to_string).collect(),
+                sort_order: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        },
+    )?;
+
+    let person_tags = query(conn, "SELECT person_id, tag_id FROM person_tags ORDER BY person_id, tag_id", |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+    })?;
+    let mut person_values = custom_values(conn, CustomFieldEntity::Person)?;
+    let mut persons = query(
+        conn,
+        "SELECT id, firstname, surname, sex, privacy, birth_date, birth_place, death_date, age, occupation,
+                gedcom_id, notes, directory_name, profile_image_path, created_at, updated_at
+         FROM persons ORDER BY id",
+        |row| {
+            Ok(PersonRecord {
+                id: row.get(0)?,
+                firstname: row.get(1)?,
+                surname: row.get(2)?,
+                sex: row.get(3)?,
+                privacy: row.get(4)?,
+                birth_date: row.get(5)?,
+                birth_place: row.get(6)?,
+                death_date: row.get(7)?,
+                age: row.get(8)?,
+                occupation: row.get(9)?,
+                gedcom_id: row.get(10)?,
+                notes: row.get(11)?,
+                directory_name: row.get(12)?,
+                profile_image_path: row.get(13)?,
+                tags: Vec::new(),
+                custom_fields: BTreeMap::new(),
+                created_at: row.get(14)?,
+                updated_at: row.get(15)?,
+            })
+        },
+    )?;
+    for person in &mut persons {
+        person.tags = person_tags.iter().filter(|(p, _)| *p == person.id).map(|(_, t)| *t).collect();
+        person.custom_fields = person_values.remove(&person.id).unwrap_or_default();
+    }
+
+    let bookmarks = query(conn, "SELECT person_id FROM bookmarked_persons ORDER BY person_id", |row| row.get(0))?;
+
+    let relationships = query(
+        conn,
+        "SELECT id, person_a_id, person_b_id, relationship_a_to_b, notes, marriage_date, marriage_place, created_at
+         FROM person_relationships ORDER BY id",
+        |row| {
+            let rel = RelationshipType::from_i32(row.get(3)?).unwrap_or(RelationshipType::Sibling);
+            Ok(RelationshipRecord {
+                id: row.get(0)?,
+                person_a: row.get(1)?,
+                person_b: row.get(2)?,
+                relationship_type: relationship_key(rel).to_string(),
+                notes: row.get(4)?,
+                marriage_date: row.get(5)?,
+                marriage_place: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        },
+    )?;
+
+    let documents = query(
+        conn,
+        "SELECT id, person_id, document_type_id, filename, relative_path, file_size, file_type, file_modified_at,
+                created_at, updated_at
+         FROM documents ORDER BY id",
+        |row| {
+            Ok(DocumentRecord {
+                id: row.get(0)?,
+                person_id: row.get(1)?,
+                document_type_id: row.get(2)?,
+                filename: row.get(3)?,
+                relative_path: row.get(4)?,
+                file_size: row.get(5)?,
+                file_type: row.get(6)?,
+                file_modified_at: row.get(7)?,
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
+            })
+        },
+    )?;
+
+    let checklist_items = query(
+        conn,
+        "SELECT id, person_id, template_item_id, title, sort_order, is_completed, completed_at
+         FROM person_checklist_items ORDER BY id",
+        |row| {
+            Ok(ChecklistItemRecord {
+                id: row.get(0)?,
+                person_id: row.get(1)?,
+                template_item_id: row.get(2)?,
+                title: row.get(3)?,
+                sort_order: row.get(4)?,
+                is_completed: row.get(5)?,
+                completed_at: row.get(6)?,
+            })
+        },
+    )?;
+
+    let resource_types = query(
+        conn,
+        "SELECT id, name, directory_name, created_at, updated_at FROM resource_types ORDER BY id",
+        |row| {
+            Ok(ResourceTypeRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                directory_name: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        },
+    )?;
+
+    let addresses = query(
+        conn,
+        "SELECT resource_id, id, street, postal_code, city, country, created_at
+         FROM resource_addresses ORDER BY resource_id, id",
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                AddressRecord {
+                    id: row.get(1)?,
+                    street: row.get(2)?,
+                    postal_code: row.get(3)?,
+                    city: row.get(4)?,
+                    country: row.get(5)?,
+                    created_at: row.get(6)?,
+                },
+            ))
+        },
+    )?;
+    let resource_documents = query(
+        conn,
+        "SELECT resource_id, id, document_type_id, filename, relative_path, file_size, file_type, file_modified_at,
+                created_at, updated_at
+         FROM resource_documents ORDER BY resource_id, id",
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                ResourceDocumentRecord {
+                    id: row.get(1)?,
+                    document_type_id: row.get(2)?,
+                    filename: row.get(3)?,
+                    relative_path: row.get(4)?,
+                    file_size: row.get(5)?,
+                    file_type: row.get(6)?,
+                    file_modified_at: row.get(7)?,
+                    created_at: row.get(8)?,
+                    updated_at: row.get(9)?,
+                },
+            ))
+        },
+    )?;
+    let mut resource_values = custom_values(conn, CustomFieldEntity::Resource)?;
+    let mut resources = query(
+        conn,
+        "SELECT id, resource_type_id, name, directory_name, information, comment, lat, lon, profile_image_path,
+                created_at, updated_at
+         FROM resources ORDER BY id",
+        |row| {
+            Ok(ResourceRecord {
+                id: row.get(0)?,
+                resource_type_id: row.get(1)?,
+                name: row.get(2)?,
+                directory_name: row.get(3)?,
+                information: row.get(4)?,
+                comment: row.get(5)?,
+                lat: row.get(6)?,
+                lon: row.get(7)?,
+                profile_image_path: row.get(8)?,
+                custom_fields: BTreeMap::new(),
+                addresses: Vec::new(),
+                documents: Vec::new(),
+                created_at: row.get(9)?,
+                updated_at: row.get(10)?,
+            })
+        },
+    )?;
+    for resource in &mut resources {
+        resource.custom_fields = resource_values.remove(&resource.id).unwrap_or_default();
+        resource.addresses = addresses.iter().filter(|(r, _)| *r == resource.id).map(|(_, a)| a.clone()).collect();
+        resource.documents =
+            resource_documents.iter().filter(|(r, _)| *r == resource.id).map(|(_, d)| d.clone()).collect();
+    }
+
+    let saved_searches = query(
+        conn,
+        "SELECT id, name, filter_json, created_at, updated_at FROM saved_searches ORDER BY id",
+        |row| {
+            let filter: String = row.get(2)?;
+            Ok(SavedSearchRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                filter: serde_json::from_str(&filter).unwrap_or(serde_json::Value::String(filter)),
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        },
+    )?;
+
+    let report_templates = query(
+        conn,
+        "SELECT id, name, description, output_format, body, created_at, updated_at FROM report_templates ORDER BY id",
+        |row| {
+            Ok(ReportTemplateRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                format: row.get(3)?,
+                body: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        },
+    )?;
+
+    Ok(ProjectFile {
+        format: PROJECT_FORMAT.to_string(),
+        version: PROJECT_VERSION,
+        exported_at: Some(Utc::now().to_rfc3339()),
+        app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        config,
+        document_types,
+        directory_templates,
+        checklist_templates,
+        tags,
+        custom_fields,
+        persons,
+        bookmarks,
+        relationships,
+        documents,
+        checklist_items,
+        resource_types,
+        resources,
+        saved_searches,
+        report_templates,
+    })
+}
+
+fn write_project(conn: &Connection, project: &ProjectFile) -> Result<()> {
+    // Standardposter från projektets första start ersätts av filens
+    conn.execute_batch(
+        "DELETE FROM person_checklist_items;
+         DELETE FROM checklist_templates;
+         DELETE FROM templates;
+         DELETE FROM document_types;
+         DELETE FROM resource_types;
+         DELETE FROM tags;
+         DELETE FROM custom_field_definitions;
+         DELETE FROM saved_searches;
+         DELETE FROM report_templates;",
+    )?;
+
+    if let Some(config) = &project.config {
+        let updated = conn.execute(
+            "UPDATE system_config SET dir_name_format = ?1, living_threshold_years = ?2, updated_at = datetime('now')
+             WHERE id = 1",
+            params![config.dir_name_format, config.living_threshold_years],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO system_config (id, media_directory_path, backup_directory_path, dir_name_format,
+                                            living_threshold_years)
+                 VALUES (1, ?1, ?2, ?3, ?4)",
+                params![
+                    config.media_directory,
+                    config.backup_directory,
+                    config.dir_name_format,
+                    config.living_threshold_years
+                ],
+            )?;
+        }
+    }
+
+    for t in &project.document_types {
+        conn.execute(
+            "INSERT INTO document_types (id, name, target_directory, default_filename, description)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![t.id, t.name, t.target_directory, t.default_filename, t.description],
+        )
+        .with_context(|| format!("Dokumenttyp {}", t.id))?;
+    }
+
+    for t in &project.directory_templates {
+        conn.execute(
+            "INSERT INTO templates (id, name, description, directories) VALUES (?1, ?2, ?3, ?4)",
+            params![t.id, t.name, t.description, t.directories.join("\n")],
+        )
+        .with_context(|| format!("Katalogmall {}", t.id))?;
+    }
+
+    for t in &project.checklist_templates {
+        conn.execute(
+            "INSERT INTO checklist_templates (id, name, description, is_active) VALUES (?1, ?2, ?3, ?4)",
+            params![t.id, t.name, t.description, t.is_active],
+        )
+        .with_context(|| format!("Checklistmall {}", t.id))?;
+        for item in &t.items {
+            conn.execute(
+                "INSERT INTO checklist_template_items (id, template_id, title, sort_order) VALUES (?1, ?2, ?3, ?4)",
+                params![item.id, t.id, item.title, item.sort_order],
+            )
+            .with_context(|| format!("Checklistmallpunkt {}", item.id))?;
+        }
+    }
+
+    for t in &project.tags {
+        conn.execute(
+            "INSERT INTO tags (id, name, color, created_at) VALUES (?1, ?2, ?3, COALESCE(?4, datetime('now')))",
+            params![t.id, t.name, t.color, t.created_at],
+        )
+        .with_context(|| format!("Tagg {}", t.id))?;
+    }
+
+    for f in &project.custom_fields {
+        conn.execute(
+            "INSERT INTO custom_field_definitions (id, entity, name, field_type, options, sort_order, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, COALESCE(?7, datetime('now')))",
+            params![
+                f.id,
+                CustomFieldEntity::from_db_str(&f.entity).as_db_str(),
+                f.name,
+                CustomFieldType::from_db_str(&f.field_type).as_db_str(),
+                f.options.join("\n"),
+                f.sort_order,
+                f.created_at
+            ],
+        )
+        .with_context(|| format!("Eget fält {}", f.id))?;
+    }
+
+    for p in &project.persons {
+        conn.execute(
+            "INSERT INTO persons (id, firstname, surname, sex, privacy, birth_date, birth_place, death_date, age,
+                                  occupation, gedcom_id, notes, directory_name, profile_image_path,
+                                  created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+                     COALESCE(?15, datetime('now')), COALESCE(?16, datetime('now')))",
+            params![
+                p.id,
+                p.firstname,
+                p.surname,
+                Sex::from_db_str(&p.sex).as_db_str(),
+                Privacy::from_db_str(&p.privacy).as_db_str(),
+                p.birth_date,
+                p.birth_place,
+                p.death_date,
+                p.age,
+                p.occupation,
+                p.gedcom_id,
+                p.notes,
+                p.directory_name,
+                p.profile_image_path,
+                p.created_at,
+                p.updated_at
+            ],
+        )
+        .with_context(|| format!("Person {}", p.id))?;
+        for tag_id in &p.tags {
+            conn.execute("INSERT INTO person_tags (person_id, tag_id) VALUES (?1, ?2)", params![p.id, tag_id])
+                .with_context(|| format!("Person {}: tagg {}", p.id, tag_id))?;
+        }
+        insert_custom_values(conn, p.id, &p.custom_fields).with_context(|| format!("Person {}: egna fält", p.id))?;
+    }
+
+    for person_id in &project.bookmarks {
+        conn.execute("INSERT INTO bookmarked_persons (person_id) VALUES (?1)", [person_id])
+            .with_context(|| format!("Bokmärke för person {}", person_id))?;
+    }
+
+    for r in &project.relationships {
+        let rel = relationship_from_key(&r.relationship_type)
+            .ok_or_else(|| anyhow!("Relation {}: okänd typ \"{}\"", r.id, r.relationship_type))?;
+        // Kanonisk ordning: lägst id först
+        let (a, b, a_to_b) = if r.person_a < r.person_b {
+            (r.person_a, r.person_b, rel)
+        } else {
+            (r.person_b, r.person_a, rel.reciprocal())
+        };
+        conn.execute(
+            "INSERT INTO person_relationships (id, person_a_id, person_b_id, relationship_a_to_b, relationship_b_to_a,
+                                               notes, marriage_date, marriage_place, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, COALESCE(?9, datetime('now')))",
+            params![
+                r.id,
+                a,
+                b,
+                a_to_b as i32,
+                a_to_b.reciprocal() as i32,
+                r.notes,
+                r.marriage_date,
+                r.marriage_place,
+                r.created_at
+            ],
+        )
+        .with_context(|| format!("Relation {}", r.id))?;
+    }
+
+    for d in &project.documents {
+        conn.execute(
+            "INSERT INTO documents (id, person_id, document_type_id, filename, relative_path, file_size, file_type,
+                                    file_modified_at, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, COALESCE(?9, datetime('now')), COALESCE(?10, datetime('now')))",
+            params![
+                d.id,
+                d.person_id,
+                d.document_type_id,
+                d.filename,
+                d.relative_path,
+                d.file_size,
+                d.file_type,
+                d.file_modified_at,
+                d.created_at,
+                d.updated_at
+            ],
+        )
+        .with_context(|| format!("Dokument {}", d.id))?;
+    }
+
+    for c in &project.checklist_items {
+        conn.execute(
+            "INSERT INTO person_checklist_items (id, person_id, template_item_id, title, sort_order, is_completed,
+                                                 completed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![c.id, c.person_id, c.template_item_id, c.title, c.sort_order, c.is_completed, c.completed_at],
+        )
+        .with_context(|| format!("Checklistpunkt {}", c.id))?;
+    }
+
+    for t in &project.resource_types {
+        conn.execute(
+            "INSERT INTO resource_types (id, name, directory_name, created_at, updated_at)
+             VALUES (?1, ?2, ?3, COALESCE(?4, datetime('now')), COALESCE(?5, datetime('now')))",
+            params![t.id, t.name, t.directory_name, t.created_at, t.updated_at],
+        )
+        .with_context(|| format!("Resurstyp {}", t.id))?;
+    }
+
+    for r in &project.resources {
+        conn.execute(
+            "INSERT INTO resources (id, resource_type_id, name, directory_name, information, comment, lat, lon,
+                                    profile_image_path, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, COALESCE(?10, datetime('now')), COALESCE(?11, datetime('now')))",
+            params![
+                r.id,
+                r.resource_type_id,
+                r.name,
+                r.directory_name,
+                r.information,
+                r.comment,
+                r.lat,
+                r.lon,
+                r.profile_image_path,
+                r.created_at,
+                r.updated_at
+            ],
+        )
+        .with_context(|| format!("Resurs {}", r.id))?;
+        insert_custom_values(conn, r.id, &r.custom_fields).with_context(|| format!("Resurs {}: egna fält", r.id))?;
+
+        for a in &r.addresses {
+            conn.execute(
+                "INSERT INTO resource_addresses (id, resource_id, street, postal_code, city, country, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, COALESCE(?7, datetime('now')))",
+                params![a.id, r.id, a.street, a.postal_code, a.city, a.country, a.created_at],
+            )
+            .with_context(|| format!("Resurs {}: adress {}", r.id, a.id))?;
+        }
+        for d in &r.documents {
+            conn.execute(
+                "INSERT INTO resource_documents (id, resource_id, document_type_id, filename, relative_path, file_size,
+                                                 file_type, file_modified_at, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, COALESCE(?9, datetime('now')), COALESCE(?10, datetime('now')))",
+                params![
+                    d.id,
+                    r.id,
+                    d.document_type_id,
+                    d.filename,
+                    d.relative_path,
+                    d.file_size,
+                    d.file_type,
+                    d.file_modified_at,
+                    d.created_at,
+                    d.updated_at
+                ],
+            )
+            .with_context(|| format!("Resurs {}: dokument {}", r.id, d.id))?;
+        }
+    }
+
+    for s in &project.saved_searches {
+        let filter = match &s.filter {
+            serde_json::Value::String(raw) => raw.clone(),
+            value => value.to_string(),
+        };
+        conn.execute(
+            "INSERT INTO saved_searches (id, name, filter_json, created_at, updated_at)
+             VALUES (?1, ?2, ?3, COALESCE(?4, datetime('now')), COALESCE(?5, datetime('now')))",
+            params![s.id, s.name, filter, s.created_at, s.updated_at],
+        )
+        .with_context(|| format!("Sparad sökning {}", s.id))?;
+    }
+
+    for t in &project.report_templates {
+        conn.execute(
+            "INSERT INTO report_templates (id, name, description, output_format, body, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, COALESCE(?6, datetime('now')), COALESCE(?7, datetime('now')))",
+            params![t.id, t.name, t.description, t.format, t.body, t.created_at, t.updated_at],
+        )
+        .with_context(|| format!("Rapportmall {}", t.id))?;
+    }
+
+    Ok(())
+}
+
+fn insert_custom_values(conn: &Connection, entity_id: i64, values: &BTreeMap<i64, String>) -> Result<()> {
+    for (field_id, value) in values {
+        conn.execute(
+            "INSERT INTO custom_field_values (field_id, entity_id, value) VALUES (?1, ?2, ?3)",
+            params![field_id, entity_id, value],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Person, PersonRelationship, Tag};
+
+    fn sample_db() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        let mut karl = Person::new(Some("Karl".into()), Some("Berg".into()), "karl_berg".into());
+        karl.birth_date = NaiveDate::from_ymd_opt(1875, 6, 3);
+        karl.privacy = Privacy::Public;
+        db.persons().create(&mut karl).unwrap();
+        let mut anna = Person::new(Some("Anna".into()), Some("Berg".into()), "anna_berg".into());
+        db.persons().create(&mut anna).unwrap();
+        let (karl, anna) = (karl.id.unwrap(), anna.id.unwrap());
+
+        // Sparas kanoniskt med lägst id först: Karl är förälder till Anna
+        let mut rel = PersonRelationship::new(anna, karl, RelationshipType::Child);
+        rel.notes = Some("Enligt husförhör".into());
+        db.relationships().create(&mut rel).unwrap();
+
+        let mut tag = Tag::new("Emigranter".into(), "#4A90D9".into());
+        db.tags().create(&mut tag).unwrap();
+        db.tags().add_to_person(tag.id.unwrap(), anna).unwrap();
+        db.persons().toggle_bookmark(karl).unwrap();
+
+        db.with_connection(|conn| {
+            conn.execute_batch(&format!(
+                "INSERT INTO custom_field_definitions (id, entity, name, field_type, options)
+                 VALUES (1, 'person', 'Församling', 'choice', 'Lund\nMalmö');
+                 INSERT INTO custom_field_values (field_id, entity_id, value) VALUES (1, {karl}, 'Lund');
+                 INSERT INTO documents (person_id, filename, relative_path, file_size)
+                 VALUES ({karl}, 'bild.jpg', 'bilder/bild.jpg', 1234);
+                 INSERT INTO resources (id, resource_type_id, name, directory_name, lat, lon)
+                 VALUES (5, (SELECT MIN(id) FROM resource_types), 'Gården', 'garden', 55.7, 13.2);
+                 INSERT INTO resource_addresses (resource_id, street, city) VALUES (5, 'Byvägen 1', 'Lund');
+                 INSERT INTO saved_searches (name, filter_json) VALUES ('Lund', '{{\"query\":\"Lund\"}}');"
+            ))?;
+            Ok(())
+        })
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = sample_db();
+        let exported = ProjectJsonService::new(&source).snapshot().unwrap();
+        assert_eq!(exported.persons.len(), 2);
+        assert_eq!(exported.relationships[0].relationship_type, "parent");
+        assert_eq!(exported.persons[0].custom_fields.get(&1).map(String::as_str), Some("Lund"));
+        assert_eq!(exported.resources[0].addresses.len(), 1);
+        assert_eq!(exported.saved_searches[0].filter["query"], "Lund");
+
+        let json = serde_json::to_string_pretty(&exported).unwrap();
+        let parsed = ProjectFile::from_json(&json).unwrap();
+        assert_eq!(parsed, exported);
+
+        let target = Database::open_in_memory().unwrap();
+        let summary = ProjectJsonService::new(&target).import_project(&parsed).unwrap();
+        assert_eq!(summary, ProjectSummary { persons: 2, relationships: 1, documents: 1, resources: 1 });
+
+        let mut imported = ProjectJsonService::new(&target).snapshot().unwrap();
+        imported.exported_at = exported.exported_at.clone();
+        // Sökvägarna följer inte med till ett projekt som redan har inställningar
+        imported.config = exported.config.clone();
+        assert_eq!(imported, exported);
+    }
+
+    #[test]
+    fn test_import_requires_empty_project() {
+        let source = sample_db();
+        let project = ProjectJsonService::new(&source).snapshot().unwrap();
+        let err = ProjectJsonService::new(&source).import_project(&project).unwrap_err();
+        assert!(err.to_string().contains("tomt projekt"));
+    }
+
+    #[test]
+    fn test_format_and_version_checks() {
+        assert!(ProjectFile::from_json("{\"format\":\"annat\",\"version\":1}").is_err());
+        assert!(ProjectFile::from_json("{\"format\":\"genlib-project\"}").is_err());
+        let newer = ProjectFile::from_json("{\"format\":\"genlib-project\",\"version\":99}").unwrap_err();
+        assert!(newer.to_string().contains("version 99"));
+
+        let minimal = ProjectFile::from_json("{\"format\":\"genlib-project\",\"version\":1}").unwrap();
+        assert!(minimal.persons.is_empty());
+
+        let mut bad = minimal.clone();
+        bad.persons.push(PersonRecord {
+            id: 1,
+            firstname: Some("Karl".into()),
+            surname: None,
+            sex: "M".into(),
+            privacy: "auto".into(),
+            birth_date: None,
+            birth_place: None,
+            death_date: None,
+            age: None,
+            occupation: None,
+            gedcom_id: None,
+            notes: None,
+            directory_name: "karl".into(),
+            profile_image_path: None,
+            tags: Vec::new(),
+            custom_fields: BTreeMap::new(),
+            created_at: None,
+            updated_at: None,
+        });
+        bad.relationships.push(RelationshipRecord {
+            id: 1,
+            person_a: 1,
+            person_b: 2,
+            relationship_type: "kusin".into(),
+            notes: None,
+            marriage_date: None,
+            marriage_place: None,
+            created_at: None,
+        });
+        let db = Database::open_in_memory().unwrap();
+        assert!(ProjectJsonService::new(&db).import_project(&bad).is_err());
+        // Transaktionen rullas tillbaka
+        assert_eq!(db.persons().find_all().unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,840 @@
+//! Egna rapportmallar
+//!
+//! Ett litet mallspråk i stil med Mustache över en dokumenterad datamodell:
+//! personer med relationer, händelser, dokument och checklista (se
+//! [`TEMPLATE_HELP`]). Mallarna sparas i projektet och skrivs ut som HTML,
+//! Markdown eller ren text. I HTML-mallar teckenkodas alla värden.
+//!
+//! Block (`{{#each}}`, `{{#if}}`, `{{else}}`, `{{/if}}` ...) som står ensamma
+//! på en rad tar bort hela raden, så att mallen kan skrivas luftigt utan
+//! tomrader i resultatet.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDate, Utc};
+use serde_json::{json, Value};
+
+use crate::db::{Database, KinshipGraph, ReportTemplate, TemplateFormat};
+use crate::models::{
+    CustomFieldDefinition, CustomFieldEntity, Document, Person, PersonChecklistItem, PersonRelationship,
+    RelationshipType, Tag,
+};
+use crate::services::chart_export::xml_escape;
+use crate::services::export::ReportOptions;
+use crate::services::timeline::kin_label;
+use crate::services::PrivacyPolicy;
+
+/// Hjälptext som visas bredvid mallredigeraren
+pub const TEMPLATE_HELP: &str = "\
+SYNTAX
+  {{ fält }}                 värdet (listor skrivs som namn åtskilda med komma)
+  {{ fält | filter }}        upper, lower, year, count, default:text, raw
+  {{#each lista}}…{{/each}}  upprepa för varje post; {{ @index }} är 1, 2, 3 …
+                             och {{ @first }}/{{ @last }} är sanna i kanterna
+  {{#if fält}}…{{else}}…{{/if}}   om fältet har ett värde (eller listan inte är tom)
+  {{#unless fält}}…{{/unless}}    om fältet saknas
+  {{! kommentar }}
+  Fält söks först i aktuell post och sedan utåt. {{ . }} är posten själv.
+  Punkt anger underfält och tal plats i en lista: {{ parents.0.full_name }}
+
+RAPPORT
+  title, generated_at, person_count, persons
+
+PERSON (i persons)
+  id, full_name, firstname, surname, sex, years, age, is_alive, is_private
+  birth_date, birth_year, birth_place, death_date, death_year
+  occupation, notes, gedcom_id
+  custom_fields   lista: name, value
+  tags            lista med taggnamn
+  parents, children, spouses, siblings
+                  lista: id, full_name, years, birth_date, death_date, is_private
+  relations       samma som ovan samt type (Far, Mor, Son, Maka …)
+  events          lista: type (birth, marriage, death), title, date, place, with
+  documents       lista: filename, type, path, size, is_image, is_pdf
+  checklist       lista: title, done, completed_at
+  checklist_done, checklist_total
+
+Levande och privata personer har bara id, full_name och is_private
+enligt vald maskering.";
+
+/// Utgångspunkt för en ny mall
+pub const EXAMPLE_TEMPLATE: &str = "\
+# {{ title }}
+
+Utskriven {{ generated_at }}, {{ person_count }} personer.
+
+{{#each persons}}
+## {{ @index }}. {{ full_name }} {{ years }}
+
+{{#if is_private}}
+*Uppgifterna visas inte.*
+{{else}}
+{{#each events}}
+- {{ title }}: {{ date | default:okänt datum }}{{#if place}} i {{ place }}{{/if}}{{#if with}} med {{ with }}{{/if}}
+{{/each}}
+{{#if occupation}}
+- Yrke: {{ occupation }}
+{{/if}}
+{{#if parents}}
+- Föräldrar: {{ parents }}
+{{/if}}
+{{#if documents}}
+- Källor: {{ documents | count }} dokument
+{{/if}}
+{{/if}}
+
+{{/each}}
+";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Upper,
+    Lower,
+    /// Årtalet ur ett datum
+    Year,
+    /// Antal poster i en lista
+    Count,
+    /// Text om värdet saknas
+    Default(String),
+    /// Ingen teckenkodning i HTML
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Each,
+    If,
+    Unless,
+}
+
+impl BlockKind {
+    fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Each => "each",
+            BlockKind::If => "if",
+            BlockKind::Unless => "unless",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Value { path: String, filters: Vec<Filter> },
+    Each { path: String, body: Vec<Node> },
+    If { path: String, negate: bool, then: Vec<Node>, otherwise: Vec<Node> },
+}
+
+/// Ett öppet block under tolkningen
+struct Block {
+    kind: BlockKind,
+    path: String,
+    line: usize,
+    then: Vec<Node>,
+    otherwise: Option<Vec<Node>>,
+}
+
+impl Block {
+    fn target(&mut self) -> &mut Vec<Node> {
+        self.otherwise.as_mut().unwrap_or(&mut self.then)
+    }
+
+    fn into_node(self) -> Node {
+        match self.kind {
+            BlockKind::Each => Node::Each { path: self.path, body: self.then },
+            BlockKind::If | BlockKind::Unless => Node::If {
+                path: self.path,
+                negate: self.kind == BlockKind::Unless,
+                then: self.then,
+                otherwise: self.otherwise.unwrap_or_default(),
+            },
+        }
+    }
+}
+
+enum Token {
+    Text(String),
+    Tag { content: String, line: usize },
+}
+
+/// En tolkad mall som kan fyllas med data
+#[derive(Debug, Clone)]
+pub struct ParsedTemplate {
+    nodes: Vec<Node>,
+}
+
+impl ParsedTemplate {
+    /// Tolka mallen. Felen anger radnummer.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut root = Vec::new();
+        let mut stack: Vec<Block> = Vec::new();
+
+        for token in tokenize(source)? {
+            let node = match token {
+                Token::Text(text) => Node::Text(text),
+                Token::Tag { content, line } => {
+                    if let Some(open) = content.strip_prefix('#') {
+                        let (name, path) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+                        let kind = match name {
+                            "each" => BlockKind::Each,
+                            "if" => BlockKind::If,
+                            "unless" => BlockKind::Unless,
+                            _ => bail!("Rad {}: okänt block {{{{#{}}}}}", line, name),
+                        };
+                        stack.push(Block {
+                            kind,
+                            path: parse_path(path.trim(), line)?,
+                            line,
+                            then: Vec::new(),
+                            otherwise: None,
+                        });
+                        continue;
+                    }
+                    if content == "else" {
+                        match stack.last_mut() {
+                            Some(block) if block.kind != BlockKind::Each && block.otherwise.is_none() => {
+                                block.otherwise = Some(Vec::new());
+                            }
+                            _ => bail!("Rad {}: {{{{else}}}} utanför {{{{#if}}}}", line),
+                        }
+                        continue;
+                    }
+                    if let Some(name) = content.strip_prefix('/') {
+                        let name = name.trim();
+                        let block = stack
+                            .pop()
+                            .ok_or_else(|| anyhow!("Rad {}: {{{{/{}}}}} saknar början", line, name))?;
+                        if block.kind.name() != name {
+                            bail!(
+                                "Rad {}: {{{{/{}}}}} avslutar {{{{#{}}}}} från rad {}",
+                                line,
+                                name,
+                                block.kind.name(),
+                                block.line
+                            );
+                        }
+                        block.into_node()
+                    } else {
+                        parse_value(&content, line)?
+                    }
+                }
+            };
+            match stack.last_mut() {
+                Some(block) => block.target().push(node),
+                None => root.push(node),
+            }
+        }
+
+        if let Some(block) = stack.pop() {
+            let name = block.kind.name();
+            bail!("Rad {}: {{{{#{}}}}} saknar {{{{/{}}}}}", block.line, name, name);
+        }
+        Ok(Self { nodes: root })
+    }
+
+    /// Fyll mallen med data. Värden teckenkodas i HTML-mallar.
+    pub fn render(&self, data: &Value, format: TemplateFormat) -> String {
+        let mut out = String::new();
+        let mut scopes = vec![Scope { value: data, index: None }];
+        render_nodes(&self.nodes, &mut scopes, format, &mut out);
+        out
+    }
+}
+
+fn is_block(content: &str) -> bool {
+    content.starts_with(['#', '/', '!']) || content == "else"
+}
+
+/// Dela upp mallen i text och taggar. Block som står ensamma på en rad tar
+/// med sig radens indrag och radbrytning.
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    let mut at_line_start = true;
+
+    while let Some(start) = rest.find("{{") {
+        let mut text = &rest[..start];
+        let tag_line = line + text.matches('\n').count();
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| anyhow!("Rad {}: {{{{ saknar avslutande }}}}", tag_line))?;
+        let content = after[..end].trim();
+        rest = &after[end + 2..];
+        line = tag_line;
+
+        let line_start = text.rfind('\n').map(|i| i + 1);
+        let standalone = is_block(content)
+            && (line_start.is_some() || at_line_start)
+            && text[line_start.unwrap_or(0)..].trim().is_empty()
+            && rest.split('\n').next().unwrap_or("").trim().is_empty();
+        if standalone {
+            text = &text[..line_start.unwrap_or(0)];
+            match rest.find('\n') {
+                Some(i) => {
+                    rest = &rest[i + 1..];
+                    line += 1;
+                }
+                None => rest = "",
+            }
+        }
+        at_line_start = standalone;
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(text.to_string()));
+        }
+        if !content.starts_with('!') {
+            tokens.push(Token::Tag { content: content.to_string(), line: tag_line });
+        }
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Fältnamn: `.`, `@index`, `@first`, `@last` eller namn med punkter
+fn parse_path(path: &str, line: usize) -> Result<String> {
+    let valid = matches!(path, "." | "@index" | "@first" | "@last")
+        || (!path.is_empty()
+            && path
+                .split('.')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_')));
+    if !valid {
+        bail!("Rad {}: ogiltigt fältnamn \"{}\"", line, path);
+    }
+    Ok(path.to_string())
+}
+
+fn parse_value(content: &str, line: usize) -> Result<Node> {
+    let mut parts = content.split('|');
+    let path = parse_path(parts.next().unwrap_or("").trim(), line)?;
+    let filters = parts
+        .map(|filter| {
+            let filter = filter.trim();
+            let (name, arg) = match filter.split_once(':') {
+                Some((name, arg)) => (name.trim(), Some(arg.trim())),
+                None => (filter, None),
+            };
+            Ok(match (name, arg) {
+                ("upper", None) => Filter::Upper,
+                ("lower", None) => Filter::Lower,
+                ("year", None) => Filter::Year,
+                ("count", None) => Filter::Count,
+                ("raw", None) => Filter::Raw,
+                ("default", Some(text)) => Filter::Default(text.to_string()),
+                _ => bail!("Rad {}: okänt filter \"{}\"", line, filter),
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Node::Value { path, filters })
+}
+
+/// Nivå i datat under utskriften; `index` är (position, antal) i `#each`
+struct Scope<'d> {
+    value: &'d Value,
+    index: Option<(usize, usize)>,
+}
+
+fn lookup<'d>(path: &str, scopes: &[Scope<'d>]) -> Cow<'d, Value> {
+    let index = || scopes.iter().rev().find_map(|s| s.index);
+    match path {
+        "." => Cow::Borrowed(scopes.last().map_or(&Value::Null, |s| s.value)),
+        "@index" => Cow::Owned(index().map_or(Value::Null, |(i, _)| json!(i + 1))),
+        "@first" => Cow::Owned(json!(index().is_some_and(|(i, _)| i == 0))),
+        "@last" => Cow::Owned(json!(index().is_some_and(|(i, n)| i + 1 == n))),
+        _ => {
+            let mut parts = path.split('.');
+            let first = parts.next().unwrap_or_default();
+            let Some(mut value) = scopes.iter().rev().find_map(|s| s.value.get(first)) else {
+                return Cow::Owned(Value::Null);
+            };
+            for part in parts {
+                let next = match part.parse::<usize>() {
+                    Ok(i) => value.get(i),
+                    Err(_) => value.get(part),
+                };
+                match next {
+                    Some(next) => value = next,
+                    None => return Cow::Owned(Value::Null),
+                }
+            }
+            Cow::Borrowed(value)
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.trim().is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Värdet som text. Listor blir namnen åtskilda med komma.
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(b) => if *b { "ja" } else { "nej" }.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(display)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Object(map) => ["full_name", "name", "title", "filename"]
+            .iter()
+            .find_map(|key| map.get(*key))
+            .map(display)
+            .unwrap_or_default(),
+    }
+}
+
+fn apply_filter<'d>(value: Cow<'d, Value>, filter: &Filter) -> Cow<'d, Value> {
+    let text = |f: &dyn Fn(String) -> String| Cow::Owned(Value::String(f(display(&value))));
+    match filter {
+        Filter::Upper => text(&|s| s.to_uppercase()),
+        Filter::Lower => text(&|s| s.to_lowercase()),
+        Filter::Year => text(&|s| {
+            if s.len() >= 4 && s.as_bytes()[..4].iter().all(u8::is_ascii_digit) {
+                s[..4].to_string()
+            } else {
+                s
+            }
+        }),
+        Filter::Count => Cow::Owned(json!(match value.as_ref() {
+            Value::Array(items) => items.len(),
+            Value::Object(map) => map.len(),
+            _ => 0,
+        })),
+        Filter::Default(default) if !truthy(&value) => Cow::Owned(Value::String(default.clone())),
+        Filter::Default(_) | Filter::Raw => value,
+    }
+}
+
+fn render_nodes<'d>(nodes: &[Node], scopes: &mut Vec<Scope<'d>>, format: TemplateFormat, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value { path, filters } => {
+                let value = filters.iter().fold(lookup(path, scopes), apply_filter);
+                let text = display(&value);
+                if format == TemplateFormat::Html && !filters.contains(&Filter::Raw) {
+                    out.push_str(&xml_escape(&text));
+                } else {
+                    out.push_str(&text);
+                }
+            }
+            Node::Each { path, body } => match lookup(path, scopes) {
+                Cow::Borrowed(Value::Array(items)) => {
+                    for (i, item) in items.iter().enumerate() {
+                        scopes.push(Scope { value: item, index: Some((i, items.len())) });
+                        render_nodes(body, scopes, format, out);
+                        scopes.pop();
+                    }
+                }
+                Cow::Borrowed(value) if truthy(value) => {
+                    scopes.push(Scope { value, index: None });
+                    render_nodes(body, scopes, format, out);
+                    scopes.pop();
+                }
+                _ => {}
+            },
+            Node::If { path, negate, then, otherwise } => {
+                let branch = if truthy(&lookup(path, scopes)) != *negate { then } else { otherwise };
+                render_nodes(branch, scopes, format, out);
+            }
+        }
+    }
+}
+
+/// Resultat av en export med egen mall
+#[derive(Debug)]
+pub struct TemplateReportResult {
+    pub person_count: usize,
+    pub file_size: usize,
+}
+
+impl TemplateReportResult {
+    pub fn summary(&self) -> String {
+        format!("Rapport exporterad: {} personer, {} bytes", self.person_count, self.file_size)
+    }
+}
+
+/// Uppslag som delas när datamodellen byggs
+struct ModelContext {
+    privacy: PrivacyPolicy,
+    graph: Arc<KinshipGraph>,
+    definitions: Vec<CustomFieldDefinition>,
+    custom_values: HashMap<i64, HashMap<i64, String>>,
+    document_types: HashMap<i64, String>,
+    tags: HashMap<i64, Vec<Tag>>,
+    /// Äktenskap med vigseluppgifter, per person
+    marriages: HashMap<i64, Vec<PersonRelationship>>,
+    documents: HashMap<i64, Vec<Document>>,
+    checklists: HashMap<i64, Vec<PersonChecklistItem>>,
+}
+
+fn date(d: Option<NaiveDate>) -> Value {
+    d.map_or(Value::Null, |d| json!(d.format("%Y-%m-%d").to_string()))
+}
+
+fn year(d: Option<NaiveDate>) -> Value {
+    d.map_or(Value::Null, |d| json!(d.format("%Y").to_string()))
+}
+
+/// Tjänst för rapporter med egna mallar
+pub struct ReportTemplateService<'a> {
+    db: &'a Database,
+    options: ReportOptions,
+}
+
+impl<'a> ReportTemplateService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self {
+            db,
+            options: ReportOptions::default(),
+        }
+    }
+
+    /// Urval och maskering som för övriga rapporter
+    pub fn with_options(mut self, options: ReportOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Fyll mallen, med högst `limit` personer (för förhandsgranskning)
+    pub fn render(&self, template: &ReportTemplate, limit: Option<usize>) -> Result<String> {
+        let parsed = ParsedTemplate::parse(&template.body)?;
+        let data = self.data_model(&template.name, limit)?;
+        Ok(parsed.render(&data, template.format))
+    }
+
+    /// Skriv rapporten till fil
+    pub fn export(&self, template: &ReportTemplate, path: &Path) -> Result<TemplateReportResult> {
+        let parsed = ParsedTemplate::parse(&template.body)?;
+        let data = self.data_model(&template.name, None)?;
+        let content = parsed.render(&data, template.format);
+        std::fs::write(path, &content).context("Kunde inte skriva fil")?;
+
+        Ok(TemplateReportResult {
+            person_count: data["persons"].as_array().map_or(0, Vec::len),
+            file_size: content.len(),
+        })
+    }
+
+    /// Datamodellen som mallen fylls med, se [`TEMPLATE_HELP`]
+    pub fn data_model(&self, title: &str, limit: Option<usize>) -> Result<Value> {
+        let persons = match self.options.person_filter {
+            Some(ref filter) => self.db.persons().advanced_search(filter)?,
+            None => self.db.persons().find_all()?,
+        };
+        let custom_fields = self.db.custom_fields();
+        let mut marriages: HashMap<i64, Vec<PersonRelationship>> = HashMap::new();
+        for rel in self.db.relationships().find_all()? {
+            if rel.relationship_a_to_b == RelationshipType::Spouse
+                && (rel.marriage_date.is_some() || rel.marriage_place.is_some())
+            {
+                marriages.entry(rel.person_a_id).or_default().push(rel.clone());
+                marriages.entry(rel.person_b_id).or_default().push(rel);
+            }
+        }
+        let ctx = ModelContext {
+            privacy: PrivacyPolicy::from_config(self.db, self.options.redaction)?,
+            graph: self.db.kinship()?,
+            definitions: custom_fields.find_definitions(CustomFieldEntity::Person)?,
+            custom_values: custom_fields.get_all_values(CustomFieldEntity::Person)?,
+            document_types: self
+                .db
+                .documents()
+                .get_all_types()?
+                .into_iter()
+                .filter_map(|t| Some((t.id?, t.name)))
+                .collect(),
+            tags: self.db.tags().find_all_by_person()?,
+            marriages,
+            documents: self.db.documents().find_all_by_person()?,
+            checklists: self.db.checklists().find_all_by_person()?,
+        };
+
+        let persons: Vec<Value> = persons
+            .iter()
+            .filter(|p| ctx.privacy.redact(p).is_some())
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|p| self.person_value(&ctx, p))
+            .collect::<Result<_>>()?;
+
+        Ok(json!({
+            "title": title,
+            "generated_at": Utc::now().format("%Y-%m-%d %H:%M").to_string(),
+            "person_count": persons.len(),
+            "persons": persons,
+        }))
+    }
+
+    fn person_value(&self, ctx: &ModelContext, person: &Person) -> Result<Value> {
+        let id = person.id.unwrap_or(0);
+        if ctx.privacy.masks(person) {
+            let shown = ctx.privacy.redact(person).unwrap_or_default();
+            return Ok(json!({ "id": id, "full_name": shown.full_name(), "is_private": true }));
+        }
+
+        let custom_fields: Vec<Value> = ctx
+            .custom_values
+            .get(&id)
+            .map(|values| {
+                ctx.definitions
+                    .iter()
+                    .filter_map(|d| {
+                        let value = values.get(&d.id?)?.trim();
+                        (!value.is_empty()).then(|| json!({ "name": d.name, "value": value }))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let tags: Vec<&str> = ctx.tags.get(&id).into_iter().flatten().map(|t| t.name.as_str()).collect();
+
+        let kin = [
+            (RelationshipType::Parent, ctx.graph.parents(id)),
+            (RelationshipType::Spouse, ctx.graph.spouses(id)),
+            (RelationshipType::Sibling, ctx.graph.siblings(id)),
+            (RelationshipType::Child, ctx.graph.children(id)),
+        ];
+        let mut relations = Vec::new();
+        let mut by_type: HashMap<RelationshipType, Vec<Value>> = HashMap::new();
+        for (relation, ids) in kin {
+            for relative_id in ids {
+                let Some((value, shown)) = self.relative(ctx, *relative_id) else {
+                    continue;
+                };
+                let mut typed = value.clone();
+                typed["type"] = json!(kin_label(relation, shown.sex));
+                relations.push(typed);
+                by_type.entry(relation).or_default().push(value);
+            }
+        }
+        let mut kin_list = |relation| Value::Array(by_type.remove(&relation).unwrap_or_default());
+
+        let mut events = Vec::new();
+        if person.birth_date.is_some() || person.birth_place.is_some() {
+            events.push(json!({
+                "type": "birth",
+                "title": "Födelse",
+                "date": date(person.birth_date),
+                "place": person.birth_place,
+            }));
+        }
+        let mut marriages: Vec<&PersonRelationship> = ctx.marriages.get(&id).into_iter().flatten().collect();
+        marriages.sort_by_key(|r| r.marriage_date);
+        for marriage in marriages {
+            let with = marriage
+                .get_other_person_id(id)
+                .and_then(|other| self.relative(ctx, other))
+                .map(|(_, shown)| shown.full_name());
+            events.push(json!({
+                "type": "marriage",
+                "title": "Vigsel",
+                "date": date(marriage.marriage_date),
+                "place": marriage.marriage_place,
+                "with": with,
+            }));
+        }
+        if person.death_date.is_some() {
+            events.push(json!({ "type": "death", "title": "Död", "date": date(person.death_date) }));
+        }
+
+        let documents: Vec<Value> = ctx
+            .documents
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(|d| {
+                let doc_type = d.document_type_id.and_then(|t| ctx.document_types.get(&t));
+                json!({
+                    "filename": d.filename,
+                    "type": doc_type.map_or("Övrigt", String::as_str),
+                    "path": d.relative_path,
+                    "size": d.file_size_display(),
+                    "is_image": d.is_image(),
+                    "is_pdf": d.is_pdf(),
+                })
+            })
+            .collect();
+
+        let checklist = ctx.checklists.get(&id).map_or(&[][..], Vec::as_slice);
+        let checklist_done = checklist.iter().filter(|i| i.is_completed).count();
+        let checklist: Vec<Value> = checklist
+            .iter()
+            .map(|i| json!({ "title": i.title, "done": i.is_completed, "completed_at": i.completed_at }))
+            .collect();
+
+        Ok(json!({
+            "id": id,
+            "full_name": person.full_name(),
+            "firstname": person.firstname,
+            "surname": person.surname,
+            "sex": person.sex.display_name(),
+            "years": person.years_display(),
+            "age": person.age,
+            "is_alive": person.is_alive(),
+            "is_private": false,
+            "birth_date": date(person.birth_date),
+            "birth_year": year(person.birth_date),
+            "birth_place": person.birth_place,
+            "death_date": date(person.death_date),
+            "death_year": year(person.death_date),
+            "occupation": person.occupation,
+            "notes": person.notes,
+            "gedcom_id": person.gedcom_id,
+            "custom_fields": custom_fields,
+            "tags": tags,
+            "parents": kin_list(RelationshipType::Parent),
+            "children": kin_list(RelationshipType::Child),
+            "spouses": kin_list(RelationshipType::Spouse),
+            "siblings": kin_list(RelationshipType::Sibling),
+            "relations": relations,
+            "events": events,
+            "documents": documents,
+            "checklist": checklist,
+            "checklist_done": checklist_done,
+            "checklist_total": checklist.len(),
+        }))
+    }
+
+    /// En släkting som den ska visas, eller `None` om den utelämnas
+    fn relative(&self, ctx: &ModelContext, id: i64) -> Option<(Value, Person)> {
+        let person = ctx.graph.person(id)?;
+        let shown = ctx.privacy.redact(person)?;
+        let value = json!({
+            "id": id,
+            "full_name": shown.full_name(),
+            "years": shown.years_display(),
+            "birth_date": date(shown.birth_date),
+            "death_date": date(shown.death_date),
+            "is_private": ctx.privacy.masks(person),
+        });
+        Some((value, shown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, PersonRelationship, Sex};
+    use crate::services::RedactionStyle;
+
+    fn render(source: &str, data: Value, format: TemplateFormat) -> String {
+        ParsedTemplate::parse(source).unwrap().render(&data, format)
+    }
+
+    #[test]
+    fn test_template_syntax() {
+        let data = json!({
+            "title": "Lista",
+            "persons": [
+                { "full_name": "Anna <Berg>", "birth_date": "1850-03-01", "tags": ["Soldat", "Torp"] },
+                { "full_name": "Karl", "birth_date": null, "tags": [] },
+            ],
+        });
+        let source = "\
+{{ title | upper }}
+{{#each persons}}
+  {{! rad per person }}
+  {{ @index }}. {{ full_name }} {{ birth_date | year | default:? }}{{#if @last}}.{{else}},{{/if}}
+  {{#unless tags}}
+  (inga taggar)
+  {{/unless}}
+{{/each}}
+Totalt {{ persons | count }} ({{ persons }})";
+        assert_eq!(
+            render(source, data.clone(), TemplateFormat::Text),
+            "LISTA\n  1. Anna <Berg> 1850,\n  2. Karl ?.\n  (inga taggar)\nTotalt 2 (Anna <Berg>, Karl)"
+        );
+        assert_eq!(
+            render("{{#each persons}}{{ full_name }}|{{ tags }}|{{ title }};{{/each}}", data.clone(), TemplateFormat::Html),
+            "Anna &lt;Berg&gt;|Soldat, Torp|Lista;Karl||Lista;"
+        );
+        assert_eq!(render("{{ persons.0 | raw }}", data, TemplateFormat::Html), "Anna <Berg>");
+
+        let error = |source: &str| ParsedTemplate::parse(source).unwrap_err().to_string();
+        assert_eq!(error("a\n{{#if x}}\nb"), "Rad 2: {{#if}} saknar {{/if}}");
+        assert_eq!(error("{{#each x}}\n{{/if}}"), "Rad 2: {{/if}} avslutar {{#each}} från rad 1");
+        assert_eq!(error("\n\n{{ namn | fet }}"), "Rad 3: okänt filter \"fet\"");
+        assert_eq!(error("{{ full name }}"), "Rad 1: ogiltigt fältnamn \"full name\"");
+        assert_eq!(error("{{#each x}}{{else}}{{/each}}"), "Rad 1: {{else}} utanför {{#if}}");
+        assert!(ParsedTemplate::parse(EXAMPLE_TEMPLATE).is_ok());
+    }
+
+    #[test]
+    fn test_data_model_and_export() {
+        let db = Database::open_in_memory().unwrap();
+        let add = |first: &str, birth: Option<(i32, u32, u32)>, sex: Sex| {
+            let mut p = Person::new(Some(first.into()), Some("Berg".into()), first.to_lowercase());
+            p.birth_date = birth.and_then(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d));
+            p.death_date = birth.and_then(|(y, _, _)| NaiveDate::from_ymd_opt(y + 70, 1, 1));
+            p.sex = sex;
+            db.persons().create(&mut p).unwrap();
+            p.id.unwrap()
+        };
+        let karl = add("Karl", Some((1820, 5, 1)), Sex::Male);
+        let anna = add("Anna", Some((1825, 2, 2)), Sex::Female);
+        let lisa = add("Lisa", None, Sex::Female);
+        let mut marriage = PersonRelationship::new(karl, anna, RelationshipType::Spouse);
+        marriage.marriage_date = NaiveDate::from_ymd_opt(1845, 6, 1);
+        marriage.marriage_place = Some("Berga".into());
+        db.relationships().create(&mut marriage).unwrap();
+        db.relationships().create(&mut PersonRelationship::new(karl, lisa, RelationshipType::Parent)).unwrap();
+        db.documents().create(&mut Document::new(karl, "bouppteckning.pdf".into(), "bouppteckning.pdf".into())).unwrap();
+        let tag_id = db.tags().create(&mut Tag::new("Soldat".into(), "#4A90D9".into())).unwrap();
+        db.tags().add_to_person(tag_id, karl).unwrap();
+        let mut item = PersonChecklistItem::new(karl, "Sök husförhör".into());
+        db.checklists().create(&mut item).unwrap();
+        db.checklists().toggle_completed(item.id.unwrap()).unwrap();
+        db.checklists().create(&mut PersonChecklistItem::new(anna, "Sök vigsel".into())).unwrap();
+
+        let template = ReportTemplate::new(
+            "Test".into(),
+            TemplateFormat::Markdown,
+            "{{#each persons}}\n{{ full_name }}: {{#each events}}{{ title }} {{ date }}{{#if place}} i {{ place }}{{/if}}{{#if with}} med {{ with }}{{/if}}; {{/each}}barn {{ children }}, {{ documents | count }} dok.\n{{/each}}".into(),
+        );
+        let service = ReportTemplateService::new(&db);
+        let text = service.render(&template, None).unwrap();
+        assert!(text.contains(
+            "Karl Berg: Födelse 1820-05-01; Vigsel 1845-06-01 i Berga med Anna Berg; Död 1890-01-01; barn Privat, 1 dok."
+        ));
+        assert!(text.contains("Privat: barn , 0 dok."));
+
+        let model = service.data_model("Test", Some(1)).unwrap();
+        assert_eq!(model["persons"].as_array().unwrap().len(), 1);
+        let model = service.data_model("Test", None).unwrap();
+        let karl_model = model["persons"].as_array().unwrap().iter().find(|p| p["id"] == karl).unwrap();
+        assert_eq!(karl_model["relations"][0]["type"], "Maka");
+        assert_eq!(karl_model["birth_year"], "1820");
+        assert_eq!(karl_model["tags"], json!(["Soldat"]));
+        assert_eq!((karl_model["checklist_done"].clone(), karl_model["checklist_total"].clone()), (json!(1), json!(1)));
+        let anna_model = model["persons"].as_array().unwrap().iter().find(|p| p["id"] == anna).unwrap();
+        assert_eq!(anna_model["events"][1]["with"], "Karl Berg");
+        assert_eq!(anna_model["checklist"][0]["done"], false);
+        assert_eq!(anna_model["tags"], json!([]));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rapport.md");
+        let options = ReportOptions { redaction: RedactionStyle::Omit, ..Default::default() };
+        let result = ReportTemplateService::new(&db).with_options(options).export(&template, &path).unwrap();
+        assert_eq!(result.person_count, 2);
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("barn , 1 dok."));
+        assert!(!written.contains("Lisa"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Forskningsluckor - vad saknas hos en persons förfäder?
+//!
+//! Används som underlag för forskningsplanering: listar förfäder (och
+//! utgångspersonen själv) som saknar grundläggande uppgifter.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::models::{DocumentType, Person};
+use crate::services::{FamilyTreeService, PrivacyPolicy, RedactionStyle};
+
+/// Typ av saknad uppgift
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GapKind {
+    BirthDate,
+    DeathDate,
+    BirthPlace,
+    Parents,
+    Sources,
+    Document,
+}
+
+impl GapKind {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            GapKind::BirthDate => "Födelsedatum",
+            GapKind::DeathDate => "Dödsdatum",
+            GapKind::BirthPlace => "Födelseort",
+            GapKind::Parents => "Föräldrar",
+            GapKind::Sources => "Källor",
+            GapKind::Document => "Dokument",
+        }
+    }
+}
+
+/// En person med en eller flera luckor
+#[derive(Debug, Clone)]
+pub struct ResearchGap {
+    pub person: Person,
+    /// Generationsavstånd från utgångspersonen (0 = personen själv)
+    pub generation: usize,
+    pub missing: Vec<GapKind>,
+}
+
+impl ResearchGap {
+    /// Namn på saknade uppgifter (dokumenttypens namn används om den anges)
+    pub fn missing_labels(&self, document_type: Option<&DocumentType>) -> Vec<String> {
+        self.missing
+            .iter()
+            .map(|kind| match (kind, document_type) {
+                (GapKind::Document, Some(dt)) => dt.name.clone(),
+                _ => kind.display_name().to_string(),
+            })
+            .collect()
+    }
+
+    /// Beskrivning av saknade uppgifter, t.ex. "Födelseort; Dödsattest"
+    pub fn missing_display(&self, document_type: Option<&DocumentType>) -> String {
+        self.missing_labels(document_type).join("; ")
+    }
+}
+
+/// Tjänst för att hitta forskningsluckor
+pub struct ResearchGapsService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ResearchGapsService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Hitta luckor hos en person och dess förfäder, sorterat på generationsavstånd.
+    /// Om `document_type_id` anges räknas avsaknad av dokument av den typen som en lucka.
+    pub fn find_gaps(
+        &self,
+        root_person_id: i64,
+        max_generations: usize,
+        document_type_id: Option<i64>,
+    ) -> Result<Vec<ResearchGap>> {
+        let mut candidates = vec![(root_person_id, 0)];
+        candidates.extend(FamilyTreeService::new(self.db).ancestors(root_person_id, max_generations)?);
+
+        let graph = self.db.kinship()?;
+        // Samma gräns för levande som sekretessinställningen
+        let policy = PrivacyPolicy::from_config(self.db, RedactionStyle::None)?;
+        let mut gaps = Vec::new();
+
+        for (person_id, generation) in candidates {
+            let Some(person) = graph.person(person_id).cloned() else {
+                continue;
+            };
+
+            // Utan födelsedatum går det inte att avgöra, och dödsdatum räknas
+            // då som en lucka
+            let presumed_living = person.birth_date.is_some() && policy.is_presumed_living(&person);
+
+            let mut missing = Vec::new();
+
+            if person.birth_date.is_none() {
+                missing.push(GapKind::BirthDate);
+            }
+            if person.death_date.is_none() && !presumed_living {
+                missing.push(GapKind::DeathDate);
+            }
+            if person.birth_place.as_deref().map_or(true, |s| s.trim().is_empty()) {
+                missing.push(GapKind::BirthPlace);
+            }
+            if graph.parents(person_id).len() < 2 {
+                missing.push(GapKind::Parents);
+            }
+
+            let documents = self.db.documents().find_by_person(person_id)?;
+            if documents.is_empty() {
+                missing.push(GapKind::Sources);
+            }
+            if let Some(type_id) = document_type_id {
+                if !documents.iter().any(|d| d.document_type_id == Some(type_id)) {
+                    missing.push(GapKind::Document);
+                }
+            }
+
+            if !missing.is_empty() {
+                gaps.push(ResearchGap {
+                    person,
+                    generation,
+                    missing,
+                });
+            }
+        }
+
+        // ancestors() är redan sorterad, men håll ordningen explicit
+        gaps.sort_by_key(|g| (g.generation, g.person.id));
+
+        Ok(gaps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, PersonRelationship, RelationshipType};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_find_gaps_sorted_by_generation() {
+        let db = Database::open_in_memory().unwrap();
+
+        let mut child = Person::new(Some("Anna".into()), Some("Berg".into()), "anna".into());
+        child.birth_date = NaiveDate::from_ymd_opt(1990, 1, 1);
+        child.birth_place = Some("Uppsala".into());
+        db.persons().create(&mut child).unwrap();
+
+        let mut father = Person::new(Some("Karl".into()), Some("Berg".into()), "karl".into());
+        father.birth_date = NaiveDate::from_ymd_opt(1890, 5, 1);
+        db.persons().create(&mut father).unwrap();
+
+        let mut grandfather = Person::new(Some("Johan".into()), Some("Berg".into()), "johan".into());
+        db.persons().create(&mut grandfather).unwrap();
+
+        let child_id = child.id.unwrap();
+        let father_id = father.id.unwrap();
+        let grandfather_id = grandfather.id.unwrap();
+
+        let mut rel = PersonRelationship::new(father_id, child_id, RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+        let mut rel = PersonRelationship::new(grandfather_id, father_id, RelationshipType::Parent);
+        db.relationships().create(&mut rel).unwrap();
+
+        let death_cert = db
+            .documents()
+            .get_all_types()
+            .unwrap()
+            .into_iter()
+            .find(|t| t.name == "Dödsattest")
+            .unwrap();
+        let mut doc = Document::new(father_id, "dod.pdf".into(), "dokument/dod.pdf".into());
+        doc.document_type_id = death_cert.id;
+        db.documents().create(&mut doc).unwrap();
+
+        let gaps = ResearchGapsService::new(&db)
+            .find_gaps(child_id, 5, death_cert.id)
+            .unwrap();
+
+        let order: Vec<(i64, usize)> = gaps
+            .iter()
+            .map(|g| (g.person.id.unwrap(), g.generation))
+            .collect();
+        assert_eq!(order, vec![(child_id, 0), (father_id, 1), (grandfather_id, 2)]);
+
+        // Barnet antas leva - saknat dödsdatum räknas inte som lucka
+        assert!(!gaps[0].missing.contains(&GapKind::DeathDate));
+        assert!(gaps[0].missing.contains(&GapKind::Parents));
+
+        // Fadern har dödsattest men saknar dödsdatum och födelseort
+        assert!(gaps[1].missing.contains(&GapKind::DeathDate));
+        assert!(gaps[1].missing.contains(&GapKind::BirthPlace));
+        assert!(!gaps[1].missing.contains(&GapKind::Document));
+        assert!(!gaps[1].missing.contains(&GapKind::Sources));
+
+        // Farfadern saknar allt
+        assert!(gaps[2].missing.contains(&GapKind::BirthDate));
+        assert!(gaps[2].missing.contains(&GapKind::Document));
+        assert_eq!(gaps[2].missing_display(Some(&death_cert)).matches("Dödsattest").count(), 1);
+
+        // Gränsen för levande följer inställningen
+        let mut config = db.config().get().unwrap();
+        config.living_threshold_years = 30;
+        db.config().save(&config).unwrap();
+        let gaps = ResearchGapsService::new(&db).find_gaps(child_id, 5, death_cert.id).unwrap();
+        assert!(gaps[0].missing.contains(&GapKind::DeathDate));
+    }
+}
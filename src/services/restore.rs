@@ -103,6 +103,7 @@ impl<'a> RestoreService<'a> {
                 // Skriv ny databas
                 let mut outfile = File::create(&db_path)?;
                 io::copy(&mut file, &mut outfile)?;
+                self.db.invalidate_kinship();
                 database_restored = true;
                 files_restored += 1;
             }
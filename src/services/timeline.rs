@@ -0,0 +1,403 @@
+//! Tidslinje för en person och dess familj
+//!
+//! Samlar daterade händelser: personens födelse, vigslar och död, samma
+//! händelser för föräldrar, syskon, makar och barn, samt daterade dokument
+//! (EXIF-datum för bilder, annars filens ändringsdatum). Varje händelse får
+//! personens ålder vid tillfället. Motsägelser och långa luckor listas
+//! separat så att de syns direkt i vyn.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+
+use crate::db::{Database, KinshipGraph};
+use crate::models::{Person, RelationshipType, Sex};
+use crate::utils::exif::ExifData;
+
+/// Minsta rimliga ålder för att gifta sig eller få barn
+const MIN_PARENT_AGE: i32 = 13;
+/// Luckor längre än så här (år) under personens liv rapporteras
+const GAP_YEARS: i32 = 20;
+
+/// Rad (band) i tidslinjen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimelineLane {
+    /// Personen själv och makar
+    Person,
+    Parents,
+    Siblings,
+    Children,
+    Documents,
+}
+
+impl TimelineLane {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TimelineLane::Person => "Personen",
+            TimelineLane::Parents => "Föräldrar",
+            TimelineLane::Siblings => "Syskon",
+            TimelineLane::Children => "Barn",
+            TimelineLane::Documents => "Dokument",
+        }
+    }
+
+    pub fn all() -> &'static [TimelineLane] {
+        &[
+            TimelineLane::Parents,
+            TimelineLane::Person,
+            TimelineLane::Siblings,
+            TimelineLane::Children,
+            TimelineLane::Documents,
+        ]
+    }
+}
+
+/// Typ av händelse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEventKind {
+    Birth,
+    Marriage,
+    Death,
+    Document,
+}
+
+/// En daterad händelse
+#[derive(Debug, Clone)]
+pub struct TimelineEvent {
+    pub date: NaiveDate,
+    pub kind: TimelineEventKind,
+    pub lane: TimelineLane,
+    /// T.ex. "Far Karl Berg föds"
+    pub title: String,
+    pub place: Option<String>,
+    /// Personen som händelsen gäller
+    pub person_id: Option<i64>,
+    pub document_id: Option<i64>,
+    /// Huvudpersonens ålder vid händelsen
+    pub age: Option<i32>,
+}
+
+/// Tidslinje för en person
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    pub person: Person,
+    /// Sorterade på datum
+    pub events: Vec<TimelineEvent>,
+    /// Motsägelser och luckor, i klartext
+    pub issues: Vec<String>,
+}
+
+impl Timeline {
+    /// Första och sista datum i tidslinjen
+    pub fn span(&self) -> Option<(NaiveDate, NaiveDate)> {
+        Some((self.events.first()?.date, self.events.last()?.date))
+    }
+}
+
+/// Fyllda år vid ett datum
+pub fn age_at(birth: NaiveDate, date: NaiveDate) -> i32 {
+    let mut age = date.year() - birth.year();
+    if (date.month(), date.day()) < (birth.month(), birth.day()) {
+        age -= 1;
+    }
+    age
+}
+
+/// Benämning på en släkting efter relation och kön
+pub(crate) fn kin_label(relation: RelationshipType, sex: Sex) -> &'static str {
+    match (relation, sex) {
+        (RelationshipType::Parent, Sex::Male) => "Far",
+        (RelationshipType::Parent, Sex::Female) => "Mor",
+        (RelationshipType::Parent, Sex::Unknown) => "Förälder",
+        (RelationshipType::Child, Sex::Male) => "Son",
+        (RelationshipType::Child, Sex::Female) => "Dotter",
+        (RelationshipType::Child, Sex::Unknown) => "Barn",
+        (RelationshipType::Spouse, Sex::Male) => "Make",
+        (RelationshipType::Spouse, Sex::Female) => "Maka",
+        (RelationshipType::Spouse, Sex::Unknown) => "Make/maka",
+        (RelationshipType::Sibling, Sex::Male) => "Bror",
+        (RelationshipType::Sibling, Sex::Female) => "Syster",
+        (RelationshipType::Sibling, Sex::Unknown) => "Syskon",
+    }
+}
+
+/// Tjänst för att bygga tidslinjer
+pub struct TimelineService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TimelineService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Bygg tidslinjen för en person
+    pub fn build(&self, person_id: i64) -> Result<Timeline> {
+        let graph = self.db.kinship()?;
+        let person = graph
+            .person(person_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Person hittades inte"))?;
+
+        let mut events = Vec::new();
+        let mut push = |date: Option<NaiveDate>,
+                        kind: TimelineEventKind,
+                        lane: TimelineLane,
+                        title: String,
+                        place: Option<String>,
+                        person_id: Option<i64>| {
+            if let Some(date) = date {
+                events.push(TimelineEvent {
+                    date,
+                    kind,
+                    lane,
+                    title,
+                    place: place.filter(|p| !p.trim().is_empty()),
+                    person_id,
+                    document_id: None,
+                    age: None,
+                });
+            }
+        };
+
+        // Personen själv
+        push(person.birth_date, TimelineEventKind::Birth, TimelineLane::Person, "Föds".into(), person.birth_place.clone(), Some(person_id));
+        push(person.death_date, TimelineEventKind::Death, TimelineLane::Person, "Dör".into(), None, Some(person_id));
+
+        // Släktingar: födelse och död
+        let relatives = [
+            (RelationshipType::Parent, TimelineLane::Parents, graph.parents(person_id)),
+            (RelationshipType::Spouse, TimelineLane::Person, graph.spouses(person_id)),
+            (RelationshipType::Sibling, TimelineLane::Siblings, graph.siblings(person_id)),
+            (RelationshipType::Child, TimelineLane::Children, graph.children(person_id)),
+        ];
+        for (relation, lane, ids) in relatives {
+            for relative in ids.iter().filter_map(|id| graph.person(*id)) {
+                let name = format!("{} {}", kin_label(relation, relative.sex), relative.full_name());
+                // Makens födelse säger lite om personen och tar plats i bandet
+                if relation != RelationshipType::Spouse {
+                    push(relative.birth_date, TimelineEventKind::Birth, lane, format!("{} föds", name), relative.birth_place.clone(), relative.id);
+                }
+                push(relative.death_date, TimelineEventKind::Death, lane, format!("{} dör", name), None, relative.id);
+            }
+        }
+
+        // Vigslar: personens egna, föräldrarnas och barnens
+        let mut married_pairs = Vec::new();
+        for (owner, lane) in std::iter::once((person_id, TimelineLane::Person))
+            .chain(graph.parents(person_id).iter().map(|id| (*id, TimelineLane::Parents)))
+            .chain(graph.children(person_id).iter().map(|id| (*id, TimelineLane::Children)))
+        {
+            for rel in self.db.relationships().find_by_person(owner)? {
+                if rel.relationship_a_to_b != RelationshipType::Spouse || married_pairs.contains(&rel.id) {
+                    continue;
+                }
+                married_pairs.push(rel.id);
+                let Some(spouse) = rel.get_other_person_id(owner).and_then(|id| graph.person(id)) else {
+                    continue;
+                };
+                let spouse_is_parent = graph.parents(person_id).iter().any(|id| spouse.id == Some(*id));
+                let title = if lane == TimelineLane::Person {
+                    format!("Vigsel med {}", spouse.full_name())
+                } else if lane == TimelineLane::Parents && spouse_is_parent {
+                    "Föräldrarna gifter sig".to_string()
+                } else {
+                    let relation = if lane == TimelineLane::Parents { RelationshipType::Parent } else { RelationshipType::Child };
+                    let owner = graph.person(owner);
+                    format!(
+                        "{} {} gifter sig med {}",
+                        kin_label(relation, owner.map(|p| p.sex).unwrap_or_default()),
+                        owner.map(|p| p.full_name()).unwrap_or_default(),
+                        spouse.full_name()
+                    )
+                };
+                push(rel.marriage_date, TimelineEventKind::Marriage, lane, title, rel.marriage_place.clone(), Some(owner));
+            }
+        }
+
+        events.extend(self.document_events(&person)?);
+
+        if let Some(birth) = person.birth_date {
+            for event in events.iter_mut().filter(|e| e.date >= birth) {
+                event.age = Some(age_at(birth, event.date));
+            }
+        }
+        events.sort_by_key(|e| (e.date, e.lane as u8));
+
+        let issues = find_issues(&person, &graph, &events);
+
+        Ok(Timeline { person, events, issues })
+    }
+
+    /// Dokument daterade med EXIF-datum (bilder) eller filens ändringsdatum
+    fn document_events(&self, person: &Person) -> Result<Vec<TimelineEvent>> {
+        let Some(person_id) = person.id else {
+            return Ok(Vec::new());
+        };
+        let media_root = self.db.config().get()?.media_directory_path;
+
+        Ok(self
+            .db
+            .documents()
+            .find_by_person(person_id)?
+            .into_iter()
+            .filter_map(|doc| {
+                let date_taken = if doc.is_image() {
+                    ExifData::from_file(&doc.full_path(&media_root, &person.directory_name))
+                        .ok()
+                        .flatten()
+                        .and_then(|exif| exif.date_taken)
+                } else {
+                    None
+                };
+                let date = date_taken.or(doc.file_modified_at)?.date();
+                Some(TimelineEvent {
+                    date,
+                    kind: TimelineEventKind::Document,
+                    lane: TimelineLane::Documents,
+                    title: doc.filename.clone(),
+                    place: None,
+                    person_id: Some(person_id),
+                    document_id: doc.id,
+                    age: None,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Motsägelser mellan datum samt långa perioder utan händelser
+fn find_issues(person: &Person, graph: &KinshipGraph, events: &[TimelineEvent]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let Some(person_id) = person.id else {
+        return issues;
+    };
+
+    if let (Some(birth), Some(death)) = (person.birth_date, person.death_date) {
+        if death < birth {
+            issues.push("Dödsdatum ligger före födelsedatum".to_string());
+        }
+    }
+
+    // Personens vigslar och barn i förhållande till hennes/hans liv
+    for event in events.iter().filter(|e| e.person_id == Some(person_id) && e.kind == TimelineEventKind::Marriage) {
+        if event.age.is_some_and(|age| age < MIN_PARENT_AGE) || (person.birth_date.is_some_and(|b| event.date < b)) {
+            issues.push(format!("{} ({}) före {} års ålder", event.title, event.date.year(), MIN_PARENT_AGE));
+        }
+        if person.death_date.is_some_and(|d| event.date > d) {
+            issues.push(format!("{} ({}) efter dödsdatum", event.title, event.date.year()));
+        }
+    }
+    for child in graph.children(person_id).iter().filter_map(|id| graph.person(*id)) {
+        let Some(child_birth) = child.birth_date else {
+            continue;
+        };
+        if let Some(birth) = person.birth_date {
+            if child_birth < birth || age_at(birth, child_birth) < MIN_PARENT_AGE {
+                issues.push(format!("Barnet {} föds {} innan personen fyllt {} år", child.full_name(), child_birth.year(), MIN_PARENT_AGE));
+            }
+        }
+        if let Some(death) = person.death_date {
+            // En far kan ha dött under graviditeten
+            let limit = if person.sex == Sex::Male { death + chrono::Duration::days(280) } else { death };
+            if child_birth > limit {
+                issues.push(format!("Barnet {} föds {} efter personens död", child.full_name(), child_birth.year()));
+            }
+        }
+    }
+
+    // Personens födelse i förhållande till föräldrarnas liv
+    if let Some(birth) = person.birth_date {
+        for parent in graph.parents(person_id).iter().filter_map(|id| graph.person(*id)) {
+            if parent.birth_date.is_some_and(|b| birth < b || age_at(b, birth) < MIN_PARENT_AGE) {
+                issues.push(format!("{} är yngre än {} år vid personens födelse", parent.full_name(), MIN_PARENT_AGE));
+            }
+            if let Some(death) = parent.death_date {
+                let limit = if parent.sex == Sex::Male { death + chrono::Duration::days(280) } else { death };
+                if birth > limit {
+                    issues.push(format!("Personen föds efter att {} dött", parent.full_name()));
+                }
+            }
+        }
+    }
+
+    // Långa luckor under personens livstid
+    if let Some(birth) = person.birth_date {
+        let end = person.death_date.unwrap_or_else(|| chrono::Local::now().date_naive());
+        let mut dates: Vec<NaiveDate> = events.iter().map(|e| e.date).filter(|d| *d >= birth && *d <= end).collect();
+        dates.push(end);
+        dates.sort();
+        for pair in dates.windows(2) {
+            if pair[1].year() - pair[0].year() >= GAP_YEARS {
+                issues.push(format!("Inga händelser mellan {} och {}", pair[0].year(), pair[1].year()));
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PersonRelationship;
+
+    fn create(db: &Database, name: &str, sex: Sex, birth: Option<(i32, u32, u32)>, death: Option<(i32, u32, u32)>) -> i64 {
+        let mut p = Person::new(Some(name.into()), Some("Berg".into()), name.to_lowercase());
+        p.sex = sex;
+        p.birth_date = birth.and_then(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d));
+        p.death_date = death.and_then(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d));
+        db.persons().create(&mut p).unwrap();
+        p.id.unwrap()
+    }
+
+    #[test]
+    fn test_age_at() {
+        let birth = NaiveDate::from_ymd_opt(1900, 6, 15).unwrap();
+        assert_eq!(age_at(birth, NaiveDate::from_ymd_opt(1930, 6, 14).unwrap()), 29);
+        assert_eq!(age_at(birth, NaiveDate::from_ymd_opt(1930, 6, 15).unwrap()), 30);
+    }
+
+    #[test]
+    fn test_family_events_and_issues() {
+        let db = Database::open_in_memory().unwrap();
+        let karl = create(&db, "Karl", Sex::Male, Some((1900, 1, 1)), Some((1960, 1, 1)));
+        let far = create(&db, "Johan", Sex::Male, Some((1870, 1, 1)), Some((1899, 1, 1)));
+        let maria = create(&db, "Maria", Sex::Female, Some((1902, 1, 1)), Some((1970, 1, 1)));
+        let erik = create(&db, "Erik", Sex::Male, Some((1925, 3, 1)), None);
+        let anna = create(&db, "Anna", Sex::Female, Some((1961, 1, 1)), None);
+
+        for (parent, child) in [(far, karl), (karl, erik), (karl, anna)] {
+            let mut rel = PersonRelationship::new(parent, child, RelationshipType::Parent);
+            db.relationships().create(&mut rel).unwrap();
+        }
+        let mut marriage = PersonRelationship::new(karl, maria, RelationshipType::Spouse);
+        marriage.marriage_date = NaiveDate::from_ymd_opt(1924, 6, 1);
+        marriage.marriage_place = Some("Lund".into());
+        db.relationships().create(&mut marriage).unwrap();
+
+        let timeline = TimelineService::new(&db).build(karl).unwrap();
+        let titles: Vec<(&str, Option<i32>)> = timeline.events.iter().map(|e| (e.title.as_str(), e.age)).collect();
+        assert_eq!(
+            titles,
+            [
+                ("Far Johan Berg föds", None),
+                ("Far Johan Berg dör", None),
+                ("Föds", Some(0)),
+                ("Vigsel med Maria Berg", Some(24)),
+                ("Son Erik Berg föds", Some(25)),
+                ("Dör", Some(60)),
+                ("Dotter Anna Berg föds", Some(61)),
+                ("Maka Maria Berg dör", Some(70)),
+            ]
+        );
+        assert_eq!(timeline.events[3].place.as_deref(), Some("Lund"));
+        assert_eq!(timeline.events[4].lane, TimelineLane::Children);
+
+        // Far dog ett år före födelsen, dottern föddes ett år efter Karls död
+        assert!(timeline.issues.iter().any(|i| i == "Personen föds efter att Johan Berg dött"));
+        assert!(timeline.issues.iter().any(|i| i == "Barnet Anna Berg föds 1961 efter personens död"));
+        assert!(timeline.issues.iter().any(|i| i == "Inga händelser mellan 1900 och 1924"));
+        assert!(timeline.issues.iter().any(|i| i == "Inga händelser mellan 1925 och 1960"));
+        assert_eq!(timeline.issues.len(), 4);
+    }
+}
@@ -0,0 +1,742 @@
+//! Layout för familjeträdet
+//!
+//! Hierarkisk layout i Reingold–Tilford-stil anpassad för par. Personer som
+//! är gifta eller har barn tillsammans bildar en *enhet* som placeras som ett
+//! block. Ättlingar läggs ut nedåt från fokuspersonens enhet, förfäder uppåt
+//! och syskon (med sina ättlingar) vid sidan av. Varje delträd har en kontur
+//! per generationsrad, så att delträd kan packas tätt utan att överlappa.
+//!
+//! Resultatet beror bara på trädets innehåll, aldrig på `HashMap`-ordning.
+//!
+//! Antavlor läggs ut separat med `layout_pedigree`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use chrono::NaiveDate;
+
+use super::family_tree::{FamilyTree, LinkType};
+
+/// Mått för layouten
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutConfig {
+    pub node_width: f32,
+    pub node_height: f32,
+    /// Avstånd mellan partners i samma enhet
+    pub spouse_spacing: f32,
+    /// Avstånd mellan enheter på samma rad
+    pub h_spacing: f32,
+    /// Avstånd mellan generationsrader
+    pub v_spacing: f32,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            node_width: 150.0,
+            node_height: 80.0,
+            spouse_spacing: 20.0,
+            h_spacing: 50.0,
+            v_spacing: 100.0,
+        }
+    }
+}
+
+/// Beräkna positioner för alla noder. Fokuspersonen hamnar i origo.
+pub fn layout_tree(tree: &mut FamilyTree, config: &LayoutConfig) {
+    if tree.nodes.is_empty() {
+        return;
+    }
+
+    let positions = Layouter::new(tree, config).run(tree.focus_person_id);
+    let (focus_x, focus_y) = tree
+        .focus_person_id
+        .and_then(|id| positions.get(&id))
+        .copied()
+        .unwrap_or((0.0, 0.0));
+
+    for (id, node) in tree.nodes.iter_mut() {
+        if let Some(&(x, y)) = positions.get(id) {
+            node.x = x - focus_x;
+            node.y = y - focus_y;
+        }
+    }
+}
+
+/// Lägg ut en antavla från vänster till höger. Varje generation är en
+/// kolumn och varje antavlenummer har en fast plats, så far hamnar ovanför
+/// och mor nedanför sitt barn. Noder utan nummer lämnas orörda.
+pub fn layout_pedigree(tree: &mut FamilyTree, config: &LayoutConfig) {
+    let depth = |number: u64| 63 - number.leading_zeros() as i32;
+    let numbers = tree
+        .nodes
+        .values()
+        .filter_map(|n| n.ahnentafel)
+        .chain(tree.references.iter().map(|r| r.ahnentafel))
+        .chain(tree.placeholders.iter().map(|p| p.ahnentafel));
+    let Some(max_depth) = numbers.map(depth).max() else {
+        return;
+    };
+
+    let slot = config.node_height + config.spouse_spacing;
+    let total_height = 2f32.powi(max_depth) * slot;
+    let position = |number: u64| {
+        let d = depth(number);
+        let index = (number - (1 << d)) as f32;
+        let span = 2f32.powi(max_depth - d) * slot;
+        (d as f32 * (config.node_width + config.h_spacing), (index + 0.5) * span - total_height / 2.0)
+    };
+
+    for node in tree.nodes.values_mut() {
+        if let Some(number) = node.ahnentafel {
+            (node.x, node.y) = position(number);
+        }
+    }
+    for reference in &mut tree.references {
+        (reference.x, reference.y) = position(reference.ahnentafel);
+    }
+    for placeholder in &mut tree.placeholders {
+        (placeholder.x, placeholder.y) = position(placeholder.ahnentafel);
+    }
+}
+
+/// Sorteringsnyckel för personer: födelsedatum (okända sist), sedan id
+type PersonKey = (bool, Option<NaiveDate>, i64);
+
+/// Åt vilket håll syskon och förfäder till en person i en enhet placeras
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+    /// Syskon före och efter personen i födelseordning
+    Split,
+}
+
+/// Par (eller ensam person) som placeras som ett block
+#[derive(Debug)]
+struct Unit {
+    members: Vec<i64>,
+    generation: i32,
+}
+
+/// Ett delträd: enheternas mittpunkter och vänster/höger kant per rad
+#[derive(Debug, Default)]
+struct Contour {
+    units: Vec<(usize, f32)>,
+    rows: BTreeMap<i32, (f32, f32)>,
+}
+
+impl Contour {
+    fn shift(&mut self, dx: f32) {
+        for (_, x) in &mut self.units {
+            *x += dx;
+        }
+        for (left, right) in self.rows.values_mut() {
+            *left += dx;
+            *right += dx;
+        }
+    }
+
+    fn extend(&mut self, row: i32, left: f32, right: f32) {
+        let entry = self.rows.entry(row).or_insert((left, right));
+        entry.0 = entry.0.min(left);
+        entry.1 = entry.1.max(right);
+    }
+
+    fn merge(&mut self, other: Contour) {
+        self.units.extend(other.units);
+        for (row, (left, right)) in other.rows {
+            self.extend(row, left, right);
+        }
+    }
+
+    fn shared_rows<'a>(&'a self, other: &'a Contour) -> impl Iterator<Item = ((f32, f32), (f32, f32))> + 'a {
+        other
+            .rows
+            .iter()
+            .filter_map(|(row, theirs)| self.rows.get(row).map(|ours| (*ours, *theirs)))
+    }
+
+    /// Förflyttning som lägger `other` till höger om allt på gemensamma rader
+    fn shift_to_right_of(&self, other: &Contour, gap: f32) -> Option<f32> {
+        self.shared_rows(other)
+            .map(|(ours, theirs)| ours.1 + gap - theirs.0)
+            .reduce(f32::max)
+    }
+
+    /// Förflyttning som lägger `other` till vänster om allt på gemensamma rader
+    fn shift_to_left_of(&self, other: &Contour, gap: f32) -> Option<f32> {
+        self.shared_rows(other)
+            .map(|(ours, theirs)| ours.0 - gap - theirs.1)
+            .reduce(f32::min)
+    }
+
+    fn overlaps(&self, other: &Contour, gap: f32) -> bool {
+        self.shared_rows(other)
+            .any(|(ours, theirs)| theirs.0 < ours.1 + gap && theirs.1 + gap > ours.0)
+    }
+
+    fn center_of(&self, unit: usize) -> Option<f32> {
+        self.units.iter().find(|(u, _)| *u == unit).map(|(_, x)| *x)
+    }
+}
+
+struct Layouter<'c> {
+    config: &'c LayoutConfig,
+    keys: HashMap<i64, PersonKey>,
+    /// Föräldrar och barn per person, sorterade på `PersonKey`
+    parents: HashMap<i64, Vec<i64>>,
+    children: HashMap<i64, Vec<i64>>,
+    units: Vec<Unit>,
+    unit_of: HashMap<i64, usize>,
+    claimed: Vec<bool>,
+}
+
+impl<'c> Layouter<'c> {
+    fn new(tree: &FamilyTree, config: &'c LayoutConfig) -> Self {
+        let keys: HashMap<i64, PersonKey> = tree
+            .nodes
+            .iter()
+            .map(|(id, node)| (*id, (node.person.birth_date.is_none(), node.person.birth_date, *id)))
+            .collect();
+        let generation: HashMap<i64, i32> = tree.nodes.iter().map(|(id, node)| (*id, node.generation)).collect();
+
+        let mut ids: Vec<i64> = keys.keys().copied().collect();
+        ids.sort_by_key(|id| keys[id]);
+
+        // Länkar kan förekomma flera gånger; samla dem i ordnade mängder
+        let mut parent_links = BTreeSet::new();
+        let mut partner_links = BTreeSet::new();
+        for link in &tree.links {
+            if link.from_id == link.to_id || !keys.contains_key(&link.from_id) || !keys.contains_key(&link.to_id) {
+                continue;
+            }
+            match link.link_type {
+                LinkType::Parent => {
+                    parent_links.insert((link.from_id, link.to_id));
+                }
+                LinkType::Spouse if generation[&link.from_id] == generation[&link.to_id] => {
+                    partner_links.insert((link.from_id.min(link.to_id), link.from_id.max(link.to_id)));
+                }
+                _ => {}
+            }
+        }
+
+        let mut parents: HashMap<i64, Vec<i64>> = HashMap::new();
+        let mut children: HashMap<i64, Vec<i64>> = HashMap::new();
+        for &(parent, child) in &parent_links {
+            parents.entry(child).or_default().push(parent);
+            children.entry(parent).or_default().push(child);
+        }
+        for list in parents.values_mut().chain(children.values_mut()) {
+            list.sort_by_key(|id| keys[id]);
+        }
+
+        // Föräldrar till samma barn hålls ihop även om de inte är gifta
+        for list in parents.values() {
+            for (i, a) in list.iter().enumerate() {
+                for b in &list[i + 1..] {
+                    if generation[a] == generation[b] {
+                        partner_links.insert((*a.min(b), *a.max(b)));
+                    }
+                }
+            }
+        }
+
+        let mut partners: HashMap<i64, Vec<i64>> = HashMap::new();
+        for &(a, b) in &partner_links {
+            partners.entry(a).or_default().push(b);
+            partners.entry(b).or_default().push(a);
+        }
+        for list in partners.values_mut() {
+            list.sort_by_key(|id| keys[id]);
+        }
+
+        // Bilda enheter av sammanhängande partners
+        let mut units = Vec::new();
+        let mut unit_of = HashMap::new();
+        for &id in &ids {
+            if unit_of.contains_key(&id) {
+                continue;
+            }
+            let mut component = vec![id];
+            let mut seen: HashSet<i64> = HashSet::from([id]);
+            let mut i = 0;
+            while i < component.len() {
+                for &partner in partners.get(&component[i]).into_iter().flatten() {
+                    if seen.insert(partner) {
+                        component.push(partner);
+                    }
+                }
+                i += 1;
+            }
+
+            let members = arrange_members(&component, &partners, &keys);
+            for &member in &members {
+                unit_of.insert(member, units.len());
+            }
+            units.push(Unit {
+                members,
+                generation: generation[&id],
+            });
+        }
+
+        let claimed = vec![false; units.len()];
+        Self {
+            config,
+            keys,
+            parents,
+            children,
+            units,
+            unit_of,
+            claimed,
+        }
+    }
+
+    /// Placera alla enheter och returnera (x, y) per person
+    fn run(mut self, focus_person_id: Option<i64>) -> HashMap<i64, (f32, f32)> {
+        let mut order: Vec<usize> = (0..self.units.len()).collect();
+        order.sort_by_key(|&u| (self.units[u].generation, u));
+
+        let root = focus_person_id
+            .and_then(|id| self.unit_of.get(&id))
+            .copied()
+            .unwrap_or(order[0]);
+
+        self.claimed[root] = true;
+        let mut block = self.descendants(root);
+        self.ancestors(root, &mut block);
+
+        // Personer som inte nås från fokuspersonen läggs till höger
+        for u in order {
+            if self.claimed[u] {
+                continue;
+            }
+            self.claimed[u] = true;
+            let mut sub = self.descendants(u);
+            self.ancestors(u, &mut sub);
+            if let Some(dx) = block.shift_to_right_of(&sub, self.config.h_spacing) {
+                sub.shift(dx);
+            }
+            block.merge(sub);
+        }
+
+        let row_height = self.config.node_height + self.config.v_spacing;
+        let mut positions = HashMap::new();
+        for &(u, center) in &block.units {
+            let y = self.units[u].generation as f32 * row_height;
+            for &member in &self.units[u].members {
+                positions.insert(member, (self.member_x(u, center, member), y));
+            }
+        }
+        positions
+    }
+
+    fn unit_width(&self, unit: usize) -> f32 {
+        let n = self.units[unit].members.len() as f32;
+        n * self.config.node_width + (n - 1.0) * self.config.spouse_spacing
+    }
+
+    fn member_x(&self, unit: usize, center: f32, person: i64) -> f32 {
+        let index = self.units[unit].members.iter().position(|m| *m == person).unwrap_or(0);
+        center - self.unit_width(unit) / 2.0
+            + self.config.node_width / 2.0
+            + index as f32 * (self.config.node_width + self.config.spouse_spacing)
+    }
+
+    fn person_x(&self, contour: &Contour, person: i64) -> f32 {
+        let unit = self.unit_of[&person];
+        let center = contour.center_of(unit).unwrap_or(0.0);
+        self.member_x(unit, center, person)
+    }
+
+    fn place_unit(&self, contour: &mut Contour, unit: usize, center: f32) {
+        let half = self.unit_width(unit) / 2.0;
+        contour.units.push((unit, center));
+        contour.extend(self.units[unit].generation, center - half, center + half);
+    }
+
+    /// Ej placerade barnenheter till en enhet, med barnet som hör till
+    /// enheten. Barn grupperas efter vilken partner som är den andra
+    /// föräldern (vänster till höger) och sorteras sedan på födelse.
+    fn child_units(&self, unit: usize) -> Vec<(usize, i64)> {
+        let members = &self.units[unit].members;
+        let generation = self.units[unit].generation;
+
+        let mut candidates: Vec<((usize, PersonKey), usize, i64)> = Vec::new();
+        for member in members {
+            for &child in self.children.get(member).into_iter().flatten() {
+                let child_unit = self.unit_of[&child];
+                if self.claimed[child_unit] || self.units[child_unit].generation <= generation {
+                    continue;
+                }
+                let indices: Vec<usize> = self.parents[&child]
+                    .iter()
+                    .filter_map(|p| members.iter().position(|m| m == p))
+                    .collect();
+                let position = indices.iter().sum::<usize>() * 2 / indices.len();
+                candidates.push(((position, self.keys[&child]), child_unit, child));
+            }
+        }
+        candidates.sort_by_key(|(key, _, _)| *key);
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|(_, child_unit, _)| seen.insert(*child_unit))
+            .map(|(_, child_unit, child)| (child_unit, child))
+            .collect()
+    }
+
+    /// Ej placerade föräldraenheter till en person
+    fn parent_units(&self, person: i64) -> Vec<usize> {
+        let generation = self.units[self.unit_of[&person]].generation;
+        let mut result: Vec<usize> = Vec::new();
+        for parent in self.parents.get(&person).into_iter().flatten() {
+            let unit = self.unit_of[parent];
+            if !self.claimed[unit] && self.units[unit].generation < generation && !result.contains(&unit) {
+                result.push(unit);
+            }
+        }
+        result
+    }
+
+    /// Lägg ut en enhet med alla ättlingar. Enheten centreras över sina
+    /// barn och hamnar i x = 0.
+    fn descendants(&mut self, unit: usize) -> Contour {
+        let kids = self.child_units(unit);
+        for (child_unit, _) in &kids {
+            self.claimed[*child_unit] = true;
+        }
+
+        let mut block = Contour::default();
+        let mut anchors = Vec::new();
+        for (child_unit, child) in kids {
+            let mut sub = self.descendants(child_unit);
+            if let Some(dx) = block.shift_to_right_of(&sub, self.config.h_spacing) {
+                sub.shift(dx);
+            }
+            anchors.push(self.person_x(&sub, child));
+            block.merge(sub);
+        }
+
+        let center = midpoint(&anchors).unwrap_or(0.0);
+        self.place_unit(&mut block, unit, center);
+        block.shift(-center);
+        block
+    }
+
+    /// Lägg till förfäder (och deras övriga barn) ovanför en enhet som
+    /// redan finns i `block`. Varje persons föräldrar centreras över
+    /// personen och dess syskon; krockar löses genom att grenen flyttas utåt.
+    fn ancestors(&mut self, unit: usize, block: &mut Contour) {
+        let members = self.units[unit].members.clone();
+        let k = members.len();
+        let gap = self.config.h_spacing;
+
+        // Inifrån och ut, vänster före höger
+        let mut order: Vec<usize> = (0..k).collect();
+        order.sort_by_key(|&i| ((2 * i + 1).abs_diff(k), i));
+
+        for i in order {
+            let person = members[i];
+            let side = match (2 * i + 1).cmp(&k) {
+                std::cmp::Ordering::Less => Side::Left,
+                std::cmp::Ordering::Greater => Side::Right,
+                std::cmp::Ordering::Equal => Side::Split,
+            };
+
+            for parent_unit in self.parent_units(person) {
+                if self.claimed[parent_unit] {
+                    continue;
+                }
+                self.claimed[parent_unit] = true;
+
+                let siblings = self.child_units(parent_unit);
+                for (sibling_unit, _) in &siblings {
+                    self.claimed[*sibling_unit] = true;
+                }
+                let person_key = self.keys[&person];
+                let (left, right): (Vec<_>, Vec<_>) = siblings.into_iter().partition(|(_, sibling)| match side {
+                    Side::Left => true,
+                    Side::Right => false,
+                    Side::Split => self.keys[sibling] < person_key,
+                });
+
+                let mut anchors = vec![self.person_x(block, person)];
+                for (sibling_unit, sibling) in left.into_iter().rev() {
+                    let mut sub = self.descendants(sibling_unit);
+                    if let Some(dx) = block.shift_to_left_of(&sub, gap) {
+                        sub.shift(dx);
+                    }
+                    anchors.push(self.person_x(&sub, sibling));
+                    block.merge(sub);
+                }
+                for (sibling_unit, sibling) in right {
+                    let mut sub = self.descendants(sibling_unit);
+                    if let Some(dx) = block.shift_to_right_of(&sub, gap) {
+                        sub.shift(dx);
+                    }
+                    anchors.push(self.person_x(&sub, sibling));
+                    block.merge(sub);
+                }
+
+                let mut branch = Contour::default();
+                self.place_unit(&mut branch, parent_unit, 0.0);
+                self.ancestors(parent_unit, &mut branch);
+                branch.shift(midpoint(&anchors).unwrap_or(0.0));
+
+                if block.overlaps(&branch, gap) {
+                    let dx = match side {
+                        Side::Left => block.shift_to_left_of(&branch, gap),
+                        Side::Right | Side::Split => block.shift_to_right_of(&branch, gap),
+                    };
+                    if let Some(dx) = dx {
+                        branch.shift(dx);
+                    }
+                }
+                block.merge(branch);
+            }
+        }
+    }
+}
+
+/// Ordna partners i en enhet: personen med flest partners i mitten, första
+/// partnern till vänster och övriga till höger. Partners till partners
+/// hamnar längst ut på samma sida som den de hör ihop med.
+fn arrange_members(component: &[i64], partners: &HashMap<i64, Vec<i64>>, keys: &HashMap<i64, PersonKey>) -> Vec<i64> {
+    if component.len() == 1 {
+        return component.to_vec();
+    }
+
+    let degree = |id: &i64| partners.get(id).map_or(0, Vec::len);
+    let hub = *component
+        .iter()
+        .max_by(|a, b| degree(a).cmp(&degree(b)).then(keys[b].cmp(&keys[a])))
+        .unwrap();
+
+    let hub_partners = partners.get(&hub).cloned().unwrap_or_default();
+    let split = hub_partners.len() / 2;
+    let mut left: Vec<i64> = hub_partners[..split].to_vec();
+    let mut right: Vec<i64> = hub_partners[split..].to_vec();
+
+    let mut placed: HashSet<i64> = hub_partners.iter().copied().collect();
+    placed.insert(hub);
+    let mut queue = hub_partners;
+    let mut i = 0;
+    while i < queue.len() {
+        let current = queue[i];
+        for &next in partners.get(&current).into_iter().flatten() {
+            if placed.insert(next) {
+                if left.contains(&current) {
+                    left.insert(0, next);
+                } else {
+                    right.push(next);
+                }
+                queue.push(next);
+            }
+        }
+        i += 1;
+    }
+
+    left.push(hub);
+    left.extend(right);
+    left
+}
+
+fn midpoint(xs: &[f32]) -> Option<f32> {
+    let min = xs.iter().copied().reduce(f32::min)?;
+    let max = xs.iter().copied().reduce(f32::max)?;
+    Some((min + max) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Person;
+    use crate::services::family_tree::{FamilyTreeLink, FamilyTreeNode};
+
+    const SPOUSE_DISTANCE: f32 = 170.0; // node_width + spouse_spacing
+
+    fn add(tree: &mut FamilyTree, id: i64, generation: i32, birth_year: i32) {
+        let mut person = Person::new(Some(format!("P{}", id)), None, format!("p{}", id));
+        person.id = Some(id);
+        person.birth_date = NaiveDate::from_ymd_opt(birth_year, 1, 1);
+        tree.nodes.insert(
+            id,
+            FamilyTreeNode {
+                person,
+                x: 0.0,
+                y: 0.0,
+                generation,
+                ahnentafel: None,
+            },
+        );
+    }
+
+    fn link(tree: &mut FamilyTree, from_id: i64, to_id: i64, link_type: LinkType) {
+        tree.links.push(FamilyTreeLink { from_id, to_id, link_type });
+    }
+
+    fn couple_with_children(tree: &mut FamilyTree, a: i64, b: i64, children: &[i64]) {
+        link(tree, a, b, LinkType::Spouse);
+        for &child in children {
+            link(tree, a, child, LinkType::Parent);
+            link(tree, b, child, LinkType::Parent);
+        }
+    }
+
+    fn x(tree: &FamilyTree, id: i64) -> f32 {
+        tree.nodes[&id].x
+    }
+
+    fn laid_out(mut tree: FamilyTree) -> FamilyTree {
+        layout_tree(&mut tree, &LayoutConfig::default());
+        tree
+    }
+
+    /// Inga noder på samma rad får överlappa
+    fn assert_no_overlap(tree: &FamilyTree) {
+        let mut rows: BTreeMap<i32, Vec<f32>> = BTreeMap::new();
+        for node in tree.nodes.values() {
+            rows.entry(node.y.round() as i32).or_default().push(node.x);
+        }
+        for xs in rows.values_mut() {
+            xs.sort_by(f32::total_cmp);
+            for pair in xs.windows(2) {
+                assert!(pair[1] - pair[0] >= SPOUSE_DISTANCE - 0.01, "överlapp: {:?}", xs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_children_centered_under_couple() {
+        let mut tree = FamilyTree::new();
+        tree.focus_person_id = Some(1);
+        add(&mut tree, 1, 0, 1900);
+        add(&mut tree, 2, 0, 1902);
+        add(&mut tree, 10, 1, 1930);
+        add(&mut tree, 11, 1, 1925);
+        add(&mut tree, 12, 1, 1935);
+        couple_with_children(&mut tree, 1, 2, &[10, 11, 12]);
+        let tree = laid_out(tree);
+
+        // Fokuspersonen i origo, partnern bredvid
+        assert_eq!((x(&tree, 1), tree.nodes[&1].y), (0.0, 0.0));
+        assert_eq!(x(&tree, 2), SPOUSE_DISTANCE);
+
+        // Barnen i födelseordning, centrerade under paret
+        assert_eq!(x(&tree, 11), -115.0);
+        assert_eq!(x(&tree, 10), 85.0);
+        assert_eq!(x(&tree, 12), 285.0);
+        assert_eq!(tree.nodes[&10].y, 180.0);
+        assert_no_overlap(&tree);
+    }
+
+    #[test]
+    fn test_pedigree_keeps_sides_apart() {
+        let mut tree = FamilyTree::new();
+        tree.focus_person_id = Some(1);
+        add(&mut tree, 1, 0, 1950);
+        add(&mut tree, 2, -1, 1920); // far
+        add(&mut tree, 3, -1, 1922); // mor
+        add(&mut tree, 4, -2, 1890); // farfar
+        add(&mut tree, 5, -2, 1892); // farmor
+        add(&mut tree, 6, -2, 1891); // morfar
+        add(&mut tree, 7, -2, 1893); // mormor
+        add(&mut tree, 8, -1, 1918); // farbror
+        add(&mut tree, 9, 0, 1948); // kusin
+        couple_with_children(&mut tree, 2, 3, &[1]);
+        couple_with_children(&mut tree, 4, 5, &[2, 8]);
+        couple_with_children(&mut tree, 6, 7, &[3]);
+        link(&mut tree, 8, 9, LinkType::Parent);
+        let tree = laid_out(tree);
+        assert_no_overlap(&tree);
+
+        // Paren sitter ihop, faderns sida till vänster om moderns
+        assert_eq!(x(&tree, 3) - x(&tree, 2), SPOUSE_DISTANCE);
+        assert_eq!(x(&tree, 5) - x(&tree, 4), SPOUSE_DISTANCE);
+        assert!(x(&tree, 5) < x(&tree, 6));
+
+        // Farbror och kusin på faderns sida
+        assert!(x(&tree, 8) < x(&tree, 2));
+        assert!(x(&tree, 9) < x(&tree, 1));
+
+        // Farföräldrarna centrerade över sina barn
+        let grandparents = (x(&tree, 4) + x(&tree, 5)) / 2.0;
+        assert_eq!(grandparents, (x(&tree, 8) + x(&tree, 2)) / 2.0);
+    }
+
+    #[test]
+    fn test_multiple_marriages() {
+        let mut tree = FamilyTree::new();
+        tree.focus_person_id = Some(1);
+        add(&mut tree, 1, 0, 1900);
+        add(&mut tree, 2, 0, 1901); // första partnern
+        add(&mut tree, 3, 0, 1905); // andra partnern
+        add(&mut tree, 10, 1, 1930);
+        add(&mut tree, 11, 1, 1920);
+        couple_with_children(&mut tree, 1, 2, &[10]);
+        couple_with_children(&mut tree, 1, 3, &[11]);
+        let tree = laid_out(tree);
+        assert_no_overlap(&tree);
+
+        // Partner - person - partner
+        assert_eq!(x(&tree, 2), -SPOUSE_DISTANCE);
+        assert_eq!(x(&tree, 3), SPOUSE_DISTANCE);
+
+        // Barnen grupperas under respektive äktenskap, inte i födelseordning
+        assert!(x(&tree, 10) < x(&tree, 11));
+    }
+
+    #[test]
+    fn test_layout_is_deterministic() {
+        let build = |reverse: bool| {
+            let mut ids: Vec<i64> = (1..=12).collect();
+            if reverse {
+                ids.reverse();
+            }
+            let mut tree = FamilyTree::new();
+            tree.focus_person_id = Some(5);
+            for &id in &ids {
+                let generation = match id {
+                    1..=4 => -1,
+                    5..=8 => 0,
+                    _ => 1,
+                };
+                add(&mut tree, id, generation, 1900 + (id as i32 % 3));
+            }
+            couple_with_children(&mut tree, 1, 2, &[5, 6]);
+            couple_with_children(&mut tree, 3, 4, &[7]);
+            couple_with_children(&mut tree, 5, 7, &[9, 10, 11]);
+            couple_with_children(&mut tree, 6, 8, &[12]);
+            if reverse {
+                tree.links.reverse();
+            }
+            laid_out(tree)
+        };
+
+        let a = build(false);
+        let b = build(true);
+        assert_no_overlap(&a);
+        for id in 1..=12 {
+            assert_eq!((a.nodes[&id].x, a.nodes[&id].y), (b.nodes[&id].x, b.nodes[&id].y), "person {}", id);
+        }
+    }
+
+    #[test]
+    fn test_pedigree_slots() {
+        let mut tree = FamilyTree::new();
+        for (id, number) in [(1, 1), (2, 2), (3, 3), (6, 6), (7, 7)] {
+            add(&mut tree, id, 0, 1900);
+            tree.nodes.get_mut(&id).unwrap().ahnentafel = Some(number);
+        }
+        layout_pedigree(&mut tree, &LayoutConfig::default());
+
+        // Fokuspersonen i mitten, far ovanför och mor nedanför
+        assert_eq!((x(&tree, 1), tree.nodes[&1].y), (0.0, 0.0));
+        assert_eq!((x(&tree, 2), tree.nodes[&2].y), (200.0, -100.0));
+        assert_eq!((x(&tree, 3), tree.nodes[&3].y), (200.0, 100.0));
+        assert_eq!((x(&tree, 6), tree.nodes[&6].y), (400.0, 50.0));
+        assert_eq!((x(&tree, 7), tree.nodes[&7].y), (400.0, 150.0));
+    }
+}
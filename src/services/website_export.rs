@@ -0,0 +1,503 @@
+//! Statisk webbplats
+//!
+//! Skriver en fristående webbplats till en katalog: en startsida med
+//! efternamnsregister och sökruta, en sida per person med relationer som
+//! länkar, släktträd som SVG och bildgalleri, samt ett sökindex som körs i
+//! webbläsaren. Allt ligger i katalogen, så sidorna kan öppnas direkt från
+//! filsystemet utan webbserver. Levande och privata personer maskeras
+//! alltid och får ingen egen sida.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::db::{Database, KinshipGraph};
+use crate::models::Person;
+use crate::services::chart_export::{load_thumbnail, xml_escape};
+use crate::services::{ChartExportService, ChartOptions, FamilyTreeService, PrivacyPolicy, RedactionStyle, TreeMode};
+
+const TREE_GENERATIONS: i32 = 2;
+const THUMBNAIL_SIZE: u32 = 240;
+const SITE_TITLE: &str = "Släktdatabas";
+
+/// Resultat av export
+#[derive(Debug)]
+pub struct WebsiteResult {
+    pub person_count: usize,
+    pub image_count: usize,
+    /// Total storlek i bytes
+    pub total_size: u64,
+}
+
+/// Post i sökindexet
+#[derive(Debug, Serialize)]
+struct SearchEntry {
+    id: i64,
+    namn: String,
+    ar: String,
+    ort: String,
+}
+
+/// Skriver webbplatsen för ett urval av personer
+pub struct WebsiteExporter<'a> {
+    db: &'a Database,
+    redaction: RedactionStyle,
+    policy: PrivacyPolicy,
+}
+
+impl<'a> WebsiteExporter<'a> {
+    /// Webbplatsen är till för att delas, så "ingen maskering" ersätts
+    /// med standardmaskeringen
+    pub fn new(db: &'a Database, redaction: RedactionStyle) -> Result<Self> {
+        let redaction = if redaction == RedactionStyle::None {
+            RedactionStyle::default()
+        } else {
+            redaction
+        };
+        Ok(Self {
+            db,
+            redaction,
+            policy: PrivacyPolicy::from_config(db, redaction)?,
+        })
+    }
+
+    pub fn export(&self, persons: &[Person], dir: &Path) -> Result<WebsiteResult> {
+        let person_dir = dir.join("personer");
+        let image_dir = dir.join("bilder");
+        std::fs::create_dir_all(&person_dir).context("Kunde inte skapa katalogen för personsidor")?;
+        std::fs::create_dir_all(&image_dir).context("Kunde inte skapa bildkatalogen")?;
+
+        let mut published: Vec<&Person> = persons.iter().filter(|p| p.id.is_some() && !self.policy.masks(p)).collect();
+        published.sort_by_key(|p| sort_key(p));
+        let site_ids: HashSet<i64> = published.iter().filter_map(|p| p.id).collect();
+
+        let graph = self.db.kinship()?;
+        let media_root = self.db.config().get()?.media_directory_path;
+        let mut images = 0;
+
+        for person in &published {
+            let (page, copied) = self.person_page(person, &graph, &site_ids, &media_root, &image_dir)?;
+            let id = person.id.unwrap_or_default();
+            std::fs::write(person_dir.join(format!("{}.html", id)), page).context("Kunde inte skriva personsida")?;
+            images += copied;
+        }
+
+        std::fs::write(dir.join("index.html"), index_page(&published)).context("Kunde inte skriva startsidan")?;
+        std::fs::write(dir.join("sokindex.js"), search_index(&published)?).context("Kunde inte skriva sökindex")?;
+        std::fs::write(dir.join("sok.js"), SEARCH_JS).context("Kunde inte skriva sökskript")?;
+        std::fs::write(dir.join("stil.css"), STYLE_CSS).context("Kunde inte skriva stilmall")?;
+
+        Ok(WebsiteResult {
+            person_count: published.len(),
+            image_count: images,
+            total_size: [dir.to_path_buf(), person_dir, image_dir].iter().map(|d| files_size(d)).sum(),
+        })
+    }
+
+    /// Personsidan och antal kopierade bilder
+    fn person_page(
+        &self,
+        person: &Person,
+        graph: &KinshipGraph,
+        site_ids: &HashSet<i64>,
+        media_root: &Path,
+        image_dir: &Path,
+    ) -> Result<(String, usize)> {
+        let id = person.id.unwrap_or_default();
+        let name = person.full_name();
+        let mut html = page_header(&name, "../");
+        html.push_str("<nav><a href=\"../index.html\">← Alla efternamn</a></nav>\n");
+        html.push_str(&format!("<h1>{}</h1>\n", xml_escape(&name)));
+
+        let years = person.years_display();
+        if !years.is_empty() {
+            html.push_str(&format!("<p class=\"years\">{}</p>\n", xml_escape(&years)));
+        }
+
+        let date = |d: Option<chrono::NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+        let born = match (date(person.birth_date), person.birth_place.as_deref().map(str::trim).filter(|p| !p.is_empty())) {
+            (Some(d), Some(place)) => Some(format!("{} i {}", d, place)),
+            (Some(d), None) => Some(d),
+            (None, place) => place.map(|p| format!("i {}", p)),
+        };
+        let facts = [("Född", born), ("Död", date(person.death_date)), ("Yrke", person.occupation.clone())];
+        html.push_str("<dl>\n");
+        for (label, value) in facts {
+            if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+                html.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", label, xml_escape(&value)));
+            }
+        }
+        html.push_str("</dl>\n");
+
+        if let Some(notes) = person.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+            html.push_str("<h2>Anteckningar</h2>\n");
+            for paragraph in notes.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                html.push_str(&format!("<p>{}</p>\n", xml_escape(paragraph.trim()).replace('\n', "<br>")));
+            }
+        }
+
+        let relations = [
+            ("Föräldrar", graph.parents(id)),
+            ("Make/Maka", graph.spouses(id)),
+            ("Barn", graph.children(id)),
+            ("Syskon", graph.siblings(id)),
+        ];
+        let relations: Vec<(&str, Vec<String>)> = relations
+            .into_iter()
+            .map(|(label, ids)| {
+                let items: Vec<String> = ids
+                    .iter()
+                    .filter_map(|rid| graph.person(*rid))
+                    .filter_map(|other| self.person_link(other, site_ids))
+                    .collect();
+                (label, items)
+            })
+            .filter(|(_, items)| !items.is_empty())
+            .collect();
+        if !relations.is_empty() {
+            html.push_str("<h2>Relationer</h2>\n");
+        }
+        for (label, items) in relations {
+            html.push_str(&format!("<h3>{}</h3>\n<ul>\n", label));
+            for item in items {
+                html.push_str(&format!("<li>{}</li>\n", item));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str(&self.tree_svg(id, site_ids)?);
+
+        let copied = self.gallery(&mut html, person, media_root, image_dir)?;
+        html.push_str(&page_footer());
+        Ok((html, copied))
+    }
+
+    /// Länk till personens sida, eller bara namnet om personen saknar sida.
+    /// `None` om personen utelämnas helt.
+    fn person_link(&self, person: &Person, site_ids: &HashSet<i64>) -> Option<String> {
+        let redacted = self.policy.redact(person)?;
+        let label = format!("{} {}", redacted.full_name(), redacted.years_display());
+        let label = xml_escape(label.trim());
+        Some(match person.id.filter(|id| site_ids.contains(id)) {
+            Some(id) => format!("<a href=\"{}.html\">{}</a>", id, label),
+            None => label,
+        })
+    }
+
+    /// Släktträd kring personen där rutorna länkar till personsidorna
+    fn tree_svg(&self, person_id: i64, site_ids: &HashSet<i64>) -> Result<String> {
+        let tree = FamilyTreeService::new(self.db).build(TreeMode::Mixed, person_id, TREE_GENERATIONS)?;
+        if tree.nodes.len() < 2 {
+            return Ok(String::new());
+        }
+        let options = ChartOptions {
+            show_photos: false,
+            redaction: self.redaction,
+            ..Default::default()
+        };
+        let href = |id: i64| (site_ids.contains(&id) && id != person_id).then(|| format!("{}.html", id));
+        let svg = ChartExportService::new(self.db).svg_with_links(&tree, &options, &href)?;
+        // XML-deklarationen hör inte hemma inuti HTML
+        let svg = svg.split_once("?>\n").map(|(_, rest)| rest).unwrap_or(&svg);
+        Ok(format!("<h2>Släktträd</h2>\n<div class=\"tree\">\n{}</div>\n", svg))
+    }
+
+    /// Bildgalleri från personens bilddokument. Originalen kopieras och
+    /// får en miniatyr bredvid sig.
+    fn gallery(&self, html: &mut String, person: &Person, media_root: &Path, image_dir: &Path) -> Result<usize> {
+        let Some(person_id) = person.id else {
+            return Ok(0);
+        };
+        let mut figures = Vec::new();
+        for document in self.db.documents().find_by_person(person_id)? {
+            let (Some(doc_id), true) = (document.id, document.is_image()) else {
+                continue;
+            };
+            let source = document.full_path(&media_root.to_path_buf(), &person.directory_name);
+            // Filändelsen tas från själva filen; bara format som webbläsare visar kopieras
+            let Some(ext) = web_image_extension(&source) else {
+                continue;
+            };
+            let Some(thumbnail) = load_thumbnail(&source, THUMBNAIL_SIZE, THUMBNAIL_SIZE) else {
+                continue;
+            };
+            let original = format!("{}_{}.{}", person_id, doc_id, ext);
+            let small = format!("{}_{}_liten.jpg", person_id, doc_id);
+            std::fs::copy(&source, image_dir.join(&original)).context("Kunde inte kopiera bild")?;
+            thumbnail.save(image_dir.join(&small)).context("Kunde inte spara miniatyr")?;
+            figures.push(format!(
+                "<figure><a href=\"../bilder/{}\"><img src=\"../bilder/{}\" alt=\"{}\"></a><figcaption>{}</figcaption></figure>\n",
+                original,
+                small,
+                xml_escape(&document.filename),
+                xml_escape(&document.filename)
+            ));
+        }
+
+        if !figures.is_empty() {
+            html.push_str("<h2>Bilder</h2>\n<div class=\"gallery\">\n");
+            for figure in &figures {
+                html.push_str(figure);
+            }
+            html.push_str("</div>\n");
+        }
+        Ok(figures.len())
+    }
+}
+
+/// Filändelse (gemener) för bildformat som kan läggas ut på webben,
+/// annars `None`
+fn web_image_extension(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "gif" | "webp").then_some(ext)
+}
+
+/// Storlek på filerna direkt i katalogen
+fn files_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok()?.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Sorteringsnyckel med å, ä och ö sist i alfabetet
+fn sort_key(person: &Person) -> (String, String) {
+    let key = |s: &Option<String>| {
+        s.as_deref()
+            .unwrap_or_default()
+            .to_lowercase()
+            .chars()
+            .map(|c| match c {
+                'å' => '\u{10fffd}',
+                'ä' | 'æ' => '\u{10fffe}',
+                'ö' | 'ø' => '\u{10ffff}',
+                c => c,
+            })
+            .collect::<String>()
+    };
+    (key(&person.surname), key(&person.firstname))
+}
+
+fn surname(person: &Person) -> String {
+    person
+        .surname
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("(Okänt efternamn)")
+        .to_string()
+}
+
+fn page_header(title: &str, root: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"sv\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"{}stil.css\">\n</head>\n<body>\n<main>\n",
+        xml_escape(title),
+        root
+    )
+}
+
+fn page_footer() -> String {
+    format!(
+        "</main>\n<footer>Skapad med Genlib {}</footer>\n</body>\n</html>\n",
+        Utc::now().format("%Y-%m-%d")
+    )
+}
+
+/// Startsidan: sökruta och personerna grupperade per efternamn under
+/// begynnelsebokstav. `persons` ska vara sorterade.
+fn index_page(persons: &[&Person]) -> String {
+    type Surnames<'p> = Vec<(String, Vec<&'p Person>)>;
+    let mut by_letter: Vec<(String, Surnames)> = Vec::new();
+    for person in persons {
+        let surname = surname(person);
+        let letter = surname.chars().next().map(|c| c.to_uppercase().to_string()).unwrap_or_default();
+        if by_letter.last().map_or(true, |(l, _)| *l != letter) {
+            by_letter.push((letter, Vec::new()));
+        }
+        let groups = &mut by_letter.last_mut().expect("bokstaven lades till ovan").1;
+        match groups.last_mut() {
+            Some((name, members)) if *name == surname => members.push(person),
+            _ => groups.push((surname, vec![person])),
+        }
+    }
+
+    let mut html = page_header(SITE_TITLE, "");
+    html.push_str(&format!("<h1>{}</h1>\n", SITE_TITLE));
+    html.push_str(&format!("<p>{} personer</p>\n", persons.len()));
+    html.push_str(
+        "<input id=\"sok\" type=\"search\" placeholder=\"Sök namn, år eller ort...\" autocomplete=\"off\">\n\
+         <ul id=\"traffar\"></ul>\n",
+    );
+
+    html.push_str("<nav class=\"letters\">");
+    for (letter, _) in &by_letter {
+        html.push_str(&format!("<a href=\"#{0}\">{0}</a> ", xml_escape(letter)));
+    }
+    html.push_str("</nav>\n");
+
+    for (letter, groups) in &by_letter {
+        html.push_str(&format!("<h2 id=\"{0}\">{0}</h2>\n", xml_escape(letter)));
+        for (surname, members) in groups {
+            html.push_str(&format!(
+                "<details>\n<summary>{} ({})</summary>\n<ul>\n",
+                xml_escape(surname),
+                members.len()
+            ));
+            for person in members {
+                let label = format!("{} {}", person.full_name(), person.years_display());
+                html.push_str(&format!(
+                    "<li><a href=\"personer/{}.html\">{}</a></li>\n",
+                    person.id.unwrap_or_default(),
+                    xml_escape(label.trim())
+                ));
+            }
+            html.push_str("</ul>\n</details>\n");
+        }
+    }
+
+    html.push_str("<script src=\"sokindex.js\"></script>\n<script src=\"sok.js\"></script>\n");
+    html.push_str(&page_footer());
+    html
+}
+
+/// Sökindexet som ett skript, eftersom webbläsare inte tillåter att JSON
+/// hämtas från filsystemet
+fn search_index(persons: &[&Person]) -> Result<String> {
+    let entries: Vec<SearchEntry> = persons
+        .iter()
+        .map(|p| SearchEntry {
+            id: p.id.unwrap_or_default(),
+            namn: p.full_name(),
+            ar: p.years_display(),
+            ort: p.birth_place.clone().unwrap_or_default(),
+        })
+        .collect();
+    Ok(format!("const PERSONER = {};\n", serde_json::to_string(&entries)?))
+}
+
+const SEARCH_JS: &str = r#"// Sökning i PERSONER (sokindex.js), helt i webbläsaren
+(function () {
+  var input = document.getElementById("sok");
+  var list = document.getElementById("traffar");
+  function normalize(s) {
+    return s.toLowerCase().normalize("NFD").replace(/[\u0300-\u036f]/g, "");
+  }
+  var index = PERSONER.map(function (p) {
+    return { p: p, text: normalize(p.namn + " " + p.ar + " " + p.ort) };
+  });
+  input.addEventListener("input", function () {
+    var terms = normalize(input.value).split(/\s+/).filter(function (t) { return t; });
+    list.innerHTML = "";
+    if (!terms.length) return;
+    var hits = index.filter(function (e) {
+      return terms.every(function (t) { return e.text.indexOf(t) >= 0; });
+    });
+    hits.slice(0, 50).forEach(function (e) {
+      var li = document.createElement("li");
+      var a = document.createElement("a");
+      a.href = "personer/" + e.p.id + ".html";
+      a.textContent = (e.p.namn + " " + e.p.ar).trim();
+      li.appendChild(a);
+      if (e.p.ort) li.appendChild(document.createTextNode(", " + e.p.ort));
+      list.appendChild(li);
+    });
+    if (hits.length > 50) {
+      var more = document.createElement("li");
+      more.textContent = "... och " + (hits.length - 50) + " till";
+      list.appendChild(more);
+    }
+  });
+})();
+"#;
+
+const STYLE_CSS: &str = "body{font-family:Georgia,serif;line-height:1.5;margin:0;background:#fafaf9;color:#1f2937}
+main{max-width:60em;margin:0 auto;padding:1em 2em}
+a{color:#1d4ed8}
+h2{border-bottom:1px solid #d1d5db;margin-top:1.5em}
+.years{color:#6b7280;margin-top:-0.5em}
+dl{display:grid;grid-template-columns:max-content auto;gap:0.2em 1em}
+dt{font-weight:bold}
+dd{margin:0}
+#sok{width:100%;font-size:1.1em;padding:0.4em;box-sizing:border-box}
+.letters a{margin-right:0.3em}
+.tree{overflow:auto;border:1px solid #e5e7eb;background:#fff}
+.tree svg{max-width:none}
+.gallery{display:flex;flex-wrap:wrap;gap:1em}
+.gallery figure{margin:0;width:160px}
+.gallery img{width:160px;height:160px;object-fit:cover;border:1px solid #e5e7eb}
+.gallery figcaption{font-size:0.8em;color:#6b7280;overflow-wrap:anywhere}
+footer{text-align:center;color:#9ca3af;font-size:0.8em;padding:2em}
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, PersonRelationship, RelationshipType};
+
+    #[test]
+    fn test_export_website() {
+        let db = Database::open_in_memory().unwrap();
+        let media = tempfile::tempdir().unwrap();
+        let mut config = db.config().get().unwrap();
+        config.media_directory_path = media.path().to_path_buf();
+        db.config().save(&config).unwrap();
+
+        let mut ids = Vec::new();
+        for (first, last, birth) in [("Karl", "Öberg", 1850), ("Anna", "Åberg", 1855), ("Lisa", "Öberg", 1995)] {
+            let mut p = Person::new(Some(first.into()), Some(last.into()), first.to_lowercase());
+            p.birth_date = chrono::NaiveDate::from_ymd_opt(birth, 1, 1);
+            if birth < 1900 {
+                p.death_date = chrono::NaiveDate::from_ymd_opt(birth + 70, 1, 1);
+            }
+            db.persons().create(&mut p).unwrap();
+            ids.push(p.id.unwrap());
+        }
+        let (karl, anna, lisa) = (ids[0], ids[1], ids[2]);
+        db.relationships().create(&mut PersonRelationship::new(karl, anna, RelationshipType::Spouse)).unwrap();
+        db.relationships().create(&mut PersonRelationship::new(karl, lisa, RelationshipType::Parent)).unwrap();
+
+        let dir = media.path().join("persons").join("karl");
+        std::fs::create_dir_all(&dir).unwrap();
+        image::RgbImage::from_pixel(30, 20, image::Rgb([10, 20, 30])).save(dir.join("gard.png")).unwrap();
+        db.documents().create(&mut Document::new(karl, "gard.png".into(), "gard.png".into())).unwrap();
+
+        let site = media.path().join("webb");
+        let persons = db.persons().find_all().unwrap();
+        let result = WebsiteExporter::new(&db, RedactionStyle::None).unwrap().export(&persons, &site).unwrap();
+        assert_eq!((result.person_count, result.image_count), (2, 1));
+
+        // Lisa lever och får varken sida eller namn
+        assert!(!site.join("personer").join(format!("{}.html", lisa)).exists());
+        let karl_page = std::fs::read_to_string(site.join("personer").join(format!("{}.html", karl))).unwrap();
+        assert!(karl_page.contains(&format!("<a href=\"{}.html\">Anna Åberg", anna)));
+        assert!(karl_page.contains("<li>Privat</li>"));
+        assert!(!karl_page.contains("Lisa"));
+        assert!(karl_page.contains("<svg"));
+        assert!(karl_page.contains(&format!("bilder/{}_", karl)));
+
+        // Svensk ordning: Å före Ö
+        let index = std::fs::read_to_string(site.join("index.html")).unwrap();
+        assert!(index.find("Åberg").unwrap() < index.find("Öberg").unwrap());
+        let search = std::fs::read_to_string(site.join("sokindex.js")).unwrap();
+        assert!(search.contains("Karl Öberg") && !search.contains("Lisa"));
+    }
+
+    #[test]
+    fn test_web_image_extension() {
+        assert_eq!(web_image_extension(Path::new("bilder/gard.PNG")).as_deref(), Some("png"));
+        assert_eq!(web_image_extension(Path::new("foto.jpeg")).as_deref(), Some("jpeg"));
+        assert_eq!(web_image_extension(Path::new("skanning.bmp")), None);
+        assert_eq!(web_image_extension(Path::new("skript.html")), None);
+        assert_eq!(web_image_extension(Path::new("utan_andelse")), None);
+    }
+}
@@ -0,0 +1,459 @@
+//! Enkel skrivare för Excel-arbetsböcker (.xlsx, Office Open XML)
+//!
+//! Varje blad får en fetstilad rubrikrad som är låst vid rullning och
+//! autofilter över hela tabellen. Text skrivs som inline-strängar så att
+//! å, ä och ö följer med oförändrade. Excel saknar datum före 1900, så
+//! äldre datum skrivs som text på formen ÅÅÅÅ-MM-DD medan senare datum blir
+//! riktiga datumceller som går att sortera och räkna med.
+
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::services::chart_export::xml_escape;
+
+/// Stilindex i styles.xml
+const STYLE_HEADER: u32 = 1;
+const STYLE_DATE: u32 = 2;
+/// Kolumnbredd (tecken)
+const MIN_WIDTH: usize = 8;
+const MAX_WIDTH: usize = 60;
+const DATE_WIDTH: usize = 12;
+
+/// Ett cellvärde
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Cell {
+    Empty,
+    Text(String),
+    Number(f64),
+    Date(NaiveDate),
+    Bool(bool),
+}
+
+impl Cell {
+    pub(crate) fn text(value: impl Into<String>) -> Self {
+        Cell::Text(value.into())
+    }
+
+    /// Text, eller tom cell för `None` och tomma strängar
+    pub(crate) fn opt_text(value: Option<&str>) -> Self {
+        match value {
+            Some(v) if !v.is_empty() => Cell::Text(v.to_string()),
+            _ => Cell::Empty,
+        }
+    }
+
+    pub(crate) fn number(value: impl Into<f64>) -> Self {
+        Cell::Number(value.into())
+    }
+
+    pub(crate) fn opt_date(value: Option<NaiveDate>) -> Self {
+        value.map_or(Cell::Empty, Cell::Date)
+    }
+
+    /// Ungefärlig bredd i tecken, för kolumnbredder
+    fn width(&self) -> usize {
+        match self {
+            Cell::Empty => 0,
+            Cell::Text(s) => s.lines().map(|l| l.chars().count()).max().unwrap_or(0),
+            Cell::Number(n) => n.to_string().len(),
+            Cell::Date(_) => DATE_WIDTH,
+            Cell::Bool(_) => 5,
+        }
+    }
+}
+
+/// Ett blad med rubrikrad och datarader
+pub(crate) struct Sheet {
+    name: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Sheet {
+    pub(crate) fn new(name: &str, headers: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Lägg till en extra kolumn i rubrikraden (t.ex. egna fält)
+    pub(crate) fn add_header(&mut self, header: &str) {
+        self.headers.push(header.to_string());
+    }
+
+    pub(crate) fn push_row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    pub(crate) fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn column_count(&self) -> usize {
+        self.rows.iter().map(Vec::len).chain([self.headers.len()]).max().unwrap_or(0).max(1)
+    }
+
+    /// Tabellens område, t.ex. "A1:F10"
+    fn range(&self) -> String {
+        format!("A1:{}{}", column_name(self.column_count() - 1), self.rows.len() + 1)
+    }
+
+    fn to_xml(&self, selected: bool) -> String {
+        let columns = self.column_count();
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+             xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">",
+        );
+        xml.push_str(&format!("<dimension ref=\"{}\"/>", self.range()));
+
+        // Lås rubrikraden
+        xml.push_str(&format!(
+            "<sheetViews><sheetView workbookViewId=\"0\"{}>\
+             <pane ySplit=\"1\" topLeftCell=\"A2\" activePane=\"bottomLeft\" state=\"frozen\"/>\
+             <selection pane=\"bottomLeft\" activeCell=\"A2\" sqref=\"A2\"/></sheetView></sheetViews>",
+            if selected { " tabSelected=\"1\"" } else { "" }
+        ));
+        xml.push_str("<sheetFormatPr defaultRowHeight=\"15\"/>");
+
+        xml.push_str("<cols>");
+        for col in 0..columns {
+            let width = self
+                .rows
+                .iter()
+                .filter_map(|r| r.get(col))
+                .map(Cell::width)
+                .chain([self.headers.get(col).map_or(0, |h| h.chars().count() + 3)])
+                .max()
+                .unwrap_or(0)
+                .clamp(MIN_WIDTH, MAX_WIDTH);
+            xml.push_str(&format!("<col min=\"{0}\" max=\"{0}\" width=\"{1}\" customWidth=\"1\"/>", col + 1, width + 1));
+        }
+        xml.push_str("</cols>");
+
+        xml.push_str("<sheetData>");
+        xml.push_str("<row r=\"1\">");
+        for (col, header) in self.headers.iter().enumerate() {
+            xml.push_str(&text_cell(&cell_ref(col, 1), header, Some(STYLE_HEADER)));
+        }
+        xml.push_str("</row>");
+        for (i, row) in self.rows.iter().enumerate() {
+            let r = i + 2;
+            xml.push_str(&format!("<row r=\"{}\">", r));
+            for (col, cell) in row.iter().enumerate() {
+                let reference = cell_ref(col, r);
+                match cell {
+                    Cell::Empty => {}
+                    Cell::Text(s) => xml.push_str(&text_cell(&reference, s, None)),
+                    Cell::Number(n) if n.is_finite() => {
+                        xml.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, n))
+                    }
+                    Cell::Number(_) => {}
+                    Cell::Date(d) => match date_serial(*d) {
+                        Some(serial) => xml.push_str(&format!(
+                            "<c r=\"{}\" s=\"{}\"><v>{}</v></c>",
+                            reference, STYLE_DATE, serial
+                        )),
+                        None => xml.push_str(&text_cell(&reference, &d.format("%Y-%m-%d").to_string(), None)),
+                    },
+                    Cell::Bool(b) => {
+                        xml.push_str(&format!("<c r=\"{}\" t=\"b\"><v>{}</v></c>", reference, u8::from(*b)))
+                    }
+                }
+            }
+            xml.push_str("</row>");
+        }
+        xml.push_str("</sheetData>");
+        xml.push_str(&format!("<autoFilter ref=\"{}\"/>", self.range()));
+        xml.push_str("</worksheet>");
+        xml
+    }
+}
+
+/// Arbetsbok med ett eller flera blad
+pub(crate) struct Workbook {
+    sheets: Vec<Sheet>,
+    active: usize,
+}
+
+impl Workbook {
+    pub(crate) fn new() -> Self {
+        Self { sheets: Vec::new(), active: 0 }
+    }
+
+    pub(crate) fn add_sheet(&mut self, sheet: Sheet) {
+        self.sheets.push(sheet);
+    }
+
+    /// Bladet som visas när filen öppnas
+    pub(crate) fn set_active(&mut self, name: &str) {
+        if let Some(index) = self.sheets.iter().position(|s| s.name == name) {
+            self.active = index;
+        }
+    }
+
+    /// Bygg .xlsx-filen i minnet
+    pub(crate) fn finish(&self) -> Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        let mut write = |name: &str, content: &str| -> Result<()> {
+            zip.start_file(name, options)?;
+            zip.write_all(content.as_bytes())?;
+            Ok(())
+        };
+
+        write("[Content_Types].xml", &self.content_types())?;
+        write("_rels/.rels", ROOT_RELS)?;
+        write("xl/workbook.xml", &self.workbook_xml())?;
+        write("xl/_rels/workbook.xml.rels", &self.workbook_rels())?;
+        write("xl/styles.xml", STYLES)?;
+        for (i, sheet) in self.sheets.iter().enumerate() {
+            write(&format!("xl/worksheets/sheet{}.xml", i + 1), &sheet.to_xml(i == self.active))?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    }
+
+    /// Spara till fil, returnerar filstorleken
+    pub(crate) fn save(&self, path: &Path) -> Result<usize> {
+        let bytes = self.finish()?;
+        std::fs::write(path, &bytes).context("Kunde inte skriva fil")?;
+        Ok(bytes.len())
+    }
+
+    fn content_types(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+             <Override PartName=\"/xl/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml\"/>",
+        );
+        for i in 1..=self.sheets.len() {
+            xml.push_str(&format!(
+                "<Override PartName=\"/xl/worksheets/sheet{}.xml\" \
+                 ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>",
+                i
+            ));
+        }
+        xml.push_str("</Types>");
+        xml
+    }
+
+    fn workbook_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+             xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">",
+        );
+        xml.push_str(&format!("<bookViews><workbookView activeTab=\"{}\"/></bookViews>", self.active));
+        xml.push_str("<sheets>");
+        for (i, sheet) in self.sheets.iter().enumerate() {
+            xml.push_str(&format!(
+                "<sheet name=\"{}\" sheetId=\"{}\" r:id=\"rId{}\"/>",
+                xml_escape(&sheet_name(&sheet.name)),
+                i + 1,
+                i + 1
+            ));
+        }
+        xml.push_str("</sheets>");
+
+        // Excel förväntar sig ett dolt namn för varje autofilter
+        xml.push_str("<definedNames>");
+        for (i, sheet) in self.sheets.iter().enumerate() {
+            xml.push_str(&format!(
+                "<definedName name=\"_xlnm._FilterDatabase\" localSheetId=\"{}\" hidden=\"1\">'{}'!{}</definedName>",
+                i,
+                xml_escape(&sheet_name(&sheet.name).replace('\'', "''")),
+                absolute_range(&sheet.range())
+            ));
+        }
+        xml.push_str("</definedNames>");
+        xml.push_str("</workbook>");
+        xml
+    }
+
+    fn workbook_rels(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">",
+        );
+        for i in 1..=self.sheets.len() {
+            xml.push_str(&format!(
+                "<Relationship Id=\"rId{0}\" \
+                 Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" \
+                 Target=\"worksheets/sheet{0}.xml\"/>",
+                i
+            ));
+        }
+        xml.push_str(&format!(
+            "<Relationship Id=\"rId{}\" \
+             Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" \
+             Target=\"styles.xml\"/>",
+            self.sheets.len() + 1
+        ));
+        xml.push_str("</Relationships>");
+        xml
+    }
+}
+
+const ROOT_RELS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+<Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+</Relationships>";
+
+/// Stil 0: standard, 1: fetstil rubrik, 2: datum ÅÅÅÅ-MM-DD
+const STYLES: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<styleSheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+<numFmts count=\"1\"><numFmt numFmtId=\"164\" formatCode=\"yyyy\\-mm\\-dd\"/></numFmts>\
+<fonts count=\"2\"><font><sz val=\"11\"/><name val=\"Calibri\"/></font><font><b/><sz val=\"11\"/><name val=\"Calibri\"/></font></fonts>\
+<fills count=\"2\"><fill><patternFill patternType=\"none\"/></fill><fill><patternFill patternType=\"gray125\"/></fill></fills>\
+<borders count=\"1\"><border><left/><right/><top/><bottom/><diagonal/></border></borders>\
+<cellStyleXfs count=\"1\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\"/></cellStyleXfs>\
+<cellXfs count=\"3\">\
+<xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\"/>\
+<xf numFmtId=\"0\" fontId=\"1\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyFont=\"1\"/>\
+<xf numFmtId=\"164\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\"/>\
+</cellXfs>\
+<cellStyles count=\"1\"><cellStyle name=\"Normal\" xfId=\"0\" builtinId=\"0\"/></cellStyles>\
+</styleSheet>";
+
+fn text_cell(reference: &str, text: &str, style: Option<u32>) -> String {
+    let style = style.map(|s| format!(" s=\"{}\"", s)).unwrap_or_default();
+    format!(
+        "<c r=\"{}\"{} t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+        reference,
+        style,
+        xml_escape(&strip_control(text))
+    )
+}
+
+/// XML tillåter inte styrtecken utom tab och radbrytning
+fn strip_control(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .collect()
+}
+
+/// Bladnamn får vara högst 31 tecken och sakna []:*?/\
+fn sheet_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .take(31)
+        .collect()
+}
+
+/// "A1:F10" -> "$A$1:$F$10"
+fn absolute_range(range: &str) -> String {
+    range
+        .split(':')
+        .map(|part| {
+            let split = part.find(|c: char| c.is_ascii_digit()).unwrap_or(part.len());
+            format!("${}${}", &part[..split], &part[split..])
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Kolumnbokstäver: 0 -> A, 25 -> Z, 26 -> AA
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+fn cell_ref(col: usize, row: usize) -> String {
+    format!("{}{}", column_name(col), row)
+}
+
+/// Excels datumnummer (dagar sedan 1899-12-30). Datum före mars 1900
+/// saknas i Excel (och februari 1900 räknas fel), så de blir `None`.
+fn date_serial(date: NaiveDate) -> Option<i64> {
+    let first = NaiveDate::from_ymd_opt(1900, 3, 1)?;
+    let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?;
+    (date >= first).then(|| (date - epoch).num_days())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_cell_helpers() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+        assert_eq!(absolute_range("A1:AB10"), "$A$1:$AB$10");
+        assert_eq!(sheet_name("Personer: [alla]"), "Personer_ _alla_");
+
+        assert_eq!(date_serial(NaiveDate::from_ymd_opt(1900, 3, 1).unwrap()), Some(61));
+        assert_eq!(date_serial(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()), Some(45292));
+        assert_eq!(date_serial(NaiveDate::from_ymd_opt(1850, 1, 1).unwrap()), None);
+    }
+
+    #[test]
+    fn test_workbook_contents() {
+        let mut persons = Sheet::new("Personer", &["Namn", "Född", "Ålder", "Levande"]);
+        persons.push_row(vec![
+            Cell::text("Åsa Öberg & <familj>"),
+            Cell::opt_date(NaiveDate::from_ymd_opt(1950, 5, 17)),
+            Cell::number(74),
+            Cell::Bool(true),
+        ]);
+        persons.push_row(vec![
+            Cell::text("Karl Berg"),
+            Cell::opt_date(NaiveDate::from_ymd_opt(1850, 1, 1)),
+            Cell::Empty,
+            Cell::Bool(false),
+        ]);
+        let mut workbook = Workbook::new();
+        workbook.add_sheet(persons);
+        workbook.add_sheet(Sheet::new("Statistik", &["Uppgift", "Antal"]));
+        workbook.set_active("Statistik");
+
+        let bytes = workbook.finish().unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+            content
+        };
+
+        let sheet = read("xl/worksheets/sheet1.xml");
+        assert!(sheet.contains("Åsa Öberg &amp; &lt;familj&gt;"));
+        assert!(sheet.contains("<c r=\"B2\" s=\"2\"><v>18400</v></c>"));
+        assert!(sheet.contains("<c r=\"C2\"><v>74</v></c>"));
+        assert!(sheet.contains("<c r=\"D2\" t=\"b\"><v>1</v></c>"));
+        assert!(sheet.contains("1850-01-01"), "datum före 1900 skrivs som text");
+        assert!(sheet.contains("state=\"frozen\""));
+        assert!(sheet.contains("<autoFilter ref=\"A1:D3\"/>"));
+        assert!(!sheet.contains("tabSelected"));
+
+        let book = read("xl/workbook.xml");
+        assert!(book.contains("activeTab=\"1\""));
+        assert!(book.contains("<sheet name=\"Statistik\" sheetId=\"2\" r:id=\"rId2\"/>"));
+        assert!(book.contains("'Personer'!$A$1:$D$3"));
+        assert!(read("[Content_Types].xml").contains("/xl/worksheets/sheet2.xml"));
+        assert!(read("xl/_rels/workbook.xml.rels").contains("Target=\"styles.xml\""));
+    }
+}
@@ -1,17 +1,19 @@
 use chrono::NaiveDate;
 use egui::{self, RichText};
 
-use crate::db::Database;
-use crate::models::Person;
+use crate::db::{Database, PersonRepository, RelationshipRepository};
+use crate::models::{CustomFieldEntity, Person, PersonRelationship, Privacy, Sex};
 use crate::ui::{
-    state::{AppState, PersonFormData},
+    state::{AppState, PendingRelation, PersonFormData},
     theme::{Colors, Icons},
+    widgets::{custom_field_editor, validate_custom_values},
 };
 
 pub struct PersonFormModal {
     form_data: PersonFormData,
     error_message: Option<String>,
     auto_generate_dir: bool,  // Auto-generera katalognamn
+    prefilled_for: Option<PendingRelation>,  // Relation vars förval fyllts i
 }
 
 impl PersonFormModal {
@@ -20,6 +22,7 @@ impl PersonFormModal {
             form_data: PersonFormData::default(),
             error_message: None,
             auto_generate_dir: true,
+            prefilled_for: None,
         }
     }
 
@@ -33,17 +36,34 @@ impl PersonFormModal {
             if self.form_data.directory_name.is_empty() {
                 if let Ok(Some(person)) = db.persons().find_by_id(person_id) {
                     self.form_data = PersonFormData::from_person(&person);
+                    self.form_data.custom_values = db
+                        .custom_fields()
+                        .get_values(CustomFieldEntity::Person, person_id)
+                        .unwrap_or_default();
                     self.auto_generate_dir = false;  // Redigerar befintlig, auto-generera inte
                 }
             }
         }
 
+        // Förval för ny person som ska kopplas till någon (t.ex. far från trädvyn)
+        if state.editing_person_id.is_none() && state.pending_relation != self.prefilled_for {
+            if let Some(relation) = state.pending_relation {
+                self.form_data.sex = relation.sex;
+            }
+            self.prefilled_for = state.pending_relation;
+        }
+
         let title = if state.editing_person_id.is_some() {
             "Redigera person"
         } else {
             "Ny person"
         };
 
+        let related_to = state
+            .pending_relation
+            .filter(|_| state.editing_person_id.is_none())
+            .and_then(|r| Some((r, db.persons().find_by_id(r.person_id).ok()??)));
+
         egui::Window::new(title)
             .collapsible(false)
             .resizable(false)
@@ -51,6 +71,18 @@ impl PersonFormModal {
             .show(ctx, |ui| {
                 ui.set_min_width(400.0);
 
+                if let Some((relation, person)) = &related_to {
+                    ui.label(
+                        RichText::new(format!(
+                            "Blir {} till {}",
+                            relation.relationship_type.display_name().to_lowercase(),
+                            person.full_name()
+                        ))
+                        .color(Colors::TEXT_SECONDARY),
+                    );
+                    ui.add_space(8.0);
+                }
+
                 // Formulär
                 let mut name_changed = false;
 
@@ -72,6 +104,16 @@ impl PersonFormModal {
                         }
                         ui.end_row();
 
+                        ui.label("Kön:");
+                        egui::ComboBox::from_id_salt("person_form_sex")
+                            .selected_text(self.form_data.sex.display_name())
+                            .show_ui(ui, |ui| {
+                                for sex in Sex::all() {
+                                    ui.selectable_value(&mut self.form_data.sex, *sex, sex.display_name());
+                                }
+                            });
+                        ui.end_row();
+
                         ui.label("Födelseort:");
                         ui.text_edit_singleline(&mut self.form_data.birth_place);
                         ui.end_row();
@@ -116,6 +158,21 @@ impl PersonFormModal {
                         });
                         ui.end_row();
 
+                        ui.label("Sekretess:");
+                        egui::ComboBox::from_id_salt("person_form_privacy")
+                            .selected_text(self.form_data.privacy.display_name())
+                            .show_ui(ui, |ui| {
+                                for privacy in Privacy::all() {
+                                    ui.selectable_value(&mut self.form_data.privacy, *privacy, privacy.display_name());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Anteckningar:");
+                        ui.add(egui::TextEdit::multiline(&mut self.form_data.notes)
+                            .desired_rows(3)
+                            .desired_width(f32::INFINITY));
+                        ui.end_row();
                     });
 
                 // Auto-generera katalognamn om aktiverat och namn ändrats
@@ -129,6 +186,14 @@ impl PersonFormModal {
                     );
                 }
 
+                // Egna fält
+                let definitions = db.custom_fields().find_definitions(CustomFieldEntity::Person).unwrap_or_default();
+                if !definitions.is_empty() {
+                    ui.add_space(8.0);
+                    ui.label(RichText::new("Egna fält").strong());
+                    custom_field_editor(ui, "person_form_custom_fields", &definitions, &mut self.form_data.custom_values);
+                }
+
                 // Felmeddelande
                 if let Some(ref error) = self.error_message {
                     ui.add_space(8.0);
@@ -189,6 +254,9 @@ impl PersonFormModal {
                 .map_err(|_| anyhow::anyhow!("Ogiltigt dödsdatum (använd YYYY-MM-DD)"))?)
         };
 
+        let definitions = db.custom_fields().find_definitions(CustomFieldEntity::Person)?;
+        validate_custom_values(&definitions, &self.form_data.custom_values).map_err(|e| anyhow::anyhow!(e))?;
+
         // Kontrollera att katalognamn är unikt
         if !db.persons().is_directory_name_unique(&self.form_data.directory_name, state.editing_person_id)? {
             return Err(anyhow::anyhow!("Katalognamnet används redan"));
@@ -206,6 +274,8 @@ impl PersonFormModal {
             Some(self.form_data.surname.clone())
         };
 
+        let notes = Some(self.form_data.notes.trim().to_string()).filter(|n| !n.is_empty());
+
         if let Some(person_id) = state.editing_person_id {
             // Uppdatera befintlig
             let mut person = db.persons().find_by_id(person_id)?
@@ -218,8 +288,12 @@ impl PersonFormModal {
             person.death_date = death_date;
             person.occupation = if self.form_data.occupation.is_empty() { None } else { Some(self.form_data.occupation.clone()) };
             person.directory_name = self.form_data.directory_name.clone();
+            person.notes = notes;
+            person.privacy = self.form_data.privacy;
+            person.sex = self.form_data.sex;
 
             db.persons().update(&mut person)?;
+            db.custom_fields().set_values(person_id, &self.form_data.custom_values)?;
         } else {
             // Skapa ny
             let birth_place = if self.form_data.birth_place.is_empty() { None } else { Some(self.form_data.birth_place.clone()) };
@@ -236,11 +310,27 @@ impl PersonFormModal {
                 age: None,
                 directory_name: self.form_data.directory_name.clone(),
                 profile_image_path: None,
+                notes,
+                privacy: self.form_data.privacy,
+                sex: self.form_data.sex,
                 created_at: None,
                 updated_at: None,
             };
 
-            db.persons().create(&mut person)?;
+            // Personen och en förvald relation skapas i samma transaktion,
+            // så att ett fel inte lämnar en ny person utan sin relation
+            let pending_relation = state.pending_relation;
+            let person_id = db.with_connection(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                let person_id = PersonRepository::insert(&tx, &mut person)?;
+                if let Some(relation) = pending_relation {
+                    let mut relationship = PersonRelationship::new(person_id, relation.person_id, relation.relationship_type);
+                    RelationshipRepository::insert(&tx, &mut relationship)?;
+                }
+                tx.commit()?;
+                Ok(person_id)
+            })?;
+            db.custom_fields().set_values(person_id, &self.form_data.custom_values)?;
 
             // Skapa katalog
             let config = db.config().get()?;
@@ -255,5 +345,6 @@ impl PersonFormModal {
         self.form_data.clear();
         self.error_message = None;
         self.auto_generate_dir = true;
+        self.prefilled_for = None;
     }
 }
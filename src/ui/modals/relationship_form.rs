@@ -1,5 +1,6 @@
 //! Modal för att skapa relationer mellan personer
 
+use chrono::NaiveDate;
 use egui::{self, RichText};
 
 use crate::db::Database;
@@ -19,6 +20,10 @@ pub struct RelationshipFormModal {
     selected_relationship_type: Option<RelationshipType>,
     /// Söktext för personfiltrering
     search_query: String,
+    /// Vigseldatum (YYYY-MM-DD) för make/maka
+    marriage_date: String,
+    /// Vigselort för make/maka
+    marriage_place: String,
     /// Felmeddelande
     error_message: Option<String>,
     /// Behöver refresha
@@ -38,6 +43,8 @@ impl RelationshipFormModal {
             selected_other_person_id: None,
             selected_relationship_type: None,
             search_query: String::new(),
+            marriage_date: String::new(),
+            marriage_place: String::new(),
             error_message: None,
             needs_refresh: true,
         }
@@ -48,6 +55,8 @@ impl RelationshipFormModal {
         self.selected_other_person_id = None;
         self.selected_relationship_type = None;
         self.search_query.clear();
+        self.marriage_date.clear();
+        self.marriage_place.clear();
         self.error_message = None;
         self.needs_refresh = true;
     }
@@ -62,6 +71,10 @@ impl RelationshipFormModal {
     ) -> bool {
         let mut should_close = false;
 
+        if let Some(rel_type) = state.relationship_form_type.take() {
+            self.selected_relationship_type = Some(rel_type);
+        }
+
         // Ladda personer om nödvändigt
         if self.needs_refresh {
             self.persons_cache = db.persons().find_all().unwrap_or_default();
@@ -112,6 +125,23 @@ impl RelationshipFormModal {
                     ui.label(RichText::new(description).small().color(Colors::TEXT_SECONDARY));
                 }
 
+                // Vigseluppgifter för make/maka
+                if self.selected_relationship_type == Some(RelationshipType::Spouse) {
+                    ui.add_space(8.0);
+                    egui::Grid::new("marriage_grid").num_columns(2).spacing([8.0, 4.0]).show(ui, |ui| {
+                        ui.label("Vigseldatum:");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.marriage_date).desired_width(100.0));
+                            ui.label(RichText::new("YYYY-MM-DD").small().color(Colors::TEXT_MUTED));
+                        });
+                        ui.end_row();
+
+                        ui.label("Vigselort:");
+                        ui.text_edit_singleline(&mut self.marriage_place);
+                        ui.end_row();
+                    });
+                }
+
                 ui.add_space(12.0);
 
                 // Sök/filtrera personer
@@ -240,6 +270,17 @@ impl RelationshipFormModal {
             relationship_type,
         );
 
+        if relationship_type == RelationshipType::Spouse {
+            let date = self.marriage_date.trim();
+            if !date.is_empty() {
+                relationship.marriage_date = Some(
+                    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| anyhow::anyhow!("Ogiltigt vigseldatum (använd YYYY-MM-DD)"))?,
+                );
+            }
+            relationship.marriage_place = Some(self.marriage_place.trim().to_string()).filter(|p| !p.is_empty());
+        }
+
         db.relationships().create(&mut relationship)?;
 
         tracing::info!(
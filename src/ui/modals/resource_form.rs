@@ -1,9 +1,15 @@
+use std::collections::HashMap;
+
 use egui::{self, RichText};
 
 use crate::db::Database;
-use crate::models::{Resource, ResourceType};
+use crate::models::{CustomFieldDefinition, CustomFieldEntity, Resource, ResourceType};
 use crate::models::resource::sanitize_directory_name;
-use crate::ui::{state::AppState, theme::{Colors, Icons}};
+use crate::ui::{
+    state::AppState,
+    theme::{Colors, Icons},
+    widgets::{custom_field_editor, validate_custom_values},
+};
 
 pub struct ResourceFormModal {
     name: String,
@@ -16,6 +22,9 @@ pub struct ResourceFormModal {
     /// true = katalognamn redigerat manuellt (låst)
     dir_locked: bool,
     types_cache: Vec<ResourceType>,
+    /// Egna fält för resurser
+    custom_definitions: Vec<CustomFieldDefinition>,
+    custom_values: HashMap<i64, String>,
     error_message: Option<String>,
     loaded_for_id: Option<i64>,
 }
@@ -32,6 +41,8 @@ impl ResourceFormModal {
             directory_name: String::new(),
             dir_locked: false,
             types_cache: Vec::new(),
+            custom_definitions: Vec::new(),
+            custom_values: HashMap::new(),
             error_message: None,
             loaded_for_id: None,
         }
@@ -46,6 +57,14 @@ impl ResourceFormModal {
             }
         }
 
+        // Fältdefinitioner laddas om när formuläret öppnas (se clear)
+        if self.custom_definitions.is_empty() {
+            self.custom_definitions = db
+                .custom_fields()
+                .find_definitions(CustomFieldEntity::Resource)
+                .unwrap_or_default();
+        }
+
         // Välj standardtyp om ingen vald
         if self.resource_type_id.is_none() {
             if let Some(first) = self.types_cache.first() {
@@ -67,6 +86,10 @@ impl ResourceFormModal {
                     self.lon_str = resource.lon.map(|v| v.to_string()).unwrap_or_default();
                     self.directory_name = resource.directory_name.clone();
                     self.dir_locked = true;
+                    self.custom_values = db
+                        .custom_fields()
+                        .get_values(CustomFieldEntity::Resource, id)
+                        .unwrap_or_default();
                     self.error_message = None;
                 }
             } else {
@@ -156,6 +179,18 @@ impl ResourceFormModal {
 
                     ui.add_space(8.0);
 
+                    // Egna fält
+                    if !self.custom_definitions.is_empty() {
+                        ui.label(RichText::new("Egna fält").strong());
+                        custom_field_editor(
+                            ui,
+                            "resource_form_custom_fields",
+                            &self.custom_definitions,
+                            &mut self.custom_values,
+                        );
+                        ui.add_space(8.0);
+                    }
+
                     // Katalognamn
                     ui.horizontal(|ui| {
                         ui.label(RichText::new("Katalognamn").strong());
@@ -226,6 +261,11 @@ impl ResourceFormModal {
             self.directory_name = sanitize_directory_name(&self.name);
         }
 
+        if let Err(msg) = validate_custom_values(&self.custom_definitions, &self.custom_values) {
+            self.error_message = Some(msg);
+            return Some(false);
+        }
+
         let lat = if self.lat_str.is_empty() {
             None
         } else {
@@ -259,7 +299,11 @@ impl ResourceFormModal {
 
         if let Some(editing_id) = state.editing_resource_id {
             resource.id = Some(editing_id);
-            match db.resources().update(&resource) {
+            match db
+                .resources()
+                .update(&resource)
+                .and_then(|_| db.custom_fields().set_values(editing_id, &self.custom_values))
+            {
                 Ok(_) => {
                     state.show_success("Resurs uppdaterad");
                     state.close_resource_form();
@@ -271,7 +315,12 @@ impl ResourceFormModal {
                 }
             }
         } else {
-            match db.resources().create(&resource) {
+            let result = db.resources().create(&resource).and_then(|created| {
+                db.custom_fields()
+                    .set_values(created.id.unwrap_or(0), &self.custom_values)
+                    .map(|_| created)
+            });
+            match result {
                 Ok(created) => {
                     state.show_success("Resurs skapad");
                     state.navigate_to_resource(created.id.unwrap_or(0));
@@ -298,5 +347,7 @@ impl ResourceFormModal {
         self.error_message = None;
         self.loaded_for_id = None;
         self.types_cache.clear();
+        self.custom_definitions.clear();
+        self.custom_values.clear();
     }
 }
@@ -21,6 +21,7 @@ pub enum View {
     DocumentTemplates,
     ResourceList,
     ResourceDetail,
+    Calendar,
 }
 
 /// Centraliserat applikationstillstånd
@@ -44,9 +45,18 @@ pub struct AppState {
     /// Person som redigeras (None = ny person)
     pub editing_person_id: Option<i64>,
 
+    /// Ny person som ska kopplas till en befintlig när den sparats
+    pub pending_relation: Option<PendingRelation>,
+
     /// Visar relationsformulär
     pub show_relationship_form: bool,
 
+    /// Person som relationsformuläret gäller (None = vald person)
+    pub relationship_form_person_id: Option<i64>,
+
+    /// Förvald relationstyp i relationsformuläret
+    pub relationship_form_type: Option<crate::models::RelationshipType>,
+
     /// Visar dokumentuppladdningsmodal
     pub show_document_upload: bool,
 
@@ -192,10 +202,35 @@ impl AppState {
         self.show_person_form = true;
     }
 
+    /// Öppna personformulär för en ny person som kopplas till `relation.person_id`
+    pub fn open_new_related_person_form(&mut self, relation: PendingRelation) {
+        self.open_new_person_form();
+        self.pending_relation = Some(relation);
+    }
+
     /// Stäng personformulär
     pub fn close_person_form(&mut self) {
         self.show_person_form = false;
         self.editing_person_id = None;
+        self.pending_relation = None;
+    }
+
+    /// Öppna relationsformuläret för en viss person, ev. med förvald typ
+    pub fn open_relationship_form(
+        &mut self,
+        person_id: i64,
+        relationship_type: Option<crate::models::RelationshipType>,
+    ) {
+        self.relationship_form_person_id = Some(person_id);
+        self.relationship_form_type = relationship_type;
+        self.show_relationship_form = true;
+    }
+
+    /// Stäng relationsformulär
+    pub fn close_relationship_form(&mut self) {
+        self.show_relationship_form = false;
+        self.relationship_form_person_id = None;
+        self.relationship_form_type = None;
     }
 
     /// Navigera till resursdetalj
@@ -309,6 +344,11 @@ pub struct PersonFormData {
     pub death_date: String,
     pub occupation: String,
     pub directory_name: String,
+    pub notes: String,
+    pub privacy: crate::models::Privacy,
+    pub sex: crate::models::Sex,
+    /// Egna fält (fält-ID -> värde)
+    pub custom_values: std::collections::HashMap<i64, String>,
 }
 
 impl PersonFormData {
@@ -331,10 +371,43 @@ impl PersonFormData {
                 .unwrap_or_default(),
             occupation: person.occupation.clone().unwrap_or_default(),
             directory_name: person.directory_name.clone(),
+            notes: person.notes.clone().unwrap_or_default(),
+            privacy: person.privacy,
+            sex: person.sex,
+            custom_values: Default::default(),
         }
     }
 }
 
+/// Relation som skapas när en ny person sparas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingRelation {
+    /// Befintlig person
+    pub person_id: i64,
+    /// Vad den nya personen är för den befintliga
+    pub relationship_type: crate::models::RelationshipType,
+    /// Förvalt kön för den nya personen
+    pub sex: crate::models::Sex,
+}
+
+impl PendingRelation {
+    pub fn father_of(person_id: i64) -> Self {
+        Self { person_id, relationship_type: crate::models::RelationshipType::Parent, sex: crate::models::Sex::Male }
+    }
+
+    pub fn mother_of(person_id: i64) -> Self {
+        Self { person_id, relationship_type: crate::models::RelationshipType::Parent, sex: crate::models::Sex::Female }
+    }
+
+    pub fn spouse_of(person_id: i64) -> Self {
+        Self { person_id, relationship_type: crate::models::RelationshipType::Spouse, sex: crate::models::Sex::Unknown }
+    }
+
+    pub fn child_of(person_id: i64) -> Self {
+        Self { person_id, relationship_type: crate::models::RelationshipType::Child, sex: crate::models::Sex::Unknown }
+    }
+}
+
 /// Formulärdata för relation
 #[derive(Debug, Default, Clone)]
 pub struct RelationshipFormData {
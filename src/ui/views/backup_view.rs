@@ -5,7 +5,8 @@ use std::path::PathBuf;
 use egui::{self, RichText};
 
 use crate::db::Database;
-use crate::services::{BackupInfo, BackupService, RestorePreview, RestoreService};
+use crate::services::project_json::ProjectFile;
+use crate::services::{BackupInfo, BackupService, ProjectJsonService, RestorePreview, RestoreService};
 use crate::ui::{
     state::AppState,
     theme::{Colors, Icons},
@@ -38,6 +39,8 @@ pub struct BackupView {
     restore_db: bool,
     /// Restore-alternativ: återställ media
     restore_media: bool,
+    /// Inläst projektfil som väntar på bekräftelse
+    pending_import: Option<(PathBuf, ProjectFile)>,
     /// Behöver uppdateras
     needs_refresh: bool,
 }
@@ -59,6 +62,7 @@ impl BackupView {
             confirm_restore: false,
             restore_db: true,
             restore_media: true,
+            pending_import: None,
             needs_refresh: true,
         }
     }
@@ -94,6 +98,11 @@ impl BackupView {
             if self.confirm_restore {
                 self.show_restore_confirm(ui, state, db);
             }
+
+            ui.add_space(24.0);
+
+            // Projektfil (JSON)
+            self.show_project_file_section(ui, state, db);
         });
     }
 
@@ -387,6 +396,102 @@ impl BackupView {
         }
     }
 
+    fn show_project_file_section(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        ui.heading("Projektfil (JSON)");
+        ui.add_space(8.0);
+
+        ui.label("Hela projektet som en läsbar JSON-fil: personer, relationer, dokumentmetadata, mallar, resurser och inställningar. Mediafilerna ingår inte.");
+        ui.label(
+            RichText::new("Import fungerar bara i ett nytt, tomt projekt.")
+                .small()
+                .color(Colors::TEXT_MUTED),
+        );
+        ui.add_space(8.0);
+
+        ui.horizontal(|ui| {
+            if ui.button(format!("{} Exportera projektfil", Icons::EXPORT)).clicked() {
+                self.export_project_file(state, db);
+            }
+            if ui.button(format!("{} Importera projektfil", Icons::IMPORT)).clicked() {
+                self.load_project_file(state);
+            }
+        });
+
+        let Some((path, project)) = &self.pending_import else {
+            return;
+        };
+
+        let mut cancel = false;
+        let mut confirm = false;
+        ui.add_space(12.0);
+        egui::Frame::none()
+            .fill(Colors::WARNING_BG)
+            .rounding(4.0)
+            .inner_margin(12.0)
+            .show(ui, |ui| {
+                ui.label(RichText::new(format!("Importera {}?", path.display())).strong());
+                ui.add_space(4.0);
+                ui.label(project.summary().display());
+                if let Some(exported_at) = &project.exported_at {
+                    ui.label(
+                        RichText::new(format!("Exporterad {}", exported_at))
+                            .small()
+                            .color(Colors::TEXT_SECONDARY),
+                    );
+                }
+                ui.label(
+                    RichText::new("Dokumenttyper, mallar och resurstyper i projektet ersätts med filens.")
+                        .color(Colors::TEXT_SECONDARY),
+                );
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    cancel = ui.button("Avbryt").clicked();
+                    confirm = ui.button(RichText::new("Importera").color(Colors::WARNING)).clicked();
+                });
+            });
+
+        if cancel {
+            self.pending_import = None;
+        } else if confirm {
+            if let Some((_, project)) = self.pending_import.take() {
+                match ProjectJsonService::new(db).import_project(&project) {
+                    Ok(summary) => state.show_success(&format!("Projekt importerat: {}", summary.display())),
+                    Err(e) => state.show_error(&format!("Import misslyckades: {:#}", e)),
+                }
+            }
+        }
+    }
+
+    fn export_project_file(&mut self, state: &mut AppState, db: &Database) {
+        let filename = format!("genlib_projekt_{}.json", chrono::Local::now().format("%Y%m%d"));
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&filename)
+            .add_filter("JSON", &["json"])
+            .save_file()
+        else {
+            return;
+        };
+
+        match ProjectJsonService::new(db).export(&path) {
+            Ok(summary) => state.show_success(&format!("Projektfil sparad: {}", summary.display())),
+            Err(e) => state.show_error(&format!("Export misslyckades: {}", e)),
+        }
+    }
+
+    fn load_project_file(&mut self, state: &mut AppState) {
+        let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+
+        let loaded = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| ProjectFile::from_json(&json));
+        match loaded {
+            Ok(project) => self.pending_import = Some((path, project)),
+            Err(e) => state.show_error(&format!("Kunde inte läsa projektfil: {}", e)),
+        }
+    }
+
     fn refresh_backups(&mut self, db: &Database) {
         let backup_service = BackupService::new(db);
         self.backups = backup_service.list_backups().unwrap_or_default();
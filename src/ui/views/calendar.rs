@@ -0,0 +1,221 @@
+use chrono::{Local, NaiveDate};
+use egui::{self, RichText};
+
+use crate::db::Database;
+use crate::models::Tag;
+use crate::services::{Anniversary, AnniversaryScope, AnniversaryService, PrivacyPolicy, RedactionStyle};
+use crate::ui::{state::AppState, theme::{Colors, Icons}};
+
+/// Valbara tidsintervall (dagar)
+const RANGES: [(i64, &str); 4] = [(7, "7 dagar"), (30, "30 dagar"), (90, "3 månader"), (365, "Ett år")];
+
+pub struct CalendarView {
+    days: i64,
+    scope: AnniversaryScope,
+    /// Utelämna levande och privata personer i .ics-exporten
+    omit_private: bool,
+    anniversaries: Vec<Anniversary>,
+    tags_cache: Vec<Tag>,
+    needs_refresh: bool,
+}
+
+impl Default for CalendarView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CalendarView {
+    pub fn new() -> Self {
+        Self {
+            days: 30,
+            scope: AnniversaryScope::All,
+            omit_private: true,
+            anniversaries: Vec::new(),
+            tags_cache: Vec::new(),
+            needs_refresh: true,
+        }
+    }
+
+    pub fn mark_needs_refresh(&mut self) {
+        self.needs_refresh = true;
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        if self.needs_refresh {
+            self.refresh(db);
+            self.needs_refresh = false;
+        }
+
+        ui.vertical(|ui| {
+            // Header
+            ui.horizontal(|ui| {
+                ui.heading(format!("{} Kalender", Icons::CALENDAR));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button(format!("{} Exportera .ics", Icons::EXPORT)).clicked() {
+                        self.export_ics(state, db);
+                    }
+                    ui.checkbox(&mut self.omit_private, "Utelämna levande och privata")
+                        .on_hover_text("Gäller exporten. Levande och privata personer tas inte med i kalenderfilen.");
+                });
+            });
+
+            ui.add_space(8.0);
+
+            // Intervall + urval
+            ui.horizontal(|ui| {
+                let mut changed = false;
+                ui.label("Visa:");
+                egui::ComboBox::from_id_salt("calendar_range")
+                    .selected_text(range_label(self.days))
+                    .show_ui(ui, |ui| {
+                        for (days, label) in RANGES {
+                            changed |= ui.selectable_value(&mut self.days, days, label).changed();
+                        }
+                    });
+
+                ui.add_space(8.0);
+                ui.label("Personer:");
+                egui::ComboBox::from_id_salt("calendar_scope")
+                    .selected_text(self.scope_label())
+                    .show_ui(ui, |ui| {
+                        changed |= ui.selectable_value(&mut self.scope, AnniversaryScope::All, "Alla").changed();
+                        changed |= ui
+                            .selectable_value(&mut self.scope, AnniversaryScope::Bookmarked, format!("{} Bokmärkta", Icons::BOOKMARK))
+                            .changed();
+                        for tag in &self.tags_cache {
+                            if let Some(id) = tag.id {
+                                changed |= ui
+                                    .selectable_value(&mut self.scope, AnniversaryScope::Tag(id), format!("🏷 {}", tag.name))
+                                    .changed();
+                            }
+                        }
+                    });
+
+                if changed {
+                    self.refresh(db);
+                }
+            });
+
+            ui.add_space(12.0);
+
+            if self.anniversaries.is_empty() {
+                ui.label(
+                    RichText::new("Inga årsdagar under perioden. Födelse-, döds- och vigseldatum behövs för att årsdagar ska visas.")
+                        .color(Colors::TEXT_SECONDARY),
+                );
+                return;
+            }
+
+            ui.label(
+                RichText::new(format!("{} årsdagar", self.anniversaries.len()))
+                    .small()
+                    .color(Colors::TEXT_MUTED),
+            );
+            ui.add_space(4.0);
+
+            let today = Local::now().date_naive();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let mut last_date = None;
+                for item in &self.anniversaries {
+                    if last_date != Some(item.next) {
+                        last_date = Some(item.next);
+                        ui.add_space(6.0);
+                        ui.label(RichText::new(day_label(item.next, today)).strong());
+                    }
+                    show_anniversary(ui, state, item);
+                }
+            });
+        });
+    }
+
+    fn scope_label(&self) -> String {
+        match self.scope {
+            AnniversaryScope::All => "Alla".to_string(),
+            AnniversaryScope::Bookmarked => format!("{} Bokmärkta", Icons::BOOKMARK),
+            AnniversaryScope::Tag(id) => self
+                .tags_cache
+                .iter()
+                .find(|t| t.id == Some(id))
+                .map(|t| format!("🏷 {}", t.name))
+                .unwrap_or_else(|| "Alla".to_string()),
+        }
+    }
+
+    fn refresh(&mut self, db: &Database) {
+        self.tags_cache = db.tags().find_all().unwrap_or_default();
+        if let AnniversaryScope::Tag(id) = self.scope {
+            if !self.tags_cache.iter().any(|t| t.id == Some(id)) {
+                self.scope = AnniversaryScope::All;
+            }
+        }
+        self.anniversaries = AnniversaryService::new(db)
+            .upcoming(Local::now().date_naive(), self.days, self.scope)
+            .unwrap_or_default();
+    }
+
+    fn export_ics(&mut self, state: &mut AppState, db: &Database) {
+        let today = Local::now().date_naive();
+        let filename = format!("genlib_arsdagar_{}.ics", today.format("%Y%m%d"));
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&filename)
+            .add_filter("iCalendar", &["ics"])
+            .save_file()
+        else {
+            return;
+        };
+
+        let mut service = AnniversaryService::new(db);
+        if self.omit_private {
+            match PrivacyPolicy::from_config(db, RedactionStyle::Omit) {
+                Ok(policy) => service = service.with_privacy(policy),
+                Err(e) => {
+                    state.show_error(&format!("Kunde inte läsa sekretessinställningar: {}", e));
+                    return;
+                }
+            }
+        }
+
+        match service.export_ics(&path, today, self.scope) {
+            Ok(count) => state.show_success(&format!("{} årsdagar sparade till {}", count, path.display())),
+            Err(e) => state.show_error(&format!("Export misslyckades: {}", e)),
+        }
+    }
+}
+
+/// En rad i kalendern eller dashboardens veckoruta
+pub fn show_anniversary(ui: &mut egui::Ui, state: &mut AppState, item: &Anniversary) {
+    ui.horizontal(|ui| {
+        ui.label(item.kind.icon());
+        let text = if item.is_round() {
+            RichText::new(item.title()).strong().color(Colors::PRIMARY)
+        } else {
+            RichText::new(item.title())
+        };
+        if ui.link(text).clicked() {
+            state.navigate_to_person(item.person_id);
+        }
+        let mut details = item.date.format("%Y-%m-%d").to_string();
+        if let Some(place) = &item.place {
+            details.push_str(&format!(", {}", place));
+        }
+        ui.label(RichText::new(details).small().color(Colors::TEXT_MUTED));
+    });
+}
+
+fn range_label(days: i64) -> &'static str {
+    RANGES
+        .iter()
+        .find(|(d, _)| *d == days)
+        .map(|(_, label)| *label)
+        .unwrap_or("")
+}
+
+/// "Idag", "Imorgon" eller "2025-06-14 (om 5 dagar)"
+fn day_label(date: NaiveDate, today: NaiveDate) -> String {
+    match (date - today).num_days() {
+        0 => format!("Idag, {}", date.format("%Y-%m-%d")),
+        1 => format!("Imorgon, {}", date.format("%Y-%m-%d")),
+        n => format!("{} (om {} dagar)", date.format("%Y-%m-%d"), n),
+    }
+}
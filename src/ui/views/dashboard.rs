@@ -1,7 +1,12 @@
+use chrono::Local;
 use egui::{self, Color32, RichText};
 
 use crate::db::Database;
-use crate::ui::{state::AppState, theme::{Colors, Icons}, View};
+use crate::services::{Anniversary, AnniversaryScope, AnniversaryService};
+use crate::ui::{state::AppState, theme::{Colors, Icons}, views::calendar::show_anniversary, View};
+
+/// Antal årsdagar som visas i veckorutan
+const WEEK_LIMIT: usize = 8;
 
 pub struct DashboardView {
     // Cachad statistik
@@ -11,6 +16,8 @@ pub struct DashboardView {
     total_size: i64,
     tasks_completed: i64,
     tasks_total: i64,
+    /// Årsdagar de närmaste sju dagarna
+    week_anniversaries: Vec<Anniversary>,
     needs_refresh: bool,
 }
 
@@ -23,6 +30,7 @@ impl DashboardView {
             total_size: 0,
             tasks_completed: 0,
             tasks_total: 0,
+            week_anniversaries: Vec::new(),
             needs_refresh: true,
         }
     }
@@ -58,6 +66,18 @@ impl DashboardView {
 
             ui.add_space(24.0);
 
+            // Årsdagar
+            ui.horizontal(|ui| {
+                ui.heading(format!("{} Den här veckan", Icons::CALENDAR));
+                if ui.link("Visa kalender").clicked() {
+                    state.navigate(View::Calendar);
+                }
+            });
+            ui.add_space(8.0);
+            self.show_week_anniversaries(ui, state);
+
+            ui.add_space(24.0);
+
             // Snabbåtgärder
             ui.heading("Snabbåtgärder");
             ui.add_space(8.0);
@@ -159,6 +179,22 @@ impl DashboardView {
         }
     }
 
+    fn show_week_anniversaries(&self, ui: &mut egui::Ui, state: &mut AppState) {
+        if self.week_anniversaries.is_empty() {
+            ui.label(RichText::new("Inga födelsedagar, dödsdagar eller bröllopsdagar den här veckan.").color(Colors::TEXT_SECONDARY));
+            return;
+        }
+
+        for item in self.week_anniversaries.iter().take(WEEK_LIMIT) {
+            show_anniversary(ui, state, item);
+        }
+
+        let rest = self.week_anniversaries.len().saturating_sub(WEEK_LIMIT);
+        if rest > 0 {
+            ui.label(RichText::new(format!("... och {} till", rest)).small().color(Colors::TEXT_MUTED));
+        }
+    }
+
     fn show_recent_tasks(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
         let recent = db.checklists().find_recent(5).unwrap_or_default();
 
@@ -209,6 +245,9 @@ impl DashboardView {
         let (completed, total) = db.checklists().get_global_progress().unwrap_or((0, 0));
         self.tasks_completed = completed;
         self.tasks_total = total;
+        self.week_anniversaries = AnniversaryService::new(db)
+            .upcoming(Local::now().date_naive(), 7, AnniversaryScope::All)
+            .unwrap_or_default();
     }
 
     pub fn mark_needs_refresh(&mut self) {
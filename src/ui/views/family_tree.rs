@@ -1,11 +1,20 @@
 //! Familjeträd-vy för visualisering av släktrelationer
 
-use egui::{self, Color32, Pos2, Rect, RichText, Stroke, Vec2};
+use std::collections::HashMap;
+
+use egui::{self, emath::Rot2, epaint::TextShape, Color32, Mesh, Pos2, Rect, RichText, Shape, Stroke, Vec2};
 
 use crate::db::Database;
-use crate::services::{FamilyTree, FamilyTreeService, LinkType};
+use crate::models::{RelationshipType, RelationshipView, Sex};
+use crate::services::chart_export::{ChartColorScheme, PageMode, PaperSize};
+use crate::services::fan_chart::{fan_segments, research_completeness, segment_at, FanSegment};
+use crate::services::{
+    ChartExportService, ChartFormat, ChartOptions, FamilyTree, FamilyTreeService, FanShape, LinkType, RedactionStyle,
+    TreeMode,
+};
+use crate::services::family_tree::split_parents;
 use crate::ui::{
-    state::AppState,
+    state::{AppState, ConfirmAction, PendingRelation},
     theme::{Colors, Icons},
     View,
 };
@@ -16,8 +25,22 @@ pub struct FamilyTreeView {
     tree: Option<FamilyTree>,
     /// Person som trädet är centrerat kring
     focus_person_id: Option<i64>,
-    /// Antal generationer att visa
-    generations: i32,
+    /// Vald trädtyp
+    mode: TreeMode,
+    /// Antal generationer att visa per trädtyp
+    generations: HashMap<TreeMode, i32>,
+    /// Solfjäderns form
+    fan_shape: FanShape,
+    /// Forskningsgrad per person (0.0-1.0), för solfjädern
+    completeness: HashMap<i64, f32>,
+    /// Inställningar för export till SVG/PDF
+    export_options: ChartOptions,
+    /// Det som högerklickades senast (för kontextmenyn)
+    context_target: Option<ContextTarget>,
+    /// Relationer för personen i kontextmenyn
+    context_relations: Vec<RelationshipView>,
+    /// Om personen i kontextmenyn redan har far respektive mor
+    context_parents: (bool, bool),
     /// Pan offset
     pan_offset: Vec2,
     /// Zoom level
@@ -37,7 +60,14 @@ impl FamilyTreeView {
         Self {
             tree: None,
             focus_person_id: None,
-            generations: 3,
+            mode: TreeMode::default(),
+            generations: TreeMode::all().iter().map(|m| (*m, m.default_generations())).collect(),
+            fan_shape: FanShape::default(),
+            completeness: HashMap::new(),
+            export_options: ChartOptions::default(),
+            context_target: None,
+            context_relations: Vec::new(),
+            context_parents: (false, false),
             pan_offset: Vec2::ZERO,
             zoom: 1.0,
             needs_refresh: true,
@@ -76,8 +106,10 @@ impl FamilyTreeView {
                         state.navigate_to_person(person_id);
                     }
                 });
+            } else if tree.mode == TreeMode::Fan {
+                self.show_fan_canvas(ui, state, tree.clone());
             } else {
-                self.show_tree_canvas(ui, state, tree.clone());
+                self.show_tree_canvas(ui, state, db, tree.clone());
             }
         }
     }
@@ -113,8 +145,28 @@ impl FamilyTreeView {
             ui.heading(format!("{} Familjeträd", Icons::TREE));
 
             // Visa fokuspersonens namn
-            if let Ok(Some(person)) = db.persons().find_by_id(person_id) {
-                ui.label(format!("- {}", person.full_name()));
+            if let Some(node) = self.tree.as_ref().and_then(|t| t.get_node(person_id)) {
+                ui.label(format!("- {}", node.person.full_name()));
+            }
+
+            ui.separator();
+
+            // Trädtyp
+            for mode in TreeMode::all() {
+                if ui.selectable_label(self.mode == *mode, mode.display_name()).clicked() && self.mode != *mode {
+                    self.mode = *mode;
+                    self.needs_refresh = true;
+                    self.pan_offset = Vec2::ZERO;
+                }
+            }
+
+            if self.mode == TreeMode::Fan {
+                ui.separator();
+                for shape in FanShape::all() {
+                    if ui.selectable_label(self.fan_shape == *shape, shape.display_name()).clicked() {
+                        self.fan_shape = *shape;
+                    }
+                }
             }
 
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -131,12 +183,18 @@ impl FamilyTreeView {
 
                 // Generationer
                 ui.label("Generationer:");
-                if ui.add(egui::DragValue::new(&mut self.generations).range(1..=5)).changed() {
+                let max_generations = self.mode.max_generations();
+                let generations = self.generations.entry(self.mode).or_insert(self.mode.default_generations());
+                if ui.add(egui::DragValue::new(generations).range(1..=max_generations)).changed() {
                     self.needs_refresh = true;
                 }
 
                 ui.separator();
 
+                ui.menu_button(format!("{} Exportera", Icons::EXPORT), |ui| {
+                    self.show_export_menu(ui, state, db);
+                });
+
                 // Reset-knapp
                 if ui.button("Återställ vy").clicked() {
                     self.pan_offset = Vec2::ZERO;
@@ -146,7 +204,7 @@ impl FamilyTreeView {
         });
     }
 
-    fn show_tree_canvas(&mut self, ui: &mut egui::Ui, state: &mut AppState, tree: FamilyTree) {
+    fn show_tree_canvas(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database, tree: FamilyTree) {
         // Hämta tillgängligt utrymme
         let available_size = ui.available_size();
 
@@ -181,6 +239,27 @@ impl FamilyTreeView {
             )
         };
 
+        let node_size = Vec2::new(140.0, 60.0) * self.zoom;
+        let reference_size = Vec2::new(120.0, 40.0) * self.zoom;
+
+        // Person under muspekaren, om den förekommer flera gånger (anförlust)
+        let hover_pos = response.hover_pos();
+        let hovered_repeat = hover_pos.and_then(|p| {
+            let over_node = tree
+                .nodes
+                .iter()
+                .find(|(_, n)| Rect::from_center_size(transform(Pos2::new(n.x, n.y)), node_size).contains(p))
+                .map(|(id, _)| *id);
+            let over_reference = tree
+                .references
+                .iter()
+                .find(|r| Rect::from_center_size(transform(Pos2::new(r.x, r.y)), reference_size).contains(p))
+                .map(|r| r.person_id);
+            over_node
+                .or(over_reference)
+                .filter(|id| tree.references.iter().any(|r| r.person_id == *id))
+        });
+
         // Rita länkar först (under noderna)
         for link in &tree.links {
             if let (Some(from_node), Some(to_node)) = (
@@ -197,7 +276,22 @@ impl FamilyTreeView {
                 };
 
                 // Rita linje
-                if link.link_type == LinkType::Parent {
+                if link.link_type == LinkType::Parent && tree.mode == TreeMode::Pedigree {
+                    // Antavla: barnet till vänster, föräldern till höger
+                    let mid_x = (from_pos.x + to_pos.x) / 2.0;
+                    painter.line_segment(
+                        [to_pos, Pos2::new(mid_x, to_pos.y)],
+                        Stroke::new(thickness * self.zoom, color),
+                    );
+                    painter.line_segment(
+                        [Pos2::new(mid_x, to_pos.y), Pos2::new(mid_x, from_pos.y)],
+                        Stroke::new(thickness * self.zoom, color),
+                    );
+                    painter.line_segment(
+                        [Pos2::new(mid_x, from_pos.y), from_pos],
+                        Stroke::new(thickness * self.zoom, color),
+                    );
+                } else if link.link_type == LinkType::Parent {
                     // Vertikal linje med böj för förälder-barn
                     let mid_y = (from_pos.y + to_pos.y) / 2.0;
                     painter.line_segment(
@@ -222,9 +316,134 @@ impl FamilyTreeView {
             }
         }
 
+        // Upprepade förfäder: liten ruta på platsen, länkad till barnet
+        let mut clicked_reference: Option<i64> = None;
+        for reference in &tree.references {
+            let (Some(child), Some(original)) = (tree.nodes.get(&reference.child_id), tree.nodes.get(&reference.person_id))
+            else {
+                continue;
+            };
+            let pos = transform(Pos2::new(reference.x, reference.y));
+            let child_pos = transform(Pos2::new(child.x, child.y));
+            let stroke = Stroke::new(2.0 * self.zoom, Colors::TEXT_SECONDARY);
+            let mid_x = (pos.x + child_pos.x) / 2.0;
+            painter.line_segment([child_pos, Pos2::new(mid_x, child_pos.y)], stroke);
+            painter.line_segment([Pos2::new(mid_x, child_pos.y), Pos2::new(mid_x, pos.y)], stroke);
+            painter.line_segment([Pos2::new(mid_x, pos.y), pos], stroke);
+
+            let highlighted = hovered_repeat == Some(reference.person_id);
+            if highlighted {
+                let original_pos = transform(Pos2::new(original.x, original.y));
+                painter.add(Shape::dashed_line(
+                    &[pos, original_pos],
+                    Stroke::new(2.0, Colors::WARNING),
+                    8.0,
+                    4.0,
+                ));
+            }
+
+            let ref_rect = Rect::from_center_size(pos, reference_size);
+            if !rect.intersects(ref_rect) {
+                continue;
+            }
+            painter.rect_filled(ref_rect, 6.0 * self.zoom, ui.visuals().extreme_bg_color);
+            let border = if highlighted {
+                Stroke::new(2.5, Colors::WARNING)
+            } else {
+                Stroke::new(1.0, Colors::TEXT_MUTED)
+            };
+            painter.rect_stroke(ref_rect, 6.0 * self.zoom, border);
+            painter.text(
+                pos + Vec2::new(0.0, -7.0 * self.zoom),
+                egui::Align2::CENTER_CENTER,
+                original.person.full_name(),
+                egui::FontId::proportional(12.0 * self.zoom),
+                Colors::TEXT_SECONDARY,
+            );
+            painter.text(
+                pos + Vec2::new(0.0, 9.0 * self.zoom),
+                egui::Align2::CENTER_CENTER,
+                format!("{} se nr {}", Icons::ARROW_RIGHT, original.ahnentafel.unwrap_or_default()),
+                egui::FontId::proportional(10.0 * self.zoom),
+                Colors::TEXT_MUTED,
+            );
+            painter.text(
+                ref_rect.left_top() + Vec2::new(6.0, 4.0) * self.zoom,
+                egui::Align2::LEFT_TOP,
+                reference.ahnentafel.to_string(),
+                egui::FontId::proportional(10.0 * self.zoom),
+                Colors::TEXT_MUTED,
+            );
+
+            if response.clicked() && response.interact_pointer_pos().is_some_and(|p| ref_rect.contains(p)) {
+                clicked_reference = Some(reference.person_id);
+            }
+        }
+
+        // Tomma platser för saknade föräldrar
+        let mut clicked_placeholder: Option<PendingRelation> = None;
+        let mut context_target: Option<ContextTarget> = None;
+        for placeholder in &tree.placeholders {
+            let Some(child) = tree.nodes.get(&placeholder.child_id) else {
+                continue;
+            };
+            let pos = transform(Pos2::new(placeholder.x, placeholder.y));
+            let child_pos = transform(Pos2::new(child.x, child.y));
+            let stroke = Stroke::new(1.0, Colors::TEXT_MUTED);
+            let mid_x = (pos.x + child_pos.x) / 2.0;
+            painter.add(Shape::dashed_line(
+                &[child_pos, Pos2::new(mid_x, child_pos.y), Pos2::new(mid_x, pos.y), pos],
+                stroke,
+                6.0 * self.zoom,
+                4.0 * self.zoom,
+            ));
+
+            let slot_rect = Rect::from_center_size(pos, node_size);
+            if !rect.intersects(slot_rect) {
+                continue;
+            }
+            let hovered = hover_pos.is_some_and(|p| slot_rect.contains(p));
+            painter.rect_filled(slot_rect, 8.0 * self.zoom, ui.visuals().extreme_bg_color);
+            let corners = [
+                slot_rect.left_top(),
+                slot_rect.right_top(),
+                slot_rect.right_bottom(),
+                slot_rect.left_bottom(),
+                slot_rect.left_top(),
+            ];
+            let border = if hovered { Stroke::new(1.5, Colors::PRIMARY) } else { stroke };
+            painter.add(Shape::dashed_line(&corners, border, 6.0 * self.zoom, 4.0 * self.zoom));
+            painter.text(
+                pos,
+                egui::Align2::CENTER_CENTER,
+                format!("{} {}", Icons::ADD, if placeholder.is_father() { "Far" } else { "Mor" }),
+                egui::FontId::proportional(13.0 * self.zoom),
+                if hovered { Colors::PRIMARY } else { Colors::TEXT_MUTED },
+            );
+            painter.text(
+                slot_rect.left_top() + Vec2::new(6.0, 4.0) * self.zoom,
+                egui::Align2::LEFT_TOP,
+                placeholder.ahnentafel.to_string(),
+                egui::FontId::proportional(10.0 * self.zoom),
+                Colors::TEXT_MUTED,
+            );
+
+            let target = ContextTarget::MissingParent {
+                child_id: placeholder.child_id,
+                father: placeholder.is_father(),
+            };
+            let pointer_inside = response.interact_pointer_pos().is_some_and(|p| slot_rect.contains(p));
+            if response.clicked() && pointer_inside {
+                clicked_placeholder = target.new_parent();
+            }
+            if response.secondary_clicked() && pointer_inside {
+                context_target = Some(target);
+            }
+        }
+
         // Rita noder
-        let node_width = 140.0 * self.zoom;
-        let node_height = 60.0 * self.zoom;
+        let node_width = node_size.x;
+        let node_height = node_size.y;
         let mut clicked_person: Option<i64> = None;
         let mut double_clicked_person: Option<i64> = None;
 
@@ -259,6 +478,9 @@ impl FamilyTreeView {
                 8.0 * self.zoom,
                 Stroke::new(1.0, ui.visuals().widgets.inactive.bg_stroke.color),
             );
+            if hovered_repeat == Some(*person_id) {
+                painter.rect_stroke(node_rect, 8.0 * self.zoom, Stroke::new(2.5, Colors::WARNING));
+            }
 
             // Rita namn
             let name = node.person.full_name();
@@ -284,6 +506,17 @@ impl FamilyTreeView {
                 );
             }
 
+            // Rita antavlenummer
+            if let Some(number) = node.ahnentafel {
+                painter.text(
+                    node_rect.left_top() + Vec2::new(6.0, 4.0) * self.zoom,
+                    egui::Align2::LEFT_TOP,
+                    number.to_string(),
+                    egui::FontId::proportional(10.0 * self.zoom),
+                    if is_focus { Color32::from_white_alpha(200) } else { Colors::TEXT_MUTED },
+                );
+            }
+
             // Kontrollera dubbelklick på nod
             if response.double_clicked() {
                 if let Some(click_pos) = response.interact_pointer_pos() {
@@ -301,6 +534,47 @@ impl FamilyTreeView {
                     }
                 }
             }
+
+            // Högerklick öppnar kontextmenyn för personen
+            if response.secondary_clicked() && response.interact_pointer_pos().is_some_and(|p| node_rect.contains(p)) {
+                context_target = Some(ContextTarget::Person(*person_id));
+            }
+        }
+
+        if response.secondary_clicked() {
+            self.context_relations = match context_target {
+                Some(ContextTarget::Person(id)) => db.relationships().find_by_person_with_names(id).unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            self.context_parents = match context_target {
+                Some(ContextTarget::Person(id)) => Self::existing_parents(db, id),
+                _ => (false, false),
+            };
+            self.context_target = context_target;
+        }
+        if self.context_target.is_some() {
+            response.context_menu(|ui| self.show_context_menu(ui, state, &tree));
+        }
+
+        if let Some(node) = hovered_repeat.and_then(|id| tree.nodes.get(&id)) {
+            let mut numbers: Vec<u64> = node.ahnentafel.into_iter().collect();
+            numbers.extend(tree.references.iter().filter(|r| r.person_id == node.person.id.unwrap_or_default()).map(|r| r.ahnentafel));
+            let numbers: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+            response.clone().on_hover_text(format!(
+                "{}\nAnförlust: förekommer som nr {}",
+                node.person.full_name(),
+                numbers.join(", ")
+            ));
+        }
+
+        // Klick på en hänvisning flyttar vyn till den första förekomsten
+        if let Some(node) = clicked_reference.and_then(|id| tree.nodes.get(&id)) {
+            self.pan_offset = -Vec2::new(node.x, node.y) * self.zoom;
+        }
+
+        // Klick på en tom plats lägger till den saknade föräldern
+        if let Some(relation) = clicked_placeholder {
+            state.open_new_related_person_form(relation);
         }
 
         // Hantera dubbelklick - navigera till persondetalj
@@ -309,13 +583,141 @@ impl FamilyTreeView {
         } else if let Some(pid) = clicked_person {
             // Hantera enkelklick - byt fokusperson
             if tree.focus_person_id != Some(pid) {
-                self.focus_person_id = Some(pid);
-                self.needs_refresh = true;
-                self.pan_offset = Vec2::ZERO;
+                self.refocus(state, pid);
             }
         }
 
         // Rita instruktioner
+        let instructions = "Dra för att panorera • Scrolla för att zooma • Klicka på person för att fokusera • Dubbelklicka för detaljer • Högerklicka för att redigera";
+        painter.text(
+            Pos2::new(rect.center().x, rect.bottom() - 20.0),
+            egui::Align2::CENTER_CENTER,
+            instructions,
+            egui::FontId::proportional(11.0),
+            Colors::TEXT_MUTED,
+        );
+    }
+
+    /// Rita solfjäderdiagram över förfäderna
+    fn show_fan_canvas(&mut self, ui: &mut egui::Ui, state: &mut AppState, tree: FamilyTree) {
+        let available_size = ui.available_size();
+        let (response, painter) = ui.allocate_painter(available_size, egui::Sense::click_and_drag());
+        let rect = response.rect;
+
+        if response.dragged() {
+            self.pan_offset += response.drag_delta();
+        }
+        if response.hovered() {
+            let scroll_delta = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll_delta != 0.0 {
+                let zoom_factor = 1.0 + scroll_delta * 0.001;
+                self.zoom = (self.zoom * zoom_factor).clamp(0.3, 3.0);
+            }
+        }
+
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        // Halvcirkeln placeras lågt så att hela bågen syns
+        let anchor = match self.fan_shape {
+            FanShape::Half => Pos2::new(rect.center().x, rect.bottom() - 80.0),
+            FanShape::Full => rect.center(),
+        };
+        let center = anchor + self.pan_offset;
+
+        let segments = fan_segments(&tree, self.fan_shape, 70.0 * self.zoom, 90.0 * self.zoom);
+        let stroke = Stroke::new(1.0, ui.visuals().extreme_bg_color);
+        let hover_pos = response.hover_pos().map(|p| p - center);
+        let hovered = hover_pos.and_then(|p| segment_at(&segments, p.x, p.y)).map(|s| s.person_id);
+
+        for segment in &segments {
+            let Some(node) = tree.nodes.get(&segment.person_id) else {
+                continue;
+            };
+            let score = self.completeness.get(&segment.person_id).copied().unwrap_or(0.0);
+            let mut fill = completeness_color(score);
+            if segment.is_reference {
+                // Upprepad förfader: blekare, med förekomsterna markerade vid hovring
+                fill = fill.gamma_multiply(0.5);
+            }
+            if hovered == Some(segment.person_id) {
+                fill = fill.gamma_multiply(0.8);
+            }
+
+            let repeated = tree.references.iter().any(|r| r.person_id == segment.person_id);
+            if repeated && hovered == Some(segment.person_id) {
+                painter.add(fan_segment_shape(center, segment, fill, Stroke::new(2.5, Colors::WARNING)));
+            } else {
+                painter.add(fan_segment_shape(center, segment, fill, stroke));
+            }
+
+            // Namn: vågrätt i mitten, längs bågen i de inre ringarna och
+            // längs radien längre ut där segmenten är smala
+            let name = if segment.depth >= 5 {
+                node.person.firstname.clone().unwrap_or_else(|| node.person.full_name())
+            } else {
+                node.person.full_name()
+            };
+            let font_size = (if segment.depth <= 3 { 12.0 } else { 10.0 }) * self.zoom;
+            let angle = segment.mid_angle();
+            let (text_pos, text_angle) = if segment.depth == 0 {
+                (center, 0.0)
+            } else {
+                let pos = center + Vec2::angled(angle) * segment.mid_radius();
+                let rotation = if segment.depth <= 3 { angle + std::f32::consts::FRAC_PI_2 } else { angle };
+                (pos, upright(rotation))
+            };
+            let galley = painter.layout_no_wrap(name, egui::FontId::proportional(font_size), Colors::TEXT_PRIMARY);
+            let offset = Rot2::from_angle(text_angle) * (galley.size() / 2.0);
+            painter.add(TextShape::new(text_pos - offset, galley, Colors::TEXT_PRIMARY).with_angle(text_angle));
+        }
+
+        if let Some(person_id) = hovered {
+            if let Some(node) = tree.nodes.get(&person_id) {
+                let score = self.completeness.get(&person_id).copied().unwrap_or(0.0);
+                let years = self.format_years(&node.person);
+                let name = if years.is_empty() {
+                    node.person.full_name()
+                } else {
+                    format!("{} ({})", node.person.full_name(), years)
+                };
+                let numbers: Vec<String> = segments
+                    .iter()
+                    .filter(|s| s.person_id == person_id)
+                    .map(|s| s.ahnentafel.to_string())
+                    .collect();
+                response.clone().on_hover_text(format!(
+                    "{}\nAntavlenummer: {}\nForskningsgrad: {:.0}%",
+                    name,
+                    numbers.join(", "),
+                    score * 100.0
+                ));
+            }
+        }
+
+        if response.double_clicked() {
+            if let Some(person_id) = hovered {
+                state.navigate_to_person(person_id);
+            }
+        } else if response.clicked() {
+            if let Some(person_id) = hovered.filter(|id| tree.focus_person_id != Some(*id)) {
+                self.refocus(state, person_id);
+            }
+        }
+
+        // Teckenförklaring
+        let mut legend_pos = Pos2::new(rect.left() + 16.0, rect.bottom() - 60.0);
+        for (label, score) in [("Ofullständig", 0.0), ("Delvis", 0.5), ("Välutforskad", 1.0)] {
+            painter.rect_filled(Rect::from_min_size(legend_pos, Vec2::splat(12.0)), 2.0, completeness_color(score));
+            painter.text(
+                legend_pos + Vec2::new(18.0, 6.0),
+                egui::Align2::LEFT_CENTER,
+                label,
+                egui::FontId::proportional(11.0),
+                Colors::TEXT_MUTED,
+            );
+            legend_pos.y += 16.0;
+        }
+
         let instructions = "Dra för att panorera • Scrolla för att zooma • Klicka på person för att fokusera • Dubbelklicka för detaljer";
         painter.text(
             Pos2::new(rect.center().x, rect.bottom() - 20.0),
@@ -326,6 +728,194 @@ impl FamilyTreeView {
         );
     }
 
+    /// Om personen redan har far respektive mor, fördelat som i trädet
+    fn existing_parents(db: &Database, person_id: i64) -> (bool, bool) {
+        let Ok(graph) = db.kinship() else {
+            return (false, false);
+        };
+        let parents = graph
+            .parents(person_id)
+            .iter()
+            .filter_map(|id| graph.person(*id).cloned())
+            .collect();
+        let (father, mother) = split_parents(parents);
+        (father.is_some(), mother.is_some())
+    }
+
+    /// Kontextmeny för en person eller en tom plats i trädet
+    fn show_context_menu(&mut self, ui: &mut egui::Ui, state: &mut AppState, tree: &FamilyTree) {
+        let Some(target) = self.context_target else {
+            return;
+        };
+
+        match target {
+            ContextTarget::Person(person_id) => {
+                let Some(node) = tree.nodes.get(&person_id) else {
+                    return;
+                };
+                ui.label(RichText::new(node.person.full_name()).strong());
+                ui.separator();
+
+                let (has_father, has_mother) = self.context_parents;
+                let spouse = PendingRelation {
+                    sex: match node.person.sex {
+                        Sex::Male => Sex::Female,
+                        Sex::Female => Sex::Male,
+                        Sex::Unknown => Sex::Unknown,
+                    },
+                    ..PendingRelation::spouse_of(person_id)
+                };
+                let additions = [
+                    ("Lägg till far", PendingRelation::father_of(person_id), !has_father),
+                    ("Lägg till mor", PendingRelation::mother_of(person_id), !has_mother),
+                    ("Lägg till make/maka", spouse, true),
+                    ("Lägg till barn", PendingRelation::child_of(person_id), true),
+                ];
+                for (label, relation, enabled) in additions {
+                    if ui.add_enabled(enabled, egui::Button::new(format!("{} {}", Icons::ADD, label))).clicked() {
+                        state.open_new_related_person_form(relation);
+                        ui.close_menu();
+                    }
+                }
+
+                if ui.button(format!("{} Koppla befintlig person...", Icons::LINK)).clicked() {
+                    state.open_relationship_form(person_id, None);
+                    ui.close_menu();
+                }
+
+                ui.add_enabled_ui(!self.context_relations.is_empty(), |ui| {
+                    ui.menu_button(format!("{} Ta bort relation", Icons::DELETE), |ui| {
+                        for relation in &self.context_relations {
+                            let label = format!("{}: {}", relation.relationship_type.display_name(), relation.other_person_name);
+                            if ui.button(label).clicked() {
+                                state.show_confirm(
+                                    &format!("Ta bort relation till {}?", relation.other_person_name),
+                                    ConfirmAction::DeleteRelationship(relation.relationship_id),
+                                );
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+
+                ui.separator();
+                if ui.button(format!("{} Öppna detaljer", Icons::PERSON)).clicked() {
+                    state.navigate_to_person(person_id);
+                    ui.close_menu();
+                }
+            }
+            ContextTarget::MissingParent { child_id, father } => {
+                let child_name = tree.nodes.get(&child_id).map(|n| n.person.full_name()).unwrap_or_default();
+                let role = if father { "far" } else { "mor" };
+                ui.label(RichText::new(format!("Saknad {} till {}", role, child_name)).strong());
+                ui.separator();
+
+                if let Some(relation) = target.new_parent() {
+                    if ui.button(format!("{} Lägg till {}", Icons::ADD, role)).clicked() {
+                        state.open_new_related_person_form(relation);
+                        ui.close_menu();
+                    }
+                }
+                if ui.button(format!("{} Koppla befintlig person...", Icons::LINK)).clicked() {
+                    state.open_relationship_form(child_id, Some(RelationshipType::Parent));
+                    ui.close_menu();
+                }
+            }
+        }
+    }
+
+    fn show_export_menu(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        let options = &mut self.export_options;
+
+        ui.checkbox(&mut options.show_photos, "Profilbilder");
+        ui.checkbox(&mut options.show_years, "Årtal");
+        ui.checkbox(&mut options.show_places, "Födelseort");
+
+        egui::Grid::new("tree_export_options")
+            .num_columns(2)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Färger:");
+                egui::ComboBox::from_id_salt("tree_export_colors")
+                    .selected_text(options.color_scheme.display_name())
+                    .show_ui(ui, |ui| {
+                        for scheme in ChartColorScheme::all() {
+                            ui.selectable_value(&mut options.color_scheme, *scheme, scheme.display_name());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Sekretess:");
+                egui::ComboBox::from_id_salt("tree_export_redaction")
+                    .selected_text(options.redaction.display_name())
+                    .show_ui(ui, |ui| {
+                        for style in RedactionStyle::all() {
+                            ui.selectable_value(&mut options.redaction, *style, style.display_name());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("PDF-sidor:");
+                egui::ComboBox::from_id_salt("tree_export_pages")
+                    .selected_text(options.page_mode.display_name())
+                    .show_ui(ui, |ui| {
+                        for mode in PageMode::all() {
+                            ui.selectable_value(&mut options.page_mode, *mode, mode.display_name());
+                        }
+                    });
+                ui.end_row();
+
+                if options.page_mode == PageMode::Tiled {
+                    ui.label("Papper:");
+                    ui.horizontal(|ui| {
+                        for paper in PaperSize::all() {
+                            ui.selectable_value(&mut options.paper, *paper, paper.display_name());
+                        }
+                        ui.checkbox(&mut options.landscape, "Liggande");
+                    });
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+
+        for format in [ChartFormat::Svg, ChartFormat::Pdf] {
+            if ui.button(format!("Spara som {}...", format.display_name())).clicked() {
+                ui.close_menu();
+                self.export_chart(state, db, format);
+            }
+        }
+    }
+
+    fn export_chart(&mut self, state: &mut AppState, db: &Database, format: ChartFormat) {
+        let Some(tree) = &self.tree else {
+            return;
+        };
+
+        let file_dialog = rfd::FileDialog::new()
+            .set_file_name(format!("slakttrad.{}", format.extension()))
+            .add_filter(format.display_name(), &[format.extension()]);
+
+        if let Some(path) = file_dialog.save_file() {
+            match ChartExportService::new(db).export(tree, format, &self.export_options, &path) {
+                Ok(result) => {
+                    state.show_success(&format!("{} sparat till {}", result.summary(), path.display()));
+                }
+                Err(e) => {
+                    state.show_error(&format!("Export misslyckades: {}", e));
+                }
+            }
+        }
+    }
+
+    /// Centrera trädet på en annan person
+    fn refocus(&mut self, state: &mut AppState, person_id: i64) {
+        state.selected_person_id = Some(person_id);
+        self.focus_person_id = Some(person_id);
+        self.needs_refresh = true;
+        self.pan_offset = Vec2::ZERO;
+    }
+
     fn format_years(&self, person: &crate::models::Person) -> String {
         match (person.birth_date, person.death_date) {
             (Some(b), Some(d)) => format!("{} - {}", b.format("%Y"), d.format("%Y")),
@@ -339,11 +929,20 @@ impl FamilyTreeView {
         self.focus_person_id = Some(person_id);
 
         let service = FamilyTreeService::new(db);
-        match service.build_tree(person_id, self.generations) {
+        let generations = self.generations.get(&self.mode).copied().unwrap_or(self.mode.default_generations());
+        match service.build(self.mode, person_id, generations) {
             Ok(tree) => self.tree = Some(tree),
             Err(_) => self.tree = None,
         }
 
+        self.completeness.clear();
+        if let Some(tree) = self.tree.as_ref().filter(|t| t.mode == TreeMode::Fan) {
+            for (id, node) in &tree.nodes {
+                let progress = db.checklists().get_progress(*id).unwrap_or((0, 0));
+                self.completeness.insert(*id, research_completeness(&node.person, progress));
+            }
+        }
+
         self.needs_refresh = false;
     }
 
@@ -359,3 +958,75 @@ impl FamilyTreeView {
         }
     }
 }
+
+/// Mål för trädets kontextmeny
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContextTarget {
+    /// En person i trädet
+    Person(i64),
+    /// Tom plats för en förälder som saknas i antavlan
+    MissingParent { child_id: i64, father: bool },
+}
+
+impl ContextTarget {
+    /// Relation för en ny person som fyller en tom plats
+    fn new_parent(&self) -> Option<PendingRelation> {
+        match *self {
+            ContextTarget::MissingParent { child_id, father: true } => Some(PendingRelation::father_of(child_id)),
+            ContextTarget::MissingParent { child_id, father: false } => Some(PendingRelation::mother_of(child_id)),
+            ContextTarget::Person(_) => None,
+        }
+    }
+}
+
+/// Färg efter forskningsgrad: rött (inget) via gult till grönt (komplett)
+fn completeness_color(score: f32) -> Color32 {
+    let score = score.clamp(0.0, 1.0);
+    let (from, to, t) = if score < 0.5 {
+        (Colors::ERROR_BG, Colors::WARNING_BG, score * 2.0)
+    } else {
+        (Colors::WARNING_BG, Colors::SUCCESS_BG, (score - 0.5) * 2.0)
+    };
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgb(lerp(from.r(), to.r()), lerp(from.g(), to.g()), lerp(from.b(), to.b()))
+}
+
+/// Vinkel som håller texten rättvänd (inte upp och ner)
+fn upright(angle: f32) -> f32 {
+    use std::f32::consts::{PI, TAU};
+    let angle = angle.rem_euclid(TAU);
+    if angle > PI / 2.0 && angle < 3.0 * PI / 2.0 {
+        angle - PI
+    } else {
+        angle
+    }
+}
+
+/// Ringsegment som fylld mesh med kontur
+fn fan_segment_shape(center: Pos2, segment: &FanSegment, fill: Color32, stroke: Stroke) -> Shape {
+    let span = segment.end_angle - segment.start_angle;
+    let steps = ((span.abs() * segment.outer_radius / 8.0).ceil() as usize).clamp(2, 128);
+    let point = |angle: f32, radius: f32| center + Vec2::angled(angle) * radius;
+
+    let mut mesh = Mesh::default();
+    let mut outline_outer = Vec::with_capacity(steps + 1);
+    let mut outline_inner = Vec::with_capacity(steps + 1);
+    for i in 0..=steps {
+        let angle = segment.start_angle + span * i as f32 / steps as f32;
+        let outer = point(angle, segment.outer_radius);
+        let inner = point(angle, segment.inner_radius);
+        mesh.colored_vertex(outer, fill);
+        mesh.colored_vertex(inner, fill);
+        outline_outer.push(outer);
+        outline_inner.push(inner);
+        if i > 0 {
+            let base = (i as u32 - 1) * 2;
+            mesh.add_triangle(base, base + 1, base + 2);
+            mesh.add_triangle(base + 1, base + 3, base + 2);
+        }
+    }
+
+    outline_inner.reverse();
+    outline_outer.extend(outline_inner);
+    Shape::Vec(vec![Shape::mesh(mesh), Shape::closed_line(outline_outer, stroke)])
+}
@@ -1,4 +1,5 @@
 pub mod backup_view;
+pub mod calendar;
 pub mod checklist_search;
 pub mod checklist_templates;
 pub mod dashboard;
@@ -16,6 +17,7 @@ pub mod setup_wizard;
 pub mod splash_screen;
 
 pub use backup_view::BackupView;
+pub use calendar::CalendarView;
 pub use checklist_search::ChecklistSearchView;
 pub use checklist_templates::ChecklistTemplatesView;
 pub use dashboard::DashboardView;
@@ -1,15 +1,19 @@
 use egui::{self, ColorImage, RichText, TextureHandle, TextureOptions};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use crate::db::Database;
-use crate::models::{Document, Person, RelationshipType};
+use crate::models::{CustomFieldDefinition, CustomFieldEntity, Document, Person, RelationshipType, Sex, Tag};
+use crate::services::family_group_sheet::FamilyGroupSheetService;
+use crate::services::RedactionStyle;
 use crate::ui::{
     state::{AppState, ConfirmAction},
     theme::{Colors, Icons},
-    widgets::{ChecklistPanel, ImageGallery},
+    widgets::{custom_field_rows, redaction_choices, tag_chip, ChecklistPanel, ImageGallery, TimelinePanel},
     View,
 };
 use crate::utils::file_ops;
+use crate::utils::path::sanitize_filename;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum PersonDetailTab {
@@ -18,6 +22,7 @@ enum PersonDetailTab {
     Documents,
     Images,
     Checklist,
+    Timeline,
 }
 
 pub struct PersonDetailView {
@@ -27,12 +32,20 @@ pub struct PersonDetailView {
     needs_refresh: bool,
     checklist_panel: ChecklistPanel,
     image_gallery: ImageGallery,
+    timeline_panel: TimelinePanel,
     /// Cachad profilbild-textur
     profile_texture: Option<TextureHandle>,
     /// Sökväg till cachad profilbild
     profile_texture_path: Option<String>,
     /// Vald flik
     selected_tab: PersonDetailTab,
+    /// Personens taggar
+    tags: Vec<Tag>,
+    /// Alla taggar (för att lägga till)
+    all_tags: Vec<Tag>,
+    /// Egna fält för personer och personens värden
+    custom_definitions: Vec<CustomFieldDefinition>,
+    custom_values: HashMap<i64, String>,
 }
 
 impl PersonDetailView {
@@ -44,9 +57,14 @@ impl PersonDetailView {
             needs_refresh: true,
             checklist_panel: ChecklistPanel::new(),
             image_gallery: ImageGallery::new(),
+            timeline_panel: TimelinePanel::new(),
             profile_texture: None,
             profile_texture_path: None,
             selected_tab: PersonDetailTab::default(),
+            tags: Vec::new(),
+            all_tags: Vec::new(),
+            custom_definitions: Vec::new(),
+            custom_values: HashMap::new(),
         }
     }
 
@@ -124,9 +142,23 @@ impl PersonDetailView {
                 if ui.button(format!("{} Redigera", Icons::EDIT)).clicked() {
                     state.open_edit_person_form(person_id);
                 }
+
+                // Familjeblad
+                let mut sheet_redaction = None;
+                ui.menu_button(format!("{} Familjeblad", Icons::DOCUMENT), |ui| {
+                    sheet_redaction = redaction_choices(ui);
+                })
+                .response
+                .on_hover_text("Skriv ut familjeblad (PDF) för personens familjer");
+                if let Some(redaction) = sheet_redaction {
+                    self.export_family_sheet(person_id, &person_name, redaction, state, db);
+                }
             });
         });
 
+        // Taggar
+        self.show_tags_row(ui, state, db, person_id);
+
         ui.add_space(16.0);
 
         // Drag-and-drop: detektera globalt (oavsett flik)
@@ -177,6 +209,7 @@ impl PersonDetailView {
             ui.selectable_value(&mut self.selected_tab, PersonDetailTab::Documents, format!("{} Dokument", Icons::DOCUMENT));
             ui.selectable_value(&mut self.selected_tab, PersonDetailTab::Images, format!("{} Bilder", Icons::IMAGE));
             ui.selectable_value(&mut self.selected_tab, PersonDetailTab::Checklist, format!("{} Uppgifter", Icons::CHECK));
+            ui.selectable_value(&mut self.selected_tab, PersonDetailTab::Timeline, format!("{} Tidslinje", Icons::CALENDAR));
         });
 
         ui.separator();
@@ -187,7 +220,13 @@ impl PersonDetailView {
                 PersonDetailTab::PersonInfo => {
                     ui.columns(2, |columns| {
                         columns[0].vertical(|ui| {
-                            Self::show_person_info_static(ui, &person, self.profile_texture.as_ref(), db);
+                            Self::show_person_info_static(
+                                ui,
+                                &person,
+                                self.profile_texture.as_ref(),
+                                (&self.custom_definitions, &self.custom_values),
+                                db,
+                            );
                         });
                         columns[1].vertical(|ui| {
                             Self::show_relations_static(ui, state, db, person_id);
@@ -203,10 +242,63 @@ impl PersonDetailView {
                 PersonDetailTab::Checklist => {
                     self.checklist_panel.show(ui, state, db, person_id);
                 }
+                PersonDetailTab::Timeline => {
+                    self.timeline_panel.show(ui, state, db, person_id);
+                }
             }
         });
     }
 
+    /// Rad med personens taggar samt möjlighet att lägga till/ta bort
+    fn show_tags_row(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database, person_id: i64) {
+        let mut add_tag: Option<i64> = None;
+        let mut remove_tag: Option<i64> = None;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 4.0;
+            for tag in &self.tags {
+                tag_chip(ui, tag);
+                if ui
+                    .small_button("✕")
+                    .on_hover_text(format!("Ta bort taggen {}", tag.name))
+                    .clicked()
+                {
+                    remove_tag = tag.id;
+                }
+            }
+
+            let available: Vec<&Tag> = self
+                .all_tags
+                .iter()
+                .filter(|t| !self.tags.iter().any(|own| own.id == t.id))
+                .collect();
+            ui.menu_button(format!("{} Tagg", Icons::ADD), |ui| {
+                if available.is_empty() {
+                    ui.label(RichText::new("Inga fler taggar - skapa under Inställningar").color(Colors::TEXT_MUTED));
+                }
+                for tag in available {
+                    if tag_chip(ui, tag).interact(egui::Sense::click()).clicked() {
+                        add_tag = tag.id;
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+
+        let result = if let Some(tag_id) = add_tag {
+            Some(db.tags().add_to_person(tag_id, person_id))
+        } else {
+            remove_tag.map(|tag_id| db.tags().remove_from_person(tag_id, person_id))
+        };
+        match result {
+            Some(Ok(())) => {
+                self.tags = db.tags().find_by_person(person_id).unwrap_or_default();
+            }
+            Some(Err(e)) => state.show_error(&format!("Kunde inte ändra taggar: {}", e)),
+            None => {}
+        }
+    }
+
     fn show_image_gallery_panel(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database, person_id: i64) {
         // Hämta media root från config
         let media_root = db
@@ -324,7 +416,13 @@ impl PersonDetailView {
             });
     }
 
-    fn show_person_info_static(ui: &mut egui::Ui, person: &Person, profile_texture: Option<&TextureHandle>, db: &Database) {
+    fn show_person_info_static(
+        ui: &mut egui::Ui,
+        person: &Person,
+        profile_texture: Option<&TextureHandle>,
+        custom_fields: (&[CustomFieldDefinition], &HashMap<i64, String>),
+        db: &Database,
+    ) {
         egui::Frame::none()
             .fill(ui.visuals().extreme_bg_color)
             .rounding(8.0)
@@ -354,6 +452,12 @@ impl PersonDetailView {
                                     ui.end_row();
                                 }
 
+                                if person.sex != Sex::Unknown {
+                                    ui.label(RichText::new("Kön:").color(Colors::TEXT_SECONDARY));
+                                    ui.label(person.sex.display_name());
+                                    ui.end_row();
+                                }
+
                                 if let Some(ref birth_place) = person.birth_place {
                                     ui.label(RichText::new("Födelseort:").color(Colors::TEXT_SECONDARY));
                                     ui.label(birth_place);
@@ -366,6 +470,8 @@ impl PersonDetailView {
                                     ui.end_row();
                                 }
 
+                                custom_field_rows(ui, custom_fields.0, custom_fields.1);
+
                                 if let Some(birth_date) = person.birth_date {
                                     ui.label(RichText::new("Födelsedatum:").color(Colors::TEXT_SECONDARY));
                                     ui.label(format!("{} {}", Icons::CALENDAR, birth_date.format("%Y-%m-%d")));
@@ -466,6 +572,10 @@ impl PersonDetailView {
                                 state.navigate_to_person(view.other_person_id);
                             }
 
+                            if rel_type == RelationshipType::Spouse {
+                                Self::show_marriage(ui, state, db, view.relationship_id);
+                            }
+
                             // Delete-knapp för relation
                             if ui
                                 .small_button(RichText::new(Icons::DELETE).color(Colors::TEXT_MUTED))
@@ -488,6 +598,67 @@ impl PersonDetailView {
             });
     }
 
+    /// Vigseluppgifter för en make/maka, med meny för att ändra dem
+    fn show_marriage(ui: &mut egui::Ui, state: &mut AppState, db: &Database, relationship_id: i64) {
+        let Ok(Some(mut rel)) = db.relationships().find_by_id(relationship_id) else {
+            return;
+        };
+
+        let summary = match (rel.marriage_date, rel.marriage_place.as_deref()) {
+            (Some(date), Some(place)) => format!("g. {}, {}", date.format("%Y-%m-%d"), place),
+            (Some(date), None) => format!("g. {}", date.format("%Y-%m-%d")),
+            (None, Some(place)) => format!("g. i {}", place),
+            (None, None) => String::new(),
+        };
+        if !summary.is_empty() {
+            ui.label(RichText::new(summary).small().color(Colors::TEXT_MUTED));
+        }
+
+        // Formulärvärden (datum, ort) hålls i egui:s minne medan menyn är öppen
+        let data_id = egui::Id::new(("marriage_edit", relationship_id));
+        ui.menu_button(RichText::new(Icons::EDIT).color(Colors::TEXT_MUTED), |ui| {
+            let mut values: (String, String) = ui.data_mut(|d| {
+                d.get_temp_mut_or_insert_with(data_id, || {
+                    (
+                        rel.marriage_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+                        rel.marriage_place.clone().unwrap_or_default(),
+                    )
+                })
+                .clone()
+            });
+
+            egui::Grid::new(("marriage_grid", relationship_id)).num_columns(2).show(ui, |ui| {
+                ui.label("Vigseldatum:");
+                ui.add(egui::TextEdit::singleline(&mut values.0).hint_text("YYYY-MM-DD").desired_width(100.0));
+                ui.end_row();
+                ui.label("Vigselort:");
+                ui.add(egui::TextEdit::singleline(&mut values.1).desired_width(160.0));
+                ui.end_row();
+            });
+
+            if ui.button(format!("{} Spara", Icons::SAVE)).clicked() {
+                let date = values.0.trim();
+                match (!date.is_empty()).then(|| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")).transpose() {
+                    Ok(date) => {
+                        rel.marriage_date = date;
+                        rel.marriage_place = Some(values.1.trim().to_string()).filter(|p| !p.is_empty());
+                        match db.relationships().update(&rel) {
+                            Ok(_) => state.show_success("Vigseluppgifter sparade"),
+                            Err(e) => state.show_error(&format!("Kunde inte spara: {}", e)),
+                        }
+                        ui.data_mut(|d| d.remove::<(String, String)>(data_id));
+                        ui.close_menu();
+                        return;
+                    }
+                    Err(_) => state.show_error("Ogiltigt vigseldatum (använd YYYY-MM-DD)"),
+                }
+            }
+            ui.data_mut(|d| d.insert_temp(data_id, values));
+        })
+        .response
+        .on_hover_text("Ändra vigseluppgifter");
+    }
+
     fn show_documents_panel(
         ui: &mut egui::Ui,
         state: &mut AppState,
@@ -587,10 +758,41 @@ impl PersonDetailView {
             });
     }
 
+    /// Spara familjeblad (PDF) för personens familjer
+    fn export_family_sheet(
+        &self,
+        person_id: i64,
+        person_name: &str,
+        redaction: RedactionStyle,
+        state: &mut AppState,
+        db: &Database,
+    ) {
+        let filename = format!("familjeblad_{}.pdf", sanitize_filename(person_name));
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&filename)
+            .add_filter("PDF", &["pdf"])
+            .save_file()
+        else {
+            return;
+        };
+
+        match FamilyGroupSheetService::new(db).export(&[person_id], redaction, &path) {
+            Ok(result) => state.show_success(&format!("{} sparade till {}", result.summary(), path.display())),
+            Err(e) => state.show_error(&format!("Kunde inte skapa familjeblad: {}", e)),
+        }
+    }
+
     fn refresh_person(&mut self, db: &Database, person_id: i64) {
         self.person_cache = db.persons().find_by_id(person_id).unwrap_or(None);
         self.document_count = db.documents().count_by_person(person_id).unwrap_or(0);
         self.is_bookmarked = db.persons().is_bookmarked(person_id).unwrap_or(false);
+        self.tags = db.tags().find_by_person(person_id).unwrap_or_default();
+        self.all_tags = db.tags().find_all().unwrap_or_default();
+        self.custom_definitions = db.custom_fields().find_definitions(CustomFieldEntity::Person).unwrap_or_default();
+        self.custom_values = db
+            .custom_fields()
+            .get_values(CustomFieldEntity::Person, person_id)
+            .unwrap_or_default();
 
         // Rensa profilbild-cache om sökvägen ändrats
         if let Some(ref person) = self.person_cache {
@@ -639,6 +841,7 @@ impl PersonDetailView {
         self.needs_refresh = true;
         self.checklist_panel.mark_needs_refresh();
         self.image_gallery.mark_needs_refresh();
+        self.timeline_panel.mark_needs_refresh();
         self.profile_texture = None;
         self.profile_texture_path = None;
     }
@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use chrono::NaiveDate;
 use egui::{self, ColorImage, RichText, TextureHandle, TextureOptions};
 
-use crate::db::{Database, SearchField, SearchFilter};
-use crate::models::Person;
-use crate::ui::{state::AppState, theme::{Colors, Icons}};
+use crate::db::{
+    CustomFieldCondition, Database, SavedSearch, SearchField, SearchFilter, TextCondition, TextOperator,
+};
+use crate::models::{ChecklistTemplate, CustomFieldDefinition, CustomFieldEntity, Person, Tag};
+use crate::services::family_group_sheet::FamilyGroupSheetService;
+use crate::services::RedactionStyle;
+use crate::ui::{state::AppState, theme::{Colors, Icons}, widgets::{redaction_choices, tag_chip, tag_chips}};
+use crate::utils::path::sanitize_filename;
 
 pub struct PersonListView {
     /// Sökfilter
@@ -26,6 +31,24 @@ pub struct PersonListView {
     profile_textures: HashMap<i64, TextureHandle>,
     /// Media root för att ladda bilder
     media_root: Option<PathBuf>,
+    /// Sparade sökningar med antal träffar
+    saved_searches_cache: Vec<(SavedSearch, i64)>,
+    /// Vald sparad sökning (markeras i sidopanelen)
+    active_saved_search: Option<i64>,
+    /// Namn för ny sparad sökning
+    new_saved_search_name: String,
+    /// Checklistmallar för massapplicering
+    checklist_templates_cache: Vec<ChecklistTemplate>,
+    /// Alla taggar
+    tags_cache: Vec<Tag>,
+    /// Taggar per person
+    person_tags_cache: HashMap<i64, Vec<Tag>>,
+    /// Egna fält för personer (för filter)
+    custom_fields_cache: Vec<CustomFieldDefinition>,
+    /// Flervalsläge för massåtgärder
+    selection_mode: bool,
+    /// Valda personer i flervalsläge
+    selected_ids: HashSet<i64>,
 }
 
 impl PersonListView {
@@ -42,6 +65,15 @@ impl PersonListView {
             needs_refresh: true,
             profile_textures: HashMap::new(),
             media_root: None,
+            saved_searches_cache: Vec::new(),
+            active_saved_search: None,
+            new_saved_search_name: String::new(),
+            checklist_templates_cache: Vec::new(),
+            tags_cache: Vec::new(),
+            person_tags_cache: HashMap::new(),
+            custom_fields_cache: Vec::new(),
+            selection_mode: false,
+            selected_ids: HashSet::new(),
         }
     }
 
@@ -74,6 +106,14 @@ impl PersonListView {
         // Ladda profilbilder för personer i cachen
         self.load_profile_textures(ui.ctx());
 
+        // Sidopanel med sparade sökningar
+        egui::SidePanel::left("saved_searches_panel")
+            .resizable(true)
+            .default_width(200.0)
+            .show_inside(ui, |ui| {
+                self.show_saved_searches(ui, state, db);
+            });
+
         ui.vertical(|ui| {
             // Header
             ui.horizontal(|ui| {
@@ -83,9 +123,21 @@ impl PersonListView {
                     if ui.button(format!("{} Ny person", Icons::ADD)).clicked() {
                         state.open_new_person_form();
                     }
+                    if ui
+                        .selectable_label(self.selection_mode, format!("{} Välj flera", Icons::CHECK))
+                        .clicked()
+                    {
+                        self.selection_mode = !self.selection_mode;
+                        self.selected_ids.clear();
+                    }
                 });
             });
 
+            if self.selection_mode {
+                ui.add_space(4.0);
+                self.show_selection_bar(ui, state, db);
+            }
+
             ui.add_space(8.0);
 
             // Fokusera sökfältet om signalerat
@@ -181,6 +233,7 @@ impl PersonListView {
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                 if ui.small_button("Återställ").clicked() {
                                     self.filter.reset_advanced();
+                                    self.active_saved_search = None;
                                     self.birth_after_str.clear();
                                     self.birth_before_str.clear();
                                     self.death_after_str.clear();
@@ -330,6 +383,89 @@ impl PersonListView {
                                     }
                                 });
                         });
+
+                        ui.add_space(4.0);
+
+                        // Rad 3: Textvillkor och uppgifter
+                        ui.horizontal(|ui| {
+                            if Self::text_condition_ui(ui, "filter_occupation", "Yrke:", &mut self.filter.occupation) {
+                                self.needs_refresh = true;
+                            }
+                            ui.separator();
+                            if Self::text_condition_ui(ui, "filter_birth_place", "Födelseort:", &mut self.filter.birth_place) {
+                                self.needs_refresh = true;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if Self::text_condition_ui(ui, "filter_surname", "Efternamn:", &mut self.filter.surname) {
+                                self.needs_refresh = true;
+                            }
+                            ui.separator();
+
+                            // Taggar
+                            ui.label("Taggar:");
+                            let tag_text = if self.filter.tag_ids.is_empty() {
+                                "Alla".to_string()
+                            } else {
+                                self.tags_cache
+                                    .iter()
+                                    .filter(|t| t.id.is_some_and(|id| self.filter.tag_ids.contains(&id)))
+                                    .map(|t| t.name.clone())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            };
+                            ui.menu_button(tag_text, |ui| {
+                                if self.tags_cache.is_empty() {
+                                    ui.label(RichText::new("Inga taggar skapade").color(Colors::TEXT_MUTED));
+                                }
+                                for tag in &self.tags_cache {
+                                    let Some(tag_id) = tag.id else { continue };
+                                    let mut checked = self.filter.tag_ids.contains(&tag_id);
+                                    if ui.checkbox(&mut checked, &tag.name).changed() {
+                                        if checked {
+                                            self.filter.tag_ids.push(tag_id);
+                                        } else {
+                                            self.filter.tag_ids.retain(|id| *id != tag_id);
+                                        }
+                                        self.needs_refresh = true;
+                                    }
+                                }
+                            });
+
+                            ui.separator();
+
+                            // Öppna checklistpunkter
+                            ui.label("Uppgifter:");
+                            let task_text = match self.filter.has_open_checklist_items {
+                                None => "Alla",
+                                Some(true) => "Har öppna",
+                                Some(false) => "Inga öppna",
+                            };
+                            egui::ComboBox::from_id_salt("filter_open_tasks")
+                                .selected_text(task_text)
+                                .show_ui(ui, |ui| {
+                                    if ui.selectable_label(self.filter.has_open_checklist_items.is_none(), "Alla").clicked() {
+                                        self.filter.has_open_checklist_items = None;
+                                        self.needs_refresh = true;
+                                    }
+                                    if ui.selectable_label(self.filter.has_open_checklist_items == Some(true), "Har öppna uppgifter").clicked() {
+                                        self.filter.has_open_checklist_items = Some(true);
+                                        self.needs_refresh = true;
+                                    }
+                                    if ui.selectable_label(self.filter.has_open_checklist_items == Some(false), "Inga öppna uppgifter").clicked() {
+                                        self.filter.has_open_checklist_items = Some(false);
+                                        self.needs_refresh = true;
+                                    }
+                                });
+                        });
+
+                        // Rad 4: Egna fält
+                        if !self.custom_fields_cache.is_empty() {
+                            ui.add_space(4.0);
+                            if self.custom_fields_filter_ui(ui) {
+                                self.needs_refresh = true;
+                            }
+                        }
                     });
             }
 
@@ -341,9 +477,11 @@ impl PersonListView {
             ui.separator();
 
             // Personlista
+            let mut toggled: Vec<i64> = Vec::new();
             egui::ScrollArea::vertical().show(ui, |ui| {
                 for person in &self.persons_cache {
                     let is_bookmarked = person.id.map(|id| self.bookmarks_cache.contains(&id)).unwrap_or(false);
+                    let is_selected = person.id.is_some_and(|id| self.selected_ids.contains(&id));
 
                     let response = egui::Frame::none()
                         .fill(ui.visuals().extreme_bg_color)
@@ -351,6 +489,15 @@ impl PersonListView {
                         .inner_margin(8.0)
                         .show(ui, |ui| {
                             ui.horizontal(|ui| {
+                                if self.selection_mode {
+                                    let mut checked = is_selected;
+                                    if ui.checkbox(&mut checked, "").changed() {
+                                        if let Some(id) = person.id {
+                                            toggled.push(id);
+                                        }
+                                    }
+                                }
+
                                 // Profilbild eller placeholder
                                 let thumb_size = egui::vec2(32.0, 32.0);
                                 if let Some(texture) = person.id.and_then(|id| self.profile_textures.get(&id)) {
@@ -387,6 +534,9 @@ impl PersonListView {
                                             ui.label(RichText::new(place).small().color(Colors::TEXT_SECONDARY));
                                         }
                                     }
+                                    if let Some(tags) = person.id.and_then(|id| self.person_tags_cache.get(&id)) {
+                                        tag_chips(ui, tags);
+                                    }
                                 });
 
                                 // Katalognamn till höger
@@ -399,19 +549,378 @@ impl PersonListView {
                     // Gör hela raden klickbar
                     if response.response.interact(egui::Sense::click()).clicked() {
                         if let Some(id) = person.id {
-                            state.navigate_to_person(id);
-                            person_selected = true;
+                            if self.selection_mode {
+                                toggled.push(id);
+                            } else {
+                                state.navigate_to_person(id);
+                                person_selected = true;
+                            }
                         }
                     }
 
                     ui.add_space(4.0);
                 }
             });
+
+            for id in toggled {
+                if !self.selected_ids.remove(&id) {
+                    self.selected_ids.insert(id);
+                }
+            }
         });
 
         person_selected
     }
 
+    /// Åtgärdsrad för flervalsläget (massmärkning med taggar)
+    fn show_selection_bar(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        let mut add_tag: Option<i64> = None;
+        let mut remove_tag: Option<i64> = None;
+
+        egui::Frame::none()
+            .fill(ui.visuals().faint_bg_color)
+            .rounding(4.0)
+            .inner_margin(8.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("{} valda", self.selected_ids.len())).strong());
+
+                    if ui.small_button("Markera alla").clicked() {
+                        self.selected_ids = self.persons_cache.iter().filter_map(|p| p.id).collect();
+                    }
+                    if ui.small_button("Avmarkera").clicked() {
+                        self.selected_ids.clear();
+                    }
+
+                    ui.separator();
+
+                    ui.add_enabled_ui(!self.selected_ids.is_empty(), |ui| {
+                        ui.menu_button(format!("{} Tagga", Icons::BOOKMARK), |ui| {
+                            if self.tags_cache.is_empty() {
+                                ui.label(RichText::new("Skapa taggar under Inställningar").color(Colors::TEXT_MUTED));
+                            }
+                            for tag in &self.tags_cache {
+                                if tag_chip(ui, tag).interact(egui::Sense::click()).clicked() {
+                                    add_tag = tag.id;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        ui.menu_button("Ta bort tagg", |ui| {
+                            for tag in &self.tags_cache {
+                                if tag_chip(ui, tag).interact(egui::Sense::click()).clicked() {
+                                    remove_tag = tag.id;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                });
+            });
+
+        let ids: Vec<i64> = self.selected_ids.iter().copied().collect();
+        if let Some(tag_id) = add_tag {
+            match db.tags().add_to_persons(tag_id, &ids) {
+                Ok(n) => {
+                    state.show_success(&format!("Taggade {} personer", n));
+                    self.needs_refresh = true;
+                }
+                Err(e) => state.show_error(&format!("Kunde inte tagga: {}", e)),
+            }
+        }
+        if let Some(tag_id) = remove_tag {
+            match db.tags().remove_from_persons(tag_id, &ids) {
+                Ok(n) => {
+                    state.show_success(&format!("Tog bort taggen från {} personer", n));
+                    self.needs_refresh = true;
+                }
+                Err(e) => state.show_error(&format!("Kunde inte ta bort tagg: {}", e)),
+            }
+        }
+    }
+
+    /// Redigera ett textvillkor (operator + värde). Returnerar true vid ändring.
+    fn text_condition_ui(
+        ui: &mut egui::Ui,
+        id: &str,
+        label: &str,
+        condition: &mut Option<TextCondition>,
+    ) -> bool {
+        let mut changed = false;
+        ui.label(label);
+
+        let selected_text = condition
+            .as_ref()
+            .map(|c| c.operator.display_name())
+            .unwrap_or("Alla");
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(selected_text)
+            .width(110.0)
+            .show_ui(ui, |ui| {
+                if ui.selectable_label(condition.is_none(), "Alla").clicked() {
+                    *condition = None;
+                    changed = true;
+                }
+                for op in TextOperator::all() {
+                    let selected = condition.as_ref().is_some_and(|c| c.operator == *op);
+                    if ui.selectable_label(selected, op.display_name()).clicked() {
+                        let value = condition.take().map(|c| c.value).unwrap_or_default();
+                        *condition = Some(TextCondition::new(*op, value));
+                        changed = true;
+                    }
+                }
+            });
+
+        if let Some(cond) = condition.as_mut() {
+            if cond.operator.needs_value()
+                && ui
+                    .add(egui::TextEdit::singleline(&mut cond.value).desired_width(120.0))
+                    .changed()
+            {
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Villkor på egna fält. Returnerar true vid ändring.
+    fn custom_fields_filter_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let mut remove: Option<usize> = None;
+
+        for (index, custom) in self.filter.custom_fields.iter_mut().enumerate() {
+            let name = self
+                .custom_fields_cache
+                .iter()
+                .find(|d| d.id == Some(custom.field_id))
+                .map(|d| format!("{}:", d.name))
+                .unwrap_or_else(|| "Okänt fält:".to_string());
+
+            ui.horizontal(|ui| {
+                let mut condition = Some(custom.condition.clone());
+                let id = format!("filter_custom_{}", index);
+                if Self::text_condition_ui(ui, &id, &name, &mut condition) {
+                    match condition {
+                        Some(c) => custom.condition = c,
+                        None => remove = Some(index),
+                    }
+                    changed = true;
+                }
+                if ui.small_button("✕").on_hover_text("Ta bort villkor").clicked() {
+                    remove = Some(index);
+                    changed = true;
+                }
+            });
+        }
+
+        if let Some(index) = remove {
+            self.filter.custom_fields.remove(index);
+        }
+
+        ui.menu_button(format!("{} Eget fält", Icons::ADD), |ui| {
+            for definition in &self.custom_fields_cache {
+                let Some(field_id) = definition.id else { continue };
+                if ui.button(&definition.name).clicked() {
+                    self.filter.custom_fields.push(CustomFieldCondition {
+                        field_id,
+                        condition: TextCondition::default(),
+                    });
+                    changed = true;
+                    ui.close_menu();
+                }
+            }
+        });
+
+        changed
+    }
+
+    /// Sidopanel med sparade sökningar (smarta grupper)
+    fn show_saved_searches(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        ui.label(RichText::new(format!("{} Smarta grupper", Icons::FILTER)).strong());
+        ui.add_space(4.0);
+
+        let mut apply: Option<SavedSearch> = None;
+        let mut update: Option<SavedSearch> = None;
+        let mut delete: Option<i64> = None;
+        let mut bulk_apply: Option<(i64, i64)> = None; // (saved_search_id, template_id)
+        let mut bulk_family_sheets: Option<(i64, RedactionStyle)> = None;
+
+        egui::ScrollArea::vertical()
+            .id_salt("saved_searches_scroll")
+            .max_height(ui.available_height() - 80.0)
+            .show(ui, |ui| {
+                if self.saved_searches_cache.is_empty() {
+                    ui.label(
+                        RichText::new("Inga sparade sökningar ännu.")
+                            .small()
+                            .color(Colors::TEXT_MUTED),
+                    );
+                }
+
+                for (search, count) in &self.saved_searches_cache {
+                    let selected = search.id.is_some() && self.active_saved_search == search.id;
+                    ui.horizontal(|ui| {
+                        let response = ui.selectable_label(selected, &search.name);
+                        if response.clicked() {
+                            apply = Some(search.clone());
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("Spara aktuellt filter hit").clicked() {
+                                let mut updated = search.clone();
+                                updated.filter = self.filter.clone();
+                                update = Some(updated);
+                                ui.close_menu();
+                            }
+                            ui.menu_button(format!("{} Lägg till checklistmall", Icons::CHECK), |ui| {
+                                if self.checklist_templates_cache.is_empty() {
+                                    ui.label(RichText::new("Inga mallar").color(Colors::TEXT_MUTED));
+                                }
+                                for template in &self.checklist_templates_cache {
+                                    if ui.button(&template.name).clicked() {
+                                        if let (Some(search_id), Some(template_id)) = (search.id, template.id) {
+                                            bulk_apply = Some((search_id, template_id));
+                                        }
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                            ui.menu_button(format!("{} Familjeblad (PDF)", Icons::DOCUMENT), |ui| {
+                                if let (Some(search_id), Some(redaction)) = (search.id, redaction_choices(ui)) {
+                                    bulk_family_sheets = Some((search_id, redaction));
+                                }
+                            });
+                            ui.separator();
+                            if ui.button(format!("{} Ta bort", Icons::DELETE)).clicked() {
+                                delete = search.id;
+                                ui.close_menu();
+                            }
+                        });
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.label(RichText::new(count.to_string()).small().color(Colors::TEXT_MUTED));
+                        });
+                    });
+                }
+            });
+
+        ui.separator();
+
+        // Spara aktuellt filter som ny sökning
+        ui.add(
+            egui::TextEdit::singleline(&mut self.new_saved_search_name)
+                .hint_text("Namn på sökning...")
+                .desired_width(f32::INFINITY),
+        );
+        let can_save = !self.new_saved_search_name.trim().is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new(format!("{} Spara sökning", Icons::SAVE)))
+            .clicked()
+        {
+            let mut search = SavedSearch::new(self.new_saved_search_name.trim().to_string(), self.filter.clone());
+            match db.saved_searches().create(&mut search) {
+                Ok(id) => {
+                    self.active_saved_search = Some(id);
+                    self.new_saved_search_name.clear();
+                    state.show_success("Sökningen sparades");
+                    self.needs_refresh = true;
+                }
+                Err(e) => state.show_error(&format!("Kunde inte spara sökningen: {}", e)),
+            }
+        }
+
+        if let Some(search) = apply {
+            self.apply_saved_search(&search);
+        }
+
+        if let Some(search) = update {
+            match db.saved_searches().update(&search) {
+                Ok(()) => {
+                    state.show_success("Sökningen uppdaterades");
+                    self.needs_refresh = true;
+                }
+                Err(e) => state.show_error(&format!("Kunde inte uppdatera sökningen: {}", e)),
+            }
+        }
+
+        if let Some(id) = delete {
+            match db.saved_searches().delete(id) {
+                Ok(()) => {
+                    if self.active_saved_search == Some(id) {
+                        self.active_saved_search = None;
+                    }
+                    self.needs_refresh = true;
+                }
+                Err(e) => state.show_error(&format!("Kunde inte ta bort sökningen: {}", e)),
+            }
+        }
+
+        if let Some((search_id, template_id)) = bulk_apply {
+            self.bulk_apply_template(search_id, template_id, state, db);
+        }
+
+        if let Some((search_id, redaction)) = bulk_family_sheets {
+            self.bulk_family_sheets(search_id, redaction, state, db);
+        }
+    }
+
+    /// Ladda en sparad sökning i filtret
+    fn apply_saved_search(&mut self, search: &SavedSearch) {
+        self.filter = search.filter.clone();
+        self.active_saved_search = search.id;
+        let fmt = |d: Option<NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
+        self.birth_after_str = fmt(self.filter.birth_after);
+        self.birth_before_str = fmt(self.filter.birth_before);
+        self.death_after_str = fmt(self.filter.death_after);
+        self.death_before_str = fmt(self.filter.death_before);
+        self.show_advanced_filters = self.filter.has_advanced_filters();
+        self.needs_refresh = true;
+    }
+
+    /// Lägg till en checklistmall hos alla personer i en sparad sökning
+    fn bulk_apply_template(&mut self, search_id: i64, template_id: i64, state: &mut AppState, db: &Database) {
+        let Some((search, _)) = self.saved_searches_cache.iter().find(|(s, _)| s.id == Some(search_id)) else {
+            return;
+        };
+
+        let result = db.persons().advanced_search(&search.filter).and_then(|persons| {
+            let ids: Vec<i64> = persons.iter().filter_map(|p| p.id).collect();
+            db.checklists().apply_template_to_persons(template_id, &ids)
+        });
+
+        match result {
+            Ok(created) => {
+                state.show_success(&format!("{} uppgifter skapades för \"{}\"", created, search.name));
+                self.needs_refresh = true;
+            }
+            Err(e) => state.show_error(&format!("Kunde inte lägga till mallen: {}", e)),
+        }
+    }
+
+    /// Skriv familjeblad för alla personer i en sparad sökning till en PDF
+    fn bulk_family_sheets(&self, search_id: i64, redaction: RedactionStyle, state: &mut AppState, db: &Database) {
+        let Some((search, _)) = self.saved_searches_cache.iter().find(|(s, _)| s.id == Some(search_id)) else {
+            return;
+        };
+        let filename = format!("familjeblad_{}.pdf", sanitize_filename(&search.name));
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&filename)
+            .add_filter("PDF", &["pdf"])
+            .save_file()
+        else {
+            return;
+        };
+
+        let result = db.persons().advanced_search(&search.filter).and_then(|persons| {
+            let ids: Vec<i64> = persons.iter().filter_map(|p| p.id).collect();
+            FamilyGroupSheetService::new(db).export(&ids, redaction, &path)
+        });
+
+        match result {
+            Ok(result) => state.show_success(&format!("{} sparade till {}", result.summary(), path.display())),
+            Err(e) => state.show_error(&format!("Kunde inte skapa familjeblad: {}", e)),
+        }
+    }
+
     fn refresh_persons(&mut self, db: &Database) {
         // Hämta media root
         self.media_root = db
@@ -440,6 +949,42 @@ impl PersonListView {
             .filter_map(|p| p.id)
             .collect();
 
+        // Sparade sökningar med aktuella antal träffar
+        self.saved_searches_cache = db
+            .saved_searches()
+            .find_all()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| {
+                let count = db.persons().count_matching(&s.filter).unwrap_or(0);
+                (s, count)
+            })
+            .collect();
+        self.checklist_templates_cache = db.checklists().list_templates(false).unwrap_or_default();
+
+        // Taggar
+        self.tags_cache = db.tags().find_all().unwrap_or_default();
+        self.person_tags_cache = db.tags().find_all_by_person().unwrap_or_default();
+        let existing_tags: HashSet<i64> = self.tags_cache.iter().filter_map(|t| t.id).collect();
+        self.filter.tag_ids.retain(|id| existing_tags.contains(id));
+
+        // Egna fält
+        self.custom_fields_cache = db
+            .custom_fields()
+            .find_definitions(CustomFieldEntity::Person)
+            .unwrap_or_default();
+
+        // Avmarkera den sparade sökningen om filtret har ändrats sedan den valdes
+        if let Some(active_id) = self.active_saved_search {
+            let unchanged = self
+                .saved_searches_cache
+                .iter()
+                .any(|(s, _)| s.id == Some(active_id) && s.filter == self.filter);
+            if !unchanged {
+                self.active_saved_search = None;
+            }
+        }
+
         // Rensa textur-cache vid refresh (profilbilder kan ha ändrats)
         self.profile_textures.clear();
     }
@@ -1,9 +1,16 @@
 //! Rapportvy för export av data
 
-use egui::{self, RichText};
-
-use crate::db::Database;
-use crate::services::export::{ExportFormat, ExportService, ReportType};
+use chrono::Utc;
+use egui::{self, Color32, Pos2, Rect, RichText, Sense, Stroke, Vec2};
+use serde_json::Value;
+
+use crate::db::{Database, ReportTemplate, SavedSearch, TemplateFormat};
+use crate::models::{DocumentType, Tag};
+use crate::services::analytics::{sex_label, Analytics};
+use crate::services::export::{ExportFormat, ExportService, ReportOptions, ReportType};
+use crate::services::report_template::{ParsedTemplate, ReportTemplateService, EXAMPLE_TEMPLATE, TEMPLATE_HELP};
+use crate::services::RedactionStyle;
+use crate::utils::path::sanitize_filename;
 use crate::ui::{
     state::AppState,
     theme::{Colors, Icons},
@@ -15,14 +22,44 @@ pub struct ReportsView {
     selected_report: ReportType,
     /// Valt exportformat
     selected_format: ExportFormat,
+    /// Inställningar för personbaserade rapporter
+    report_options: ReportOptions,
+    /// Personer att välja utgångsperson bland (id, namn)
+    persons_cache: Vec<(i64, String)>,
+    /// Dokumenttyper för luckrapporten
+    document_types_cache: Vec<DocumentType>,
+    /// Sparade sökningar som kan användas som urval
+    saved_searches_cache: Vec<SavedSearch>,
+    /// Vald sparad sökning (None = alla personer)
+    selected_saved_search: Option<i64>,
+    /// Taggar som kan användas som filter
+    tags_cache: Vec<Tag>,
+    /// Vald tagg (None = alla)
+    selected_tag: Option<i64>,
+    /// Egna rapportmallar
+    templates_cache: Vec<ReportTemplate>,
+    /// Mallen som redigeras (None = inbyggd rapport vald)
+    template_editor: Option<ReportTemplate>,
+    /// Data för förhandsgranskningen och urvalet det gäller
+    template_preview_data: Option<(PreviewKey, Value)>,
+    /// Väntar på bekräftelse att ta bort mallen
+    confirm_template_delete: bool,
     /// Senaste exportresultat (meddelande)
     last_result: Option<String>,
     /// Statistik-cache
     stats_cache: Option<StatsCache>,
+    /// Släktanalys över alla personer
+    analytics_cache: Option<Analytics>,
     /// Behöver refresh
     needs_refresh: bool,
 }
 
+/// Urval och maskering som förhandsgranskningen byggdes för
+type PreviewKey = (Option<i64>, Option<i64>, RedactionStyle);
+
+/// Antal personer i förhandsgranskningen av en mall
+const PREVIEW_PERSONS: usize = 20;
+
 struct StatsCache {
     total_persons: i64,
     living_persons: i64,
@@ -42,8 +79,20 @@ impl ReportsView {
         Self {
             selected_report: ReportType::AllPersons,
             selected_format: ExportFormat::Json,
+            report_options: ReportOptions::default(),
+            persons_cache: Vec::new(),
+            document_types_cache: Vec::new(),
+            saved_searches_cache: Vec::new(),
+            selected_saved_search: None,
+            tags_cache: Vec::new(),
+            selected_tag: None,
+            templates_cache: Vec::new(),
+            template_editor: None,
+            template_preview_data: None,
+            confirm_template_delete: false,
             last_result: None,
             stats_cache: None,
+            analytics_cache: None,
             needs_refresh: true,
         }
     }
@@ -69,29 +118,36 @@ impl ReportsView {
 
         ui.add_space(16.0);
 
-        // Statistiköversikt
-        self.show_statistics_overview(ui);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            // Statistiköversikt
+            self.show_statistics_overview(ui);
 
-        ui.add_space(16.0);
+            ui.add_space(16.0);
 
-        // Export-sektion
-        self.show_export_section(ui, state, db);
+            // Diagram
+            self.show_analytics(ui);
 
-        ui.add_space(16.0);
+            ui.add_space(16.0);
+
+            // Export-sektion
+            self.show_export_section(ui, state, db);
 
-        // Senaste resultat
-        if let Some(ref result) = self.last_result {
-            egui::Frame::none()
-                .fill(Colors::SUCCESS.gamma_multiply(0.2))
-                .rounding(8.0)
-                .inner_margin(12.0)
-                .show(ui, |ui| {
-                    ui.horizontal(|ui| {
-                        ui.label(RichText::new(Icons::CHECK).color(Colors::SUCCESS));
-                        ui.label(result);
+            ui.add_space(16.0);
+
+            // Senaste resultat
+            if let Some(ref result) = self.last_result {
+                egui::Frame::none()
+                    .fill(Colors::SUCCESS.gamma_multiply(0.2))
+                    .rounding(8.0)
+                    .inner_margin(12.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(Icons::CHECK).color(Colors::SUCCESS));
+                            ui.label(result);
+                        });
                     });
-                });
-        }
+            }
+        });
     }
 
     fn show_statistics_overview(&self, ui: &mut egui::Ui) {
@@ -117,6 +173,113 @@ impl ReportsView {
             });
     }
 
+    fn show_analytics(&self, ui: &mut egui::Ui) {
+        let Some(ref analytics) = self.analytics_cache else {
+            return;
+        };
+
+        egui::Frame::none()
+            .fill(ui.visuals().extreme_bg_color)
+            .rounding(8.0)
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                egui::CollapsingHeader::new(RichText::new(format!("{} Släktanalys", Icons::DASHBOARD)).heading())
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.columns(2, |columns| {
+                            Self::show_analytics_left(&mut columns[0], analytics);
+                            Self::show_analytics_right(&mut columns[1], analytics);
+                        });
+                    });
+            });
+    }
+
+    fn show_analytics_left(ui: &mut egui::Ui, analytics: &Analytics) {
+        // Efternamn över tid, en linje per namn
+        let mut surnames: Vec<(String, Vec<(f64, f64)>)> = Vec::new();
+        for row in &analytics.surnames_by_decade {
+            match surnames.iter_mut().find(|(name, _)| *name == row.surname) {
+                Some((_, points)) => points.push((row.decade as f64, row.count as f64)),
+                None => surnames.push((row.surname.clone(), vec![(row.decade as f64, row.count as f64)])),
+            }
+        }
+        let series: Vec<Series> = surnames
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, points))| Series { name, color: SERIES_COLORS[i % SERIES_COLORS.len()], points })
+            .collect();
+        chart_card(ui, "Efternamn per födelsedecennium", |ui| line_chart(ui, &series, "st"));
+
+        // Medellivslängd per kön
+        let mut lifespan: Vec<Series> = Vec::new();
+        for row in &analytics.lifespan_by_decade {
+            let name = sex_label(&row.sex).to_string();
+            match lifespan.iter_mut().find(|s| s.name == name) {
+                Some(series) => series.points.push((row.decade as f64, row.average_years)),
+                None => lifespan.push(Series {
+                    color: sex_color(&row.sex),
+                    name,
+                    points: vec![(row.decade as f64, row.average_years)],
+                }),
+            }
+        }
+        chart_card(ui, "Medellivslängd per födelsedecennium", |ui| line_chart(ui, &lifespan, "år"));
+
+        let mortality: Vec<Bar> = analytics
+            .child_mortality
+            .iter()
+            .map(|row| Bar {
+                label: format!("{}-tal", row.decade),
+                value: row.percent(),
+                text: format!("{:.0} % ({} av {})", row.percent(), row.died_under_15, row.births),
+            })
+            .collect();
+        chart_card(ui, "Döda före 15 års ålder", |ui| bar_chart(ui, &mortality, Colors::ERROR));
+
+        let lines: Vec<Bar> = analytics
+            .generation_depth
+            .iter()
+            .map(|line| Bar {
+                label: line.full_name.clone(),
+                value: line.generations as f64,
+                text: format!("{} gen., {} ättlingar", line.generations, line.descendants),
+            })
+            .collect();
+        chart_card(ui, "Generationsdjup per släktlinje", |ui| bar_chart(ui, &lines, Colors::CHILD));
+    }
+
+    fn show_analytics_right(ui: &mut egui::Ui, analytics: &Analytics) {
+        let marriage: Vec<Bar> = analytics
+            .first_marriage_age
+            .iter()
+            .map(|row| Bar {
+                label: sex_label(&row.sex).to_string(),
+                value: row.average_age,
+                text: format!("{:.1} år ({}–{}, {} st)", row.average_age, row.min_age, row.max_age, row.count),
+            })
+            .collect();
+        chart_card(ui, "Ålder vid första giftermål", |ui| bar_chart(ui, &marriage, Colors::SPOUSE));
+
+        let children: Vec<Bar> = analytics
+            .children_per_couple
+            .iter()
+            .map(|row| Bar {
+                label: format!("{} barn", row.children),
+                value: row.couples as f64,
+                text: format!("{} par", row.couples),
+            })
+            .collect();
+        let title = format!("Barn per par (medel {:.1})", analytics.average_children_per_couple);
+        chart_card(ui, &title, |ui| bar_chart(ui, &children, Colors::PARENT));
+
+        let places: Vec<Bar> = analytics
+            .top_birth_places
+            .iter()
+            .map(|row| Bar { label: row.place.clone(), value: row.count as f64, text: row.count.to_string() })
+            .collect();
+        chart_card(ui, "Vanligaste födelseorter", |ui| bar_chart(ui, &places, Colors::PRIMARY));
+    }
+
     fn stat_card(ui: &mut egui::Ui, icon: &str, label: &str, value: i64) {
         egui::Frame::none()
             .fill(ui.visuals().faint_bg_color)
@@ -141,60 +304,108 @@ impl ReportsView {
                 ui.add_space(8.0);
 
                 ui.horizontal(|ui| {
-                    // Rapporttyp
+                    // Rapporttyp eller egen mall
                     ui.label("Rapport:");
+                    let selected_text = match self.template_editor {
+                        Some(ref template) => format!("Mall: {}", template.name),
+                        None => self.selected_report.display_name().to_string(),
+                    };
                     egui::ComboBox::from_id_salt("report_type")
-                        .selected_text(self.selected_report.display_name())
+                        .selected_text(selected_text)
                         .show_ui(ui, |ui| {
-                            ui.selectable_value(
-                                &mut self.selected_report,
-                                ReportType::AllPersons,
-                                ReportType::AllPersons.display_name(),
-                            );
-                            ui.selectable_value(
-                                &mut self.selected_report,
-                                ReportType::AllRelationships,
-                                ReportType::AllRelationships.display_name(),
-                            );
-                            ui.selectable_value(
-                                &mut self.selected_report,
-                                ReportType::Statistics,
-                                ReportType::Statistics.display_name(),
-                            );
+                            for report in ReportType::all() {
+                                let selected = self.template_editor.is_none() && self.selected_report == *report;
+                                if ui.selectable_label(selected, report.display_name()).clicked() {
+                                    self.selected_report = *report;
+                                    self.template_editor = None;
+                                }
+                            }
+                            if !self.templates_cache.is_empty() {
+                                ui.separator();
+                            }
+                            for template in &self.templates_cache {
+                                let selected = self.template_editor.as_ref().is_some_and(|t| t.id == template.id);
+                                if ui.selectable_label(selected, format!("Mall: {}", template.name)).clicked() {
+                                    self.template_editor = Some(template.clone());
+                                    self.confirm_template_delete = false;
+                                }
+                            }
                         });
 
+                    if ui.button(format!("{} Ny mall", Icons::ADD)).clicked() {
+                        self.template_editor = Some(ReportTemplate::new(
+                            "Ny mall".to_string(),
+                            TemplateFormat::Markdown,
+                            EXAMPLE_TEMPLATE.to_string(),
+                        ));
+                        self.confirm_template_delete = false;
+                    }
+
+                    if self.template_editor.is_some() {
+                        return;
+                    }
                     ui.separator();
 
-                    // Format
+                    // Format (beror på rapporttyp)
+                    let formats = self.selected_report.formats();
+                    if !formats.contains(&self.selected_format) {
+                        self.selected_format = formats[0];
+                    }
                     ui.label("Format:");
                     egui::ComboBox::from_id_salt("export_format")
                         .selected_text(self.selected_format.display_name())
                         .show_ui(ui, |ui| {
-                            ui.selectable_value(
-                                &mut self.selected_format,
-                                ExportFormat::Json,
-                                ExportFormat::Json.display_name(),
-                            );
-                            ui.selectable_value(
-                                &mut self.selected_format,
-                                ExportFormat::Csv,
-                                ExportFormat::Csv.display_name(),
-                            );
-                            ui.selectable_value(
-                                &mut self.selected_format,
-                                ExportFormat::Pdf,
-                                ExportFormat::Pdf.display_name(),
-                            );
+                            for format in formats {
+                                ui.selectable_value(&mut self.selected_format, *format, format.display_name());
+                            }
                         });
                 });
 
+                if self.template_editor.is_some() {
+                    ui.add_space(8.0);
+                    self.show_selection_options(ui);
+                    ui.add_space(8.0);
+                    self.show_privacy_options(ui);
+                    ui.add_space(12.0);
+                    self.show_template_editor(ui, state, db);
+                    return;
+                }
+
+                if self.selected_report == ReportType::RelationshipGraph {
+                    ui.add_space(8.0);
+                    self.show_selection_options(ui);
+                    ui.add_space(4.0);
+                    self.show_graph_root_options(ui);
+                } else if self.selected_report.requires_root_person() {
+                    ui.add_space(8.0);
+                    self.show_root_person_options(ui, state);
+                } else if self.selected_report != ReportType::AllResources {
+                    ui.add_space(8.0);
+                    self.show_selection_options(ui);
+                }
+
+                // Statistik och resurser innehåller inga enskilda personer
+                if !matches!(self.selected_report, ReportType::Statistics | ReportType::AllResources) {
+                    ui.add_space(8.0);
+                    self.show_privacy_options(ui);
+                }
+
                 ui.add_space(12.0);
 
                 // Beskrivning av vald rapport
                 let description = match self.selected_report {
-                    ReportType::AllPersons => "Exporterar alla personer med namn, datum, ålder och anteckningar.",
+                    ReportType::AllPersons => "Exporterar alla personer med namn, datum, ålder, anteckningar och egna fält.",
                     ReportType::AllRelationships => "Exporterar alla relationer mellan personer med namn och relationstyp.",
                     ReportType::Statistics => "Exporterar en sammanfattning med statistik om databasen.",
+                    ReportType::ResearchGaps => "Listar personen och dess förfäder som saknar födelsedatum, dödsdatum, födelseort, föräldrar, källor eller vald dokumenttyp. Sorteras på generationsavstånd.",
+                    ReportType::AllResources => "Exporterar alla resurser med typ, information, koordinater och egna fält.",
+                    ReportType::PedigreeCollapse => "Listar förfäder som förekommer på flera platser i antavlan med antavlenummer, samt släktskapskoefficienten för föräldrapar som är släkt med varandra.",
+                    ReportType::DescendantRegister => "Ättlingar från vald stamfar eller stammor, generation för generation i Register/NGSQ-stil: varje ättling numreras, barn med egen familj markeras med + och får ett eget stycke, och makar anges med föräldrar.",
+                    ReportType::Ahnentafel => "Antavla numrerad enligt Sosa–Stradonitz (far = 2n, mor = 2n + 1) med ett stycke per förfader: levnadsdata, orter, yrke och anteckningar. Förfäder som förekommer flera gånger hänvisar till första numret.",
+                    ReportType::Analytics => "Exporterar efternamn över tid, medellivslängd per decennium och kön, barnadödlighet, ålder vid första giftermål, barn per par, vanligaste födelseorter och generationsdjup per släktlinje.",
+                    ReportType::PersonDossier => "Allt om vald person i en PDF: personuppgifter och egna fält, relationer per typ, checklistans status, miniatyrer av alla bilder, innehållet i textdokument och en förteckning över bifogade PDF-filer och andra dokument.",
+                    ReportType::Website => "Skriver en fristående webbplats till vald katalog: efternamnsregister med sökning, en sida per person med relationer som länkar, släktträd och bildgalleri. Sidorna öppnas direkt i webbläsaren utan internet. Levande och privata personer maskeras alltid.",
+                    ReportType::RelationshipGraph => "Exporterar personer som noder (namn, kön, årtal, födelseort) och relationer som typade kanter, för Graphviz (DOT) eller Gephi och yEd (GraphML).",
                 };
                 ui.label(RichText::new(description).small().color(Colors::TEXT_SECONDARY));
 
@@ -210,20 +421,381 @@ impl ReportsView {
             });
     }
 
-    fn do_export(&mut self, state: &mut AppState, db: &Database) {
-        // Generera filnamn
-        let filename = ExportService::generate_filename(self.selected_report, self.selected_format);
+    /// Redigera egen mall med förhandsgranskning av de första personerna i urvalet
+    fn show_template_editor(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        // Datat byggs om bara när urvalet eller maskeringen ändras
+        let key = (self.selected_saved_search, self.selected_tag, self.report_options.redaction);
+        if self.template_preview_data.as_ref().map_or(true, |(k, _)| *k != key) {
+            let data = ReportTemplateService::new(db)
+                .with_options(self.selection_options())
+                .data_model("", Some(PREVIEW_PERSONS))
+                .unwrap_or(Value::Null);
+            self.template_preview_data = Some((key, data));
+        }
+
+        let Some(ref mut template) = self.template_editor else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Namn:");
+            ui.add(egui::TextEdit::singleline(&mut template.name).desired_width(200.0));
+            ui.separator();
+            ui.label("Format:");
+            egui::ComboBox::from_id_salt("template_format")
+                .selected_text(template.format.display_name())
+                .show_ui(ui, |ui| {
+                    for format in TemplateFormat::all() {
+                        ui.selectable_value(&mut template.format, *format, format.display_name());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Beskrivning:");
+            let description = template.description.get_or_insert_with(String::new);
+            ui.add(egui::TextEdit::singleline(description).desired_width(f32::INFINITY));
+        });
+        ui.add_space(8.0);
+
+        let preview = match ParsedTemplate::parse(&template.body) {
+            Ok(parsed) => {
+                let mut data = self.template_preview_data.as_ref().map(|(_, d)| d.clone()).unwrap_or_default();
+                data["title"] = Value::String(template.name.clone());
+                Ok(parsed.render(&data, template.format))
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        ui.columns(2, |columns| {
+            columns[0].label(RichText::new("Mall").strong());
+            egui::ScrollArea::vertical()
+                .id_salt("template_body")
+                .max_height(420.0)
+                .show(&mut columns[0], |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut template.body)
+                            .code_editor()
+                            .desired_rows(24)
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+
+            columns[1].label(
+                RichText::new(format!("Förhandsgranskning (högst {} personer)", PREVIEW_PERSONS)).strong(),
+            );
+            egui::ScrollArea::vertical()
+                .id_salt("template_preview")
+                .max_height(420.0)
+                .show(&mut columns[1], |ui| match preview {
+                    Ok(ref text) => {
+                        ui.add(
+                            egui::Label::new(RichText::new(text).monospace().size(12.0)).wrap(),
+                        );
+                    }
+                    Err(ref error) => {
+                        ui.label(RichText::new(error).color(Colors::ERROR));
+                    }
+                });
+        });
+
+        ui.add_space(4.0);
+        egui::CollapsingHeader::new("Mallspråk och datamodell")
+            .id_salt("template_help")
+            .show(ui, |ui| {
+                ui.label(RichText::new(TEMPLATE_HELP).monospace().size(12.0));
+            });
+
+        ui.add_space(12.0);
+        let saved = self.templates_cache.iter().find(|t| t.id.is_some() && t.id == template.id);
+        let unsaved = saved != Some(&*template);
+        let mut save = false;
+        let mut delete = false;
+        let mut export = false;
+        ui.horizontal(|ui| {
+            if ui.button(format!("{} Exportera till fil", Icons::DOWNLOAD)).clicked() {
+                export = true;
+            }
+            ui.separator();
+            if ui.add_enabled(unsaved, egui::Button::new(format!("{} Spara mall", Icons::SAVE))).clicked() {
+                save = true;
+            }
+            if template.id.is_some() {
+                if self.confirm_template_delete {
+                    ui.label("Ta bort mallen?");
+                    if ui.button(RichText::new("Ja, ta bort").color(Colors::ERROR)).clicked() {
+                        delete = true;
+                    }
+                    if ui.button("Avbryt").clicked() {
+                        self.confirm_template_delete = false;
+                    }
+                } else if ui.button(format!("{} Ta bort", Icons::DELETE)).clicked() {
+                    self.confirm_template_delete = true;
+                }
+            }
+            if unsaved {
+                ui.label(RichText::new("Osparade ändringar").small().color(Colors::WARNING));
+            }
+        });
+
+        if save {
+            self.save_template(state, db);
+        }
+        if delete {
+            self.delete_template(state, db);
+        }
+        if export {
+            self.export_template(state, db);
+        }
+    }
+
+    fn save_template(&mut self, state: &mut AppState, db: &Database) {
+        let Some(ref mut template) = self.template_editor else {
+            return;
+        };
+        if template.description.as_deref().is_some_and(|d| d.trim().is_empty()) {
+            template.description = None;
+        }
+        let result = match template.id {
+            Some(_) => db.report_templates().update(template),
+            None => db.report_templates().create(template).map(|_| ()),
+        };
+        match result {
+            Ok(()) => {
+                state.show_success(&format!("Mallen {} sparades", template.name.trim()));
+                self.templates_cache = db.report_templates().find_all().unwrap_or_default();
+                // Hämta den sparade versionen så att den jämförs lika med listan
+                if let Some(saved) = self.templates_cache.iter().find(|t| t.id == template.id) {
+                    *template = saved.clone();
+                }
+            }
+            Err(e) => state.show_error(&format!("Kunde inte spara mallen: {}", e)),
+        }
+    }
 
-        // Öppna fildialog för att välja var filen ska sparas
-        let file_dialog = rfd::FileDialog::new()
+    fn delete_template(&mut self, state: &mut AppState, db: &Database) {
+        let Some(id) = self.template_editor.as_ref().and_then(|t| t.id) else {
+            return;
+        };
+        match db.report_templates().delete(id) {
+            Ok(()) => {
+                state.show_success("Mallen togs bort");
+                self.template_editor = None;
+                self.confirm_template_delete = false;
+                self.templates_cache = db.report_templates().find_all().unwrap_or_default();
+            }
+            Err(e) => state.show_error(&format!("Kunde inte ta bort mallen: {}", e)),
+        }
+    }
+
+    fn export_template(&mut self, state: &mut AppState, db: &Database) {
+        let Some(ref template) = self.template_editor else {
+            return;
+        };
+        let filename = format!(
+            "genlib_{}_{}.{}",
+            sanitize_filename(template.name.trim()),
+            Utc::now().format("%Y%m%d_%H%M%S"),
+            template.format.extension()
+        );
+        let Some(path) = rfd::FileDialog::new()
             .set_file_name(&filename)
-            .add_filter(
-                self.selected_format.display_name(),
-                &[self.selected_format.extension()],
+            .add_filter(template.format.display_name(), &[template.format.extension()])
+            .save_file()
+        else {
+            return;
+        };
+
+        match ReportTemplateService::new(db)
+            .with_options(self.selection_options())
+            .export(template, &path)
+        {
+            Ok(result) => {
+                let msg = format!("{} sparad till {}", result.summary(), path.display());
+                self.last_result = Some(msg.clone());
+                state.show_success(&msg);
+            }
+            Err(e) => state.show_error(&format!("Export misslyckades: {}", e)),
+        }
+    }
+
+    fn show_root_person_options(&mut self, ui: &mut egui::Ui, state: &AppState) {
+        // Förvälj aktuell person
+        if self.report_options.root_person_id.is_none() {
+            self.report_options.root_person_id = state.selected_person_id;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Utgångsperson:");
+            let selected_name = self
+                .report_options
+                .root_person_id
+                .and_then(|id| self.persons_cache.iter().find(|(pid, _)| *pid == id))
+                .map(|(_, name)| name.clone())
+                .unwrap_or_else(|| "Välj person...".to_string());
+            egui::ComboBox::from_id_salt("report_root_person")
+                .selected_text(selected_name)
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for (id, name) in &self.persons_cache {
+                        ui.selectable_value(&mut self.report_options.root_person_id, Some(*id), name);
+                    }
+                });
+
+            if self.selected_report != ReportType::PersonDossier {
+                ui.separator();
+
+                ui.label("Generationer:");
+                ui.add(egui::DragValue::new(&mut self.report_options.max_generations).range(1..=15));
+            }
+
+            if self.selected_report == ReportType::DescendantRegister {
+                ui.separator();
+                ui.checkbox(&mut self.report_options.include_sources, "Ta med källor (dokument)");
+            }
+
+            // Dokumenttyp används bara för forskningsluckor
+            if self.selected_report == ReportType::ResearchGaps {
+                ui.separator();
+
+                ui.label("Dokumenttyp:");
+                let selected_type = self
+                    .report_options
+                    .document_type_id
+                    .and_then(|id| self.document_types_cache.iter().find(|t| t.id == Some(id)))
+                    .map(|t| t.name.clone())
+                    .unwrap_or_else(|| "Ingen".to_string());
+                egui::ComboBox::from_id_salt("report_document_type")
+                    .selected_text(selected_type)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.report_options.document_type_id, None, "Ingen");
+                        for doc_type in &self.document_types_cache {
+                            ui.selectable_value(
+                                &mut self.report_options.document_type_id,
+                                doc_type.id,
+                                &doc_type.name,
+                            );
+                        }
+                    });
+            }
+        });
+    }
+
+    /// Valfri utgångsperson för släktgrafen
+    fn show_graph_root_options(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Utgångsperson:");
+            let selected_name = self
+                .report_options
+                .root_person_id
+                .and_then(|id| self.persons_cache.iter().find(|(pid, _)| *pid == id))
+                .map(|(_, name)| name.clone())
+                .unwrap_or_else(|| "Ingen (hela urvalet)".to_string());
+            egui::ComboBox::from_id_salt("report_graph_root")
+                .selected_text(selected_name)
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.report_options.root_person_id, None, "Ingen (hela urvalet)");
+                    for (id, name) in &self.persons_cache {
+                        ui.selectable_value(&mut self.report_options.root_person_id, Some(*id), name);
+                    }
+                });
+
+            if self.report_options.root_person_id.is_some() {
+                ui.separator();
+                ui.label("Generationer:");
+                ui.add(egui::DragValue::new(&mut self.report_options.max_generations).range(1..=15))
+                    .on_hover_text("Föräldrar och barn räknas som ett led; makar tas med i samma generation");
+            }
+        });
+    }
+
+    /// Välj urval (alla personer eller en sparad sökning)
+    fn show_privacy_options(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Levande/privata:");
+            egui::ComboBox::from_id_salt("report_redaction")
+                .selected_text(self.report_options.redaction.display_name())
+                .show_ui(ui, |ui| {
+                    for style in RedactionStyle::all() {
+                        ui.selectable_value(&mut self.report_options.redaction, *style, style.display_name());
+                    }
+                });
+        });
+        if self.report_options.redaction == RedactionStyle::None {
+            ui.label(
+                RichText::new("Varning: levande personer exporteras med alla uppgifter.")
+                    .small()
+                    .color(Colors::WARNING),
             );
+        }
+    }
+
+    fn show_selection_options(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Urval:");
+            let selected_name = self
+                .selected_saved_search
+                .and_then(|id| self.saved_searches_cache.iter().find(|s| s.id == Some(id)))
+                .map(|s| s.name.clone())
+                .unwrap_or_else(|| "Alla personer".to_string());
+            egui::ComboBox::from_id_salt("report_selection")
+                .selected_text(selected_name)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.selected_saved_search, None, "Alla personer");
+                    for search in &self.saved_searches_cache {
+                        ui.selectable_value(&mut self.selected_saved_search, search.id, &search.name);
+                    }
+                });
 
-        if let Some(path) = file_dialog.save_file() {
-            let export_service = ExportService::new(db);
+            ui.separator();
+
+            ui.label("Tagg:");
+            let selected_tag = self
+                .selected_tag
+                .and_then(|id| self.tags_cache.iter().find(|t| t.id == Some(id)))
+                .map(|t| t.name.clone())
+                .unwrap_or_else(|| "Alla".to_string());
+            egui::ComboBox::from_id_salt("report_tag")
+                .selected_text(selected_tag)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.selected_tag, None, "Alla");
+                    for tag in &self.tags_cache {
+                        ui.selectable_value(&mut self.selected_tag, tag.id, &tag.name);
+                    }
+                });
+        });
+    }
+
+    fn do_export(&mut self, state: &mut AppState, db: &Database) {
+        if self.selected_report.requires_root_person() && self.report_options.root_person_id.is_none() {
+            state.show_error("Välj en utgångsperson för rapporten");
+            return;
+        }
+
+        // Generera filnamn
+        let filename = ExportService::generate_filename(self.selected_report, self.selected_format);
+
+        // Öppna fildialog för att välja var filen ska sparas (webbplatsen
+        // skrivs till en katalog)
+        let target = if self.selected_report == ReportType::Website {
+            rfd::FileDialog::new().set_title("Välj katalog för webbplatsen").pick_folder()
+        } else {
+            rfd::FileDialog::new()
+                .set_file_name(&filename)
+                .add_filter(
+                    self.selected_format.display_name(),
+                    &[self.selected_format.extension()],
+                )
+                .save_file()
+        };
+
+        if let Some(path) = target {
+            let options = if self.selected_report.requires_root_person() {
+                self.report_options.clone()
+            } else {
+                self.selection_options()
+            };
+            let export_service = ExportService::new(db).with_options(options);
 
             match export_service.export_to_file(self.selected_report, self.selected_format, &path) {
                 Ok(result) => {
@@ -242,6 +814,22 @@ impl ReportsView {
         }
     }
 
+    /// Rapportinställningar med valt urval (sparad sökning och tagg)
+    fn selection_options(&self) -> ReportOptions {
+        let mut options = self.report_options.clone();
+        options.person_filter = self
+            .selected_saved_search
+            .and_then(|id| self.saved_searches_cache.iter().find(|s| s.id == Some(id)))
+            .map(|s| s.filter.clone());
+        if let Some(tag_id) = self.selected_tag {
+            let filter = options.person_filter.get_or_insert_with(Default::default);
+            if !filter.tag_ids.contains(&tag_id) {
+                filter.tag_ids.push(tag_id);
+            }
+        }
+        options
+    }
+
     fn refresh_stats(&mut self, db: &Database) {
         let total_persons = db.persons().count().unwrap_or(0);
         let all_persons = db.persons().find_all().unwrap_or_default();
@@ -250,6 +838,20 @@ impl ReportsView {
         let total_relationships = db.relationships().find_all().map(|r| r.len()).unwrap_or(0) as i64;
         let total_documents = db.documents().count().unwrap_or(0);
 
+        let mut persons: Vec<(i64, String)> = all_persons
+            .iter()
+            .filter_map(|p| p.id.map(|id| (id, format!("{} {}", p.full_name(), p.years_display()).trim().to_string())))
+            .collect();
+        persons.sort_by(|a, b| a.1.cmp(&b.1));
+        self.persons_cache = persons;
+        self.document_types_cache = db.documents().get_all_types().unwrap_or_default();
+        self.saved_searches_cache = db.saved_searches().find_all().unwrap_or_default();
+        self.tags_cache = db.tags().find_all().unwrap_or_default();
+        self.templates_cache = db.report_templates().find_all().unwrap_or_default();
+        self.template_preview_data = None;
+
+        self.analytics_cache = ExportService::new(db).analytics().ok();
+
         self.stats_cache = Some(StatsCache {
             total_persons,
             living_persons,
@@ -263,3 +865,153 @@ impl ReportsView {
         self.needs_refresh = true;
     }
 }
+
+/// Färger för dataserier i diagram
+const SERIES_COLORS: [Color32; 8] = [
+    Colors::PRIMARY,
+    Colors::SPOUSE,
+    Colors::CHILD,
+    Colors::PARENT,
+    Colors::WARNING,
+    Color32::from_rgb(20, 184, 166),
+    Color32::from_rgb(249, 115, 22),
+    Colors::TEXT_SECONDARY,
+];
+
+fn sex_color(code: &str) -> Color32 {
+    match code {
+        "M" => Colors::SIBLING,
+        "F" => Colors::SPOUSE,
+        _ => Colors::TEXT_SECONDARY,
+    }
+}
+
+/// En dataserie i ett linjediagram, punkter som (x, y)
+struct Series {
+    name: String,
+    color: Color32,
+    points: Vec<(f64, f64)>,
+}
+
+/// En stapel i ett liggande stapeldiagram
+struct Bar {
+    label: String,
+    value: f64,
+    text: String,
+}
+
+/// Ram runt ett diagram med rubrik
+fn chart_card(ui: &mut egui::Ui, title: &str, add_contents: impl FnOnce(&mut egui::Ui)) {
+    egui::Frame::none()
+        .fill(ui.visuals().faint_bg_color)
+        .rounding(4.0)
+        .inner_margin(8.0)
+        .show(ui, |ui| {
+            ui.set_min_width(ui.available_width());
+            ui.label(RichText::new(title).strong());
+            ui.add_space(4.0);
+            add_contents(ui);
+        });
+    ui.add_space(8.0);
+}
+
+/// Liggande staplar med etikett till vänster och värde till höger
+fn bar_chart(ui: &mut egui::Ui, bars: &[Bar], color: Color32) {
+    if bars.is_empty() {
+        ui.label(RichText::new("Inga uppgifter").small().color(Colors::TEXT_MUTED));
+        return;
+    }
+
+    let max = bars.iter().map(|b| b.value).fold(0.0, f64::max).max(f64::EPSILON);
+    let row_height = 18.0;
+    let width = ui.available_width();
+    let label_width = (width * 0.35).min(160.0);
+    let text_width = (width * 0.3).min(150.0);
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(width, bars.len() as f32 * row_height), Sense::hover());
+    let painter = ui.painter_at(rect);
+    let font = egui::FontId::proportional(11.0);
+    let bar_space = (width - label_width - text_width - 8.0).max(10.0);
+
+    for (i, bar) in bars.iter().enumerate() {
+        let y = rect.top() + i as f32 * row_height;
+        let center = y + row_height / 2.0;
+        painter.text(
+            Pos2::new(rect.left(), center),
+            egui::Align2::LEFT_CENTER,
+            &bar.label,
+            font.clone(),
+            ui.visuals().text_color(),
+        );
+        let length = (bar.value / max) as f32 * bar_space;
+        let bar_rect = Rect::from_min_size(Pos2::new(rect.left() + label_width, y + 3.0), Vec2::new(length.max(1.0), row_height - 6.0));
+        painter.rect_filled(bar_rect, 2.0, color);
+        painter.text(
+            Pos2::new(bar_rect.right() + 6.0, center),
+            egui::Align2::LEFT_CENTER,
+            &bar.text,
+            font.clone(),
+            Colors::TEXT_SECONDARY,
+        );
+    }
+}
+
+/// Linjediagram med gemensam x-axel (årtal) och teckenförklaring
+fn line_chart(ui: &mut egui::Ui, series: &[Series], unit: &str) {
+    let points = series.iter().flat_map(|s| s.points.iter());
+    let (mut min_x, mut max_x, mut max_y) = (f64::MAX, f64::MIN, 0.0_f64);
+    for (x, y) in points {
+        min_x = min_x.min(*x);
+        max_x = max_x.max(*x);
+        max_y = max_y.max(*y);
+    }
+    if series.is_empty() || min_x > max_x {
+        ui.label(RichText::new("Inga uppgifter").small().color(Colors::TEXT_MUTED));
+        return;
+    }
+    let span_x = (max_x - min_x).max(10.0);
+    let max_y = max_y.max(1.0);
+
+    let (rect, response) = ui.allocate_exact_size(Vec2::new(ui.available_width(), 140.0), Sense::hover());
+    let painter = ui.painter_at(rect);
+    let font = egui::FontId::proportional(10.0);
+    let plot = Rect::from_min_max(rect.min + Vec2::new(32.0, 4.0), rect.max - Vec2::new(8.0, 16.0));
+    let to_screen = |x: f64, y: f64| {
+        Pos2::new(
+            plot.left() + ((x - min_x) / span_x) as f32 * plot.width(),
+            plot.bottom() - (y / max_y) as f32 * plot.height(),
+        )
+    };
+
+    // Axlar och skalvärden
+    let axis = Stroke::new(1.0, Colors::TEXT_MUTED);
+    painter.line_segment([plot.left_bottom(), plot.right_bottom()], axis);
+    painter.line_segment([plot.left_bottom(), plot.left_top()], axis);
+    painter.text(plot.left_top() - Vec2::new(4.0, 0.0), egui::Align2::RIGHT_TOP, format!("{:.0}", max_y), font.clone(), Colors::TEXT_SECONDARY);
+    painter.text(plot.left_bottom() - Vec2::new(4.0, 0.0), egui::Align2::RIGHT_BOTTOM, "0", font.clone(), Colors::TEXT_SECONDARY);
+    painter.text(plot.left_bottom() + Vec2::new(0.0, 2.0), egui::Align2::LEFT_TOP, format!("{:.0}", min_x), font.clone(), Colors::TEXT_SECONDARY);
+    painter.text(plot.right_bottom() + Vec2::new(0.0, 2.0), egui::Align2::RIGHT_TOP, format!("{:.0}", min_x + span_x), font.clone(), Colors::TEXT_SECONDARY);
+
+    let mut hovered: Option<String> = None;
+    for s in series {
+        let screen: Vec<Pos2> = s.points.iter().map(|(x, y)| to_screen(*x, *y)).collect();
+        if screen.len() > 1 {
+            painter.add(egui::Shape::line(screen.clone(), Stroke::new(2.0, s.color)));
+        }
+        for (pos, (x, y)) in screen.iter().zip(&s.points) {
+            painter.circle_filled(*pos, 3.0, s.color);
+            if response.hover_pos().is_some_and(|p| p.distance(*pos) < 6.0) {
+                hovered = Some(format!("{}, {:.0}-tal: {:.1} {}", s.name, x, y, unit));
+            }
+        }
+    }
+    if let Some(text) = hovered {
+        response.on_hover_text(text);
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        for s in series {
+            ui.label(RichText::new("■").color(s.color));
+            ui.label(RichText::new(&s.name).small());
+        }
+    });
+}
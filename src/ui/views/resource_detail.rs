@@ -2,10 +2,13 @@ use egui::{self, ColorImage, RichText, TextureHandle, TextureOptions};
 use std::collections::HashMap;
 
 use crate::db::Database;
-use crate::models::{Resource, ResourceAddress, ResourceDocument, ResourceType};
+use crate::models::{
+    CustomFieldDefinition, CustomFieldEntity, Resource, ResourceAddress, ResourceDocument, ResourceType,
+};
 use crate::ui::{
     state::{AppState, ConfirmAction},
     theme::{Colors, Icons},
+    widgets::custom_field_rows,
     View,
 };
 use crate::utils::file_ops;
@@ -23,6 +26,9 @@ pub struct ResourceDetailView {
     type_cache: Option<ResourceType>,
     addresses_cache: Vec<ResourceAddress>,
     documents_cache: Vec<ResourceDocument>,
+    /// Egna fält för resurser och resursens värden
+    custom_definitions: Vec<CustomFieldDefinition>,
+    custom_values: HashMap<i64, String>,
     needs_refresh: bool,
     selected_tab: ResourceDetailTab,
     /// Cachade miniatyrer (document_id -> texture)
@@ -46,6 +52,8 @@ impl ResourceDetailView {
             type_cache: None,
             addresses_cache: Vec::new(),
             documents_cache: Vec::new(),
+            custom_definitions: Vec::new(),
+            custom_values: HashMap::new(),
             needs_refresh: true,
             selected_tab: ResourceDetailTab::default(),
             thumbnails: HashMap::new(),
@@ -191,6 +199,17 @@ impl ResourceDetailView {
                 }
             }
 
+            // Egna fält
+            if !self.custom_values.is_empty() {
+                egui::Grid::new("resource_custom_fields")
+                    .num_columns(2)
+                    .spacing([16.0, 4.0])
+                    .show(ui, |ui| {
+                        custom_field_rows(ui, &self.custom_definitions, &self.custom_values);
+                    });
+                ui.add_space(8.0);
+            }
+
             // Koordinater
             if resource.lat.is_some() || resource.lon.is_some() {
                 ui.label(RichText::new("Koordinater").strong());
@@ -800,6 +819,11 @@ impl ResourceDetailView {
         if let Ok(docs) = db.resources().get_documents(resource_id) {
             self.documents_cache = docs;
         }
+        self.custom_definitions = db.custom_fields().find_definitions(CustomFieldEntity::Resource).unwrap_or_default();
+        self.custom_values = db
+            .custom_fields()
+            .get_values(CustomFieldEntity::Resource, resource_id)
+            .unwrap_or_default();
     }
 }
 
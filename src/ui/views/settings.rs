@@ -4,12 +4,16 @@ use crate::db::Database;
 use crate::models::config::{
     default_shortcuts, AppSettings, ShortcutAction, ShortcutMap,
 };
-use crate::models::{ResourceType};
+use crate::models::{
+    format_hex_color, CustomFieldDefinition, CustomFieldEntity, CustomFieldType, ResourceType, Tag,
+    DEFAULT_LIVING_THRESHOLD_YEARS, DEFAULT_TAG_COLOR,
+};
 use crate::models::resource::sanitize_directory_name;
 use crate::ui::{
     shortcuts::capture_shortcut,
     state::AppState,
     theme::{Colors, Icons},
+    widgets::{tag_chip, tag_color},
     View,
 };
 
@@ -27,10 +31,24 @@ pub struct SettingsView {
     resource_types_cache: Vec<ResourceType>,
     resource_types_loaded: bool,
     new_resource_type_name: String,
+    // Taggar (tagg, antal personer)
+    tags_cache: Vec<(Tag, i64)>,
+    tags_loaded: bool,
+    new_tag_name: String,
+    new_tag_color: egui::Color32,
+    // Egna fält (definition, antal poster med värde)
+    custom_fields_cache: Vec<(CustomFieldDefinition, i64)>,
+    custom_fields_loaded: bool,
+    new_field_entity: CustomFieldEntity,
+    new_field_name: String,
+    new_field_type: CustomFieldType,
+    new_field_options: String,
     // Katalognamnformat
     selected_format: crate::models::DirNameFormat,
     format_loaded: bool,
     rename_status: Option<String>,
+    // Sekretess
+    living_threshold_years: i32,
 }
 
 impl SettingsView {
@@ -47,9 +65,20 @@ impl SettingsView {
             resource_types_cache: Vec::new(),
             resource_types_loaded: false,
             new_resource_type_name: String::new(),
+            tags_cache: Vec::new(),
+            tags_loaded: false,
+            new_tag_name: String::new(),
+            new_tag_color: tag_color(&Tag::new(String::new(), DEFAULT_TAG_COLOR.to_string())),
+            custom_fields_cache: Vec::new(),
+            custom_fields_loaded: false,
+            new_field_entity: CustomFieldEntity::default(),
+            new_field_name: String::new(),
+            new_field_type: CustomFieldType::default(),
+            new_field_options: String::new(),
             selected_format: crate::models::DirNameFormat::default(),
             format_loaded: false,
             rename_status: None,
+            living_threshold_years: DEFAULT_LIVING_THRESHOLD_YEARS,
         }
     }
 
@@ -63,9 +92,39 @@ impl SettingsView {
         if self.needs_refresh {
             self.refresh_config(db);
             self.resource_types_loaded = false;
+            self.tags_loaded = false;
+            self.custom_fields_loaded = false;
             self.needs_refresh = false;
         }
 
+        if !self.custom_fields_loaded {
+            let mut fields = Vec::new();
+            for entity in CustomFieldEntity::all() {
+                for definition in db.custom_fields().find_definitions(*entity).unwrap_or_default() {
+                    let count = definition
+                        .id
+                        .and_then(|id| db.custom_fields().count_values(id).ok())
+                        .unwrap_or(0);
+                    fields.push((definition, count));
+                }
+            }
+            self.custom_fields_cache = fields;
+            self.custom_fields_loaded = true;
+        }
+
+        if !self.tags_loaded {
+            if let Ok(tags) = db.tags().find_all() {
+                self.tags_cache = tags
+                    .into_iter()
+                    .map(|t| {
+                        let count = t.id.and_then(|id| db.tags().count_persons(id).ok()).unwrap_or(0);
+                        (t, count)
+                    })
+                    .collect();
+                self.tags_loaded = true;
+            }
+        }
+
         if !self.resource_types_loaded {
             if let Ok(types) = db.resources().get_all_types() {
                 self.resource_types_cache = types;
@@ -257,6 +316,36 @@ impl SettingsView {
 
                     ui.add_space(16.0);
 
+                    // Sekretess
+                    egui::Frame::none()
+                        .fill(ui.visuals().extreme_bg_color)
+                        .rounding(8.0)
+                        .inner_margin(16.0)
+                        .show(ui, |ui| {
+                            ui.set_min_width(ui.available_width());
+                            ui.label(RichText::new("Sekretess").strong());
+                            ui.add_space(8.0);
+
+                            ui.label("Personer med sekretess \"Automatisk\" maskeras i exporter och rapporter om de antas leva.");
+                            ui.horizontal(|ui| {
+                                ui.label("Räkna som levande om född inom");
+                                ui.add(egui::DragValue::new(&mut self.living_threshold_years).range(1..=150));
+                                ui.label("år och utan dödsdatum");
+                            });
+                            ui.label(
+                                RichText::new("Personer utan både födelse- och dödsdatum räknas alltid som levande.")
+                                    .small()
+                                    .color(Colors::TEXT_MUTED),
+                            );
+                            ui.add_space(4.0);
+
+                            if ui.button("Spara sekretess").clicked() {
+                                self.save_privacy(db, state);
+                            }
+                        });
+
+                    ui.add_space(16.0);
+
                     // Dokumentmallar
                     egui::Frame::none()
                         .fill(ui.visuals().extreme_bg_color)
@@ -298,6 +387,16 @@ impl SettingsView {
 
                     ui.add_space(16.0);
 
+                    // Taggar
+                    self.show_tags_section(ui, state, db);
+
+                    ui.add_space(16.0);
+
+                    // Egna fält
+                    self.show_custom_fields_section(ui, state, db);
+
+                    ui.add_space(16.0);
+
                     // Rapporter & Export
                     egui::Frame::none()
                         .fill(ui.visuals().extreme_bg_color)
@@ -463,6 +562,234 @@ impl SettingsView {
             });
     }
 
+    fn show_tags_section(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        egui::Frame::none()
+            .fill(ui.visuals().extreme_bg_color)
+            .rounding(8.0)
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                ui.set_min_width(ui.available_width());
+                ui.label(RichText::new("Taggar").strong());
+                ui.add_space(8.0);
+
+                ui.label("Egna taggar för att gruppera personer, t.ex. Emigranter eller Soldater.");
+                ui.add_space(4.0);
+
+                // Lista befintliga taggar
+                let tags = self.tags_cache.clone();
+                for (tag, count) in &tags {
+                    ui.horizontal(|ui| {
+                        // Färgväljare - sparas direkt vid ändring
+                        let mut color = tag_color(tag);
+                        if ui.color_edit_button_srgba(&mut color).changed() {
+                            let mut updated = tag.clone();
+                            updated.color = format_hex_color(color.r(), color.g(), color.b());
+                            if let Err(e) = db.tags().update(&updated) {
+                                state.show_error(&format!("Kunde inte uppdatera tagg: {}", e));
+                            }
+                            self.tags_loaded = false;
+                        }
+                        tag_chip(ui, tag);
+                        ui.label(
+                            RichText::new(format!("({} personer)", count))
+                                .small()
+                                .color(Colors::TEXT_MUTED),
+                        );
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if let Some(tag_id) = tag.id {
+                                if ui.small_button(Icons::DELETE)
+                                    .on_hover_text("Ta bort tagg")
+                                    .clicked()
+                                {
+                                    match db.tags().delete(tag_id) {
+                                        Ok(_) => {
+                                            self.tags_loaded = false;
+                                            state.show_success(&format!("Taggen \"{}\" raderad", tag.name));
+                                        }
+                                        Err(e) => {
+                                            state.show_error(&format!("Kunde inte radera tagg: {}", e));
+                                        }
+                                    }
+                                }
+                            }
+                        });
+                    });
+                }
+
+                ui.add_space(8.0);
+                ui.separator();
+                ui.add_space(4.0);
+
+                // Lägg till ny tagg (inline)
+                ui.label(RichText::new("Ny tagg:").small());
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgba(&mut self.new_tag_color);
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_tag_name)
+                            .hint_text("Namn på taggen")
+                            .desired_width(200.0),
+                    );
+                    if ui.button(format!("{} Lägg till", Icons::ADD)).clicked() {
+                        let name = self.new_tag_name.trim().to_string();
+                        if !name.is_empty() {
+                            let c = self.new_tag_color;
+                            let mut tag = Tag::new(name.clone(), format_hex_color(c.r(), c.g(), c.b()));
+                            match db.tags().create(&mut tag) {
+                                Ok(_) => {
+                                    self.new_tag_name.clear();
+                                    self.tags_loaded = false;
+                                    state.show_success(&format!("Taggen \"{}\" skapad", name));
+                                }
+                                Err(e) => {
+                                    state.show_error(&format!("Kunde inte skapa tagg: {}", e));
+                                }
+                            }
+                        }
+                    }
+                });
+            });
+    }
+
+    fn show_custom_fields_section(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database) {
+        egui::Frame::none()
+            .fill(ui.visuals().extreme_bg_color)
+            .rounding(8.0)
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                ui.set_min_width(ui.available_width());
+                ui.label(RichText::new("Egna fält").strong());
+                ui.add_space(8.0);
+
+                ui.label("Extra uppgifter för personer och resurser, t.ex. soldatnummer, regemente eller DNA-kit.");
+                ui.add_space(4.0);
+
+                let fields = self.custom_fields_cache.clone();
+                for entity in CustomFieldEntity::all() {
+                    let entity_fields: Vec<&(CustomFieldDefinition, i64)> =
+                        fields.iter().filter(|(d, _)| d.entity == *entity).collect();
+                    if entity_fields.is_empty() {
+                        continue;
+                    }
+
+                    ui.label(RichText::new(entity.display_name()).small().strong());
+                    for (index, (definition, count)) in entity_fields.iter().enumerate() {
+                        let Some(field_id) = definition.id else { continue };
+                        ui.horizontal(|ui| {
+                            ui.label(&definition.name);
+                            let mut details = definition.field_type.display_name().to_string();
+                            if definition.field_type == CustomFieldType::Choice {
+                                details = format!("{}: {}", details, definition.options.join(", "));
+                            }
+                            ui.label(
+                                RichText::new(format!("({}, {} poster)", details, count))
+                                    .small()
+                                    .color(Colors::TEXT_MUTED),
+                            );
+
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.small_button(Icons::DELETE).on_hover_text("Ta bort fält och alla värden").clicked() {
+                                    match db.custom_fields().delete_definition(field_id) {
+                                        Ok(_) => {
+                                            self.custom_fields_loaded = false;
+                                            state.show_success(&format!("Fältet \"{}\" raderat", definition.name));
+                                        }
+                                        Err(e) => {
+                                            state.show_error(&format!("Kunde inte radera fält: {}", e));
+                                        }
+                                    }
+                                }
+
+                                // Flytta upp/ner genom att byta sorteringsordning med grannen
+                                let down = ui.add_enabled(index + 1 < entity_fields.len(), egui::Button::new("⏷").small()).clicked();
+                                let up = ui.add_enabled(index > 0, egui::Button::new("⏶").small()).clicked();
+                                let neighbour = if down {
+                                    entity_fields.get(index + 1)
+                                } else if up {
+                                    entity_fields.get(index - 1)
+                                } else {
+                                    None
+                                };
+                                if let Some((other, _)) = neighbour {
+                                    let mut this = definition.clone();
+                                    let mut other = other.clone();
+                                    std::mem::swap(&mut this.sort_order, &mut other.sort_order);
+                                    let result = db
+                                        .custom_fields()
+                                        .update_definition(&this)
+                                        .and_then(|_| db.custom_fields().update_definition(&other));
+                                    if let Err(e) = result {
+                                        state.show_error(&format!("Kunde inte flytta fält: {}", e));
+                                    }
+                                    self.custom_fields_loaded = false;
+                                }
+                            });
+                        });
+                    }
+                    ui.add_space(4.0);
+                }
+
+                ui.add_space(4.0);
+                ui.separator();
+                ui.add_space(4.0);
+
+                // Lägg till nytt fält
+                ui.label(RichText::new("Nytt fält:").small());
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("new_custom_field_entity")
+                        .selected_text(self.new_field_entity.display_name())
+                        .show_ui(ui, |ui| {
+                            for entity in CustomFieldEntity::all() {
+                                ui.selectable_value(&mut self.new_field_entity, *entity, entity.display_name());
+                            }
+                        });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_field_name)
+                            .hint_text("Namn, t.ex. Soldatnummer")
+                            .desired_width(180.0),
+                    );
+                    egui::ComboBox::from_id_salt("new_custom_field_type")
+                        .selected_text(self.new_field_type.display_name())
+                        .show_ui(ui, |ui| {
+                            for field_type in CustomFieldType::all() {
+                                ui.selectable_value(&mut self.new_field_type, *field_type, field_type.display_name());
+                            }
+                        });
+                });
+                if self.new_field_type == CustomFieldType::Choice {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_field_options)
+                            .hint_text("Alternativ, separerade med komma")
+                            .desired_width(360.0),
+                    );
+                }
+                if ui.button(format!("{} Lägg till fält", Icons::ADD)).clicked() {
+                    let name = self.new_field_name.trim().to_string();
+                    if !name.is_empty() {
+                        let mut definition = CustomFieldDefinition::new(self.new_field_entity, name.clone(), self.new_field_type);
+                        if self.new_field_type == CustomFieldType::Choice {
+                            definition.options = self
+                                .new_field_options
+                                .split(',')
+                                .map(|o| o.trim().to_string())
+                                .filter(|o| !o.is_empty())
+                                .collect();
+                        }
+                        match db.custom_fields().create_definition(&mut definition) {
+                            Ok(_) => {
+                                self.new_field_name.clear();
+                                self.new_field_options.clear();
+                                self.custom_fields_loaded = false;
+                                state.show_success(&format!("Fältet \"{}\" skapat", name));
+                            }
+                            Err(e) => {
+                                state.show_error(&format!("Kunde inte skapa fält: {}", e));
+                            }
+                        }
+                    }
+                }
+            });
+    }
+
     fn show_shortcuts_section(&mut self, ui: &mut egui::Ui, state: &mut AppState) {
         // Hantera tangentfångst
         if self.capturing_action.is_some() {
@@ -612,6 +939,7 @@ impl SettingsView {
             self.backup_path = config.backup_directory_path.display().to_string();
             if !self.format_loaded {
                 self.selected_format = config.dir_name_format;
+                self.living_threshold_years = config.living_threshold_years;
                 self.format_loaded = true;
             }
         }
@@ -626,6 +954,7 @@ impl SettingsView {
             media_directory_path: PathBuf::from(&self.media_path),
             backup_directory_path: PathBuf::from(&self.backup_path),
             dir_name_format: self.selected_format,
+            living_threshold_years: self.living_threshold_years,
             created_at: None,
             updated_at: None,
         };
@@ -651,6 +980,7 @@ impl SettingsView {
             media_directory_path: PathBuf::from(&self.media_path),
             backup_directory_path: PathBuf::from(&self.backup_path),
             dir_name_format: self.selected_format,
+            living_threshold_years: self.living_threshold_years,
             created_at: None,
             updated_at: None,
         };
@@ -665,6 +995,25 @@ impl SettingsView {
         }
     }
 
+    fn save_privacy(&mut self, db: &Database, state: &mut AppState) {
+        let result = db.config().get().and_then(|mut config| {
+            config.living_threshold_years = self.living_threshold_years;
+            db.config().save(&config)
+        });
+
+        match result {
+            Ok(_) => {
+                state.show_success(&format!(
+                    "Sekretess sparad: levande om född inom {} år",
+                    self.living_threshold_years
+                ));
+            }
+            Err(e) => {
+                state.show_error(&format!("Kunde inte spara sekretess: {}", e));
+            }
+        }
+    }
+
     fn rename_person_directories(db: &Database) -> anyhow::Result<(usize, Vec<String>)> {
         let config = db.config().get()?;
         let format = config.dir_name_format;
@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use egui::{self, RichText};
 
 use crate::db::Database;
-use crate::models::{DirNameFormat, SystemConfig};
+use crate::models::{DirNameFormat, SystemConfig, DEFAULT_LIVING_THRESHOLD_YEARS};
 use crate::ui::{
     state::AppState,
     theme::{Colors, Icons},
@@ -231,6 +231,7 @@ impl SetupWizardView {
             media_directory_path: PathBuf::from(self.media_path.trim()),
             backup_directory_path: PathBuf::from(self.backup_path.trim()),
             dir_name_format: self.dir_name_format,
+            living_threshold_years: DEFAULT_LIVING_THRESHOLD_YEARS,
             created_at: None,
             updated_at: None,
         };
@@ -0,0 +1,90 @@
+//! Redigering av egna fält i formulär
+
+use std::collections::HashMap;
+
+use egui::{self, RichText};
+
+use crate::models::{CustomFieldDefinition, CustomFieldType};
+use crate::ui::theme::Colors;
+
+/// Visa inmatningsfält för egna fält i ett grid med två kolumner.
+/// `values` är fält-ID -> värde; fält utan värde saknas i kartan.
+pub fn custom_field_editor(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    definitions: &[CustomFieldDefinition],
+    values: &mut HashMap<i64, String>,
+) {
+    if definitions.is_empty() {
+        return;
+    }
+
+    egui::Grid::new(id_salt)
+        .num_columns(2)
+        .spacing([8.0, 8.0])
+        .show(ui, |ui| {
+            for definition in definitions {
+                let Some(field_id) = definition.id else {
+                    continue;
+                };
+                let value = values.entry(field_id).or_default();
+
+                ui.label(format!("{}:", definition.name));
+                match definition.field_type {
+                    CustomFieldType::Choice => {
+                        let selected = if value.is_empty() { "-" } else { value.as_str() };
+                        egui::ComboBox::from_id_salt((id_salt, field_id))
+                            .selected_text(selected.to_string())
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(value, String::new(), "-");
+                                for option in &definition.options {
+                                    ui.selectable_value(value, option.clone(), option);
+                                }
+                            });
+                    }
+                    field_type => {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(value).hint_text(field_type.hint()));
+                            if let Err(msg) = definition.validate_value(value) {
+                                ui.label(RichText::new("⚠").color(Colors::WARNING)).on_hover_text(msg);
+                            }
+                        });
+                    }
+                }
+                ui.end_row();
+            }
+        });
+}
+
+/// Visa ifyllda egna fält som rader i ett befintligt grid med två kolumner
+pub fn custom_field_rows(
+    ui: &mut egui::Ui,
+    definitions: &[CustomFieldDefinition],
+    values: &HashMap<i64, String>,
+) {
+    for definition in definitions {
+        let Some(value) = definition.id.and_then(|id| values.get(&id)) else {
+            continue;
+        };
+        ui.label(RichText::new(format!("{}:", definition.name)).color(Colors::TEXT_SECONDARY));
+        if definition.field_type == CustomFieldType::Url {
+            ui.hyperlink(value);
+        } else {
+            ui.label(value);
+        }
+        ui.end_row();
+    }
+}
+
+/// Validera alla värden mot definitionerna. Returnerar första felet.
+pub fn validate_custom_values(
+    definitions: &[CustomFieldDefinition],
+    values: &HashMap<i64, String>,
+) -> Result<(), String> {
+    for definition in definitions {
+        if let Some(value) = definition.id.and_then(|id| values.get(&id)) {
+            definition.validate_value(value)?;
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,15 @@
 //! Återanvändbara widgets
 
 pub mod checklist_panel;
+pub mod custom_fields;
 pub mod image_gallery;
+pub mod redaction_menu;
+pub mod tag_chip;
+pub mod timeline_panel;
 
 pub use checklist_panel::ChecklistPanel;
+pub use custom_fields::{custom_field_editor, custom_field_rows, validate_custom_values};
 pub use image_gallery::ImageGallery;
+pub use redaction_menu::redaction_choices;
+pub use tag_chip::{tag_chip, tag_chips, tag_color};
+pub use timeline_panel::TimelinePanel;
@@ -0,0 +1,31 @@
+//! Val av maskering för levande och privata personer i menyer
+
+use egui::{self, RichText};
+
+use crate::services::RedactionStyle;
+use crate::ui::theme::Colors;
+
+/// Visa maskeringsalternativen som menyval. Returnerar valt alternativ
+/// och stänger menyn när användaren klickar.
+pub fn redaction_choices(ui: &mut egui::Ui) -> Option<RedactionStyle> {
+    ui.label(RichText::new("Levande/privata:").small().color(Colors::TEXT_MUTED));
+    let mut chosen = None;
+    for style in RedactionStyle::all() {
+        let text = if *style == RedactionStyle::None {
+            RichText::new(style.display_name()).color(Colors::WARNING)
+        } else {
+            RichText::new(style.display_name())
+        };
+        let response = ui.button(text);
+        let response = if *style == RedactionStyle::None {
+            response.on_hover_text("Varning: levande personer exporteras med alla uppgifter.")
+        } else {
+            response
+        };
+        if response.clicked() {
+            chosen = Some(*style);
+            ui.close_menu();
+        }
+    }
+    chosen
+}
@@ -0,0 +1,37 @@
+//! Färgade etiketter (chips) för taggar
+
+use egui::{self, Color32, RichText};
+
+use crate::models::Tag;
+use crate::ui::theme::Colors;
+
+/// Taggens färg, eller standardfärg om hex-strängen är ogiltig
+pub fn tag_color(tag: &Tag) -> Color32 {
+    tag.rgb()
+        .map(|(r, g, b)| Color32::from_rgb(r, g, b))
+        .unwrap_or(Colors::PRIMARY)
+}
+
+/// Visa en tagg som chip. Responsen kan användas för klick/hover.
+pub fn tag_chip(ui: &mut egui::Ui, tag: &Tag) -> egui::Response {
+    let color = tag_color(tag);
+    egui::Frame::none()
+        .fill(color.gamma_multiply(0.25))
+        .stroke(egui::Stroke::new(1.0, color))
+        .rounding(8.0)
+        .inner_margin(egui::Margin::symmetric(6.0, 1.0))
+        .show(ui, |ui| {
+            ui.label(RichText::new(&tag.name).small().color(color));
+        })
+        .response
+}
+
+/// Visa en rad med chips för flera taggar
+pub fn tag_chips(ui: &mut egui::Ui, tags: &[Tag]) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 4.0;
+        for tag in tags {
+            tag_chip(ui, tag);
+        }
+    });
+}
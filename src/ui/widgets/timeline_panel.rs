@@ -0,0 +1,276 @@
+//! Tidslinje-panel: personens och familjens händelser som ett horisontellt band
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use egui::{self, Color32, Pos2, Rect, RichText, Sense, Stroke, Vec2};
+
+use crate::db::Database;
+use crate::services::timeline::{Timeline, TimelineEvent, TimelineEventKind, TimelineLane, TimelineService};
+use crate::ui::{
+    state::AppState,
+    theme::{Colors, Icons},
+};
+
+const LANE_HEIGHT: f32 = 44.0;
+const AXIS_HEIGHT: f32 = 24.0;
+const LABEL_WIDTH: f32 = 80.0;
+
+/// Tidslinje som visas i persondetaljvyn
+pub struct TimelinePanel {
+    timeline: Option<Timeline>,
+    person_id: Option<i64>,
+    needs_refresh: bool,
+    /// Valfria band
+    show_parents: bool,
+    show_siblings: bool,
+    show_children: bool,
+    show_documents: bool,
+    /// Bredd per år i pixlar
+    year_width: f32,
+}
+
+impl Default for TimelinePanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimelinePanel {
+    pub fn new() -> Self {
+        Self {
+            timeline: None,
+            person_id: None,
+            needs_refresh: true,
+            show_parents: true,
+            show_siblings: false,
+            show_children: true,
+            show_documents: true,
+            year_width: 24.0,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, state: &mut AppState, db: &Database, person_id: i64) {
+        if self.needs_refresh || self.person_id != Some(person_id) {
+            self.refresh(db, person_id);
+        }
+
+        egui::Frame::none()
+            .fill(ui.visuals().extreme_bg_color)
+            .rounding(8.0)
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                ui.set_min_width(ui.available_width());
+
+                ui.horizontal(|ui| {
+                    ui.heading(format!("{} Tidslinje", Icons::CALENDAR));
+                    ui.separator();
+                    ui.checkbox(&mut self.show_parents, "Föräldrar");
+                    ui.checkbox(&mut self.show_siblings, "Syskon");
+                    ui.checkbox(&mut self.show_children, "Barn");
+                    ui.checkbox(&mut self.show_documents, "Dokument");
+                    ui.separator();
+                    ui.label("Skala:");
+                    ui.add(egui::Slider::new(&mut self.year_width, 6.0..=120.0).show_value(false));
+                });
+                ui.add_space(8.0);
+
+                let Some(timeline) = self.timeline.as_ref() else {
+                    ui.label(RichText::new("Kunde inte läsa tidslinjen").color(Colors::TEXT_MUTED));
+                    return;
+                };
+                if timeline.events.is_empty() {
+                    ui.label(RichText::new("Inga daterade händelser").color(Colors::TEXT_MUTED));
+                    return;
+                }
+
+                for issue in &timeline.issues {
+                    ui.label(RichText::new(format!("⚠ {}", issue)).small().color(Colors::WARNING));
+                }
+                if !timeline.issues.is_empty() {
+                    ui.add_space(8.0);
+                }
+
+                let lanes: Vec<TimelineLane> = TimelineLane::all()
+                    .iter()
+                    .copied()
+                    .filter(|lane| match lane {
+                        TimelineLane::Person => true,
+                        TimelineLane::Parents => self.show_parents,
+                        TimelineLane::Siblings => self.show_siblings,
+                        TimelineLane::Children => self.show_children,
+                        TimelineLane::Documents => self.show_documents,
+                    })
+                    .collect();
+
+                if let Some(action) = Self::show_band(ui, timeline, &lanes, self.year_width) {
+                    match action {
+                        BandAction::Person(id) => state.navigate_to_person(id),
+                        BandAction::Document(id) => state.navigate_to_document(id),
+                    }
+                }
+            });
+    }
+
+    /// Rita bandet med bandnamn till vänster och en horisontellt
+    /// scrollbar tidsaxel till höger
+    fn show_band(ui: &mut egui::Ui, timeline: &Timeline, lanes: &[TimelineLane], year_width: f32) -> Option<BandAction> {
+        let (first, last) = timeline.span()?;
+        let start_year = first.year() - 1;
+        let end_year = last.year() + 2;
+        let height = AXIS_HEIGHT + lanes.len() as f32 * LANE_HEIGHT;
+        let width = (end_year - start_year) as f32 * year_width;
+        let start = NaiveDate::from_ymd_opt(start_year, 1, 1)?;
+        let x_of = |date: NaiveDate| (date - start).num_days() as f32 / 365.25 * year_width;
+
+        let mut action = None;
+        ui.horizontal_top(|ui| {
+            // Bandnamn
+            let (label_rect, _) = ui.allocate_exact_size(Vec2::new(LABEL_WIDTH, height), Sense::hover());
+            for (i, lane) in lanes.iter().enumerate() {
+                let y = label_rect.top() + AXIS_HEIGHT + (i as f32 + 0.5) * LANE_HEIGHT;
+                ui.painter().text(
+                    Pos2::new(label_rect.left(), y),
+                    egui::Align2::LEFT_CENTER,
+                    lane.display_name(),
+                    egui::FontId::proportional(12.0),
+                    Colors::TEXT_SECONDARY,
+                );
+            }
+
+            egui::ScrollArea::horizontal().id_salt("timeline_band").show(ui, |ui| {
+                let (response, painter) = ui.allocate_painter(Vec2::new(width, height), Sense::click());
+                let rect = response.rect;
+                let lane_y = |lane: TimelineLane| {
+                    let index = lanes.iter().position(|l| *l == lane)?;
+                    Some(rect.top() + AXIS_HEIGHT + (index as f32 + 0.5) * LANE_HEIGHT)
+                };
+
+                // Tidsaxel med årtal
+                let step = if year_width >= 30.0 { 5 } else if year_width >= 12.0 { 10 } else { 25 };
+                let first_tick = start_year + (step - start_year.rem_euclid(step)) % step;
+                for year in (first_tick..=end_year).step_by(step as usize) {
+                    let Some(date) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+                        continue;
+                    };
+                    let x = rect.left() + x_of(date);
+                    painter.line_segment(
+                        [Pos2::new(x, rect.top() + AXIS_HEIGHT - 4.0), Pos2::new(x, rect.bottom())],
+                        Stroke::new(1.0, ui.visuals().faint_bg_color),
+                    );
+                    painter.text(
+                        Pos2::new(x, rect.top() + 2.0),
+                        egui::Align2::CENTER_TOP,
+                        year.to_string(),
+                        egui::FontId::proportional(11.0),
+                        Colors::TEXT_MUTED,
+                    );
+                }
+
+                // Bandlinjer och personens livstid
+                for lane in lanes {
+                    if let Some(y) = lane_y(*lane) {
+                        painter.line_segment(
+                            [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+                            Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color),
+                        );
+                    }
+                }
+                if let (Some(birth), Some(y)) = (timeline.person.birth_date, lane_y(TimelineLane::Person)) {
+                    let end = timeline.person.death_date.unwrap_or(last);
+                    let life = Rect::from_x_y_ranges(rect.left() + x_of(birth)..=rect.left() + x_of(end), y - 3.0..=y + 3.0);
+                    painter.rect_filled(life, 3.0, Colors::PRIMARY.gamma_multiply(0.3));
+                }
+
+                // Händelser. Etiketter ritas bara där de får plats; alla syns i verktygstipset.
+                let mut label_end: HashMap<TimelineLane, f32> = HashMap::new();
+                let mut hovered: Option<&TimelineEvent> = None;
+                for event in &timeline.events {
+                    let Some(y) = lane_y(event.lane) else {
+                        continue;
+                    };
+                    let center = Pos2::new(rect.left() + x_of(event.date), y);
+                    let color = lane_color(event.lane);
+                    match event.kind {
+                        TimelineEventKind::Birth => {
+                            painter.circle_filled(center, 5.0, color);
+                        }
+                        TimelineEventKind::Marriage => {
+                            painter.circle_stroke(center, 5.0, Stroke::new(2.0, Colors::SPOUSE));
+                        }
+                        TimelineEventKind::Death => {
+                            painter.text(center, egui::Align2::CENTER_CENTER, "†", egui::FontId::proportional(14.0), color);
+                        }
+                        TimelineEventKind::Document => {
+                            painter.rect_filled(Rect::from_center_size(center, Vec2::splat(8.0)), 1.0, color);
+                        }
+                    }
+
+                    let label = match event.age {
+                        Some(age) => format!("{} ({} år)", event.title, age),
+                        None => event.title.clone(),
+                    };
+                    let galley = painter.layout_no_wrap(label, egui::FontId::proportional(10.0), Colors::TEXT_SECONDARY);
+                    let label_pos = Pos2::new(center.x + 8.0, y - 16.0);
+                    let end = label_end.entry(event.lane).or_insert(f32::MIN);
+                    if label_pos.x > *end {
+                        *end = label_pos.x + galley.size().x + 8.0;
+                        painter.galley(label_pos, galley, Colors::TEXT_SECONDARY);
+                    }
+
+                    let hit = Rect::from_center_size(center, Vec2::splat(14.0));
+                    if response.hover_pos().is_some_and(|p| hit.contains(p)) {
+                        hovered = Some(event);
+                    }
+                }
+
+                if let Some(event) = hovered {
+                    let mut text = format!("{}\n{}", event.date.format("%Y-%m-%d"), event.title);
+                    if let Some(place) = &event.place {
+                        text.push_str(&format!("\n{}", place));
+                    }
+                    if let Some(age) = event.age {
+                        text.push_str(&format!("\n{} är {} år", timeline.person.full_name(), age));
+                    }
+                    response.clone().on_hover_text(text);
+
+                    if response.clicked() {
+                        action = match (event.document_id, event.person_id) {
+                            (Some(id), _) => Some(BandAction::Document(id)),
+                            (None, Some(id)) if Some(id) != timeline.person.id => Some(BandAction::Person(id)),
+                            _ => None,
+                        };
+                    }
+                }
+            });
+        });
+
+        action
+    }
+
+    fn refresh(&mut self, db: &Database, person_id: i64) {
+        self.person_id = Some(person_id);
+        self.timeline = TimelineService::new(db).build(person_id).ok();
+        self.needs_refresh = false;
+    }
+
+    pub fn mark_needs_refresh(&mut self) {
+        self.needs_refresh = true;
+    }
+}
+
+/// Klick i bandet
+enum BandAction {
+    Person(i64),
+    Document(i64),
+}
+
+fn lane_color(lane: TimelineLane) -> Color32 {
+    match lane {
+        TimelineLane::Person => Colors::PRIMARY,
+        TimelineLane::Parents => Colors::PARENT,
+        TimelineLane::Siblings => Colors::SIBLING,
+        TimelineLane::Children => Colors::CHILD,
+        TimelineLane::Documents => Colors::TEXT_SECONDARY,
+    }
+}